@@ -0,0 +1,254 @@
+use std::io;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime};
+use actix_web::web::Bytes;
+use futures_util::Stream;
+use sqlx::SqlitePool;
+use uuid::Uuid;
+use tokio::fs::{self, File};
+use tokio::io::{AsyncReadExt, AsyncWriteExt, BufReader, BufWriter};
+use crate::crypto::CryptoService;
+use crate::db;
+
+// Taille des blocs chiffrés indépendamment
+const CHUNK_SIZE: usize = 64 * 1024;
+// Nonce et tag AES-GCM ajoutés à chaque bloc
+const CHUNK_OVERHEAD: usize = 12 + 16;
+// En-tête des fichiers chiffrés (format v1)
+const MAGIC: &[u8; 4] = b"FPB1";
+// Délai après lequel un envoi interrompu (.part) est considéré comme abandonné
+const STALE_UPLOAD: Duration = Duration::from_secs(24 * 3600);
+
+fn blob_path(dir: &str, id: &str) -> PathBuf {
+    Path::new(dir).join(id)
+}
+
+fn upload_path(dir: &str, id: &str) -> PathBuf {
+    Path::new(dir).join(format!("{}.part", id))
+}
+
+/// Le fichier porte-t-il un nom de pièce jointe (identifiant UUID, éventuellement en `.part`) ?
+/// Tout autre fichier du répertoire est laissé en place par le nettoyage
+fn is_attachment_file(name: &str) -> bool {
+    let id = name.strip_suffix(".part").unwrap_or(name);
+    Uuid::parse_str(id).is_ok_and(|uuid| uuid.to_string() == id)
+}
+
+/// Écriture chiffrée d'une pièce jointe, bloc par bloc. Chaque bloc est précédé d'un octet
+/// indiquant s'il est le dernier et de sa taille ; le fichier reste en `.part` jusqu'à `commit`
+pub struct BlobWriter<'a> {
+    file: BufWriter<File>,
+    id: String,
+    crypto: &'a CryptoService,
+    index: u64,
+    pending: Vec<u8>,
+    size: u64,
+}
+
+impl<'a> BlobWriter<'a> {
+    pub async fn create(dir: &str, id: &str, crypto: &'a CryptoService) -> io::Result<Self> {
+        let mut file = BufWriter::new(File::create(upload_path(dir, id)).await?);
+        file.write_all(MAGIC).await?;
+
+        Ok(Self {
+            file,
+            id: id.to_string(),
+            crypto,
+            index: 0,
+            pending: Vec::with_capacity(CHUNK_SIZE),
+            size: 0,
+        })
+    }
+
+    /// Taille en clair reçue jusqu'ici
+    pub fn size(&self) -> u64 {
+        self.size
+    }
+
+    pub async fn write(&mut self, data: &[u8]) -> io::Result<()> {
+        self.size += data.len() as u64;
+        self.pending.extend_from_slice(data);
+
+        // Le dernier bloc n'est connu qu'à la fin : on garde toujours au moins un bloc en attente
+        while self.pending.len() > CHUNK_SIZE {
+            let chunk: Vec<u8> = self.pending.drain(..CHUNK_SIZE).collect();
+            self.write_chunk(&chunk, false).await?;
+        }
+
+        Ok(())
+    }
+
+    async fn write_chunk(&mut self, data: &[u8], last: bool) -> io::Result<()> {
+        let sealed = self.crypto
+            .encrypt_chunk(&self.id, self.index, last, data)
+            .map_err(io::Error::other)?;
+
+        self.file.write_u8(last as u8).await?;
+        self.file.write_u32(sealed.len() as u32).await?;
+        self.file.write_all(&sealed).await?;
+        self.index += 1;
+
+        Ok(())
+    }
+
+    /// Chiffre le dernier bloc et force l'écriture sur disque ; renvoie la taille en clair
+    pub async fn finish(mut self) -> io::Result<u64> {
+        let pending = std::mem::take(&mut self.pending);
+        self.write_chunk(&pending, true).await?;
+        self.file.flush().await?;
+        self.file.into_inner().sync_all().await?;
+
+        Ok(self.size)
+    }
+}
+
+/// Rend visible une pièce jointe dont l'envoi est terminé et enregistré en base
+pub async fn commit(dir: &str, id: &str) -> io::Result<()> {
+    fs::rename(upload_path(dir, id), blob_path(dir, id)).await
+}
+
+/// Abandonne un envoi en cours
+pub async fn discard_upload(dir: &str, id: &str) {
+    if let Err(e) = fs::remove_file(upload_path(dir, id)).await {
+        log::warn!("Failed to remove incomplete upload {}: {}", id, e);
+    }
+}
+
+/// Supprime le fichier chiffré d'une pièce jointe (absent : rien à faire)
+pub async fn remove(dir: &str, id: &str) -> io::Result<()> {
+    match fs::remove_file(blob_path(dir, id)).await {
+        Err(e) if e.kind() != io::ErrorKind::NotFound => Err(e),
+        _ => Ok(()),
+    }
+}
+
+struct BlobReader {
+    file: BufReader<File>,
+    id: String,
+    crypto: CryptoService,
+    index: u64,
+    done: bool,
+}
+
+impl BlobReader {
+    async fn next_chunk(&mut self) -> io::Result<Option<Bytes>> {
+        if self.done {
+            return Ok(None);
+        }
+
+        let invalid = |msg: &str| io::Error::new(io::ErrorKind::InvalidData, msg.to_string());
+
+        // Une fin de fichier avant le bloc marqué comme dernier signale une troncature
+        let last = match self.file.read_u8().await {
+            Ok(flag) => flag == 1,
+            Err(e) if e.kind() == io::ErrorKind::UnexpectedEof => return Err(invalid("Attachment is truncated")),
+            Err(e) => return Err(e),
+        };
+        let len = self.file.read_u32().await? as usize;
+        if len > CHUNK_SIZE + CHUNK_OVERHEAD {
+            return Err(invalid("Invalid attachment chunk"));
+        }
+
+        let mut sealed = vec![0u8; len];
+        self.file.read_exact(&mut sealed).await?;
+        let data = self.crypto
+            .decrypt_chunk(&self.id, self.index, last, &sealed)
+            .map_err(|e| invalid(&e))?;
+        self.index += 1;
+
+        if last {
+            self.done = true;
+            if self.file.read_u8().await.is_ok() {
+                return Err(invalid("Unexpected data after the last attachment chunk"));
+            }
+        }
+
+        Ok(Some(Bytes::from(data)))
+    }
+}
+
+/// Flux déchiffré d'une pièce jointe ; il s'interrompt en erreur si le fichier a été altéré ou tronqué
+pub async fn open(
+    dir: &str,
+    id: &str,
+    crypto: CryptoService,
+) -> io::Result<impl Stream<Item = io::Result<Bytes>>> {
+    let mut file = BufReader::new(File::open(blob_path(dir, id)).await?);
+
+    let mut magic = [0u8; 4];
+    file.read_exact(&mut magic).await?;
+    if &magic != MAGIC {
+        return Err(io::Error::new(io::ErrorKind::InvalidData, "Unknown attachment format"));
+    }
+
+    let reader = BlobReader { file, id: id.to_string(), crypto, index: 0, done: false };
+
+    Ok(futures_util::stream::unfold(reader, |mut reader| async move {
+        match reader.next_chunk().await {
+            Ok(Some(chunk)) => Some((Ok(chunk), reader)),
+            Ok(None) => None,
+            Err(e) => {
+                log::error!("Failed to read attachment {}: {}", reader.id, e);
+                reader.done = true;
+                Some((Err(e), reader))
+            }
+        }
+    }))
+}
+
+/// Supprime les fichiers qui n'ont plus de pièce jointe en base (élément purgé, utilisateur
+/// supprimé) ainsi que les envois abandonnés ; renvoie le nombre de fichiers supprimés.
+/// Seuls les fichiers nommés comme des pièces jointes sont concernés, de sorte qu'un
+/// ATTACHMENTS_DIR mal configuré (le répertoire de la base, par exemple) n'est jamais vidé
+pub async fn sweep_orphans(pool: &SqlitePool, dir: &str) -> Result<u64, String> {
+    // Le répertoire est lu avant la base : un envoi terminé entre-temps est encore en `.part`
+    let mut files = Vec::new();
+    let mut entries = fs::read_dir(dir).await.map_err(|e| e.to_string())?;
+    while let Some(entry) = entries.next_entry().await.map_err(|e| e.to_string())? {
+        files.push(entry);
+    }
+
+    let known = db::attachment_ids(pool).await.map_err(|e| e.to_string())?;
+    let mut removed = 0;
+
+    for entry in files {
+        let name = entry.file_name().to_string_lossy().into_owned();
+        if !is_attachment_file(&name) {
+            continue;
+        }
+        let orphan = match name.strip_suffix(".part") {
+            Some(_) => entry
+                .metadata()
+                .await
+                .and_then(|m| m.modified())
+                .map(|modified| SystemTime::now().duration_since(modified).unwrap_or_default() > STALE_UPLOAD)
+                .unwrap_or(false),
+            None => !known.contains(&name),
+        };
+
+        if orphan {
+            match fs::remove_file(entry.path()).await {
+                Ok(()) => removed += 1,
+                Err(e) => log::warn!("Failed to remove orphaned attachment {}: {}", name, e),
+            }
+        }
+    }
+
+    Ok(removed)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn sweeps_only_attachment_files() {
+        let id = Uuid::new_v4().to_string();
+
+        assert!(is_attachment_file(&id));
+        assert!(is_attachment_file(&format!("{}.part", id)));
+        for name in ["vault.db", "vault.db-wal", "attachments", ".env", &id.to_uppercase(), &id.replace('-', "")] {
+            assert!(!is_attachment_file(name), "{}", name);
+        }
+    }
+}
@@ -207,8 +207,8 @@ pub fn extract_token_from_header(req: &HttpRequest) -> Result<String, HttpRespon
         Some(auth_header) => {
             match auth_header.to_str() {
                 Ok(auth_str) => {
-                    if auth_str.starts_with("Bearer ") {
-                        Ok(auth_str[7..].to_string())
+                    if let Some(token) = auth_str.strip_prefix("Bearer ") {
+                        Ok(token.to_string())
                     } else {
                         Err(HttpResponse::BadRequest().json(ErrorResponse {
                             error: "Invalid authorization header format".to_string(),
@@ -4,7 +4,6 @@ use uuid::Uuid;
 use bcrypt::{hash, verify, DEFAULT_COST};
 use jsonwebtoken::{encode, decode, Header, Validation, EncodingKey, DecodingKey};
 use chrono::{Utc, Duration};
-use serde::{Deserialize, Serialize};
 use crate::models::{Admin, ClaimsAdmin, ErrorResponse, LoginRequestAdmin, LoginResponseAdmin, RegisterRequestAdmin};
 
 const JWT_SECRET: &[u8] = b"your-secret-key-change-in-production";
@@ -192,9 +191,9 @@ fn generate_jwt(id: &str, admin_username: &str, role: &str) -> String {
     let claims_admin = ClaimsAdmin {
         sub: id.to_string(),
         admin_username: admin_username.to_string(),
-        exp: expiration as i64,
+        exp: expiration,
         role: role.to_string(),
-        iat: iat as i64,
+        iat,
     };
 
     encode(
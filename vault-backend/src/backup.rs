@@ -0,0 +1,879 @@
+use std::ffi::{CStr, CString};
+use std::fmt;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::ptr;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc;
+use std::time::Duration;
+use actix_web::web::Bytes;
+use argon2::Params;
+use chrono::{DateTime, Utc};
+use futures_util::Stream;
+use libsqlite3_sys as ffi;
+use rand::RngCore;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use sqlx::sqlite::{SqliteConnection, SqlitePool};
+use sqlx::{ConnectOptions, Connection, Row};
+use tokio::fs::{self, File};
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWriteExt, BufReader, BufWriter};
+use uuid::Uuid;
+use crate::config::AppConfig;
+use crate::crypto::CryptoService;
+use crate::db;
+use crate::encryption;
+use crate::models::BackupFile;
+
+// En-tête des archives de sauvegarde (format v1) : MAGIC, sel, puis paramètres Argon2id
+const MAGIC: &[u8; 4] = b"FPK1";
+const FORMAT_VERSION: u32 = 1;
+const SALT_LEN: usize = 16;
+const HEADER_LEN: usize = 4 + SALT_LEN + 3 * 4;
+// Taille des blocs chiffrés indépendamment, nonce et tag AES-GCM en plus
+const CHUNK_SIZE: usize = 64 * 1024;
+const CHUNK_OVERHEAD: usize = 12 + 16;
+// Taille maximale du manifeste
+const MAX_MANIFEST_SIZE: u64 = 64 * 1024;
+// Longueur minimale de la clé de sauvegarde
+const MIN_KEY_LEN: usize = 16;
+// Tentatives lorsque la base est momentanément verrouillée par un écrivain
+const BUSY_RETRIES: u32 = 50;
+
+const PREFIX: &str = "frozpass-";
+const EXTENSION: &str = ".fpbk";
+const DATABASE_ENTRY: &str = "vault.db";
+const MANIFEST_ENTRY: &str = "manifest.json";
+const ATTACHMENT_ENTRY: &str = "attachments/";
+
+static RUNNING: AtomicBool = AtomicBool::new(false);
+
+/// Phrase secrète des sauvegardes, distincte du MASTER_PASSWORD. Une clé est dérivée
+/// pour chaque archive avec un sel aléatoire
+#[derive(Clone)]
+pub struct BackupKey(String);
+
+impl BackupKey {
+    pub fn new(passphrase: String) -> Result<Self, String> {
+        if passphrase.chars().count() < MIN_KEY_LEN {
+            return Err(format!("must be at least {} characters", MIN_KEY_LEN));
+        }
+        Ok(Self(passphrase))
+    }
+
+    /// Clé de chiffrement dérivée avec le sel et les paramètres Argon2id d'une archive (ou d'un réplica)
+    pub fn derive(&self, salt: &[u8], params: Params) -> Result<CryptoService, String> {
+        CryptoService::with_salt(&self.0, salt, params)
+    }
+
+    /// Vrai si la clé reprend le secret donné (le MASTER_PASSWORD ne doit pas servir de clé de sauvegarde)
+    pub fn matches(&self, secret: &str) -> bool {
+        self.0 == secret
+    }
+}
+
+impl fmt::Debug for BackupKey {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("BackupKey(***)")
+    }
+}
+
+/// Une seule sauvegarde à la fois (manuelle ou planifiée) ; libéré à la destruction
+pub struct BackupLock(());
+
+impl BackupLock {
+    pub fn acquire() -> Option<Self> {
+        RUNNING
+            .compare_exchange(false, true, Ordering::AcqRel, Ordering::Acquire)
+            .ok()
+            .map(|_| Self(()))
+    }
+}
+
+impl Drop for BackupLock {
+    fn drop(&mut self) {
+        RUNNING.store(false, Ordering::Release);
+    }
+}
+
+/// Métadonnées d'une archive, chiffrées avec son contenu
+#[derive(Debug, Serialize, Deserialize)]
+pub struct BackupManifest {
+    pub format: u32,
+    pub created_at: String,
+    pub created_by: String,
+    pub app_version: String,
+    /// Chiffrement des champs du coffre contenus dans la base
+    pub cipher: String,
+    pub key_derivation: String,
+    /// Empreinte de la clé maître : la restauration exige le même MASTER_PASSWORD
+    pub master_key_check: String,
+    /// Génération de la clé SQLCipher de la base (absente : base en clair)
+    pub database_key_version: Option<u32>,
+    pub database_bytes: u64,
+}
+
+/// Résultat de la vérification (ou de la restauration) d'une archive
+#[derive(Debug, Serialize)]
+pub struct BackupReport {
+    pub name: String,
+    pub manifest: BackupManifest,
+    pub attachments: u64,
+    /// Pièces jointes référencées en base mais absentes de l'archive (supprimées pendant la sauvegarde)
+    pub missing_attachments: u64,
+}
+
+// ==================== FORMAT DES ARCHIVES ====================
+
+fn header_bytes(salt: &[u8; SALT_LEN], params: &Params) -> Vec<u8> {
+    let mut header = MAGIC.to_vec();
+    header.extend_from_slice(salt);
+    header.extend_from_slice(&params.m_cost().to_be_bytes());
+    header.extend_from_slice(&params.t_cost().to_be_bytes());
+    header.extend_from_slice(&params.p_cost().to_be_bytes());
+    header
+}
+
+/// Contexte authentifié de chaque bloc : lie les blocs à l'en-tête de leur archive
+fn chunk_context(header: &[u8]) -> String {
+    let hex: String = header.iter().map(|b| format!("{:02x}", b)).collect();
+    format!("frozpass/backup/{}", hex)
+}
+
+/// Écriture d'une archive : un flux chiffré par blocs (comme les pièces jointes) contenant une
+/// suite d'entrées [longueur du nom u16][nom][taille u64][contenu][SHA-256], terminée par un nom vide
+struct ArchiveWriter {
+    file: BufWriter<File>,
+    crypto: CryptoService,
+    context: String,
+    index: u64,
+    pending: Vec<u8>,
+}
+
+impl ArchiveWriter {
+    async fn create(path: &Path, key: &BackupKey) -> Result<Self, String> {
+        let mut salt = [0u8; SALT_LEN];
+        rand::thread_rng().fill_bytes(&mut salt);
+        let params = Params::default();
+        let header = header_bytes(&salt, &params);
+        let crypto = key.derive(&salt, params)?;
+
+        let mut file = BufWriter::new(File::create(path).await.map_err(|e| e.to_string())?);
+        file.write_all(&header).await.map_err(|e| e.to_string())?;
+
+        Ok(Self {
+            file,
+            crypto,
+            context: chunk_context(&header),
+            index: 0,
+            pending: Vec::with_capacity(CHUNK_SIZE),
+        })
+    }
+
+    async fn write(&mut self, data: &[u8]) -> io::Result<()> {
+        self.pending.extend_from_slice(data);
+
+        // Le dernier bloc n'est connu qu'à la fin : on garde toujours au moins un bloc en attente
+        while self.pending.len() > CHUNK_SIZE {
+            let chunk: Vec<u8> = self.pending.drain(..CHUNK_SIZE).collect();
+            self.write_chunk(&chunk, false).await?;
+        }
+
+        Ok(())
+    }
+
+    async fn write_chunk(&mut self, data: &[u8], last: bool) -> io::Result<()> {
+        let sealed = self.crypto
+            .encrypt_chunk(&self.context, self.index, last, data)
+            .map_err(io::Error::other)?;
+
+        self.file.write_u8(last as u8).await?;
+        self.file.write_u32(sealed.len() as u32).await?;
+        self.file.write_all(&sealed).await?;
+        self.index += 1;
+
+        Ok(())
+    }
+
+    async fn add_entry<R: AsyncRead + Unpin>(&mut self, name: &str, size: u64, mut reader: R) -> io::Result<()> {
+        self.write(&(name.len() as u16).to_be_bytes()).await?;
+        self.write(name.as_bytes()).await?;
+        self.write(&size.to_be_bytes()).await?;
+
+        let mut hasher = Sha256::new();
+        let mut buffer = vec![0u8; CHUNK_SIZE];
+        let mut copied = 0u64;
+        loop {
+            let read = reader.read(&mut buffer).await?;
+            if read == 0 {
+                break;
+            }
+            copied += read as u64;
+            if copied > size {
+                break;
+            }
+            hasher.update(&buffer[..read]);
+            self.write(&buffer[..read]).await?;
+        }
+
+        if copied != size {
+            return Err(io::Error::other(format!("{} changed while it was being backed up", name)));
+        }
+
+        self.write(&hasher.finalize()).await
+    }
+
+    /// Ajoute le marqueur de fin, chiffre le dernier bloc et force l'écriture sur disque
+    async fn finish(mut self) -> io::Result<()> {
+        self.write(&0u16.to_be_bytes()).await?;
+        let pending = std::mem::take(&mut self.pending);
+        self.write_chunk(&pending, true).await?;
+        self.file.flush().await?;
+        self.file.into_inner().sync_all().await
+    }
+}
+
+/// Lecture d'une archive : déchiffre les blocs à la demande et s'arrête en erreur au moindre
+/// bloc altéré, réordonné ou manquant
+struct ArchiveReader {
+    file: BufReader<File>,
+    crypto: CryptoService,
+    context: String,
+    index: u64,
+    buffer: Vec<u8>,
+    position: usize,
+    done: bool,
+}
+
+impl ArchiveReader {
+    async fn open(path: &Path, key: &BackupKey) -> Result<Self, String> {
+        let mut file = BufReader::new(File::open(path).await.map_err(|e| e.to_string())?);
+
+        let mut header = [0u8; HEADER_LEN];
+        file.read_exact(&mut header).await.map_err(|_| "Not a FrozPass backup archive".to_string())?;
+        if &header[..4] != MAGIC {
+            return Err("Not a FrozPass backup archive".into());
+        }
+
+        let mut salt = [0u8; SALT_LEN];
+        salt.copy_from_slice(&header[4..4 + SALT_LEN]);
+        let cost = |i: usize| {
+            let start = 4 + SALT_LEN + i * 4;
+            u32::from_be_bytes([header[start], header[start + 1], header[start + 2], header[start + 3]])
+        };
+        let (m_cost, t_cost, p_cost) = (cost(0), cost(1), cost(2));
+
+        // Des paramètres démesurés rendraient la dérivation interminable
+        if m_cost > 1024 * 1024 || t_cost > 16 || p_cost > 16 {
+            return Err("Unsupported key derivation parameters".into());
+        }
+        let params = Params::new(m_cost, t_cost, p_cost, Some(32))
+            .map_err(|e| format!("Invalid key derivation parameters: {}", e))?;
+        let crypto = key.derive(&salt, params)?;
+
+        Ok(Self {
+            file,
+            crypto,
+            context: chunk_context(&header),
+            index: 0,
+            buffer: Vec::new(),
+            position: 0,
+            done: false,
+        })
+    }
+
+    /// Déchiffre le bloc suivant ; `false` une fois le dernier bloc lu
+    async fn next_chunk(&mut self) -> Result<bool, String> {
+        if self.done {
+            return Ok(false);
+        }
+
+        let last = match self.file.read_u8().await {
+            Ok(flag) => flag == 1,
+            Err(e) if e.kind() == io::ErrorKind::UnexpectedEof => return Err("Backup archive is truncated".into()),
+            Err(e) => return Err(e.to_string()),
+        };
+        let len = self.file.read_u32().await.map_err(|_| "Backup archive is truncated".to_string())? as usize;
+        if len > CHUNK_SIZE + CHUNK_OVERHEAD {
+            return Err("Invalid backup archive chunk".into());
+        }
+
+        let mut sealed = vec![0u8; len];
+        self.file.read_exact(&mut sealed).await.map_err(|_| "Backup archive is truncated".to_string())?;
+        self.buffer = self.crypto
+            .decrypt_chunk(&self.context, self.index, last, &sealed)
+            .map_err(|_| match self.index {
+                0 => "Wrong backup key or corrupted archive".to_string(),
+                index => format!("Backup archive is corrupted (chunk {})", index),
+            })?;
+        self.position = 0;
+        self.index += 1;
+
+        if last {
+            self.done = true;
+            if self.file.read_u8().await.is_ok() {
+                return Err("Unexpected data after the end of the backup archive".into());
+            }
+        }
+
+        Ok(true)
+    }
+
+    /// Jusqu'à `max` octets déchiffrés
+    async fn read_some(&mut self, max: usize) -> Result<Vec<u8>, String> {
+        while self.position == self.buffer.len() {
+            if !self.next_chunk().await? {
+                return Err("Unexpected end of backup archive".into());
+            }
+        }
+
+        let end = self.buffer.len().min(self.position + max);
+        let data = self.buffer[self.position..end].to_vec();
+        self.position = end;
+        Ok(data)
+    }
+
+    async fn read_exact(&mut self, len: usize) -> Result<Vec<u8>, String> {
+        let mut data = Vec::with_capacity(len);
+        while data.len() < len {
+            let part = self.read_some(len - data.len()).await?;
+            data.extend_from_slice(&part);
+        }
+        Ok(data)
+    }
+
+    /// En-tête de l'entrée suivante ; `None` au marqueur de fin
+    async fn next_entry(&mut self) -> Result<Option<(String, u64)>, String> {
+        let len = self.read_exact(2).await?;
+        let len = u16::from_be_bytes([len[0], len[1]]) as usize;
+        if len == 0 {
+            return Ok(None);
+        }
+
+        let name = String::from_utf8(self.read_exact(len).await?)
+            .map_err(|_| "Invalid entry name in backup archive".to_string())?;
+        let size = self.read_exact(8).await?;
+        let mut bytes = [0u8; 8];
+        bytes.copy_from_slice(&size);
+
+        Ok(Some((name, u64::from_be_bytes(bytes))))
+    }
+
+    /// Copie le contenu d'une entrée dans un fichier et vérifie son empreinte
+    async fn extract(&mut self, name: &str, size: u64, dest: &Path) -> Result<(), String> {
+        let mut file = BufWriter::new(File::create(dest).await.map_err(|e| e.to_string())?);
+        let mut hasher = Sha256::new();
+        let mut remaining = size;
+
+        while remaining > 0 {
+            let data = self.read_some(remaining.min(CHUNK_SIZE as u64) as usize).await?;
+            hasher.update(&data);
+            file.write_all(&data).await.map_err(|e| e.to_string())?;
+            remaining -= data.len() as u64;
+        }
+        file.flush().await.map_err(|e| e.to_string())?;
+        file.into_inner().sync_all().await.map_err(|e| e.to_string())?;
+
+        self.check_digest(name, hasher).await
+    }
+
+    async fn check_digest(&mut self, name: &str, hasher: Sha256) -> Result<(), String> {
+        let expected = self.read_exact(32).await?;
+        if hasher.finalize()[..] != expected[..] {
+            return Err(format!("Checksum mismatch for {}", name));
+        }
+        Ok(())
+    }
+
+    /// Vérifie que le marqueur de fin coïncide avec le dernier bloc
+    async fn finish(&mut self) -> Result<(), String> {
+        if self.position < self.buffer.len() || self.next_chunk().await? {
+            return Err("Unexpected data after the end of the backup archive".into());
+        }
+        Ok(())
+    }
+}
+
+// ==================== INSTANTANÉ DE LA BASE ====================
+
+/// Copie cohérente de la base en cours d'utilisation, via l'API de sauvegarde de SQLite.
+/// La connexion source reste verrouillée le temps de la copie ; une base chiffrée est copiée
+/// avec sa clé (SQLCipher refuse de mélanger pages chiffrées et en clair)
+async fn snapshot(pool: &SqlitePool, dest: &Path, key: Option<&str>) -> Result<(), String> {
+    let mut conn = pool.acquire().await.map_err(|e| e.to_string())?;
+    snapshot_connection(&mut conn, dest, key).await
+}
+
+/// Instantané à partir d'une connexion déjà ouverte
+pub async fn snapshot_connection(conn: &mut SqliteConnection, dest: &Path, key: Option<&str>) -> Result<(), String> {
+    let dest = CString::new(dest.to_string_lossy().as_bytes()).map_err(|e| e.to_string())?;
+    let key = key
+        .map(|key| CString::new(format!("PRAGMA key = {};", key)))
+        .transpose()
+        .map_err(|e| e.to_string())?;
+
+    // La copie (et ses attentes en cas de verrou) bloque : elle tourne hors des threads de
+    // l'exécuteur, pendant que la connexion reste verrouillée ici jusqu'à la fin
+    let mut handle = conn.lock_handle().await.map_err(|e| e.to_string())?;
+    let source = RawConnection(handle.as_raw_handle().as_ptr());
+    let (running, finished) = mpsc::channel::<()>();
+    let copy = tokio::task::spawn_blocking(move || {
+        let _running = running;
+        copy_database(source, &dest, key.as_deref())
+    });
+
+    // Déclaré après `handle`, le garde est libéré avant lui, y compris si l'appelant abandonne
+    // l'attente (client déconnecté) : la connexion n'est jamais rendue pendant la copie
+    let _wait = CopyGuard(finished);
+    copy.await.map_err(|e| format!("Database snapshot task failed: {}", e))?
+}
+
+/// Connexion SQLite brute transmise au thread de copie
+struct RawConnection(*mut ffi::sqlite3);
+
+// SAFETY : sqlx ouvre ses connexions sans mutex SQLite (NOMUTEX) ; celle-ci n'est utilisée que par
+// le thread de copie, tant que `snapshot_connection` garde son verrou (voir `CopyGuard`)
+unsafe impl Send for RawConnection {}
+
+/// Attend la fin du thread de copie avant de rendre la connexion
+struct CopyGuard(mpsc::Receiver<()>);
+
+impl Drop for CopyGuard {
+    fn drop(&mut self) {
+        // Retourne dès que le thread de copie a libéré l'émetteur
+        let _ = self.0.recv();
+    }
+}
+
+fn copy_database(source: RawConnection, dest_path: &CStr, key: Option<&CStr>) -> Result<(), String> {
+    let source = source.0;
+    // SAFETY : `source` est une connexion ouverte que l'appelant garde verrouillée ; la connexion
+    // de destination n'existe que dans ce bloc et est toujours fermée
+    unsafe {
+        let mut dest = ptr::null_mut();
+        let flags = ffi::SQLITE_OPEN_READWRITE | ffi::SQLITE_OPEN_CREATE;
+        if ffi::sqlite3_open_v2(dest_path.as_ptr(), &mut dest, flags, ptr::null()) != ffi::SQLITE_OK {
+            let error = error_message(dest);
+            ffi::sqlite3_close(dest);
+            return Err(error);
+        }
+
+        if let Some(key) = key {
+            if ffi::sqlite3_exec(dest, key.as_ptr(), None, ptr::null_mut(), ptr::null_mut()) != ffi::SQLITE_OK {
+                let error = error_message(dest);
+                ffi::sqlite3_close(dest);
+                return Err(error);
+            }
+        }
+
+        let backup = ffi::sqlite3_backup_init(dest, c"main".as_ptr(), source, c"main".as_ptr());
+        if backup.is_null() {
+            let error = error_message(dest);
+            ffi::sqlite3_close(dest);
+            return Err(error);
+        }
+
+        let mut result = Ok(());
+        let mut retries = 0;
+        loop {
+            match ffi::sqlite3_backup_step(backup, -1) {
+                ffi::SQLITE_DONE => break,
+                ffi::SQLITE_OK => {}
+                ffi::SQLITE_BUSY | ffi::SQLITE_LOCKED if retries < BUSY_RETRIES => {
+                    retries += 1;
+                    std::thread::sleep(Duration::from_millis(100));
+                }
+                code => {
+                    result = Err(format!("SQLite backup failed (code {})", code));
+                    break;
+                }
+            }
+        }
+
+        if ffi::sqlite3_backup_finish(backup) != ffi::SQLITE_OK && result.is_ok() {
+            result = Err(error_message(dest));
+        }
+        ffi::sqlite3_close(dest);
+        result
+    }
+}
+
+unsafe fn error_message(db: *mut ffi::sqlite3) -> String {
+    if db.is_null() {
+        return "Out of memory".into();
+    }
+    CStr::from_ptr(ffi::sqlite3_errmsg(db)).to_string_lossy().into_owned()
+}
+
+// ==================== SAUVEGARDE ====================
+
+fn archive_name(now: DateTime<Utc>) -> String {
+    let suffix = Uuid::new_v4().simple().to_string();
+    format!("{}{}-{}{}", PREFIX, now.format("%Y%m%dT%H%M%SZ"), &suffix[..8], EXTENSION)
+}
+
+fn is_archive_name(name: &str) -> bool {
+    name.starts_with(PREFIX)
+        && name.ends_with(EXTENSION)
+        && name.chars().all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '.')
+}
+
+/// Chemin d'une archive du répertoire des sauvegardes à partir de son nom, validé
+pub fn archive_path(config: &AppConfig, name: &str) -> Option<PathBuf> {
+    is_archive_name(name).then(|| Path::new(&config.backup_dir).join(name))
+}
+
+fn backup_file(name: String, metadata: &std::fs::Metadata) -> BackupFile {
+    let created_at = metadata.modified().map(DateTime::<Utc>::from).unwrap_or_else(|_| Utc::now());
+    BackupFile { name, size: metadata.len(), created_at: created_at.to_rfc3339() }
+}
+
+/// Crée une archive chiffrée de la base et des pièces jointes dans le répertoire des sauvegardes,
+/// puis applique la rétention. L'appelant détient le verrou des sauvegardes
+pub async fn create(
+    _lock: &BackupLock,
+    pool: &SqlitePool,
+    config: &AppConfig,
+    crypto: &CryptoService,
+    key: &BackupKey,
+    created_by: &str,
+) -> Result<BackupFile, String> {
+    fs::create_dir_all(&config.backup_dir).await.map_err(|e| e.to_string())?;
+
+    let now = Utc::now();
+    let name = archive_name(now);
+    let dir = Path::new(&config.backup_dir);
+    let snapshot_path = dir.join(format!(".{}.db.tmp", name));
+    let upload_path = dir.join(format!("{}.part", name));
+
+    let database_key = encryption::database_key(config, crypto)?;
+    let written = match snapshot(pool, &snapshot_path, database_key.as_deref()).await {
+        Ok(()) => write_archive(config, crypto, key, created_by, now, &snapshot_path, &upload_path).await,
+        Err(e) => Err(format!("Database snapshot failed: {}", e)),
+    };
+    if let Err(e) = fs::remove_file(&snapshot_path).await {
+        if e.kind() != io::ErrorKind::NotFound {
+            log::warn!("Failed to remove database snapshot {}: {}", snapshot_path.display(), e);
+        }
+    }
+
+    let path = dir.join(&name);
+    if let Err(e) = written {
+        fs::remove_file(&upload_path).await.ok();
+        return Err(e);
+    }
+    fs::rename(&upload_path, &path).await.map_err(|e| e.to_string())?;
+
+    match apply_retention(config).await {
+        Ok(0) => {}
+        Ok(removed) => log::info!("🗑️  Removed {} old backup(s)", removed),
+        Err(e) => log::warn!("Backup retention failed: {}", e),
+    }
+
+    let metadata = fs::metadata(&path).await.map_err(|e| e.to_string())?;
+    Ok(backup_file(name, &metadata))
+}
+
+async fn write_archive(
+    config: &AppConfig,
+    crypto: &CryptoService,
+    key: &BackupKey,
+    created_by: &str,
+    now: DateTime<Utc>,
+    snapshot_path: &Path,
+    dest: &Path,
+) -> Result<(), String> {
+    let database = File::open(snapshot_path).await.map_err(|e| e.to_string())?;
+    let database_bytes = database.metadata().await.map_err(|e| e.to_string())?.len();
+
+    let manifest = serde_json::to_vec(&BackupManifest {
+        format: FORMAT_VERSION,
+        created_at: now.to_rfc3339(),
+        created_by: created_by.to_string(),
+        app_version: env!("CARGO_PKG_VERSION").to_string(),
+        cipher: "AES-256-GCM".into(),
+        key_derivation: "Argon2id".into(),
+        master_key_check: crypto.key_check(),
+        database_key_version: config.database_encryption.then_some(config.database_key_version),
+        database_bytes,
+    })
+    .map_err(|e| e.to_string())?;
+
+    let mut writer = ArchiveWriter::create(dest, key).await?;
+    let io_error = |e: io::Error| e.to_string();
+
+    writer.add_entry(MANIFEST_ENTRY, manifest.len() as u64, manifest.as_slice()).await.map_err(io_error)?;
+    writer.add_entry(DATABASE_ENTRY, database_bytes, BufReader::new(database)).await.map_err(io_error)?;
+
+    // Les pièces jointes sont déjà chiffrées avec la clé maître : elles sont copiées telles quelles
+    let mut entries = match fs::read_dir(&config.attachments_dir).await {
+        Ok(entries) => Some(entries),
+        Err(e) if e.kind() == io::ErrorKind::NotFound => None,
+        Err(e) => return Err(e.to_string()),
+    };
+    while let Some(entry) = match entries.as_mut() {
+        Some(entries) => entries.next_entry().await.map_err(io_error)?,
+        None => None,
+    } {
+        let name = entry.file_name().to_string_lossy().into_owned();
+        if name.ends_with(".part") || !entry.file_type().await.map(|t| t.is_file()).unwrap_or(false) {
+            continue;
+        }
+
+        // Une pièce jointe supprimée depuis l'instantané n'est simplement pas copiée
+        let file = match File::open(entry.path()).await {
+            Ok(file) => file,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => continue,
+            Err(e) => return Err(e.to_string()),
+        };
+        let size = file.metadata().await.map_err(io_error)?.len();
+        writer
+            .add_entry(&format!("{}{}", ATTACHMENT_ENTRY, name), size, BufReader::new(file))
+            .await
+            .map_err(io_error)?;
+    }
+
+    writer.finish().await.map_err(io_error)
+}
+
+/// Archives présentes dans le répertoire des sauvegardes, de la plus récente à la plus ancienne
+pub async fn list(config: &AppConfig) -> io::Result<Vec<BackupFile>> {
+    let mut entries = match fs::read_dir(&config.backup_dir).await {
+        Ok(entries) => entries,
+        Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(e) => return Err(e),
+    };
+
+    let mut backups = Vec::new();
+    while let Some(entry) = entries.next_entry().await? {
+        let name = entry.file_name().to_string_lossy().into_owned();
+        if is_archive_name(&name) {
+            backups.push(backup_file(name, &entry.metadata().await?));
+        }
+    }
+
+    // Les noms commencent par l'horodatage de création
+    backups.sort_by(|a, b| b.name.cmp(&a.name));
+    Ok(backups)
+}
+
+/// Supprime les archives au-delà du nombre conservé ; renvoie le nombre d'archives supprimées
+async fn apply_retention(config: &AppConfig) -> io::Result<usize> {
+    let backups = list(config).await?;
+    let mut removed = 0;
+
+    for backup in backups.iter().skip(config.backup_retention) {
+        match fs::remove_file(Path::new(&config.backup_dir).join(&backup.name)).await {
+            Ok(()) => removed += 1,
+            Err(e) => log::warn!("Failed to remove old backup {}: {}", backup.name, e),
+        }
+    }
+
+    Ok(removed)
+}
+
+/// Flux brut (chiffré) d'une archive, pour la sortir du volume de données
+pub async fn open(path: &Path) -> io::Result<impl Stream<Item = io::Result<Bytes>>> {
+    let file = File::open(path).await?;
+
+    Ok(futures_util::stream::unfold(Some(file), |file| async move {
+        let mut file = file?;
+        let mut buffer = vec![0u8; CHUNK_SIZE];
+        match file.read(&mut buffer).await {
+            Ok(0) => None,
+            Ok(read) => {
+                buffer.truncate(read);
+                Some((Ok(Bytes::from(buffer)), Some(file)))
+            }
+            Err(e) => Some((Err(e), None)),
+        }
+    }))
+}
+
+// ==================== VÉRIFICATION ET RESTAURATION ====================
+
+/// Déchiffre une archive vers `database` et `attachments_dir` en vérifiant chaque entrée, la clé
+/// maître, l'intégrité de la base et la présence des pièces jointes qu'elle référence
+async fn unpack(
+    archive: &Path,
+    crypto: &CryptoService,
+    key: &BackupKey,
+    database: &Path,
+    attachments_dir: &Path,
+) -> Result<BackupReport, String> {
+    let mut reader = ArchiveReader::open(archive, key).await?;
+
+    let manifest: BackupManifest = match reader.next_entry().await? {
+        Some((name, size)) if name == MANIFEST_ENTRY && size <= MAX_MANIFEST_SIZE => {
+            let data = reader.read_exact(size as usize).await?;
+            let mut hasher = Sha256::new();
+            hasher.update(&data);
+            reader.check_digest(&name, hasher).await?;
+            serde_json::from_slice(&data).map_err(|e| format!("Invalid backup manifest: {}", e))?
+        }
+        _ => return Err("Backup manifest is missing".into()),
+    };
+
+    if manifest.format != FORMAT_VERSION {
+        return Err(format!("Unsupported backup format {}", manifest.format));
+    }
+    if manifest.master_key_check != crypto.key_check() {
+        return Err("Backup was made with a different MASTER_PASSWORD".into());
+    }
+
+    fs::create_dir_all(attachments_dir).await.map_err(|e| e.to_string())?;
+    let mut has_database = false;
+    let mut attachments = 0;
+
+    while let Some((name, size)) = reader.next_entry().await? {
+        let dest = if name == DATABASE_ENTRY && !has_database {
+            has_database = true;
+            database.to_path_buf()
+        } else {
+            match name.strip_prefix(ATTACHMENT_ENTRY) {
+                Some(id) if !id.is_empty() && id.chars().all(|c| c.is_ascii_alphanumeric() || c == '-') => {
+                    attachments += 1;
+                    attachments_dir.join(id)
+                }
+                _ => return Err(format!("Unexpected entry '{}' in backup archive", name)),
+            }
+        };
+        reader.extract(&name, size, &dest).await?;
+    }
+    reader.finish().await?;
+
+    if !has_database {
+        return Err("Backup archive contains no database".into());
+    }
+
+    let database_key = manifest
+        .database_key_version
+        .map(|version| encryption::key_pragma(crypto, version))
+        .transpose()?;
+    let missing_attachments = check_database(database, attachments_dir, database_key.as_deref()).await?;
+    let name = archive.file_name().map(|n| n.to_string_lossy().into_owned()).unwrap_or_default();
+
+    Ok(BackupReport { name, manifest, attachments, missing_attachments })
+}
+
+/// `PRAGMA integrity_check` sur la base extraite ; renvoie le nombre de pièces jointes manquantes.
+/// La copie est ouverte en écriture : la vérification des index FTS5 l'exige
+pub async fn check_database(database: &Path, attachments_dir: &Path, key: Option<&str>) -> Result<u64, String> {
+    let mut conn = db::connect_options(database, key)
+        .connect()
+        .await
+        .map_err(|e| format!("Cannot open restored database: {}", e))?;
+
+    db::check_access(&mut conn, key.is_some()).await.map_err(|e| e.to_string())?;
+    db::check_integrity(&mut conn).await.map_err(|e| e.to_string())?;
+
+    // Les sauvegardes antérieures aux pièces jointes n'ont pas de table `attachments`
+    let has_attachments: bool = sqlx::query_scalar(
+        "SELECT COUNT(*) > 0 FROM sqlite_master WHERE type = 'table' AND name = 'attachments'"
+    )
+    .fetch_one(&mut conn)
+    .await
+    .map_err(|e| e.to_string())?;
+
+    let mut missing = 0;
+    if has_attachments {
+        let rows = sqlx::query("SELECT id FROM attachments")
+            .fetch_all(&mut conn)
+            .await
+            .map_err(|e| e.to_string())?;
+        for row in rows {
+            let id: String = row.get("id");
+            if fs::metadata(attachments_dir.join(&id)).await.is_err() {
+                log::warn!("Attachment {} is missing from the backup", id);
+                missing += 1;
+            }
+        }
+    }
+
+    conn.close().await.ok();
+    Ok(missing)
+}
+
+pub async fn remove_path(path: &Path) {
+    let result = match fs::metadata(path).await {
+        Ok(metadata) if metadata.is_dir() => fs::remove_dir_all(path).await,
+        Ok(_) => fs::remove_file(path).await,
+        Err(_) => return,
+    };
+    if let Err(e) = result {
+        log::warn!("Failed to remove {}: {}", path.display(), e);
+    }
+}
+
+/// Déchiffre et vérifie entièrement une archive sans rien modifier
+pub async fn verify(
+    config: &AppConfig,
+    crypto: &CryptoService,
+    key: &BackupKey,
+    archive: &Path,
+) -> Result<BackupReport, String> {
+    let staging = Path::new(&config.backup_dir).join(format!(".verify-{}", Uuid::new_v4()));
+    fs::create_dir_all(&staging).await.map_err(|e| e.to_string())?;
+
+    let report = unpack(archive, crypto, key, &staging.join(DATABASE_ENTRY), &staging.join("attachments")).await;
+    remove_path(&staging).await;
+    report
+}
+
+/// Remplace la base et les pièces jointes par le contenu d'une archive, une fois celle-ci
+/// entièrement vérifiée. Le serveur doit être arrêté ; les données remplacées sont conservées
+/// à côté avec le suffixe `.pre-restore-<date>`
+pub async fn restore(
+    config: &AppConfig,
+    crypto: &CryptoService,
+    key: &BackupKey,
+    archive: &Path,
+) -> Result<BackupReport, String> {
+    let staged_database = PathBuf::from(format!("{}.restore", config.database_path));
+    let staged_attachments = PathBuf::from(format!("{}.restore", config.attachments_dir));
+
+    remove_path(&staged_database).await;
+    remove_path(&staged_attachments).await;
+
+    let report = match unpack(archive, crypto, key, &staged_database, &staged_attachments).await {
+        Ok(report) => report,
+        Err(e) => {
+            remove_path(&staged_database).await;
+            remove_path(&staged_attachments).await;
+            return Err(e);
+        }
+    };
+
+    let configured = config.database_encryption.then_some(config.database_key_version);
+    if report.manifest.database_key_version != configured {
+        match report.manifest.database_key_version {
+            Some(version) => log::warn!("⚠️  The restored database is encrypted: start the server with DATABASE_ENCRYPTION=true and DATABASE_KEY_VERSION={}", version),
+            None => log::warn!("⚠️  The restored database is not encrypted: run `encrypt-db` or start the server with DATABASE_ENCRYPTION=false"),
+        }
+    }
+
+    replace_data(config, &staged_database, &staged_attachments).await?;
+    Ok(report)
+}
+
+/// Met en place une base et un répertoire de pièces jointes restaurés ; les données remplacées
+/// sont conservées à côté avec le suffixe `.pre-restore-<date>`
+pub async fn replace_data(config: &AppConfig, staged_database: &Path, staged_attachments: &Path) -> Result<(), String> {
+    let suffix = format!("pre-restore-{}", Utc::now().format("%Y%m%dT%H%M%SZ"));
+    let io_error = |e: io::Error| e.to_string();
+
+    // Le journal WAL et la mémoire partagée accompagnent l'ancienne base
+    for file in ["", "-wal", "-shm"] {
+        let current = PathBuf::from(format!("{}{}", config.database_path, file));
+        if fs::metadata(&current).await.is_ok() {
+            let kept = format!("{}{}.{}", config.database_path, file, suffix);
+            fs::rename(&current, &kept).await.map_err(io_error)?;
+        }
+    }
+    fs::rename(staged_database, &config.database_path).await.map_err(io_error)?;
+
+    if fs::metadata(&config.attachments_dir).await.is_ok() {
+        fs::rename(&config.attachments_dir, format!("{}.{}", config.attachments_dir, suffix)).await.map_err(io_error)?;
+    }
+    fs::rename(staged_attachments, &config.attachments_dir).await.map_err(io_error)
+}
@@ -0,0 +1,72 @@
+use std::path::Path;
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use crate::backup::{self, BackupKey, BackupLock};
+use crate::config::AppConfig;
+use crate::crypto::CryptoService;
+use crate::db;
+use crate::encryption;
+use crate::replica;
+
+const USAGE: &str = "Usage: vault-backend [backup | verify <archive> | restore <archive> | encrypt-db | rekey-db <version> | replica-list | replica-restore [<RFC 3339 time>]]";
+
+fn print<T: Serialize>(value: &T) -> Result<(), String> {
+    println!("{}", serde_json::to_string_pretty(value).map_err(|e| e.to_string())?);
+    Ok(())
+}
+
+fn backup_key(config: &AppConfig) -> Result<&BackupKey, String> {
+    config.backup_key.as_ref().ok_or_else(|| "BACKUP_KEY is not set".to_string())
+}
+
+/// Commandes d'exploitation : sauvegarde (`backup`, `verify`, `restore`) et chiffrement de la base
+/// (`encrypt-db`, `rekey-db`), consultation et restauration du réplica (`replica-list`,
+/// `replica-restore`). Hormis `backup`, `verify` et `replica-list`, elles se lancent serveur arrêté
+pub async fn run(args: &[String], config: &AppConfig, crypto: &CryptoService) -> Result<(), String> {
+    match args {
+        [command] if command == "backup" => {
+            let key = backup_key(config)?;
+            let lock = BackupLock::acquire().ok_or("A backup is already running")?;
+            let database_key = encryption::database_key(config, crypto)?;
+            let pool = db::connect(&config.database_path, false, database_key.as_deref(), config.replica_url.is_some())
+                .await
+                .map_err(|e| e.to_string())?;
+            let file = backup::create(&lock, &pool, config, crypto, key, "cli").await;
+            pool.close().await;
+            print(&file?)
+        }
+        [command, archive] if command == "verify" => {
+            print(&backup::verify(config, crypto, backup_key(config)?, Path::new(archive)).await?)
+        }
+        [command, archive] if command == "restore" => {
+            print(&backup::restore(config, crypto, backup_key(config)?, Path::new(archive)).await?)
+        }
+        [command] if command == "encrypt-db" => {
+            let plaintext = encryption::encrypt_database(config, crypto).await?;
+            log::info!("🔐 Database encrypted with key version {}", config.database_key_version);
+            log::warn!("⚠️  The plaintext database was kept as {}: delete it once the server starts with DATABASE_ENCRYPTION=true", plaintext);
+            Ok(())
+        }
+        [command, version] if command == "rekey-db" => {
+            let version = version.parse().map_err(|_| format!("Invalid key version '{}'", version))?;
+            encryption::rekey_database(config, crypto, version).await?;
+            log::info!("🔐 Database rekeyed: set DATABASE_KEY_VERSION={} before restarting the server", version);
+            Ok(())
+        }
+        [command] if command == "replica-list" => {
+            print(&replica::list(config, crypto, backup_key(config)?).await?)
+        }
+        [command, rest @ ..] if command == "replica-restore" && rest.len() <= 1 => {
+            let at = rest
+                .first()
+                .map(|at| {
+                    DateTime::parse_from_rfc3339(at)
+                        .map(|at| at.with_timezone(&Utc))
+                        .map_err(|_| format!("Invalid point in time '{}' (expected RFC 3339, e.g. 2026-10-19T08:30:00Z)", at))
+                })
+                .transpose()?;
+            print(&replica::restore(config, crypto, backup_key(config)?, at).await?)
+        }
+        _ => Err(USAGE.into()),
+    }
+}
@@ -0,0 +1,115 @@
+use std::str::FromStr;
+use crate::backup::BackupKey;
+use crate::s3::S3Credentials;
+
+/// Paramètres d'exécution lus depuis l'environnement (.env ou variables du pod)
+#[derive(Debug, Clone)]
+pub struct AppConfig {
+    /// Fichier de la base SQLite
+    pub database_path: String,
+    /// Ouvre la base avec SQLCipher (binaire compilé avec la feature `sqlcipher`)
+    pub database_encryption: bool,
+    /// Génération de la clé SQLCipher, dérivée du MASTER_PASSWORD ; incrémentée par `rekey-db`
+    pub database_key_version: u32,
+    /// Nombre maximum de versions conservées pour chaque secret
+    pub max_secret_versions: i64,
+    /// Durée de conservation des éléments dans la corbeille avant purge automatique
+    pub trash_retention_days: i64,
+    /// Intervalle entre deux passages du job de purge de la corbeille
+    pub trash_purge_interval_secs: u64,
+    /// Répertoire des pièces jointes chiffrées
+    pub attachments_dir: String,
+    /// Taille maximale d'une pièce jointe (octets)
+    pub attachment_max_bytes: u64,
+    /// Volume total de pièces jointes autorisé par utilisateur (éléments personnels) et par groupe (octets)
+    pub attachment_quota_bytes: u64,
+    /// Répertoire des archives de sauvegarde chiffrées
+    pub backup_dir: String,
+    /// Clé des sauvegardes, distincte du MASTER_PASSWORD ; sans elle les sauvegardes sont désactivées
+    pub backup_key: Option<BackupKey>,
+    /// Intervalle entre deux sauvegardes planifiées (heures, 0 pour désactiver)
+    pub backup_interval_hours: u64,
+    /// Nombre d'archives conservées dans le répertoire des sauvegardes
+    pub backup_retention: usize,
+    /// Adresse publique de la page d'ouverture des liens à usage unique (`{base}/{id}#{clé}`)
+    pub secret_link_base_url: String,
+    /// Webhook qui reçoit les notifications (demandes d'accès...) en JSON ; absent : simple journalisation
+    pub notify_webhook_url: Option<String>,
+    /// Intervalle de vérification des adhésions temporaires arrivées à échéance
+    pub membership_expiry_interval_secs: u64,
+    /// Intervalle de clôture des emprunts d'identifiants arrivés à échéance sans retour
+    pub lease_expiry_interval_secs: u64,
+    /// Délai d'attente par défaut d'une demande d'accès d'urgence, pendant lequel le propriétaire peut refuser
+    pub emergency_default_wait_hours: i64,
+    /// Durée de l'accès d'urgence une fois accordé
+    pub emergency_access_hours: i64,
+    /// Intervalle de traitement des demandes d'accès d'urgence (accès automatique, échéances)
+    pub emergency_check_interval_secs: u64,
+    /// Emplacement du réplica : répertoire local ou `s3://bucket/préfixe` ; absent : réplication désactivée
+    pub replica_url: Option<String>,
+    /// Point d'accès S3 (MinIO, stockage compatible...) ; par défaut celui d'AWS pour la région
+    pub replica_s3_endpoint: Option<String>,
+    pub replica_s3_region: String,
+    pub replica_s3_credentials: Option<S3Credentials>,
+    /// Intervalle d'envoi des nouvelles pages du journal WAL
+    pub replica_sync_interval_secs: u64,
+    /// Intervalle entre deux instantanés complets (nouvelle génération)
+    pub replica_snapshot_interval_hours: u64,
+    /// Taille du journal WAL au-delà de laquelle il est vidé dans la base et une génération commence
+    pub replica_max_wal_bytes: u64,
+    /// Durée sur laquelle une restauration à un instant donné reste possible
+    pub replica_retention_hours: i64,
+}
+
+impl AppConfig {
+    pub fn from_env() -> Self {
+        Self {
+            database_path: env_or("DATABASE_PATH", "data/vault.db".to_string()),
+            database_encryption: env_or("DATABASE_ENCRYPTION", false),
+            database_key_version: env_or("DATABASE_KEY_VERSION", 1).max(1),
+            max_secret_versions: env_or("MAX_SECRET_VERSIONS", 10).max(1),
+            trash_retention_days: env_or("TRASH_RETENTION_DAYS", 30).max(0),
+            trash_purge_interval_secs: env_or("TRASH_PURGE_INTERVAL_SECS", 3600).max(60),
+            attachments_dir: env_or("ATTACHMENTS_DIR", "data/attachments".to_string()),
+            attachment_max_bytes: env_or("ATTACHMENT_MAX_BYTES", 10 * 1024 * 1024),
+            attachment_quota_bytes: env_or("ATTACHMENT_QUOTA_BYTES", 100 * 1024 * 1024),
+            backup_dir: env_or("BACKUP_DIR", "data/backups".to_string()),
+            backup_key: std::env::var("BACKUP_KEY").ok().and_then(|key| {
+                BackupKey::new(key)
+                    .map_err(|e| log::warn!("⚠️  Invalid BACKUP_KEY: {}, backups disabled", e))
+                    .ok()
+            }),
+            backup_interval_hours: env_or("BACKUP_INTERVAL_HOURS", 24),
+            backup_retention: env_or("BACKUP_RETENTION", 7).max(1),
+            secret_link_base_url: env_or("SECRET_LINK_BASE_URL", "/secret".to_string()).trim_end_matches('/').to_string(),
+            notify_webhook_url: std::env::var("NOTIFY_WEBHOOK_URL").ok().filter(|url| !url.trim().is_empty()),
+            membership_expiry_interval_secs: env_or("MEMBERSHIP_EXPIRY_INTERVAL_SECS", 60).max(10),
+            lease_expiry_interval_secs: env_or("LEASE_EXPIRY_INTERVAL_SECS", 60).max(10),
+            emergency_default_wait_hours: env_or("EMERGENCY_DEFAULT_WAIT_HOURS", 48).clamp(1, 720),
+            emergency_access_hours: env_or("EMERGENCY_ACCESS_HOURS", 24).clamp(1, 720),
+            emergency_check_interval_secs: env_or("EMERGENCY_CHECK_INTERVAL_SECS", 60).max(10),
+            replica_url: std::env::var("REPLICA_URL").ok().filter(|url| !url.trim().is_empty()),
+            replica_s3_endpoint: std::env::var("REPLICA_S3_ENDPOINT").ok().filter(|url| !url.trim().is_empty()),
+            replica_s3_region: env_or("REPLICA_S3_REGION", "us-east-1".to_string()),
+            replica_s3_credentials: match (std::env::var("REPLICA_S3_ACCESS_KEY"), std::env::var("REPLICA_S3_SECRET_KEY")) {
+                (Ok(access_key), Ok(secret_key)) => Some(S3Credentials { access_key, secret_key }),
+                _ => None,
+            },
+            replica_sync_interval_secs: env_or("REPLICA_SYNC_INTERVAL_SECS", 1).max(1),
+            replica_snapshot_interval_hours: env_or("REPLICA_SNAPSHOT_INTERVAL_HOURS", 24).max(1),
+            replica_max_wal_bytes: env_or("REPLICA_MAX_WAL_BYTES", 16 * 1024 * 1024).max(1024 * 1024),
+            replica_retention_hours: env_or("REPLICA_RETENTION_HOURS", 72).max(1),
+        }
+    }
+}
+
+/// Lit une variable d'environnement et retombe sur la valeur par défaut si elle est absente ou invalide
+fn env_or<T: FromStr>(key: &str, default: T) -> T {
+    match std::env::var(key) {
+        Ok(value) => value.parse().unwrap_or_else(|_| {
+            log::warn!("⚠️  Invalid value for {}: '{}', using default", key, value);
+            default
+        }),
+        Err(_) => default,
+    }
+}
@@ -1,11 +1,15 @@
 use aes_gcm::{
-    aead::{Aead, KeyInit},
+    aead::{Aead, KeyInit, Payload},
     Aes256Gcm, Nonce,
 };
-use argon2::{Argon2, PasswordHasher};
+use argon2::{Algorithm, Argon2, Params, PasswordHasher, Version};
 use argon2::password_hash::SaltString;
 use base64::{Engine as _, engine::general_purpose};
+use hmac::{Hmac, Mac};
 use rand::RngCore;
+use sha2::Sha256;
+
+type HmacSha256 = Hmac<Sha256>;
 
 #[derive(Debug, Clone)]
 pub struct EncryptedData {
@@ -16,6 +20,10 @@ pub struct EncryptedData {
 #[derive(Clone)]
 pub struct CryptoService {
     cipher: Aes256Gcm,
+    // Clé distincte de la clé de chiffrement, réservée aux index aveugles
+    index_key: [u8; 32],
+    // Clé distincte dont dérivent les clés SQLCipher de la base
+    database_key: [u8; 32],
 }
 
 impl CryptoService {
@@ -27,8 +35,52 @@ impl CryptoService {
         // Dérivation de la clé à partir du master password
         let key = Self::derive_key(master_password)?;
         let cipher = Aes256Gcm::new(&key.into());
+        let index_key = Self::derive_subkey(&key, b"frozpass/blind-index/v1")?;
+        let database_key = Self::derive_subkey(&key, b"frozpass/database")?;
         
-        Ok(Self { cipher })
+        Ok(Self { cipher, index_key, database_key })
+    }
+
+    /// Service dont la clé est dérivée d'une phrase secrète avec un sel et des paramètres
+    /// Argon2id explicites (archives de sauvegarde : un sel aléatoire par archive)
+    pub fn with_salt(passphrase: &str, salt: &[u8], params: Params) -> Result<Self, String> {
+        if passphrase.is_empty() {
+            return Err("Passphrase cannot be empty".to_string());
+        }
+
+        let mut key = [0u8; 32];
+        Argon2::new(Algorithm::Argon2id, Version::V0x13, params)
+            .hash_password_into(passphrase.as_bytes(), salt, &mut key)
+            .map_err(|e| format!("Key derivation failed: {}", e))?;
+
+        let cipher = Aes256Gcm::new(&key.into());
+        let index_key = Self::derive_subkey(&key, b"frozpass/blind-index/v1")?;
+        let database_key = Self::derive_subkey(&key, b"frozpass/database")?;
+
+        Ok(Self { cipher, index_key, database_key })
+    }
+
+    /// Empreinte de la clé : permet de vérifier qu'une donnée chiffrée ailleurs (sauvegarde)
+    /// l'a été avec la même clé, sans rien révéler de celle-ci
+    pub fn key_check(&self) -> String {
+        self.blind_index("key_check", "frozpass")
+    }
+
+    /// Sous-clé séparée par domaine (HMAC-SHA256 de l'étiquette)
+    fn derive_subkey(key: &[u8; 32], label: &[u8]) -> Result<[u8; 32], String> {
+        let mut mac = <HmacSha256 as Mac>::new_from_slice(key)
+            .map_err(|e| format!("Subkey derivation failed: {}", e))?;
+        mac.update(label);
+
+        let mut subkey = [0u8; 32];
+        subkey.copy_from_slice(&mac.finalize().into_bytes());
+        Ok(subkey)
+    }
+
+    /// Clé SQLCipher de la base pour une génération donnée ; changer de génération (rekey)
+    /// renouvelle la clé de la base sans toucher au MASTER_PASSWORD
+    pub fn database_key(&self, version: u32) -> Result<[u8; 32], String> {
+        Self::derive_subkey(&self.database_key, format!("v{}", version).as_bytes())
     }
 
     fn derive_key(password: &str) -> Result<[u8; 32], String> {
@@ -63,10 +115,10 @@ impl CryptoService {
 
         let mut nonce_bytes = [0u8; 12];
         rand::thread_rng().fill_bytes(&mut nonce_bytes);
-        let nonce = Nonce::from_slice(&nonce_bytes);
+        let nonce = Nonce::from(nonce_bytes);
 
         let ciphertext = self.cipher
-            .encrypt(nonce, plaintext.as_bytes())
+            .encrypt(&nonce, plaintext.as_bytes())
             .map_err(|e| format!("Encryption failed: {}", e))?;
 
         Ok(EncryptedData {
@@ -76,10 +128,10 @@ impl CryptoService {
     }
 
     pub fn decrypt(&self, encrypted: &EncryptedData) -> Result<String, String> {
-        let nonce = Nonce::from_slice(&encrypted.nonce);
+        let nonce = Nonce::from(encrypted.nonce);
         
         let plaintext = self.cipher
-            .decrypt(nonce, encrypted.ciphertext.as_ref())
+            .decrypt(&nonce, encrypted.ciphertext.as_ref())
             .map_err(|e| format!("Decryption failed: {}", e))?;
 
         String::from_utf8(plaintext)
@@ -118,4 +170,78 @@ impl CryptoService {
         let encrypted = Self::decode_from_base64(encoded)?;
         self.decrypt(&encrypted)
     }
-}
\ No newline at end of file
+
+    /// Chiffre un champ de métadonnées ; une valeur vide reste vide
+    pub fn seal_field(&self, value: &str) -> Result<String, String> {
+        if value.is_empty() {
+            Ok(String::new())
+        } else {
+            self.encrypt_and_encode(value)
+        }
+    }
+
+    /// Déchiffre un champ produit par `seal_field`
+    pub fn open_field(&self, value: &str) -> Result<String, String> {
+        if value.is_empty() {
+            Ok(String::new())
+        } else {
+            self.decode_and_decrypt(value)
+        }
+    }
+
+    /// Index aveugle : HMAC-SHA256 de la valeur, séparé par domaine pour qu'un même texte
+    /// donne des index différents selon le champ
+    pub fn blind_index(&self, domain: &str, value: &str) -> String {
+        let mut mac = <HmacSha256 as Mac>::new_from_slice(&self.index_key)
+            .expect("HMAC accepts keys of any size");
+        mac.update(domain.as_bytes());
+        mac.update(&[0]);
+        mac.update(value.as_bytes());
+
+        mac.finalize()
+            .into_bytes()
+            .iter()
+            .map(|b| format!("{:02x}", b))
+            .collect()
+    }
+
+    /// Chiffre un bloc de fichier : nonce (12 octets) suivi du chiffré. Le contexte (identifiant
+    /// du fichier), le rang du bloc et le marqueur de dernier bloc sont authentifiés, ce qui
+    /// empêche de réordonner, mélanger ou tronquer les blocs
+    pub fn encrypt_chunk(&self, context: &str, index: u64, last: bool, data: &[u8]) -> Result<Vec<u8>, String> {
+        let mut nonce_bytes = [0u8; 12];
+        rand::thread_rng().fill_bytes(&mut nonce_bytes);
+        let aad = Self::chunk_aad(context, index, last);
+
+        let ciphertext = self.cipher
+            .encrypt(&Nonce::from(nonce_bytes), Payload { msg: data, aad: &aad })
+            .map_err(|e| format!("Encryption failed: {}", e))?;
+
+        let mut sealed = nonce_bytes.to_vec();
+        sealed.extend_from_slice(&ciphertext);
+        Ok(sealed)
+    }
+
+    /// Déchiffre un bloc produit par `encrypt_chunk`
+    pub fn decrypt_chunk(&self, context: &str, index: u64, last: bool, sealed: &[u8]) -> Result<Vec<u8>, String> {
+        if sealed.len() < 12 {
+            return Err("Invalid encrypted chunk".to_string());
+        }
+        let mut nonce = [0u8; 12];
+        nonce.copy_from_slice(&sealed[..12]);
+        let ciphertext = &sealed[12..];
+        let aad = Self::chunk_aad(context, index, last);
+
+        self.cipher
+            .decrypt(&Nonce::from(nonce), Payload { msg: ciphertext, aad: &aad })
+            .map_err(|e| format!("Decryption failed: {}", e))
+    }
+
+    fn chunk_aad(context: &str, index: u64, last: bool) -> Vec<u8> {
+        let mut aad = context.as_bytes().to_vec();
+        aad.push(0);
+        aad.extend_from_slice(&index.to_be_bytes());
+        aad.push(last as u8);
+        aad
+    }
+}
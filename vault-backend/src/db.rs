@@ -1,684 +1,5256 @@
-use actix_web::HttpResponse;
-use actix_web::http::Error;
-use sqlx::SqlitePool;
+use std::collections::{HashMap, HashSet};
+use sqlx::{Row, Sqlite, SqliteConnection, SqlitePool, Transaction};
+use sqlx::sqlite::{SqliteConnectOptions, SqliteJournalMode};
 use uuid::Uuid;
 use chrono::Utc;
-use crate::models::{AddResponseGroups, AddUserGroups, CreateGroupRequest, CreateGroupResponse, DeleteGroups, ResponseGetApiKeyInGroups, ResponseGetAccountInGroups, GetAccountResponse, GetAllGroups, GetApiKeyResponse, ResponseGetApiKeyInTitle, User};
+use crate::models::{AccessRequest, AddResponseGroups, EmergencyContact, EmergencyRequest, ExpiringMembership, ItemLease, Attachment, GroupMember, GroupRole, SharePermission, ItemShare, ItemAccessResponse, SharedItem, SecretLinkInfo, ExpiringItem, ExpiringItemsResponse, GroupExpiringItems, GroupPolicy, Folder, SearchResult, AuditEntry, RevealItemResponse, SecretVersionInfo, TrashItem, DeleteUserResponse, ItemTemplate, ItemTransfer, AddUserGroups, CreateGroupRequest, CreateGroupResponse, DeleteGroups, ResponseGetApiKeyInGroups, ResponseGetAccountInGroups, GetAccountResponse, GetAllGroups, GetApiKeyResponse, ResponseGetApiKeyInTitle, Page, UserSummary, VaultItemSummary};
+use crate::pagination::{ListQuery, ListSource};
 use crate::crypto::CryptoService;
-pub struct GroupService;
+use crate::items::{self, Fields, Schema, Template, TemplateField};
+use crate::search::{self, url_host};
+use crate::expiry;
+use crate::links;
+// Sources listables : colonnes utilisées pour le tri et les filtres
+pub const ITEM_LIST: ListSource = ListSource {
+    id_column: "id",
+    title_column: None,
+    title_index_column: Some("title_index"),
+    created_column: "created_at",
+    updated_column: "updated_at",
+    url_index_column: Some("url_host_index"),
+    vault_items: true,
+};
+
+pub const USER_LIST: ListSource = ListSource {
+    id_column: "id",
+    title_column: Some("username"),
+    title_index_column: None,
+    created_column: "created_at",
+    updated_column: "created_at",
+    url_index_column: None,
+    vault_items: false,
+};
+
+pub const AUDIT_LIST: ListSource = ListSource {
+    id_column: "id",
+    title_column: Some("action"),
+    title_index_column: None,
+    created_column: "created_at",
+    updated_column: "created_at",
+    url_index_column: None,
+    vault_items: false,
+};
+
+pub const GROUP_LIST: ListSource = ListSource {
+    id_column: "name",
+    title_column: Some("name"),
+    title_index_column: None,
+    created_column: "created_at",
+    updated_column: "created_at",
+    url_index_column: None,
+    vault_items: false,
+};
+
+pub const EMERGENCY_REQUEST_LIST: ListSource = ListSource {
+    id_column: "id",
+    title_column: Some("target_name"),
+    title_index_column: None,
+    created_column: "created_at",
+    updated_column: "decided_at",
+    url_index_column: None,
+    vault_items: false,
+};
+
+pub const ACCESS_REQUEST_LIST: ListSource = ListSource {
+    id_column: "id",
+    title_column: Some("group_name"),
+    title_index_column: None,
+    created_column: "created_at",
+    updated_column: "decided_at",
+    url_index_column: None,
+    vault_items: false,
+};
+
 // Initialize database tables
+/// Ouvre la base SQLite ; `create` crée le fichier s'il n'existe pas encore. Avec une clé
+/// SQLCipher (valeur de `PRAGMA key`), celle-ci est appliquée avant toute autre instruction.
+/// `replicated` laisse les points de contrôle du journal WAL au réplicateur
+pub async fn connect(database_path: &str, create: bool, key: Option<&str>, replicated: bool) -> Result<SqlitePool, sqlx::Error> {
+    let mut options = connect_options(database_path, key).create_if_missing(create);
+    if replicated {
+        options = replicated_options(options);
+    }
+    let pool = SqlitePool::connect_with(options).await?;
+
+    let mut conn = pool.acquire().await?;
+    check_access(&mut conn, key.is_some()).await?;
+
+    Ok(pool)
+}
+
+pub fn connect_options(database_path: impl AsRef<std::path::Path>, key: Option<&str>) -> SqliteConnectOptions {
+    let options = SqliteConnectOptions::new().filename(database_path);
+    match key {
+        Some(key) => options.pragma("key", key.to_string()),
+        None => options,
+    }
+}
+
+/// Journal WAL sans point de contrôle automatique : seul le réplicateur tronque le journal,
+/// après en avoir expédié les trames
+pub fn replicated_options(options: SqliteConnectOptions) -> SqliteConnectOptions {
+    options.journal_mode(SqliteJournalMode::Wal).pragma("wal_autocheckpoint", "0")
+}
+
+/// Vérifie que la base est lisible avec ou sans clé, et que SQLCipher est disponible si une clé est donnée
+pub async fn check_access(conn: &mut SqliteConnection, encrypted: bool) -> Result<(), sqlx::Error> {
+    if encrypted {
+        let cipher_version: Option<String> = sqlx::query_scalar("PRAGMA cipher_version")
+            .fetch_optional(&mut *conn)
+            .await?;
+        if cipher_version.is_none() {
+            return Err(sqlx::Error::Protocol(
+                "DATABASE_ENCRYPTION requires a build with the `sqlcipher` feature".into(),
+            ));
+        }
+    }
+
+    match sqlx::query("SELECT COUNT(*) FROM sqlite_master").execute(&mut *conn).await {
+        Err(e) if e.to_string().contains("file is not a database") => Err(sqlx::Error::Protocol(if encrypted {
+            "Cannot decrypt the database: wrong key, or the database is not encrypted yet (run `vault-backend encrypt-db`)".into()
+        } else {
+            "The database is encrypted: set DATABASE_ENCRYPTION=true".into()
+        })),
+        Err(e) => Err(e),
+        Ok(_) => Ok(()),
+    }
+}
+
+/// `PRAGMA integrity_check` ; une base endommagée donne une erreur `Protocol` détaillant les problèmes
+pub async fn check_integrity(conn: &mut SqliteConnection) -> Result<(), sqlx::Error> {
+    let problems: Vec<String> = sqlx::query_scalar("PRAGMA integrity_check")
+        .fetch_all(&mut *conn)
+        .await?;
+
+    if problems != ["ok"] {
+        return Err(sqlx::Error::Protocol(format!("Database integrity check failed: {}", problems.join("; "))));
+    }
+    Ok(())
+}
+
 pub async fn init_tables(pool: &SqlitePool) -> Result<(), sqlx::Error> {
+    // Éléments du coffre de tous types, personnels (username) ou de groupe (group_name).
+    // Les champs propres au type sont chiffrés ensemble en JSON dans `payload` (voir `items.rs`)
     sqlx::query(
-        "CREATE TABLE IF NOT EXISTS add_api_key (
+        "CREATE TABLE IF NOT EXISTS vault_items (
             id TEXT PRIMARY KEY,
-            username TEXT NOT NULL,
-            title TEXT NOT NULL UNIQUE,
-            api_key TEXT NOT NULL,
-            created_at TEXT NOT NULL
+            item_type TEXT NOT NULL,
+            username TEXT,
+            group_name TEXT,
+            title TEXT NOT NULL,
+            title_index TEXT NOT NULL,
+            url TEXT NOT NULL DEFAULT '',
+            url_host_index TEXT,
+            payload TEXT NOT NULL,
+            notes TEXT,
+            folder_id TEXT,
+            created_at TEXT NOT NULL,
+            updated_at TEXT,
+            deleted_at TEXT,
+            deleted_by TEXT,
+            expires_at TEXT,
+            rotate_every INTEGER,
+            rotated_at TEXT,
+            rotation_requested_at TEXT,
+            UNIQUE(item_type, title_index)
         )"
     )
     .execute(pool)
     .await?;
 
+    // Échéances : date d'expiration, intervalle de rotation (jours) et dernier renouvellement du
+    // secret (absent : date de dernière modification)
+    add_column_if_missing(pool, "vault_items", "expires_at", "TEXT").await?;
+    add_column_if_missing(pool, "vault_items", "rotate_every", "INTEGER").await?;
+    add_column_if_missing(pool, "vault_items", "rotated_at", "TEXT").await?;
+    // Rotation demandée hors calendrier (départ d'un membre du groupe), levée au prochain renouvellement
+    add_column_if_missing(pool, "vault_items", "rotation_requested_at", "TEXT").await?;
+
+    for column in ["username", "group_name", "url_host_index"] {
+        sqlx::query(&format!(
+            "CREATE INDEX IF NOT EXISTS idx_vault_items_{column} ON vault_items({column})"
+        ))
+        .execute(pool)
+        .await?;
+    }
+
     sqlx::query(
-        "CREATE TABLE IF NOT EXISTS add_account (
+        "CREATE TABLE IF NOT EXISTS users (
             id TEXT PRIMARY KEY,
-            username TEXT NOT NULL,
-            title TEXT NOT NULL UNIQUE,
-            user_account TEXT NOT NULL,
-            password_account TEXT NOT NULL,
-            url TEXT NOT NULL,
+            username TEXT NOT NULL UNIQUE,
+            password_hash TEXT NOT NULL,
             created_at TEXT NOT NULL
         )"
     )
     .execute(pool)
     .await?;
+
+    // Adhésions ; `role` : viewer, editor ou owner (voir `GroupRole`) ; `original_owner` : propriétaire
+    // nommé à la création du groupe, seul à pouvoir nommer d'autres propriétaires en dehors des administrateurs
     sqlx::query(
-        "CREATE TABLE IF NOT EXISTS api_key_in_groups (
+        "CREATE TABLE IF NOT EXISTS user_groups (
             id TEXT PRIMARY KEY,
-            title TEXT NOT NULL UNIQUE,
-            api_key TEXT NOT NULL,
+            username TEXT NOT NULL,
             group_name TEXT NOT NULL,
+            role TEXT NOT NULL DEFAULT 'editor',
+            original_owner INTEGER NOT NULL DEFAULT 0,
+            created_at TEXT NOT NULL,
+            UNIQUE(username, group_name)
+        )"
+    )
+    .execute(pool)
+    .await?;
+
+    // Les membres existants gardent le droit de modifier les éléments de leurs groupes
+    add_column_if_missing(pool, "user_groups", "role", "TEXT NOT NULL DEFAULT 'editor'").await?;
+    add_column_if_missing(pool, "user_groups", "original_owner", "INTEGER NOT NULL DEFAULT 0").await?;
+    // Adhésions temporaires (accès accordé sur demande pour une durée limitée)
+    add_column_if_missing(pool, "user_groups", "expires_at", "TEXT").await?;
+
+    // Hiérarchie des groupes : les membres d'un groupe accèdent aussi aux groupes descendants
+    sqlx::query(
+        "CREATE TABLE IF NOT EXISTS group_parents (
+            group_name TEXT PRIMARY KEY,
+            parent_name TEXT NOT NULL,
+            updated_by TEXT NOT NULL,
+            updated_at TEXT NOT NULL
+        )"
+    )
+    .execute(pool)
+    .await?;
+
+    sqlx::query("CREATE INDEX IF NOT EXISTS idx_group_parents_parent ON group_parents(parent_name)")
+        .execute(pool)
+        .await?;
+
+    sqlx::query(
+        "CREATE TABLE IF NOT EXISTS admin (
+            id TEXT PRIMARY KEY,
+            admin_username TEXT NOT NULL UNIQUE,
+            admin_password_hash TEXT NOT NULL,
+            role TEXT NOT NULL DEFAULT 'admin',
             created_at TEXT NOT NULL
         )"
     )
     .execute(pool)
     .await?;
+
     sqlx::query(
-        "CREATE TABLE IF NOT EXISTS users (
+        "CREATE TABLE IF NOT EXISTS session_revocations (
+            user_id TEXT PRIMARY KEY,
+            revoked_at INTEGER NOT NULL
+        )"
+    )
+    .execute(pool)
+    .await?;
+
+    sqlx::query(
+        "CREATE TABLE IF NOT EXISTS audit_log (
             id TEXT PRIMARY KEY,
-            username TEXT NOT NULL UNIQUE,
-            password_hash TEXT NOT NULL,
+            actor TEXT NOT NULL,
+            action TEXT NOT NULL,
+            target_type TEXT,
+            target_id TEXT,
+            details TEXT NOT NULL DEFAULT '',
             created_at TEXT NOT NULL
         )"
     )
     .execute(pool)
     .await?;
 
+    // Chaque version contient le contenu complet (chiffré) de l'élément
     sqlx::query(
-        "CREATE TABLE IF NOT EXISTS user_groups (
+        "CREATE TABLE IF NOT EXISTS secret_versions (
             id TEXT PRIMARY KEY,
-            username TEXT NOT NULL,
-            group_name TEXT NOT NULL,
+            item_type TEXT NOT NULL,
+            item_id TEXT NOT NULL,
+            version INTEGER NOT NULL,
+            secret TEXT NOT NULL,
+            action TEXT NOT NULL,
+            restored_from INTEGER,
+            created_by TEXT NOT NULL,
             created_at TEXT NOT NULL,
-            UNIQUE(username, group_name)
+            UNIQUE(item_type, item_id, version)
         )"
     )
     .execute(pool)
     .await?;
 
+    // Dossiers hiérarchiques, personnels (owner) ou de groupe (group_name) ; le nom est chiffré
     sqlx::query(
-        "CREATE TABLE IF NOT EXISTS account_in_groups (
+        "CREATE TABLE IF NOT EXISTS folders (
             id TEXT PRIMARY KEY,
-            title TEXT NOT NULL UNIQUE,
-            user_account TEXT NOT NULL,
-            password_account TEXT NOT NULL,
-            url TEXT NOT NULL,
-            group_name TEXT NOT NULL,
-            created_at TEXT NOT NULL
+            name TEXT NOT NULL,
+            parent_id TEXT,
+            owner TEXT,
+            group_name TEXT,
+            created_at TEXT NOT NULL,
+            updated_at TEXT
         )"
     )
     .execute(pool)
     .await?;
 
+    // Tags chiffrés, filtrables par leur index aveugle
     sqlx::query(
-        "CREATE TABLE IF NOT EXISTS admin (
+        "CREATE TABLE IF NOT EXISTS item_tags (
+            item_type TEXT NOT NULL,
+            item_id TEXT NOT NULL,
+            tag TEXT NOT NULL,
+            tag_index TEXT NOT NULL,
+            PRIMARY KEY (item_type, item_id, tag_index)
+        )"
+    )
+    .execute(pool)
+    .await?;
+
+    sqlx::query("CREATE INDEX IF NOT EXISTS idx_item_tags_tag_index ON item_tags(tag_index)")
+        .execute(pool)
+        .await?;
+
+    // Pièces jointes : le contenu est chiffré dans le répertoire des pièces jointes, sous le nom `id`
+    sqlx::query(
+        "CREATE TABLE IF NOT EXISTS attachments (
             id TEXT PRIMARY KEY,
-            admin_username TEXT NOT NULL UNIQUE,
-            admin_password_hash TEXT NOT NULL,
-            role TEXT NOT NULL DEFAULT 'admin',
+            item_type TEXT NOT NULL,
+            item_id TEXT NOT NULL,
+            filename TEXT NOT NULL,
+            content_type TEXT,
+            size INTEGER NOT NULL,
+            created_by TEXT NOT NULL,
             created_at TEXT NOT NULL
         )"
     )
     .execute(pool)
     .await?;
-    
-    log::info!("Database tables initialized successfully");
-    Ok(())
-}
 
-// API Key operations avec chiffrement
-pub async fn insert_api_key(
-    pool: &SqlitePool,
-    api_key: &str,
-    title: &str,
-    username: &str,
-    crypto: &CryptoService,
-) -> Result<(String, String), sqlx::Error> {
-    let id = Uuid::new_v4().to_string();
-    let created_at = Utc::now().to_rfc3339();
-    
-    // Chiffre la clé API
-    let encrypted_api_key = crypto.encrypt_and_encode(api_key)
-        .map_err(|e| sqlx::Error::Protocol(format!("Encryption failed: {}", e)))?;
-   
-    sqlx::query("INSERT INTO add_api_key (id, username, title, api_key, created_at) VALUES (?, ?, ?, ?, ?)")
-        .bind(&id)
-        .bind(username)
-        .bind(title)
-        .bind(&encrypted_api_key)
-        .bind(&created_at)
+    sqlx::query("CREATE INDEX IF NOT EXISTS idx_attachments_item ON attachments(item_type, item_id)")
         .execute(pool)
         .await?;
-   
-    Ok((id, created_at))
-}
 
-// Account operations avec chiffrement
-pub async fn insert_account(
-    pool: &SqlitePool,
-    user_account: &str,
-    password_account: &str,
-    title: &str,
-    url: &str,
-    username: &str,
-    crypto: &CryptoService,
-) -> Result<(String, String), sqlx::Error> {
-    let id = Uuid::new_v4().to_string();
-    let created_at = Utc::now().to_rfc3339();
-    
-    // Chiffre le mot de passe
-    let encrypted_password = crypto.encrypt_and_encode(password_account)
-        .map_err(|e| sqlx::Error::Protocol(format!("Encryption failed: {}", e)))?;
-   
+    // Politiques de groupe : âge maximal des secrets, imposé comme intervalle de rotation
     sqlx::query(
-        "INSERT INTO add_account (id, username, title, user_account, password_account, url, created_at) VALUES (?, ?, ?, ?, ?, ?, ?)"
+        "CREATE TABLE IF NOT EXISTS group_policies (
+            group_name TEXT PRIMARY KEY,
+            max_secret_age_days INTEGER NOT NULL,
+            updated_by TEXT NOT NULL,
+            updated_at TEXT NOT NULL
+        )"
     )
-    .bind(&id)
-    .bind(username)
-    .bind(title)
-    .bind(user_account)
-    .bind(&encrypted_password)
-    .bind(url)
-    .bind(&created_at)
     .execute(pool)
     .await?;
-   
-    Ok((id, created_at))
-}
 
-pub async fn delete_api_key_by_title(
-    pool: &SqlitePool,
-    id: &str,
-) -> Result<String, sqlx::Error> {
-    let result = sqlx::query("DELETE FROM add_api_key WHERE id = ?")
-        .bind(id)
+    // Modèles d'éléments définis par les administrateurs ; `fields` contient la définition JSON des champs
+    sqlx::query(
+        "CREATE TABLE IF NOT EXISTS item_templates (
+            name TEXT PRIMARY KEY,
+            label TEXT NOT NULL,
+            fields TEXT NOT NULL,
+            created_by TEXT NOT NULL,
+            created_at TEXT NOT NULL,
+            updated_at TEXT
+        )"
+    )
+    .execute(pool)
+    .await?;
+
+    // Partages d'éléments personnels avec d'autres utilisateurs ; `permission` : read ou edit
+    sqlx::query(
+        "CREATE TABLE IF NOT EXISTS item_shares (
+            item_type TEXT NOT NULL,
+            item_id TEXT NOT NULL,
+            username TEXT NOT NULL,
+            permission TEXT NOT NULL,
+            shared_by TEXT NOT NULL,
+            created_at TEXT NOT NULL,
+            PRIMARY KEY (item_type, item_id, username)
+        )"
+    )
+    .execute(pool)
+    .await?;
+
+    sqlx::query("CREATE INDEX IF NOT EXISTS idx_item_shares_username ON item_shares(username)")
         .execute(pool)
         .await?;
 
-    Ok(format!("{} row(s) affected", result.rows_affected()))
-}
+    // Liens à usage unique : contenu chiffré avec une clé que seul le lien transporte (voir `links.rs`)
+    sqlx::query(
+        "CREATE TABLE IF NOT EXISTS secret_links (
+            id TEXT PRIMARY KEY,
+            content TEXT NOT NULL,
+            salt TEXT NOT NULL,
+            passphrase_required INTEGER NOT NULL,
+            max_views INTEGER NOT NULL,
+            views INTEGER NOT NULL DEFAULT 0,
+            failed_attempts INTEGER NOT NULL DEFAULT 0,
+            locked_until TEXT,
+            item_type TEXT,
+            item_id TEXT,
+            created_by TEXT NOT NULL,
+            created_at TEXT NOT NULL,
+            expires_at TEXT NOT NULL
+        )"
+    )
+    .execute(pool)
+    .await?;
+    // Ouverture suspendue après une série d'essais erronés (voir `links::lockout`)
+    add_column_if_missing(pool, "secret_links", "locked_until", "TEXT").await?;
 
-pub async fn delete_account_by_title(
-    pool: &SqlitePool,
-    id: &str,
-) -> Result<String, sqlx::Error> {
-    let result = sqlx::query("DELETE FROM add_account WHERE id = ?")
-        .bind(id)
+    // Demandes d'adhésion aux groupes, conservées comme historique une fois traitées
+    sqlx::query(
+        "CREATE TABLE IF NOT EXISTS access_requests (
+            id TEXT PRIMARY KEY,
+            group_name TEXT NOT NULL,
+            username TEXT NOT NULL,
+            role TEXT NOT NULL,
+            justification TEXT NOT NULL,
+            duration_hours INTEGER,
+            status TEXT NOT NULL DEFAULT 'pending',
+            created_at TEXT NOT NULL,
+            decided_by TEXT,
+            decided_at TEXT,
+            decision_note TEXT,
+            access_expires_at TEXT
+        )"
+    )
+    .execute(pool)
+    .await?;
+
+    // Une seule demande en attente par utilisateur et par groupe
+    sqlx::query(
+        "CREATE UNIQUE INDEX IF NOT EXISTS idx_access_requests_pending
+         ON access_requests(group_name, username) WHERE status = 'pending'"
+    )
+    .execute(pool)
+    .await?;
+
+    sqlx::query("CREATE INDEX IF NOT EXISTS idx_access_requests_username ON access_requests(username)")
         .execute(pool)
         .await?;
 
-    Ok(format!("{} row(s) affected", result.rows_affected()))
-}
-
-pub async fn insert_user(
-    pool: &SqlitePool,
-    username: &str,
-    password_hash: &str,
-) -> Result<(String, String), sqlx::Error> {
-    let id = Uuid::new_v4().to_string();
-    let created_at = Utc::now().to_rfc3339();
+    // Emprunts exclusifs des identifiants de groupe, conservés comme historique une fois rendus
+    sqlx::query(
+        "CREATE TABLE IF NOT EXISTS item_leases (
+            id TEXT PRIMARY KEY,
+            item_type TEXT NOT NULL,
+            item_id TEXT NOT NULL,
+            group_name TEXT NOT NULL,
+            username TEXT NOT NULL,
+            reason TEXT,
+            status TEXT NOT NULL DEFAULT 'active',
+            checked_out_at TEXT NOT NULL,
+            expires_at TEXT NOT NULL,
+            returned_at TEXT,
+            returned_by TEXT
+        )"
+    )
+    .execute(pool)
+    .await?;
 
+    // Un seul emprunt en cours par élément
     sqlx::query(
-        "INSERT INTO users (id, username, password_hash, created_at)
-         VALUES (?, ?, ?, ?)"
+        "CREATE UNIQUE INDEX IF NOT EXISTS idx_item_leases_active
+         ON item_leases(item_type, item_id) WHERE status = 'active'"
     )
-    .bind(&id)
-    .bind(username)
-    .bind(password_hash)
-    .bind(&created_at)
     .execute(pool)
     .await?;
 
-    Ok((id, created_at))
-}
+    sqlx::query("CREATE INDEX IF NOT EXISTS idx_item_leases_group ON item_leases(group_name, checked_out_at)")
+        .execute(pool)
+        .await?;
 
-pub async fn delete_user(
-    pool: &SqlitePool, 
-    id: &str
-) -> Result<u64, sqlx::Error> {
-    let result = sqlx::query("DELETE FROM users WHERE id = ?")
-        .bind(id)
+    // Contacts d'urgence d'un coffre personnel (`user`) ou d'un groupe (`group`)
+    sqlx::query(
+        "CREATE TABLE IF NOT EXISTS emergency_contacts (
+            target_type TEXT NOT NULL,
+            target_name TEXT NOT NULL,
+            contact TEXT NOT NULL,
+            wait_hours INTEGER NOT NULL,
+            added_by TEXT NOT NULL,
+            created_at TEXT NOT NULL,
+            PRIMARY KEY (target_type, target_name, contact)
+        )"
+    )
+    .execute(pool)
+    .await?;
+
+    sqlx::query("CREATE INDEX IF NOT EXISTS idx_emergency_contacts_contact ON emergency_contacts(contact)")
         .execute(pool)
         .await?;
 
-    Ok(result.rows_affected())
-}
+    // Demandes d'accès d'urgence, conservées comme historique
+    sqlx::query(
+        "CREATE TABLE IF NOT EXISTS emergency_requests (
+            id TEXT PRIMARY KEY,
+            target_type TEXT NOT NULL,
+            target_name TEXT NOT NULL,
+            requester TEXT NOT NULL,
+            reason TEXT NOT NULL,
+            status TEXT NOT NULL DEFAULT 'pending',
+            created_at TEXT NOT NULL,
+            grant_at TEXT NOT NULL,
+            decided_by TEXT,
+            decided_at TEXT,
+            decision_note TEXT,
+            override_requested_by TEXT,
+            granted_at TEXT,
+            access_expires_at TEXT
+        )"
+    )
+    .execute(pool)
+    .await?;
 
-pub async fn get_account_by_username(
-    pool: &SqlitePool,
-    username: &str,
-    crypto: &CryptoService,
-) -> Result<Vec<GetAccountResponse>, sqlx::Error> {
-    let rows = sqlx::query_as::<_, GetAccountResponse>(
-        r#"
-        SELECT id, username, title, user_account, password_account, url, created_at
-        FROM add_account
-        WHERE username = ?
-        "#
+    // Une seule demande en cours (en attente ou accordée) par contact et par cible
+    sqlx::query(
+        "CREATE UNIQUE INDEX IF NOT EXISTS idx_emergency_requests_open
+         ON emergency_requests(target_type, target_name, requester) WHERE status IN ('pending', 'granted')"
     )
-    .bind(username)
-    .fetch_all(pool)
-    .await
-    .map_err(|e| {
-        log::error!("Database query failed for get_account_by_username: {:?}", e);
-        e
-    })?;
+    .execute(pool)
+    .await?;
 
-    // Déchiffre chaque mot de passe
-    let mut accounts = Vec::new();
-    for mut row in rows {
-        row.password_account = crypto.decode_and_decrypt(&row.password_account)
-            .map_err(|e| {
-                log::error!("Decryption failed for account {}: {}", row.title, e);
-                sqlx::Error::Protocol(format!("Decryption failed: {}", e))
-            })?;
-        
-        accounts.push(row);
+    // L'ancien index indexait l'identifiant des comptes dans une colonne dédiée ;
+    // il est recréé puis reconstruit par `migrate_legacy_items`
+    let fields_column = sqlx::query("SELECT 1 FROM pragma_table_info('vault_search') WHERE name = 'fields'")
+        .fetch_optional(pool)
+        .await?;
+    if fields_column.is_none() {
+        sqlx::query("DROP TABLE IF EXISTS vault_search")
+            .execute(pool)
+            .await?;
     }
 
-    Ok(accounts)
+    // Index plein texte des métadonnées, alimenté uniquement de jetons aveugles (voir `search.rs`)
+    sqlx::query(
+        "CREATE VIRTUAL TABLE IF NOT EXISTS vault_search USING fts5(
+            item_type UNINDEXED,
+            item_id UNINDEXED,
+            owner UNINDEXED,
+            group_name UNINDEXED,
+            title,
+            url,
+            fields,
+            tags,
+            notes
+        )"
+    )
+    .execute(pool)
+    .await?;
+
+    log::info!("Database tables initialized successfully");
+    Ok(())
 }
 
-pub async fn get_api_key_by_username(
+/// Ajoute une colonne à une table existante si elle n'y est pas encore
+async fn add_column_if_missing(
     pool: &SqlitePool,
-    username: &str,
-    crypto: &CryptoService,
-) -> Result<Vec<GetApiKeyResponse>, sqlx::Error> {
-    let rows = sqlx::query_as::<_, GetApiKeyResponse>(
-        r#"
-        SELECT id, username, title, api_key, created_at
-        FROM add_api_key
-        WHERE username = ?
-        "#
-    )
-    .bind(username)
-    .fetch_all(pool)
-    .await
-    .map_err(|e| {
-        log::error!("Database query failed for get_api_key_by_username: {:?}", e);
-        e
-    })?;
+    table: &str,
+    column: &str,
+    definition: &str,
+) -> Result<(), sqlx::Error> {
+    let exists = sqlx::query("SELECT 1 FROM pragma_table_info(?) WHERE name = ?")
+        .bind(table)
+        .bind(column)
+        .fetch_optional(pool)
+        .await?;
 
-    // Déchiffre chaque clé API
-    let mut api_keys = Vec::new();
-    for mut row in rows {
-        row.api_key = crypto.decode_and_decrypt(&row.api_key)
-            .map_err(|e| {
-                log::error!("Decryption failed for API key {}: {}", row.title, e);
-                sqlx::Error::Protocol(format!("Decryption failed: {}", e))
-            })?;
-        
-        api_keys.push(row);
+    if exists.is_none() {
+        sqlx::query(&format!("ALTER TABLE {} ADD COLUMN {} {}", table, column, definition))
+            .execute(pool)
+            .await?;
+        log::info!("Column '{}' added to table '{}'", column, table);
     }
 
-    Ok(api_keys)
+    Ok(())
+}
+
+// ==================== MÉTADONNÉES CHIFFRÉES ====================
+
+/// Métadonnées d'un élément prêtes à être stockées : champs chiffrés et index aveugles
+struct SealedMetadata {
+    title: String,
+    title_index: String,
+    url: String,
+    url_host_index: Option<String>,
+    notes: Option<String>,
+}
+
+fn seal_metadata(
+    crypto: &CryptoService,
+    title: &str,
+    url: &str,
+    notes: Option<&str>,
+) -> Result<SealedMetadata, sqlx::Error> {
+    let seal = |value: &str| {
+        crypto.seal_field(value)
+            .map_err(|e| sqlx::Error::Protocol(format!("Encryption failed: {}", e)))
+    };
+
+    Ok(SealedMetadata {
+        title: seal(title)?,
+        title_index: crypto.blind_index("title", title),
+        url: seal(url)?,
+        url_host_index: url_host(url).map(|host| crypto.blind_index("url_host", &host)),
+        notes: notes.map(seal).transpose()?,
+    })
+}
+
+/// Déchiffre un champ de métadonnées lu en base
+fn open_field(crypto: &CryptoService, value: &str) -> Result<String, sqlx::Error> {
+    crypto.open_field(value)
+        .map_err(|e| sqlx::Error::Protocol(format!("Decryption failed: {}", e)))
+}
+
+fn open_notes(crypto: &CryptoService, notes: Option<String>) -> Result<Option<String>, sqlx::Error> {
+    notes.map(|n| open_field(crypto, &n)).transpose()
+}
+
+/// Chiffre le contenu d'un élément (JSON) pour la colonne `payload` ou l'historique
+fn seal_payload(crypto: &CryptoService, fields: &Fields) -> Result<String, sqlx::Error> {
+    let json = serde_json::to_string(fields)
+        .map_err(|e| sqlx::Error::Protocol(format!("Serialization failed: {}", e)))?;
+    crypto.encrypt_and_encode(&json)
+        .map_err(|e| sqlx::Error::Protocol(format!("Encryption failed: {}", e)))
+}
+
+fn open_payload(crypto: &CryptoService, payload: &str) -> Result<Fields, sqlx::Error> {
+    let json = crypto.decode_and_decrypt(payload)
+        .map_err(|e| sqlx::Error::Protocol(format!("Decryption failed: {}", e)))?;
+    serde_json::from_str(&json)
+        .map_err(|e| sqlx::Error::Protocol(format!("Invalid item payload: {}", e)))
+}
+
+/// Schéma d'un type d'élément stocké (type intégré ou modèle) et sa portée ; `None` si le type est inconnu
+pub async fn item_schema<'e, E>(executor: E, item_type: &str) -> Result<Option<(Schema, bool)>, sqlx::Error>
+where
+    E: sqlx::Executor<'e, Database = Sqlite>,
+{
+    if let Some((kind, in_group)) = items::parse_item_type(item_type) {
+        return Ok(Some((Schema::Kind(kind), in_group)));
+    }
+
+    let (name, in_group) = items::split_item_type(item_type);
+    let definition: Option<String> = sqlx::query_scalar("SELECT fields FROM item_templates WHERE name = ?")
+        .bind(name)
+        .fetch_optional(executor)
+        .await?;
+
+    definition
+        .map(|definition| {
+            Ok((Schema::Template(Template { name: name.to_string(), fields: parse_template_fields(&definition)? }), in_group))
+        })
+        .transpose()
+}
+
+/// Schéma d'un type d'élément stocké, en erreur si le type est inconnu
+async fn schema_of<'e, E>(executor: E, item_type: &str) -> Result<Schema, sqlx::Error>
+where
+    E: sqlx::Executor<'e, Database = Sqlite>,
+{
+    item_schema(executor, item_type)
+        .await?
+        .map(|(schema, _)| schema)
+        .ok_or_else(|| sqlx::Error::Protocol(format!("Unknown item type: {}", item_type)))
+}
+
+// Tables des comptes et clés API d'avant `vault_items`, avec le type d'élément correspondant
+const LEGACY_TABLES: [(&str, &str); 4] = [
+    ("add_account", "account"),
+    ("add_api_key", "api_key"),
+    ("account_in_groups", "account_group"),
+    ("api_key_in_groups", "api_key_group"),
+];
+
+/// Reprend les comptes et clés API des anciennes tables dans `vault_items` (métadonnées chiffrées,
+/// secret converti en contenu JSON, historique compris) puis supprime ces tables.
+/// Reconstruit ensuite l'index de recherche si nécessaire
+pub async fn migrate_legacy_items(pool: &SqlitePool, crypto: &CryptoService) -> Result<(), sqlx::Error> {
+    let mut migrated = 0;
+
+    for (table, item_type) in LEGACY_TABLES {
+        let exists = sqlx::query("SELECT 1 FROM sqlite_master WHERE type = 'table' AND name = ?")
+            .bind(table)
+            .fetch_optional(pool)
+            .await?;
+        if exists.is_none() {
+            continue;
+        }
+
+        let is_account = item_type.starts_with("account");
+        let in_group = item_type.ends_with("_group");
+
+        // Les bases les plus anciennes n'ont pas toutes ces colonnes
+        for column in ["updated_at", "deleted_at", "deleted_by", "notes", "folder_id", "title_index"] {
+            add_column_if_missing(pool, table, column, "TEXT").await?;
+        }
+
+        let rows = sqlx::query(&format!(
+            "SELECT id, {owner} AS owner, {group} AS group_name, title, title_index, {url} AS url,
+                    {user_account} AS user_account, {secret} AS secret, notes, folder_id,
+                    created_at, updated_at, deleted_at, deleted_by
+             FROM {table}",
+            owner = if in_group { "NULL" } else { "username" },
+            group = if in_group { "group_name" } else { "NULL" },
+            url = if is_account { "url" } else { "''" },
+            user_account = if is_account { "user_account" } else { "''" },
+            secret = if is_account { "password_account" } else { "api_key" },
+        ))
+        .fetch_all(pool)
+        .await?;
+
+        let mut tx = pool.begin().await?;
+
+        for row in rows {
+            let id: String = row.try_get("id")?;
+
+            // Sans index aveugle, les métadonnées sont encore en clair
+            let encrypted = row.try_get::<Option<String>, _>("title_index")?.is_some();
+            let read = |column: &str| -> Result<String, sqlx::Error> {
+                let value: String = row.try_get(column)?;
+                if encrypted { open_field(crypto, &value) } else { Ok(value) }
+            };
+            let title = read("title")?;
+            let url = read("url")?;
+            let user_account = read("user_account")?;
+            let notes = row.try_get::<Option<String>, _>("notes")?
+                .map(|n| if encrypted { open_field(crypto, &n) } else { Ok(n) })
+                .transpose()?;
+
+            let to_payload = |encrypted_secret: &str| -> Result<String, sqlx::Error> {
+                let secret = crypto.decode_and_decrypt(encrypted_secret)
+                    .map_err(|e| sqlx::Error::Protocol(format!("Decryption failed for {} {}: {}", item_type, id, e)))?;
+                let fields = if is_account {
+                    items::fields(&[("user_account", &user_account), ("password", &secret)])
+                } else {
+                    items::fields(&[("api_key", &secret)])
+                };
+                seal_payload(crypto, &fields)
+            };
+
+            let payload = to_payload(&row.try_get::<String, _>("secret")?)?;
+            let sealed = seal_metadata(crypto, &title, &url, notes.as_deref())?;
+
+            sqlx::query(
+                "INSERT INTO vault_items (id, item_type, username, group_name, title, title_index, url, url_host_index, payload, notes, folder_id, created_at, updated_at, deleted_at, deleted_by)
+                 VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)"
+            )
+            .bind(&id)
+            .bind(item_type)
+            .bind(row.try_get::<Option<String>, _>("owner")?)
+            .bind(row.try_get::<Option<String>, _>("group_name")?)
+            .bind(&sealed.title)
+            .bind(&sealed.title_index)
+            .bind(&sealed.url)
+            .bind(&sealed.url_host_index)
+            .bind(&payload)
+            .bind(&sealed.notes)
+            .bind(row.try_get::<Option<String>, _>("folder_id")?)
+            .bind(row.try_get::<String, _>("created_at")?)
+            .bind(row.try_get::<Option<String>, _>("updated_at")?)
+            .bind(row.try_get::<Option<String>, _>("deleted_at")?)
+            .bind(row.try_get::<Option<String>, _>("deleted_by")?)
+            .execute(&mut *tx)
+            .await?;
+
+            let versions: Vec<(String, String)> = sqlx::query_as(
+                "SELECT id, secret FROM secret_versions WHERE item_type = ? AND item_id = ?"
+            )
+            .bind(item_type)
+            .bind(&id)
+            .fetch_all(&mut *tx)
+            .await?;
+
+            for (version_id, secret) in versions {
+                sqlx::query("UPDATE secret_versions SET secret = ? WHERE id = ?")
+                    .bind(to_payload(&secret)?)
+                    .bind(&version_id)
+                    .execute(&mut *tx)
+                    .await?;
+            }
+
+            migrated += 1;
+        }
+
+        sqlx::query(&format!("DROP TABLE {}", table))
+            .execute(&mut *tx)
+            .await?;
+        tx.commit().await?;
+
+        log::info!("Legacy table '{}' migrated to vault_items", table);
+    }
+
+    if migrated > 0 {
+        log::info!("Migrated {} legacy item(s)", migrated);
+    }
+
+    let indexed: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM vault_search")
+        .fetch_one(pool)
+        .await?;
+    if migrated > 0 || indexed == 0 {
+        rebuild_search_index(pool, crypto).await?;
+    }
+
+    Ok(())
+}
+
+// ==================== ÉLÉMENTS DU COFFRE ====================
+
+/// Élément à créer, personnel (`owner`) ou de groupe (`group_name`)
+pub struct NewItem<'a> {
+    pub schema: &'a Schema,
+    pub owner: Option<&'a str>,
+    pub group_name: Option<&'a str>,
+    pub title: &'a str,
+    pub url: &'a str,
+    pub notes: Option<&'a str>,
+    pub fields: &'a Fields,
+    // Échéances facultatives : date d'expiration et intervalle de rotation (jours)
+    pub expires_at: Option<&'a str>,
+    pub rotate_every: Option<i64>,
+    pub created_by: &'a str,
+}
+
+/// Crée un élément de n'importe quel type après validation de son contenu ;
+/// renvoie son identifiant, son type stocké et sa date de création
+pub async fn insert_item(
+    pool: &SqlitePool,
+    item: &NewItem<'_>,
+    crypto: &CryptoService,
+) -> Result<(String, String, String), sqlx::Error> {
+    let item_type = items::item_type(item.schema.name(), item.group_name.is_some());
+    let fields = items::validate(item.schema, item.fields).map_err(sqlx::Error::Protocol)?;
+    let expires_at = item.expires_at.map(expiry::parse_expires_at).transpose().map_err(sqlx::Error::Protocol)?;
+    let rotate_every = item.rotate_every.map(expiry::check_rotate_every).transpose().map_err(sqlx::Error::Protocol)?;
+
+    if let Some(group_name) = item.group_name {
+        if !group_exists(pool, group_name).await? {
+            return Err(sqlx::Error::Protocol("Group does not exist".into()));
+        }
+    }
+
+    let id = Uuid::new_v4().to_string();
+    let created_at = Utc::now().to_rfc3339();
+
+    let payload = seal_payload(crypto, &fields)?;
+    let sealed = seal_metadata(crypto, item.title, item.url, item.notes)?;
+
+    let mut tx = pool.begin().await?;
+
+    sqlx::query(
+        "INSERT INTO vault_items (id, item_type, username, group_name, title, title_index, url, url_host_index, payload, notes, created_at, expires_at, rotate_every, rotated_at)
+         VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)"
+    )
+    .bind(&id)
+    .bind(&item_type)
+    .bind(if item.group_name.is_some() { None } else { item.owner })
+    .bind(item.group_name)
+    .bind(&sealed.title)
+    .bind(&sealed.title_index)
+    .bind(&sealed.url)
+    .bind(&sealed.url_host_index)
+    .bind(&payload)
+    .bind(&sealed.notes)
+    .bind(&created_at)
+    .bind(&expires_at)
+    .bind(rotate_every)
+    .bind(&created_at)
+    .execute(&mut *tx)
+    .await?;
+
+    record_secret_version(&mut tx, &item_type, &id, &payload, "create", item.created_by, &created_at).await?;
+    index_item(&mut tx, &item_type, &id, crypto).await?;
+    tx.commit().await?;
+
+    Ok((id, item_type, created_at))
+}
+
+/// Place un élément personnel dans la corbeille (suppression logique)
+pub async fn trash_personal_item(
+    pool: &SqlitePool,
+    item_type: &str,
+    id: &str,
+    username: &str,
+) -> Result<u64, sqlx::Error> {
+    let mut tx = pool.begin().await?;
+
+    let result = sqlx::query(
+        "UPDATE vault_items SET deleted_at = ?, deleted_by = ?
+         WHERE id = ? AND item_type = ? AND username = ? AND group_name IS NULL AND deleted_at IS NULL"
+    )
+    .bind(Utc::now().to_rfc3339())
+    .bind(username)
+    .bind(id)
+    .bind(item_type)
+    .bind(username)
+    .execute(&mut *tx)
+    .await?;
+
+    unindex_item(&mut tx, item_type, id).await?;
+    tx.commit().await?;
+
+    Ok(result.rows_affected())
+}
+
+/// Place un élément de groupe dans la corbeille et l'inscrit au journal d'audit
+pub async fn trash_group_item(
+    pool: &SqlitePool,
+    item_type: &str,
+    id: &str,
+    username: &str,
+) -> Result<u64, sqlx::Error> {
+    let mut tx = pool.begin().await?;
+
+    let group_name: Option<String> = sqlx::query_scalar(
+        "UPDATE vault_items SET deleted_at = ?, deleted_by = ?
+         WHERE id = ? AND item_type = ? AND group_name IS NOT NULL AND deleted_at IS NULL
+         RETURNING group_name"
+    )
+    .bind(Utc::now().to_rfc3339())
+    .bind(username)
+    .bind(id)
+    .bind(item_type)
+    .fetch_optional(&mut *tx)
+    .await?;
+
+    let Some(group_name) = group_name else {
+        return Ok(0);
+    };
+
+    unindex_item(&mut tx, item_type, id).await?;
+    let details = format!("{} {} moved to trash", item_type, id);
+    record_audit(&mut *tx, username, "trash_item", Some("group"), Some(&group_name), &details).await?;
+    tx.commit().await?;
+
+    Ok(1)
+}
+
+/// Nombre d'éléments personnels d'un utilisateur : comptes, clés API et éléments d'autres types
+async fn count_personal_items(
+    conn: &mut SqliteConnection,
+    username: &str,
+) -> Result<(u64, u64, u64), sqlx::Error> {
+    let rows: Vec<(String, i64)> = sqlx::query_as(
+        "SELECT item_type, COUNT(*) FROM vault_items WHERE username = ? AND group_name IS NULL GROUP BY item_type"
+    )
+    .bind(username)
+    .fetch_all(&mut *conn)
+    .await?;
+
+    let mut counts = (0, 0, 0);
+    for (item_type, count) in rows {
+        match item_type.as_str() {
+            "account" => counts.0 += count as u64,
+            "api_key" => counts.1 += count as u64,
+            _ => counts.2 += count as u64,
+        }
+    }
+
+    Ok(counts)
+}
+
+/// Supprime un utilisateur et traite ses données dans une seule transaction :
+/// ses éléments sont supprimés ou transférés, ses adhésions retirées et ses sessions révoquées
+pub async fn delete_user(
+    pool: &SqlitePool,
+    id: &str,
+    username: &str,
+    transfer: &ItemTransfer,
+) -> Result<DeleteUserResponse, sqlx::Error> {
+    let mut tx = pool.begin().await?;
+    let mut response = DeleteUserResponse {
+        id: id.to_string(),
+        username: username.to_string(),
+        ..Default::default()
+    };
+
+    let (accounts, api_keys, others) = count_personal_items(&mut tx, username).await?;
+
+    // Partages reçus par l'utilisateur, puis ceux de ses éléments qui ne restent pas personnels
+    // (ou qui deviennent ceux du destinataire du transfert)
+    sqlx::query("DELETE FROM item_shares WHERE username = ?")
+        .bind(username)
+        .execute(&mut *tx)
+        .await?;
+
+    let keep_shares_except = match transfer {
+        ItemTransfer::ToUser(new_owner) => Some(new_owner.as_str()),
+        _ => None,
+    };
+    sqlx::query(
+        "DELETE FROM item_shares
+         WHERE item_id IN (SELECT id FROM vault_items WHERE username = ? AND group_name IS NULL)
+           AND (? IS NULL OR username = ?)"
+    )
+    .bind(username)
+    .bind(keep_shares_except)
+    .bind(keep_shares_except)
+    .execute(&mut *tx)
+    .await?;
+
+    match transfer {
+        ItemTransfer::Delete => {
+            for dependent in ["secret_versions", "item_tags", "attachments"] {
+                sqlx::query(&format!(
+                    "DELETE FROM {} WHERE item_id IN (SELECT id FROM vault_items WHERE username = ? AND group_name IS NULL)",
+                    dependent
+                ))
+                .bind(username)
+                .execute(&mut *tx)
+                .await?;
+            }
+
+            sqlx::query("DELETE FROM vault_items WHERE username = ? AND group_name IS NULL")
+                .bind(username)
+                .execute(&mut *tx)
+                .await?;
+
+            response.accounts_deleted = accounts;
+            response.api_keys_deleted = api_keys;
+            response.other_items_deleted = others;
+
+            sqlx::query("DELETE FROM folders WHERE owner = ?")
+                .bind(username)
+                .execute(&mut *tx)
+                .await?;
+        }
+        ItemTransfer::ToUser(new_owner) => {
+            sqlx::query("UPDATE vault_items SET username = ? WHERE username = ? AND group_name IS NULL")
+                .bind(new_owner)
+                .bind(username)
+                .execute(&mut *tx)
+                .await?;
+
+            // Les dossiers suivent leurs éléments
+            sqlx::query("UPDATE folders SET owner = ? WHERE owner = ?")
+                .bind(new_owner)
+                .bind(username)
+                .execute(&mut *tx)
+                .await?;
+
+            response.accounts_transferred = accounts;
+            response.api_keys_transferred = api_keys;
+            response.other_items_transferred = others;
+            response.transferred_to = Some(format!("user:{}", new_owner));
+        }
+        ItemTransfer::ToGroup(group_name) => {
+            // Les identifiants sont conservés pour que l'historique et les tags suivent l'élément
+            for dependent in ["secret_versions", "item_tags", "attachments"] {
+                sqlx::query(&format!(
+                    "UPDATE {} SET item_type = item_type || '_group'
+                     WHERE item_id IN (SELECT id FROM vault_items WHERE username = ? AND group_name IS NULL)",
+                    dependent
+                ))
+                .bind(username)
+                .execute(&mut *tx)
+                .await?;
+            }
+
+            sqlx::query(
+                "UPDATE vault_items SET item_type = item_type || '_group', username = NULL, group_name = ?
+                 WHERE username = ? AND group_name IS NULL"
+            )
+            .bind(group_name)
+            .bind(username)
+            .execute(&mut *tx)
+            .await?;
+
+            sqlx::query("UPDATE folders SET owner = NULL, group_name = ? WHERE owner = ?")
+                .bind(group_name)
+                .bind(username)
+                .execute(&mut *tx)
+                .await?;
+
+            response.accounts_transferred = accounts;
+            response.api_keys_transferred = api_keys;
+            response.other_items_transferred = others;
+            response.transferred_to = Some(format!("group:{}", group_name));
+        }
+    }
+
+    // L'index de recherche suit le devenir des éléments
+    match transfer {
+        ItemTransfer::Delete => {
+            sqlx::query("DELETE FROM vault_search WHERE owner = ?")
+                .bind(username)
+                .execute(&mut *tx)
+                .await?;
+        }
+        ItemTransfer::ToUser(new_owner) => {
+            sqlx::query("UPDATE vault_search SET owner = ? WHERE owner = ?")
+                .bind(new_owner)
+                .bind(username)
+                .execute(&mut *tx)
+                .await?;
+        }
+        ItemTransfer::ToGroup(group_name) => {
+            sqlx::query(
+                "UPDATE vault_search SET item_type = item_type || '_group', owner = NULL, group_name = ? WHERE owner = ?"
+            )
+            .bind(group_name)
+            .bind(username)
+            .execute(&mut *tx)
+            .await?;
+        }
+    }
+
+    sqlx::query("DELETE FROM secret_links WHERE created_by = ?")
+        .bind(username)
+        .execute(&mut *tx)
+        .await?;
+
+    sqlx::query("DELETE FROM access_requests WHERE username = ?")
+        .bind(username)
+        .execute(&mut *tx)
+        .await?;
+
+    // Contacts et demandes d'urgence visant son coffre ou dont il est l'auteur
+    sqlx::query(
+        "DELETE FROM emergency_contacts WHERE contact = ? OR (target_type = 'user' AND target_name = ?)"
+    )
+    .bind(username)
+    .bind(username)
+    .execute(&mut *tx)
+    .await?;
+
+    sqlx::query(
+        "DELETE FROM emergency_requests WHERE requester = ? OR (target_type = 'user' AND target_name = ?)"
+    )
+    .bind(username)
+    .bind(username)
+    .execute(&mut *tx)
+    .await?;
+
+    // Les identifiants empruntés par l'utilisateur sont rendus ; l'historique est conservé
+    sqlx::query(
+        "UPDATE item_leases SET status = 'released', returned_at = ?, returned_by = 'system'
+         WHERE username = ? AND status = 'active'"
+    )
+    .bind(Utc::now().to_rfc3339())
+    .bind(username)
+    .execute(&mut *tx)
+    .await?;
+
+    response.memberships_removed = sqlx::query("DELETE FROM user_groups WHERE username = ?")
+        .bind(username)
+        .execute(&mut *tx)
+        .await?
+        .rows_affected();
+
+    revoke_sessions(&mut tx, id).await?;
+    response.sessions_revoked = true;
+
+    let result = sqlx::query("DELETE FROM users WHERE id = ?")
+        .bind(id)
+        .execute(&mut *tx)
+        .await?;
+
+    if result.rows_affected() == 0 {
+        return Err(sqlx::Error::RowNotFound);
+    }
+
+    tx.commit().await?;
+    Ok(response)
+}
+
+/// Récupère le nom d'un utilisateur à partir de son ID
+pub async fn find_username_by_id(
+    pool: &SqlitePool,
+    id: &str,
+) -> Result<Option<String>, sqlx::Error> {
+    sqlx::query_scalar("SELECT username FROM users WHERE id = ?")
+        .bind(id)
+        .fetch_optional(pool)
+        .await
+}
+
+/// Vérifie qu'un utilisateur existe
+pub async fn user_exists(
+    pool: &SqlitePool,
+    username: &str,
+) -> Result<bool, sqlx::Error> {
+    let row = sqlx::query("SELECT 1 FROM users WHERE username = ?")
+        .bind(username)
+        .fetch_optional(pool)
+        .await?;
+
+    Ok(row.is_some())
+}
+
+/// Vérifie qu'un groupe existe (au moins un membre)
+pub async fn group_exists(
+    pool: &SqlitePool,
+    group_name: &str,
+) -> Result<bool, sqlx::Error> {
+    let row = sqlx::query("SELECT 1 FROM user_groups WHERE group_name = ? LIMIT 1")
+        .bind(group_name)
+        .fetch_optional(pool)
+        .await?;
+
+    Ok(row.is_some())
+}
+
+/// Éléments personnels dont le titre est déjà pris par un élément de groupe du même type (les titres y sont uniques)
+pub async fn group_transfer_conflicts(
+    pool: &SqlitePool,
+    username: &str,
+    crypto: &CryptoService,
+) -> Result<Vec<(String, String)>, sqlx::Error> {
+    let rows: Vec<(String, String)> = sqlx::query_as(
+        "SELECT p.item_type, p.title FROM vault_items p
+         WHERE p.username = ? AND p.group_name IS NULL
+           AND EXISTS (
+               SELECT 1 FROM vault_items g
+               WHERE g.item_type = p.item_type || '_group' AND g.title_index = p.title_index
+           )"
+    )
+    .bind(username)
+    .fetch_all(pool)
+    .await?;
+
+    let mut conflicts = rows
+        .into_iter()
+        .map(|(item_type, title)| Ok((item_type, open_field(crypto, &title)?)))
+        .collect::<Result<Vec<_>, sqlx::Error>>()?;
+    conflicts.sort_by(|a, b| a.1.cmp(&b.1));
+    Ok(conflicts)
+}
+
+// ==================== MODÈLES D'ÉLÉMENTS ====================
+
+fn parse_template_fields(definition: &str) -> Result<Vec<TemplateField>, sqlx::Error> {
+    serde_json::from_str(definition)
+        .map_err(|e| sqlx::Error::Protocol(format!("Invalid template definition: {}", e)))
+}
+
+fn template_from_row(mut template: ItemTemplate) -> Result<ItemTemplate, sqlx::Error> {
+    template.fields = parse_template_fields(&template.definition)?;
+    Ok(template)
+}
+
+/// Liste les modèles d'éléments
+pub async fn get_templates(pool: &SqlitePool) -> Result<Vec<ItemTemplate>, sqlx::Error> {
+    sqlx::query_as::<_, ItemTemplate>(
+        "SELECT name, label, fields AS definition, created_by, created_at, updated_at FROM item_templates ORDER BY name"
+    )
+    .fetch_all(pool)
+    .await?
+    .into_iter()
+    .map(template_from_row)
+    .collect()
+}
+
+/// Crée un modèle après vérification de sa définition
+pub async fn create_template(
+    pool: &SqlitePool,
+    name: &str,
+    label: &str,
+    fields: &[TemplateField],
+    created_by: &str,
+) -> Result<ItemTemplate, sqlx::Error> {
+    items::check_template(name, fields).map_err(sqlx::Error::Protocol)?;
+    let definition = serde_json::to_string(fields)
+        .map_err(|e| sqlx::Error::Protocol(format!("Serialization failed: {}", e)))?;
+    let created_at = Utc::now().to_rfc3339();
+
+    sqlx::query(
+        "INSERT INTO item_templates (name, label, fields, created_by, created_at) VALUES (?, ?, ?, ?, ?)"
+    )
+    .bind(name)
+    .bind(label)
+    .bind(&definition)
+    .bind(created_by)
+    .bind(&created_at)
+    .execute(pool)
+    .await?;
+
+    Ok(ItemTemplate {
+        name: name.to_string(),
+        label: label.to_string(),
+        fields: fields.to_vec(),
+        definition,
+        created_by: created_by.to_string(),
+        created_at,
+        updated_at: None,
+    })
+}
+
+/// Remplace le libellé et les champs d'un modèle puis réindexe ses éléments (un champ devenu secret
+/// quitte l'index). Les éléments ne sont revalidés qu'à leur prochaine modification ; les champs
+/// retirés disparaissent alors de leur contenu
+pub async fn update_template(
+    pool: &SqlitePool,
+    name: &str,
+    label: &str,
+    fields: &[TemplateField],
+    crypto: &CryptoService,
+) -> Result<ItemTemplate, sqlx::Error> {
+    items::check_template(name, fields).map_err(sqlx::Error::Protocol)?;
+    let definition = serde_json::to_string(fields)
+        .map_err(|e| sqlx::Error::Protocol(format!("Serialization failed: {}", e)))?;
+
+    let mut tx = pool.begin().await?;
+
+    let template = sqlx::query_as::<_, ItemTemplate>(
+        "UPDATE item_templates SET label = ?, fields = ?, updated_at = ? WHERE name = ?
+         RETURNING name, label, fields AS definition, created_by, created_at, updated_at"
+    )
+    .bind(label)
+    .bind(&definition)
+    .bind(Utc::now().to_rfc3339())
+    .bind(name)
+    .fetch_optional(&mut *tx)
+    .await?
+    .ok_or(sqlx::Error::RowNotFound)?;
+
+    let item_types = [name.to_string(), items::item_type(name, true)];
+    for item_type in &item_types {
+        let ids: Vec<String> = sqlx::query_scalar("SELECT id FROM vault_items WHERE item_type = ? AND deleted_at IS NULL")
+            .bind(item_type)
+            .fetch_all(&mut *tx)
+            .await?;
+        for id in ids {
+            index_item(&mut tx, item_type, &id, crypto).await?;
+        }
+    }
+
+    tx.commit().await?;
+
+    template_from_row(template)
+}
+
+/// Nombre d'éléments (corbeille comprise) créés à partir d'un modèle
+pub async fn count_template_items(pool: &SqlitePool, name: &str) -> Result<i64, sqlx::Error> {
+    sqlx::query_scalar("SELECT COUNT(*) FROM vault_items WHERE item_type IN (?, ?)")
+        .bind(name)
+        .bind(items::item_type(name, true))
+        .fetch_one(pool)
+        .await
+}
+
+/// Supprime un modèle ; renvoie le nombre de lignes supprimées
+pub async fn delete_template(pool: &SqlitePool, name: &str) -> Result<u64, sqlx::Error> {
+    let result = sqlx::query("DELETE FROM item_templates WHERE name = ?")
+        .bind(name)
+        .execute(pool)
+        .await?;
+
+    Ok(result.rows_affected())
+}
+
+// ==================== SESSIONS ====================
+
+/// Invalide tous les tokens émis jusqu'ici pour un utilisateur
+async fn revoke_sessions(
+    tx: &mut Transaction<'_, Sqlite>,
+    user_id: &str,
+) -> Result<(), sqlx::Error> {
+    sqlx::query(
+        "INSERT INTO session_revocations (user_id, revoked_at) VALUES (?, ?)
+         ON CONFLICT(user_id) DO UPDATE SET revoked_at = excluded.revoked_at"
+    )
+    .bind(user_id)
+    .bind(Utc::now().timestamp())
+    .execute(&mut **tx)
+    .await?;
+
+    Ok(())
+}
+
+/// Un token est révoqué s'il a été émis avant (ou pendant) la dernière révocation de l'utilisateur
+pub async fn is_session_revoked(
+    pool: &SqlitePool,
+    user_id: &str,
+    issued_at: i64,
+) -> Result<bool, sqlx::Error> {
+    let row = sqlx::query("SELECT 1 FROM session_revocations WHERE user_id = ? AND revoked_at >= ?")
+        .bind(user_id)
+        .bind(issued_at)
+        .fetch_optional(pool)
+        .await?;
+
+    Ok(row.is_some())
+}
+
+/// Liste les comptes d'un utilisateur (métadonnées uniquement, voir `reveal_item`)
+pub async fn get_account_by_username(
+    pool: &SqlitePool,
+    username: &str,
+    list: &ListQuery,
+    crypto: &CryptoService,
+) -> Result<Page<GetAccountResponse>, sqlx::Error> {
+    let mut page = list.fetch_page::<GetAccountResponse>(
+        pool,
+        "id, username, title, url, payload, notes, folder_id, created_at, updated_at",
+        "FROM vault_items WHERE item_type = 'account' AND username = ? AND group_name IS NULL AND deleted_at IS NULL",
+        &[username],
+    )
+    .await
+    .map_err(|e| {
+        log::error!("Database query failed for get_account_by_username: {:?}", e);
+        e
+    })?;
+
+    let ids: Vec<&str> = page.items.iter().map(|item| item.id.as_str()).collect();
+    let mut tags = load_tags(pool, &ids, crypto).await?;
+
+    for item in &mut page.items {
+        item.title = open_field(crypto, &item.title)?;
+        item.user_account = items::text(&open_payload(crypto, &item.payload)?, "user_account").to_string();
+        item.url = open_field(crypto, &item.url)?;
+        item.notes = open_notes(crypto, item.notes.take())?;
+        item.tags = tags.remove(&item.id).unwrap_or_default();
+    }
+
+    Ok(page)
+}
+
+/// Liste les clés API d'un utilisateur (métadonnées uniquement)
+pub async fn get_api_key_by_username(
+    pool: &SqlitePool,
+    username: &str,
+    list: &ListQuery,
+    crypto: &CryptoService,
+) -> Result<Page<GetApiKeyResponse>, sqlx::Error> {
+    let mut page = list.fetch_page::<GetApiKeyResponse>(
+        pool,
+        "id, username, title, notes, folder_id, created_at, updated_at",
+        "FROM vault_items WHERE item_type = 'api_key' AND username = ? AND group_name IS NULL AND deleted_at IS NULL",
+        &[username],
+    )
+    .await
+    .map_err(|e| {
+        log::error!("Database query failed for get_api_key_by_username: {:?}", e);
+        e
+    })?;
+
+    let ids: Vec<&str> = page.items.iter().map(|item| item.id.as_str()).collect();
+    let mut tags = load_tags(pool, &ids, crypto).await?;
+
+    for item in &mut page.items {
+        item.title = open_field(crypto, &item.title)?;
+        item.notes = open_notes(crypto, item.notes.take())?;
+        item.tags = tags.remove(&item.id).unwrap_or_default();
+    }
+
+    Ok(page)
+}
+
+/// Liste les éléments du coffre personnel de `username`, ou de celui d'un groupe, éventuellement
+/// d'un seul type. Seuls les champs non secrets du contenu sont renvoyés (voir `reveal_item`)
+pub async fn get_items(
+    pool: &SqlitePool,
+    username: &str,
+    group_name: Option<&str>,
+    item_type: Option<&str>,
+    list: &ListQuery,
+    crypto: &CryptoService,
+) -> Result<Page<VaultItemSummary>, sqlx::Error> {
+    let mut from_sql = String::from("FROM vault_items WHERE deleted_at IS NULL");
+    let mut binds = Vec::new();
+
+    match group_name {
+        Some(group_name) => {
+            from_sql.push_str(" AND group_name = ?");
+            binds.push(group_name);
+        }
+        None => {
+            from_sql.push_str(" AND username = ? AND group_name IS NULL");
+            binds.push(username);
+        }
+    }
+    if let Some(item_type) = item_type {
+        from_sql.push_str(" AND item_type = ?");
+        binds.push(item_type);
+    }
+
+    let mut page = list.fetch_page::<VaultItemSummary>(
+        pool,
+        "id, item_type, username, group_name, title, url, payload, notes, folder_id, created_at, updated_at,
+         expires_at, rotate_every, COALESCE(rotated_at, updated_at, created_at) AS rotated_at",
+        &from_sql,
+        &binds,
+    )
+    .await
+    .map_err(|e| {
+        log::error!("Database query failed for get_items: {:?}", e);
+        e
+    })?;
+
+    let ids: Vec<&str> = page.items.iter().map(|item| item.id.as_str()).collect();
+    let mut tags = load_tags(pool, &ids, crypto).await?;
+    let mut schemas: HashMap<String, Schema> = HashMap::new();
+
+    for item in &mut page.items {
+        if !schemas.contains_key(&item.item_type) {
+            schemas.insert(item.item_type.clone(), schema_of(pool, &item.item_type).await?);
+        }
+        let schema = &schemas[&item.item_type];
+        item.title = open_field(crypto, &item.title)?;
+        item.url = open_field(crypto, &item.url)?;
+        item.notes = open_notes(crypto, item.notes.take())?;
+        item.fields = items::public_fields(schema, &open_payload(crypto, &item.payload)?);
+        item.tags = tags.remove(&item.id).unwrap_or_default();
+    }
+
+    Ok(page)
+}
+
+/// Liste les utilisateurs sans leurs données sensibles (hash du mot de passe)
+pub async fn get_all_accounts(
+    pool: &SqlitePool,
+    list: &ListQuery,
+) -> Result<Page<UserSummary>, sqlx::Error> {
+    list.fetch_page::<UserSummary>(
+        pool,
+        "id, username, created_at",
+        "FROM users WHERE 1 = 1",
+        &[],
+    )
+    .await
+    .map_err(|e| {
+        log::error!("Database query failed for get_all_accounts: {:?}", e);
+        e
+    })
+}
+
+pub async fn create_group(
+    pool: &SqlitePool,
+    body: CreateGroupRequest,
+) -> Result<CreateGroupResponse, String> {
+    let group_name = body.group_name.trim();
+    
+    // Vérification du nom vide
+    if group_name.is_empty() {
+        return Err("Le nom du groupe ne peut pas être vide.".to_string());
+    }
+    
+    // Vérification si le groupe existe déjà
+    let check = sqlx::query("SELECT id FROM user_groups WHERE group_name = ?")
+        .bind(group_name)
+        .fetch_optional(pool)
+        .await
+        .map_err(|e| e.to_string())?;
+    
+    if check.is_some() {
+        return Err("Le groupe existe déjà".to_string());
+    }
+
+    if body.owners.iter().any(|owner| !body.usernames.contains(owner)) {
+        return Err("Les propriétaires doivent faire partie des membres du groupe.".to_string());
+    }
+    
+    let group_id = Uuid::new_v4().to_string();
+    let created_at = Utc::now().to_rfc3339();
+    
+    // Insertion des utilisateurs dans le groupe
+    for username in &body.usernames {
+        let id = Uuid::new_v4().to_string();
+        let owner = body.owners.contains(username);
+        let role = if owner { GroupRole::Owner } else { GroupRole::Editor };
+        sqlx::query("INSERT INTO user_groups (id, username, group_name, role, original_owner, created_at) VALUES (?, ?, ?, ?, ?, ?)")
+            .bind(&id)
+            .bind(username)
+            .bind(group_name)
+            .bind(role.as_str())
+            .bind(owner)
+            .bind(&created_at)
+            .execute(pool)
+            .await
+            .map_err(|e| e.to_string())?;
+    }
+    
+    Ok(CreateGroupResponse {
+        id: group_id,
+        created_at,
+        message: "Groupe créé avec succès".to_string(),
+    })
+}
+
+pub async fn add_account(
+    pool: &SqlitePool, 
+    body: AddUserGroups
+) -> Result<AddResponseGroups, String> {
+    // Vérifie si le groupe existe
+    let group_exists = sqlx::query("SELECT group_name FROM user_groups WHERE group_name = ?")
+        .bind(&body.group_name)
+        .fetch_optional(pool)
+        .await
+        .map_err(|e| e.to_string())?;
+    
+    if group_exists.is_none() {
+        return Err("Le groupe n'existe pas.".to_string());
+    }
+    
+    // Vérifie si l'utilisateur est déjà dans le groupe
+    let user_exists = sqlx::query("SELECT id FROM user_groups WHERE group_name = ? AND username = ?")
+        .bind(&body.group_name)
+        .bind(&body.username)
+        .fetch_optional(pool)
+        .await
+        .map_err(|e| e.to_string())?;
+    
+    if user_exists.is_some() {
+        return Err("L'utilisateur est déjà dans le groupe".to_string());
+    }
+    
+    // Ajoute l'utilisateur dans le groupe
+    let id = Uuid::new_v4().to_string();
+    let now = Utc::now();
+    let created_at = now.to_rfc3339();
+    let expires_at = body.expires_in_hours.map(|hours| (now + chrono::Duration::hours(hours)).to_rfc3339());
+    
+    sqlx::query("INSERT INTO user_groups (id, username, group_name, role, created_at, expires_at) VALUES (?, ?, ?, ?, ?, ?)")
+        .bind(&id)
+        .bind(&body.username)
+        .bind(&body.group_name)
+        .bind(body.role.as_str())
+        .bind(&created_at)
+        .bind(&expires_at)
+        .execute(pool)
+        .await
+        .map_err(|e| e.to_string())?;
+    
+    Ok(AddResponseGroups {
+        id,
+        username: body.username,
+        group_name: body.group_name,
+        created_at,
+        expires_at,
+        message: "Utilisateur ajouté au groupe avec succès".to_string(),
+    })
+}
+
+pub async fn fetch_groups(
+    pool: &SqlitePool,
+    list: &ListQuery,
+) -> Result<Page<GetAllGroups>, sqlx::Error> {
+    list.fetch_page::<GetAllGroups>(
+        pool,
+        "name, member_count, created_at, description, NULL AS role, parent_group",
+        r#"FROM (
+            SELECT 
+                group_name AS name,
+                COUNT(DISTINCT username) AS member_count,
+                MIN(created_at) AS created_at,
+                '' AS description,
+                (SELECT p.parent_name FROM group_parents p WHERE p.group_name = user_groups.group_name) AS parent_group
+            FROM user_groups
+            GROUP BY group_name
+        ) WHERE 1 = 1"#,
+        &[],
+    )
+    .await
+    .map_err(|e| {
+        log::error!("Database query failed for fetch_groups: {:?}", e);
+        e
+    })
+}
+
+pub async fn delete_groups(pool: &SqlitePool, body: DeleteGroups) -> Result<DeleteGroups, String> {
+    // Vérifie si le groupe existe
+    let group_exists = sqlx::query("SELECT group_name FROM user_groups WHERE group_name = ?")
+        .bind(&body.group_name)
+        .fetch_optional(pool)
+        .await
+        .map_err(|e| e.to_string())?;
+    
+    if group_exists.is_none() {
+        return Err("Le groupe n'existe pas.".to_string());
+    }
+    
+    // Supprime tous les utilisateurs du groupe
+    let result = sqlx::query("DELETE FROM user_groups WHERE group_name = ?")
+        .bind(&body.group_name)
+        .execute(pool)
+        .await
+        .map_err(|e| e.to_string())?;
+    
+    if result.rows_affected() == 0 {
+        return Err("Aucun membre trouvé dans le groupe.".to_string());
+    }
+
+    sqlx::query("DELETE FROM group_policies WHERE group_name = ?")
+        .bind(&body.group_name)
+        .execute(pool)
+        .await
+        .map_err(|e| e.to_string())?;
+
+    sqlx::query("DELETE FROM access_requests WHERE group_name = ?")
+        .bind(&body.group_name)
+        .execute(pool)
+        .await
+        .map_err(|e| e.to_string())?;
+
+    for table in ["emergency_contacts", "emergency_requests"] {
+        sqlx::query(&format!("DELETE FROM {} WHERE target_type = 'group' AND target_name = ?", table))
+            .bind(&body.group_name)
+            .execute(pool)
+            .await
+            .map_err(|e| e.to_string())?;
+    }
+
+    // Les sous-groupes deviennent des groupes racines
+    sqlx::query("DELETE FROM group_parents WHERE group_name = ? OR parent_name = ?")
+        .bind(&body.group_name)
+        .bind(&body.group_name)
+        .execute(pool)
+        .await
+        .map_err(|e| e.to_string())?;
+    
+    log::info!("Groupe '{}' supprimé avec {} membre(s)", body.group_name, result.rows_affected());
+    
+    Ok(body)
+}
+
+/// Groupes d'un utilisateur, directs ou hérités d'un groupe parent, avec le rôle effectif qu'il y occupe
+pub async fn get_groups_by_username(
+    pool: &SqlitePool,
+    username: &str,
+    list: &ListQuery,
+) -> Result<Page<GetAllGroups>, sqlx::Error> {
+    let now = Utc::now().to_rfc3339();
+    list.fetch_page::<GetAllGroups>(
+        pool,
+        "name, member_count, created_at, description, role, parent_group",
+        r#"FROM (
+            WITH RECURSIVE reachable(group_name, rank) AS (
+                SELECT group_name, CASE role WHEN 'owner' THEN 3 WHEN 'editor' THEN 2 ELSE 1 END
+                FROM user_groups WHERE username = ? AND (expires_at IS NULL OR expires_at > ?)
+                UNION
+                SELECT p.group_name, r.rank
+                FROM group_parents p JOIN reachable r ON p.parent_name = r.group_name
+            )
+            SELECT
+                r.group_name AS name,
+                (SELECT COUNT(DISTINCT m.username) FROM user_groups m
+                 WHERE m.group_name = r.group_name AND (m.expires_at IS NULL OR m.expires_at > ?)) AS member_count,
+                (SELECT MIN(m.created_at) FROM user_groups m WHERE m.group_name = r.group_name) AS created_at,
+                '' AS description,
+                CASE MAX(r.rank) WHEN 3 THEN 'owner' WHEN 2 THEN 'editor' WHEN 1 THEN 'viewer' END AS role,
+                (SELECT p.parent_name FROM group_parents p WHERE p.group_name = r.group_name) AS parent_group
+            FROM reachable r
+            GROUP BY r.group_name
+        ) WHERE 1 = 1"#,
+        &[username, &now, &now],
+    )
+    .await
+    .map_err(|e| {
+        log::error!("Database query failed for get_groups_by_username: {:?}", e);
+        e
+    })
+}
+
+fn parse_role(role: &str) -> Result<GroupRole, sqlx::Error> {
+    GroupRole::parse(role).ok_or_else(|| sqlx::Error::Protocol(format!("Invalid group role '{}'", role)))
+}
+
+// Groupes accessibles à un utilisateur (`?`) : ceux dont il est membre et tous leurs descendants.
+// Une adhésion temporaire échue (`?` : maintenant) ne donne plus accès
+const REACHABLE_GROUPS: &str = "WITH RECURSIVE reachable(name) AS (
+        SELECT group_name FROM user_groups WHERE username = ? AND (expires_at IS NULL OR expires_at > ?)
+        UNION
+        SELECT p.group_name FROM group_parents p JOIN reachable r ON p.parent_name = r.name
+    ) SELECT name FROM reachable";
+
+// Groupes dont un utilisateur (`?`) est propriétaire, directement ou par héritage, à l'instant `?`
+const OWNED_GROUPS: &str = "WITH RECURSIVE owned(name) AS (
+        SELECT group_name FROM user_groups WHERE username = ? AND role = 'owner' AND (expires_at IS NULL OR expires_at > ?)
+        UNION
+        SELECT p.group_name FROM group_parents p JOIN owned o ON p.parent_name = o.name
+    ) SELECT name FROM owned";
+
+// Un groupe (`?`) et tous ses ancêtres, du plus proche au plus lointain
+const GROUP_LINEAGE: &str = "WITH RECURSIVE lineage(name, depth) AS (
+        SELECT ?, 0
+        UNION
+        SELECT p.parent_name, l.depth + 1 FROM group_parents p JOIN lineage l ON p.group_name = l.name
+    )";
+
+/// Rôle effectif d'un utilisateur dans un groupe : le plus élevé entre son adhésion directe et
+/// celles des groupes parents ; `None` s'il n'en est membre ni directement ni par héritage.
+/// Une adhésion temporaire échue ne compte plus, même avant son retrait par la tâche d'expiration
+pub async fn group_role(
+    pool: &SqlitePool,
+    group_name: &str,
+    username: &str,
+) -> Result<Option<GroupRole>, sqlx::Error> {
+    let roles: Vec<String> = sqlx::query_scalar(&format!(
+        "{GROUP_LINEAGE} SELECT ug.role FROM user_groups ug JOIN lineage l ON ug.group_name = l.name
+         WHERE ug.username = ? AND (ug.expires_at IS NULL OR ug.expires_at > ?)"
+    ))
+    .bind(group_name)
+    .bind(username)
+    .bind(Utc::now().to_rfc3339())
+    .fetch_all(pool)
+    .await?;
+
+    let roles = roles.iter().map(|role| parse_role(role)).collect::<Result<Vec<_>, _>>()?;
+    Ok(roles.into_iter().max())
+}
+
+/// Membres d'un groupe et leurs rôles effectifs, y compris ceux hérités des groupes parents
+/// (`inherited_from` : groupe d'où vient le rôle retenu)
+pub async fn get_group_members(pool: &SqlitePool, group_name: &str) -> Result<Vec<GroupMember>, sqlx::Error> {
+    let rows = sqlx::query_as::<_, GroupMember>(&format!(
+        "{GROUP_LINEAGE}
+         SELECT ug.username, ug.role, ug.created_at, CASE WHEN l.depth = 0 THEN NULL ELSE ug.group_name END AS inherited_from,
+                ug.expires_at
+         FROM user_groups ug JOIN lineage l ON ug.group_name = l.name
+         WHERE ug.expires_at IS NULL OR ug.expires_at > ?
+         ORDER BY ug.username, l.depth"
+    ))
+    .bind(group_name)
+    .bind(Utc::now().to_rfc3339())
+    .fetch_all(pool)
+    .await?;
+
+    // Une ligne par utilisateur : l'adhésion la plus proche, sauf si un ancêtre donne plus de droits
+    let mut members: Vec<GroupMember> = Vec::new();
+    for row in rows {
+        match members.last_mut() {
+            Some(member) if member.username == row.username => {
+                if parse_role(&row.role)? > parse_role(&member.role)? {
+                    *member = row;
+                }
+            }
+            _ => members.push(row),
+        }
+    }
+
+    Ok(members)
+}
+
+/// Rattache un groupe à un groupe parent, ou le détache avec `None`, et l'inscrit au journal
+/// d'audit ; renvoie l'ancien parent. `RowNotFound` si un des groupes n'existe pas,
+/// `Protocol` si le rattachement créerait un cycle
+pub async fn set_group_parent(
+    pool: &SqlitePool,
+    group_name: &str,
+    parent: Option<&str>,
+    actor: &str,
+) -> Result<Option<String>, sqlx::Error> {
+    if !group_exists(pool, group_name).await? {
+        return Err(sqlx::Error::RowNotFound);
+    }
+    if let Some(parent) = parent {
+        if !group_exists(pool, parent).await? {
+            return Err(sqlx::Error::RowNotFound);
+        }
+    }
+
+    let mut tx = pool.begin().await?;
+
+    let previous: Option<String> = sqlx::query_scalar("SELECT parent_name FROM group_parents WHERE group_name = ?")
+        .bind(group_name)
+        .fetch_optional(&mut *tx)
+        .await?;
+
+    match parent {
+        Some(parent) => {
+            // Le groupe ne peut pas devenir le descendant de lui-même
+            let cycle: Option<i64> = sqlx::query_scalar(&format!("{GROUP_LINEAGE} SELECT 1 FROM lineage WHERE name = ?"))
+                .bind(parent)
+                .bind(group_name)
+                .fetch_optional(&mut *tx)
+                .await?;
+            if cycle.is_some() {
+                return Err(sqlx::Error::Protocol(format!(
+                    "'{}' cannot be a parent of '{}': it is the group itself or one of its descendants", parent, group_name
+                )));
+            }
+
+            sqlx::query(
+                "INSERT INTO group_parents (group_name, parent_name, updated_by, updated_at) VALUES (?, ?, ?, ?)
+                 ON CONFLICT(group_name) DO UPDATE SET
+                     parent_name = excluded.parent_name,
+                     updated_by = excluded.updated_by,
+                     updated_at = excluded.updated_at"
+            )
+            .bind(group_name)
+            .bind(parent)
+            .bind(actor)
+            .bind(Utc::now().to_rfc3339())
+            .execute(&mut *tx)
+            .await?;
+        }
+        None => {
+            sqlx::query("DELETE FROM group_parents WHERE group_name = ?")
+                .bind(group_name)
+                .execute(&mut *tx)
+                .await?;
+        }
+    }
+
+    let details = format!("parent: {} -> {}", previous.as_deref().unwrap_or("none"), parent.unwrap_or("none"));
+    record_audit(&mut *tx, actor, "group_parent", Some("group"), Some(group_name), &details).await?;
+    tx.commit().await?;
+
+    Ok(previous)
+}
+
+/// Parent direct d'un groupe
+pub async fn group_parent(pool: &SqlitePool, group_name: &str) -> Result<Option<String>, sqlx::Error> {
+    sqlx::query_scalar("SELECT parent_name FROM group_parents WHERE group_name = ?")
+        .bind(group_name)
+        .fetch_optional(pool)
+        .await
+}
+
+/// Groupes dont l'utilisateur est le seul propriétaire alors que d'autres membres y restent
+pub async fn sole_owner_groups(pool: &SqlitePool, username: &str) -> Result<Vec<String>, sqlx::Error> {
+    sqlx::query_scalar(
+        "SELECT ug.group_name FROM user_groups ug
+         WHERE ug.username = ? AND ug.role = 'owner'
+           AND NOT EXISTS (SELECT 1 FROM user_groups o WHERE o.group_name = ug.group_name AND o.role = 'owner' AND o.username <> ug.username)
+           AND EXISTS (SELECT 1 FROM user_groups m WHERE m.group_name = ug.group_name AND m.username <> ug.username)
+         ORDER BY ug.group_name"
+    )
+    .bind(username)
+    .fetch_all(pool)
+    .await
+}
+
+/// Change le rôle d'un membre et l'inscrit au journal d'audit ; renvoie l'ancien rôle.
+/// `RowNotFound` si l'utilisateur n'est pas membre ; un groupe qui a un propriétaire le garde
+pub async fn set_group_role(
+    pool: &SqlitePool,
+    group_name: &str,
+    username: &str,
+    role: GroupRole,
+    actor: &str,
+) -> Result<GroupRole, sqlx::Error> {
+    let now = Utc::now().to_rfc3339();
+    let mut tx = pool.begin().await?;
+
+    let (current, active): (String, bool) = sqlx::query_as(
+        "SELECT role, expires_at IS NULL OR expires_at > ? FROM user_groups WHERE group_name = ? AND username = ?"
+    )
+    .bind(&now)
+    .bind(group_name)
+    .bind(username)
+    .fetch_optional(&mut *tx)
+    .await?
+    .ok_or(sqlx::Error::RowNotFound)?;
+    let current = parse_role(&current)?;
+
+    // Seuls les propriétaires dont l'adhésion court encore comptent
+    if active && current == GroupRole::Owner && role != GroupRole::Owner {
+        let owners: i64 = sqlx::query_scalar(
+            "SELECT COUNT(*) FROM user_groups
+             WHERE group_name = ? AND role = 'owner' AND (expires_at IS NULL OR expires_at > ?)"
+        )
+        .bind(group_name)
+        .bind(&now)
+        .fetch_one(&mut *tx)
+        .await?;
+        if owners <= 1 {
+            return Err(sqlx::Error::Protocol("A group must keep at least one owner".into()));
+        }
+    }
+
+    sqlx::query("UPDATE user_groups SET role = ? WHERE group_name = ? AND username = ?")
+        .bind(role.as_str())
+        .bind(group_name)
+        .bind(username)
+        .execute(&mut *tx)
+        .await?;
+
+    let details = format!("{}: {} -> {}", username, current, role);
+    record_audit(&mut *tx, actor, "group_role", Some("group"), Some(group_name), &details).await?;
+    tx.commit().await?;
+
+    Ok(current)
+}
+
+/// Indique si l'utilisateur a été nommé propriétaire du groupe à sa création et l'est resté
+pub async fn is_original_owner(pool: &SqlitePool, group_name: &str, username: &str) -> Result<bool, sqlx::Error> {
+    let row = sqlx::query("SELECT 1 FROM user_groups WHERE group_name = ? AND username = ? AND role = 'owner' AND original_owner = 1")
+        .bind(group_name)
+        .bind(username)
+        .fetch_optional(pool)
+        .await?;
+
+    Ok(row.is_some())
+}
+
+/// Ajoute un utilisateur existant à un groupe existant et l'inscrit au journal d'audit.
+/// `RowNotFound` si le groupe n'existe pas ; `Protocol` si l'utilisateur est inconnu ou déjà membre
+pub async fn add_group_member(
+    pool: &SqlitePool,
+    group_name: &str,
+    username: &str,
+    role: GroupRole,
+    expires_at: Option<&str>,
+    actor: &str,
+) -> Result<GroupMember, sqlx::Error> {
+    if !group_exists(pool, group_name).await? {
+        return Err(sqlx::Error::RowNotFound);
+    }
+    if !user_exists(pool, username).await? {
+        return Err(sqlx::Error::Protocol(format!("User '{}' does not exist", username)));
+    }
+
+    let mut tx = pool.begin().await?;
+
+    let member = sqlx::query_as::<_, GroupMember>(
+        "INSERT INTO user_groups (id, username, group_name, role, created_at, expires_at) VALUES (?, ?, ?, ?, ?, ?)
+         ON CONFLICT(username, group_name) DO NOTHING
+         RETURNING username, role, created_at, expires_at"
+    )
+    .bind(Uuid::new_v4().to_string())
+    .bind(username)
+    .bind(group_name)
+    .bind(role.as_str())
+    .bind(Utc::now().to_rfc3339())
+    .bind(expires_at)
+    .fetch_optional(&mut *tx)
+    .await?
+    .ok_or_else(|| sqlx::Error::Protocol(format!("{} is already a member of group '{}'", username, group_name)))?;
+
+    let mut details = format!("{} ({})", username, role);
+    if let Some(expires_at) = expires_at {
+        details.push_str(&format!(" until {}", expires_at));
+    }
+    record_audit(&mut *tx, actor, "add_member", Some("group"), Some(group_name), &details).await?;
+    tx.commit().await?;
+
+    Ok(member)
+}
+
+/// Retire un membre d'un groupe et l'inscrit au journal d'audit. Avec `flag_rotation`, les secrets
+/// du groupe, que l'ancien membre a pu consulter, sont signalés comme à renouveler ; renvoie leur nombre.
+/// `RowNotFound` si l'utilisateur n'est pas membre ; le dernier propriétaire ne peut pas partir
+pub async fn remove_group_member(
+    pool: &SqlitePool,
+    group_name: &str,
+    username: &str,
+    flag_rotation: bool,
+    actor: &str,
+) -> Result<u64, sqlx::Error> {
+    let mut tx = pool.begin().await?;
+
+    let now = Utc::now().to_rfc3339();
+    let (role, active): (String, bool) = sqlx::query_as(
+        "SELECT role, expires_at IS NULL OR expires_at > ? FROM user_groups WHERE group_name = ? AND username = ?"
+    )
+    .bind(&now)
+    .bind(group_name)
+    .bind(username)
+    .fetch_optional(&mut *tx)
+    .await?
+    .ok_or(sqlx::Error::RowNotFound)?;
+
+    let (members, owners): (i64, i64) = sqlx::query_as(
+        "SELECT COUNT(*), COUNT(CASE WHEN role = 'owner' AND (expires_at IS NULL OR expires_at > ?) THEN 1 END)
+         FROM user_groups WHERE group_name = ?"
+    )
+    .bind(&now)
+    .bind(group_name)
+    .fetch_one(&mut *tx)
+    .await?;
+
+    if members <= 1 {
+        return Err(sqlx::Error::Protocol("Cannot remove the last member of a group, delete the group instead".into()));
+    }
+    if active && parse_role(&role)? == GroupRole::Owner && owners <= 1 {
+        return Err(sqlx::Error::Protocol("A group must keep at least one owner".into()));
+    }
+
+    sqlx::query("DELETE FROM user_groups WHERE group_name = ? AND username = ?")
+        .bind(group_name)
+        .bind(username)
+        .execute(&mut *tx)
+        .await?;
+
+    let flagged = if flag_rotation {
+        sqlx::query(
+            "UPDATE vault_items SET rotation_requested_at = ?
+             WHERE group_name = ? AND deleted_at IS NULL AND rotation_requested_at IS NULL"
+        )
+        .bind(Utc::now().to_rfc3339())
+        .bind(group_name)
+        .execute(&mut *tx)
+        .await?
+        .rows_affected()
+    } else {
+        0
+    };
+
+    let details = format!("{} ({}), {} secret(s) flagged for rotation", username, role, flagged);
+    record_audit(&mut *tx, actor, "remove_member", Some("group"), Some(group_name), &details).await?;
+    tx.commit().await?;
+
+    Ok(flagged)
+}
+
+/// Liste les comptes d'un groupe (métadonnées uniquement)
+pub async fn get_account_by_group_name(
+    pool: &SqlitePool,
+    group_name: &str,
+    list: &ListQuery,
+    crypto: &CryptoService,
+) -> Result<Page<ResponseGetAccountInGroups>, sqlx::Error> {
+    let mut page = list.fetch_page::<ResponseGetAccountInGroups>(
+        pool,
+        "id, group_name, title, url, payload, notes, folder_id, created_at, updated_at",
+        "FROM vault_items WHERE item_type = 'account_group' AND group_name = ? AND deleted_at IS NULL",
+        &[group_name],
+    )
+    .await
+    .map_err(|e| {
+        log::error!("Database query failed for get_account_by_group_name: {:?}", e);
+        e
+    })?;
+
+    let ids: Vec<&str> = page.items.iter().map(|item| item.id.as_str()).collect();
+    let mut tags = load_tags(pool, &ids, crypto).await?;
+
+    for item in &mut page.items {
+        item.title = open_field(crypto, &item.title)?;
+        item.user_account = items::text(&open_payload(crypto, &item.payload)?, "user_account").to_string();
+        item.url = open_field(crypto, &item.url)?;
+        item.notes = open_notes(crypto, item.notes.take())?;
+        item.tags = tags.remove(&item.id).unwrap_or_default();
+    }
+
+    Ok(page)
+}
+
+/// Liste les clés API d'un groupe (métadonnées uniquement)
+pub async fn get_api_key_by_group_name(
+    pool: &SqlitePool,
+    group_name: &str,
+    list: &ListQuery,
+    crypto: &CryptoService,
+) -> Result<Page<ResponseGetApiKeyInGroups>, sqlx::Error> {
+    let mut page = list.fetch_page::<ResponseGetApiKeyInGroups>(
+        pool,
+        "id, group_name, title, notes, folder_id, created_at, updated_at",
+        "FROM vault_items WHERE item_type = 'api_key_group' AND group_name = ? AND deleted_at IS NULL",
+        &[group_name],
+    )
+    .await
+    .map_err(|e| {
+        log::error!("Database query failed for get_api_key_by_group_name: {:?}", e);
+        e
+    })?;
+
+    let ids: Vec<&str> = page.items.iter().map(|item| item.id.as_str()).collect();
+    let mut tags = load_tags(pool, &ids, crypto).await?;
+
+    for item in &mut page.items {
+        item.title = open_field(crypto, &item.title)?;
+        item.notes = open_notes(crypto, item.notes.take())?;
+        item.tags = tags.remove(&item.id).unwrap_or_default();
+    }
+
+    Ok(page)
+}
+
+pub async fn get_api_key_by_title_and_username(
+    pool: &SqlitePool,
+    title: &str,
+    username: &str,
+    crypto: &CryptoService,
+) -> Result<Vec<ResponseGetApiKeyInTitle>, sqlx::Error> {
+    let rows: Vec<(String, String)> = sqlx::query_as(
+        r#"
+        SELECT id, payload
+        FROM vault_items
+        WHERE item_type = 'api_key' AND title_index = ? AND username = ? AND group_name IS NULL AND deleted_at IS NULL
+        "#
+    )
+    .bind(crypto.blind_index("title", title))
+    .bind(username)
+    .fetch_all(pool)
+    .await
+    .map_err(|e| {
+        log::error!("Database query failed for get_api_key_by_title_and_username: {:?}", e);
+        e
+    })?;
+
+    let mut api_keys = Vec::new();
+
+    for (id, payload) in rows {
+        match open_payload(crypto, &payload) {
+            Ok(fields) => {
+                api_keys.push(ResponseGetApiKeyInTitle {
+                    id,
+                    api_key: items::text(&fields, "api_key").to_string(),
+                });
+            }
+            Err(e) => {
+                log::error!("Decryption failed for API key '{}': {}", title, e);
+            }
+        }
+    }
+
+    Ok(api_keys)
+}
+
+/// Déchiffre le contenu d'un seul élément (l'accès doit avoir été vérifié au préalable).
+/// `secret` reprend le champ secret principal du type, `fields` le contenu complet
+pub async fn reveal_item(
+    pool: &SqlitePool,
+    item_type: &str,
+    item_id: &str,
+    crypto: &CryptoService,
+) -> Result<Option<RevealItemResponse>, sqlx::Error> {
+    let schema = schema_of(pool, item_type).await?;
+
+    let row: Option<(String, String)> = sqlx::query_as(
+        "SELECT title, payload FROM vault_items WHERE id = ? AND item_type = ? AND deleted_at IS NULL"
+    )
+    .bind(item_id)
+    .bind(item_type)
+    .fetch_optional(pool)
+    .await?;
+
+    let Some((title, payload)) = row else {
+        return Ok(None);
+    };
+    let title = open_field(crypto, &title)?;
+
+    let fields = open_payload(crypto, &payload)
+        .map_err(|e| {
+            log::error!("Decryption failed for {} '{}': {}", item_type, title, e);
+            e
+        })?;
+
+    Ok(Some(RevealItemResponse {
+        id: item_id.to_string(),
+        item_type: item_type.to_string(),
+        title,
+        secret: items::text(&fields, schema.primary_secret()).to_string(),
+        fields,
+    }))
+}
+
+// ==================== AUDIT ====================
+
+/// Ajoute une entrée au journal d'audit
+pub async fn record_audit<'e, E>(
+    executor: E,
+    actor: &str,
+    action: &str,
+    target_type: Option<&str>,
+    target_id: Option<&str>,
+    details: &str,
+) -> Result<(), sqlx::Error>
+where
+    E: sqlx::Executor<'e, Database = Sqlite>,
+{
+    sqlx::query(
+        "INSERT INTO audit_log (id, actor, action, target_type, target_id, details, created_at)
+         VALUES (?, ?, ?, ?, ?, ?, ?)"
+    )
+    .bind(Uuid::new_v4().to_string())
+    .bind(actor)
+    .bind(action)
+    .bind(target_type)
+    .bind(target_id)
+    .bind(details)
+    .bind(Utc::now().to_rfc3339())
+    .execute(executor)
+    .await?;
+
+    Ok(())
+}
+
+/// Liste le journal d'audit
+pub async fn get_audit_log(
+    pool: &SqlitePool,
+    list: &ListQuery,
+) -> Result<Page<AuditEntry>, sqlx::Error> {
+    list.fetch_page::<AuditEntry>(
+        pool,
+        "id, actor, action, target_type, target_id, details, created_at",
+        "FROM audit_log WHERE 1 = 1",
+        &[],
+    )
+    .await
+    .map_err(|e| {
+        log::error!("Database query failed for get_audit_log: {:?}", e);
+        e
+    })
+}
+
+// ==================== HISTORIQUE DES SECRETS ====================
+
+/// Rôle d'un utilisateur sur un élément : `Owner` pour son élément personnel, son rôle dans le
+/// groupe pour un élément de groupe ; `None` s'il n'y a pas accès
+pub async fn item_role(
+    pool: &SqlitePool,
+    item_type: &str,
+    item_id: &str,
+    username: &str,
+) -> Result<Option<GroupRole>, sqlx::Error> {
+    check_item_access(pool, item_type, item_id, username, false).await
+}
+
+/// Même vérification que `item_role`, pour un élément placé dans la corbeille
+pub async fn trashed_item_role(
+    pool: &SqlitePool,
+    item_type: &str,
+    item_id: &str,
+    username: &str,
+) -> Result<Option<GroupRole>, sqlx::Error> {
+    check_item_access(pool, item_type, item_id, username, true).await
+}
+
+async fn check_item_access(
+    pool: &SqlitePool,
+    item_type: &str,
+    item_id: &str,
+    username: &str,
+    in_trash: bool,
+) -> Result<Option<GroupRole>, sqlx::Error> {
+    let (_, in_group) = items::split_item_type(item_type);
+
+    let deleted = if in_trash { "deleted_at IS NOT NULL" } else { "deleted_at IS NULL" };
+
+    if in_group {
+        let group_name: Option<String> = sqlx::query_scalar(&format!(
+            "SELECT group_name FROM vault_items WHERE id = ? AND item_type = ? AND group_name IS NOT NULL AND {}",
+            deleted
+        ))
+        .bind(item_id)
+        .bind(item_type)
+        .fetch_optional(pool)
+        .await?;
+
+        return match group_name {
+            Some(group_name) => group_role(pool, &group_name, username).await,
+            None => Ok(None),
+        };
+    }
+
+    let role: Option<String> = sqlx::query_scalar(&format!(
+        "SELECT 'owner' FROM vault_items WHERE id = ? AND item_type = ? AND username = ? AND group_name IS NULL AND {}",
+        deleted
+    ))
+    .bind(item_id)
+    .bind(item_type)
+    .bind(username)
+    .fetch_optional(pool)
+    .await?;
+
+    if role.is_some() || in_trash {
+        return role.as_deref().map(parse_role).transpose();
+    }
+
+    // Élément personnel d'un autre utilisateur partagé avec celui-ci
+    let permission: Option<String> = sqlx::query_scalar(
+        "SELECT s.permission FROM item_shares s
+         JOIN vault_items t ON t.id = s.item_id AND t.item_type = s.item_type
+         WHERE s.item_id = ? AND s.item_type = ? AND s.username = ? AND t.deleted_at IS NULL"
+    )
+    .bind(item_id)
+    .bind(item_type)
+    .bind(username)
+    .fetch_optional(pool)
+    .await?;
+
+    if let Some(permission) = permission {
+        return Ok(Some(match permission.as_str() {
+            "edit" => SharePermission::Edit.role(),
+            _ => SharePermission::Read.role(),
+        }));
+    }
+
+    // Accès d'urgence accordé sur le coffre du propriétaire : lecture seule
+    let emergency: Option<i64> = sqlx::query_scalar(
+        "SELECT 1 FROM emergency_requests r
+         JOIN vault_items t ON r.target_type = 'user' AND r.target_name = t.username
+         WHERE t.id = ? AND t.item_type = ? AND t.group_name IS NULL AND t.deleted_at IS NULL
+           AND r.requester = ? AND r.status = 'granted' AND r.access_expires_at > ?"
+    )
+    .bind(item_id)
+    .bind(item_type)
+    .bind(username)
+    .bind(Utc::now().to_rfc3339())
+    .fetch_optional(pool)
+    .await?;
+
+    Ok(emergency.map(|_| GroupRole::Viewer))
+}
+
+/// Enregistre une nouvelle version (contenu déjà chiffré) d'un élément et retourne son numéro
+async fn record_secret_version(
+    tx: &mut Transaction<'_, Sqlite>,
+    item_type: &str,
+    item_id: &str,
+    encrypted_secret: &str,
+    action: &str,
+    created_by: &str,
+    created_at: &str,
+) -> Result<i64, sqlx::Error> {
+    insert_secret_version(tx, item_type, item_id, encrypted_secret, action, None, created_by, created_at).await
+}
+
+#[allow(clippy::too_many_arguments)]
+async fn insert_secret_version(
+    tx: &mut Transaction<'_, Sqlite>,
+    item_type: &str,
+    item_id: &str,
+    encrypted_secret: &str,
+    action: &str,
+    restored_from: Option<i64>,
+    created_by: &str,
+    created_at: &str,
+) -> Result<i64, sqlx::Error> {
+    let version: i64 = sqlx::query_scalar(
+        "SELECT COALESCE(MAX(version), 0) + 1 FROM secret_versions WHERE item_type = ? AND item_id = ?"
+    )
+    .bind(item_type)
+    .bind(item_id)
+    .fetch_one(&mut **tx)
+    .await?;
+
+    sqlx::query(
+        "INSERT INTO secret_versions (id, item_type, item_id, version, secret, action, restored_from, created_by, created_at)
+         VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?)"
+    )
+    .bind(Uuid::new_v4().to_string())
+    .bind(item_type)
+    .bind(item_id)
+    .bind(version)
+    .bind(encrypted_secret)
+    .bind(action)
+    .bind(restored_from)
+    .bind(created_by)
+    .bind(created_at)
+    .execute(&mut **tx)
+    .await?;
+
+    Ok(version)
+}
+
+/// Les éléments créés avant l'historique n'ont aucune version :
+/// on conserve leur valeur actuelle comme première version avant de l'écraser
+async fn snapshot_legacy_secret(
+    tx: &mut Transaction<'_, Sqlite>,
+    item_type: &str,
+    item_id: &str,
+) -> Result<(), sqlx::Error> {
+    let has_versions = sqlx::query("SELECT 1 FROM secret_versions WHERE item_type = ? AND item_id = ? LIMIT 1")
+        .bind(item_type)
+        .bind(item_id)
+        .fetch_optional(&mut **tx)
+        .await?;
+
+    if has_versions.is_some() {
+        return Ok(());
+    }
+
+    let current: Option<(String, String)> = sqlx::query_as(
+        "SELECT payload, created_at FROM vault_items WHERE id = ? AND item_type = ?"
+    )
+    .bind(item_id)
+    .bind(item_type)
+    .fetch_optional(&mut **tx)
+    .await?;
+
+    match current {
+        Some((secret, created_at)) => {
+            record_secret_version(tx, item_type, item_id, &secret, "legacy", "unknown", &created_at).await?;
+            Ok(())
+        }
+        None => Err(sqlx::Error::RowNotFound),
+    }
+}
+
+/// Supprime les versions les plus anciennes au-delà de la limite configurée
+async fn prune_secret_versions(
+    tx: &mut Transaction<'_, Sqlite>,
+    item_type: &str,
+    item_id: &str,
+    max_versions: i64,
+) -> Result<(), sqlx::Error> {
+    let result = sqlx::query(
+        "DELETE FROM secret_versions
+         WHERE item_type = ? AND item_id = ?
+         AND version <= (SELECT MAX(version) FROM secret_versions WHERE item_type = ? AND item_id = ?) - ?"
+    )
+    .bind(item_type)
+    .bind(item_id)
+    .bind(item_type)
+    .bind(item_id)
+    .bind(max_versions)
+    .execute(&mut **tx)
+    .await?;
+
+    if result.rows_affected() > 0 {
+        log::info!("Pruned {} old version(s) of {} {}", result.rows_affected(), item_type, item_id);
+    }
+
+    Ok(())
+}
+
+/// Modifie le contenu d'un élément : les champs absents de `changes` sont conservés, ceux à `null` effacés.
+/// Le résultat est validé selon le type et l'ancien contenu reste dans l'historique
+#[allow(clippy::too_many_arguments)]
+pub async fn update_item_fields(
+    pool: &SqlitePool,
+    item_type: &str,
+    item_id: &str,
+    changes: &Fields,
+    updated_by: &str,
+    max_versions: i64,
+    crypto: &CryptoService,
+) -> Result<(i64, String), sqlx::Error> {
+    let schema = schema_of(pool, item_type).await?;
+    let updated_at = Utc::now().to_rfc3339();
+
+    let mut tx = pool.begin().await?;
+    let version = write_fields(&mut tx, &schema, item_type, item_id, changes, updated_by, max_versions, &updated_at, crypto).await?;
+    // Les champs non secrets sont indexés pour la recherche
+    index_item(&mut tx, item_type, item_id, crypto).await?;
+    tx.commit().await?;
+
+    Ok((version, updated_at))
+}
+
+/// Nouveau titre, URL ou notes d'un élément : `None` conserve la valeur, des notes vides sont effacées
+#[derive(Default)]
+pub struct MetadataChanges<'a> {
+    pub title: Option<&'a str>,
+    pub url: Option<&'a str>,
+    pub notes: Option<&'a str>,
+}
+
+impl MetadataChanges<'_> {
+    pub fn is_empty(&self) -> bool {
+        self.title.is_none() && self.url.is_none() && self.notes.is_none()
+    }
+}
+
+/// Modifie dans une même transaction les métadonnées et les champs d'un élément : les champs sont
+/// validés avant toute écriture et un échec n'enregistre rien. Renvoie la version créée (aucune si
+/// seules les métadonnées changent) et la date de modification
+#[allow(clippy::too_many_arguments)]
+pub async fn update_item(
+    pool: &SqlitePool,
+    item_type: &str,
+    item_id: &str,
+    changes: &Fields,
+    metadata: &MetadataChanges<'_>,
+    updated_by: &str,
+    max_versions: i64,
+    crypto: &CryptoService,
+) -> Result<(Option<i64>, String), sqlx::Error> {
+    let schema = schema_of(pool, item_type).await?;
+    let updated_at = Utc::now().to_rfc3339();
+
+    let mut tx = pool.begin().await?;
+    let version = match changes.is_empty() {
+        true => None,
+        false => Some(write_fields(&mut tx, &schema, item_type, item_id, changes, updated_by, max_versions, &updated_at, crypto).await?),
+    };
+    if !metadata.is_empty() {
+        write_metadata(&mut tx, item_type, item_id, metadata, &updated_at, crypto).await?;
+    }
+    index_item(&mut tx, item_type, item_id, crypto).await?;
+    tx.commit().await?;
+
+    Ok((version, updated_at))
+}
+
+/// Fusionne et valide les champs modifiés, puis enregistre le nouveau contenu et sa version
+#[allow(clippy::too_many_arguments)]
+async fn write_fields(
+    tx: &mut Transaction<'_, Sqlite>,
+    schema: &Schema,
+    item_type: &str,
+    item_id: &str,
+    changes: &Fields,
+    updated_by: &str,
+    max_versions: i64,
+    updated_at: &str,
+    crypto: &CryptoService,
+) -> Result<i64, sqlx::Error> {
+    let current: String = sqlx::query_scalar(
+        "SELECT payload FROM vault_items WHERE id = ? AND item_type = ? AND deleted_at IS NULL"
+    )
+    .bind(item_id)
+    .bind(item_type)
+    .fetch_optional(&mut **tx)
+    .await?
+    .ok_or(sqlx::Error::RowNotFound)?;
+
+    let current = open_payload(crypto, &current)?;
+    let fields = items::merge(schema, &current, changes)
+        .map_err(sqlx::Error::Protocol)?;
+    let payload = seal_payload(crypto, &fields)?;
+    // Seul un changement de secret compte comme une rotation
+    let rotated = items::secrets_changed(schema, &current, &fields);
+
+    snapshot_legacy_secret(tx, item_type, item_id).await?;
+
+    sqlx::query(
+        "UPDATE vault_items SET payload = ?, updated_at = ?,
+             rotated_at = CASE WHEN ? THEN ? ELSE COALESCE(rotated_at, updated_at, created_at) END,
+             rotation_requested_at = CASE WHEN ? THEN NULL ELSE rotation_requested_at END
+         WHERE id = ? AND item_type = ?"
+    )
+    .bind(&payload)
+    .bind(updated_at)
+    .bind(rotated)
+    .bind(updated_at)
+    .bind(rotated)
+    .bind(item_id)
+    .bind(item_type)
+    .execute(&mut **tx)
+    .await?;
+
+    let version = record_secret_version(tx, item_type, item_id, &payload, "update", updated_by, updated_at).await?;
+    prune_secret_versions(tx, item_type, item_id, max_versions).await?;
+
+    Ok(version)
+}
+
+/// Remplace le titre, l'URL ou les notes d'un élément
+async fn write_metadata(
+    tx: &mut Transaction<'_, Sqlite>,
+    item_type: &str,
+    item_id: &str,
+    changes: &MetadataChanges<'_>,
+    updated_at: &str,
+    crypto: &CryptoService,
+) -> Result<(), sqlx::Error> {
+    let (current_title, current_url, current_notes): (String, String, Option<String>) = sqlx::query_as(
+        "SELECT title, url, notes FROM vault_items WHERE id = ? AND item_type = ? AND deleted_at IS NULL"
+    )
+    .bind(item_id)
+    .bind(item_type)
+    .fetch_optional(&mut **tx)
+    .await?
+    .ok_or(sqlx::Error::RowNotFound)?;
+
+    let title = match changes.title {
+        Some(title) => title.to_string(),
+        None => open_field(crypto, &current_title)?,
+    };
+    let url = match changes.url {
+        Some(url) => url.to_string(),
+        None => open_field(crypto, &current_url)?,
+    };
+    let notes = match changes.notes {
+        Some(notes) => Some(notes.to_string()).filter(|n| !n.is_empty()),
+        None => open_notes(crypto, current_notes)?,
+    };
+
+    let sealed = seal_metadata(crypto, &title, &url, notes.as_deref())?;
+
+    sqlx::query(
+        "UPDATE vault_items SET title = ?, title_index = ?, url = ?, url_host_index = ?, notes = ?, updated_at = ?,
+             rotated_at = COALESCE(rotated_at, updated_at, created_at)
+         WHERE id = ? AND item_type = ?"
+    )
+    .bind(&sealed.title)
+    .bind(&sealed.title_index)
+    .bind(&sealed.url)
+    .bind(&sealed.url_host_index)
+    .bind(&sealed.notes)
+    .bind(updated_at)
+    .bind(item_id)
+    .bind(item_type)
+    .execute(&mut **tx)
+    .await?;
+
+    Ok(())
+}
+
+/// Liste les versions d'un élément (métadonnées uniquement, sans le contenu)
+pub async fn get_secret_versions(
+    pool: &SqlitePool,
+    item_type: &str,
+    item_id: &str,
+) -> Result<Vec<SecretVersionInfo>, sqlx::Error> {
+    sqlx::query_as::<_, SecretVersionInfo>(
+        r#"
+        SELECT version, action, restored_from, created_by, created_at,
+               version = (SELECT MAX(version) FROM secret_versions WHERE item_type = ? AND item_id = ?) AS is_current
+        FROM secret_versions
+        WHERE item_type = ? AND item_id = ?
+        ORDER BY version DESC
+        "#
+    )
+    .bind(item_type)
+    .bind(item_id)
+    .bind(item_type)
+    .bind(item_id)
+    .fetch_all(pool)
+    .await
+    .map_err(|e| {
+        log::error!("Database query failed for get_secret_versions: {:?}", e);
+        e
+    })
+}
+
+/// Récupère et déchiffre le contenu d'une version précise d'un élément
+pub async fn reveal_secret_version(
+    pool: &SqlitePool,
+    item_type: &str,
+    item_id: &str,
+    version: i64,
+    crypto: &CryptoService,
+) -> Result<Option<(Fields, SecretVersionInfo)>, sqlx::Error> {
+    let row: Option<(String, String, Option<i64>, String, String)> = sqlx::query_as(
+        "SELECT secret, action, restored_from, created_by, created_at
+         FROM secret_versions
+         WHERE item_type = ? AND item_id = ? AND version = ?"
+    )
+    .bind(item_type)
+    .bind(item_id)
+    .bind(version)
+    .fetch_optional(pool)
+    .await?;
+
+    let Some((secret, action, restored_from, created_by, created_at)) = row else {
+        return Ok(None);
+    };
+
+    let fields = open_payload(crypto, &secret)
+        .map_err(|e| {
+            log::error!("Decryption failed for version {} of {} {}: {}", version, item_type, item_id, e);
+            e
+        })?;
+
+    let is_current: bool = sqlx::query_scalar(
+        "SELECT ? = MAX(version) FROM secret_versions WHERE item_type = ? AND item_id = ?"
+    )
+    .bind(version)
+    .bind(item_type)
+    .bind(item_id)
+    .fetch_one(pool)
+    .await?;
+
+    Ok(Some((fields, SecretVersionInfo {
+        version,
+        action,
+        restored_from,
+        created_by,
+        created_at,
+        is_current,
+    })))
+}
+
+/// Restaure une ancienne version : son contenu redevient courant sous un nouveau numéro
+#[allow(clippy::too_many_arguments)]
+pub async fn restore_secret_version(
+    pool: &SqlitePool,
+    item_type: &str,
+    item_id: &str,
+    version: i64,
+    restored_by: &str,
+    max_versions: i64,
+    crypto: &CryptoService,
+) -> Result<(i64, String), sqlx::Error> {
+    let mut tx = pool.begin().await?;
+
+    let secret: String = sqlx::query_scalar(
+        "SELECT secret FROM secret_versions WHERE item_type = ? AND item_id = ? AND version = ?"
+    )
+    .bind(item_type)
+    .bind(item_id)
+    .bind(version)
+    .fetch_optional(&mut *tx)
+    .await?
+    .ok_or(sqlx::Error::RowNotFound)?;
+
+    let updated_at = Utc::now().to_rfc3339();
+
+    // Revenir à un ancien secret compte comme une rotation
+    let result = sqlx::query("UPDATE vault_items SET payload = ?, updated_at = ?, rotated_at = ?, rotation_requested_at = NULL WHERE id = ? AND item_type = ? AND deleted_at IS NULL")
+        .bind(&secret)
+        .bind(&updated_at)
+        .bind(&updated_at)
+        .bind(item_id)
+        .bind(item_type)
+        .execute(&mut *tx)
+        .await?;
+
+    if result.rows_affected() == 0 {
+        return Err(sqlx::Error::RowNotFound);
+    }
+
+    let new_version = insert_secret_version(&mut tx, item_type, item_id, &secret, "restore", Some(version), restored_by, &updated_at).await?;
+    prune_secret_versions(&mut tx, item_type, item_id, max_versions).await?;
+    index_item(&mut tx, item_type, item_id, crypto).await?;
+    tx.commit().await?;
+
+    Ok((new_version, updated_at))
+}
+
+
+// ==================== CORBEILLE ====================
+
+/// Liste les éléments personnels d'un utilisateur placés dans la corbeille
+pub async fn get_trash_by_username(
+    pool: &SqlitePool,
+    username: &str,
+    crypto: &CryptoService,
+) -> Result<Vec<TrashItem>, sqlx::Error> {
+    let mut items = sqlx::query_as::<_, TrashItem>(
+        r#"
+        SELECT item_type, id, title, group_name, deleted_at, deleted_by
+        FROM vault_items
+        WHERE username = ? AND group_name IS NULL AND deleted_at IS NOT NULL
+        ORDER BY deleted_at DESC
+        "#
+    )
+    .bind(username)
+    .fetch_all(pool)
+    .await
+    .map_err(|e| {
+        log::error!("Database query failed for get_trash_by_username: {:?}", e);
+        e
+    })?;
+
+    for item in &mut items {
+        item.title = open_field(crypto, &item.title)?;
+    }
+
+    Ok(items)
+}
+
+/// Liste les éléments d'un groupe placés dans la corbeille
+pub async fn get_trash_by_group_name(
+    pool: &SqlitePool,
+    group_name: &str,
+    crypto: &CryptoService,
+) -> Result<Vec<TrashItem>, sqlx::Error> {
+    let mut items = sqlx::query_as::<_, TrashItem>(
+        r#"
+        SELECT item_type, id, title, group_name, deleted_at, deleted_by
+        FROM vault_items
+        WHERE group_name = ? AND deleted_at IS NOT NULL
+        ORDER BY deleted_at DESC
+        "#
+    )
+    .bind(group_name)
+    .fetch_all(pool)
+    .await
+    .map_err(|e| {
+        log::error!("Database query failed for get_trash_by_group_name: {:?}", e);
+        e
+    })?;
+
+    for item in &mut items {
+        item.title = open_field(crypto, &item.title)?;
+    }
+
+    Ok(items)
+}
+
+/// Sort un élément de la corbeille
+pub async fn restore_trashed_item(
+    pool: &SqlitePool,
+    item_type: &str,
+    item_id: &str,
+    crypto: &CryptoService,
+) -> Result<u64, sqlx::Error> {
+    let mut tx = pool.begin().await?;
+
+    let result = sqlx::query(
+        "UPDATE vault_items SET deleted_at = NULL, deleted_by = NULL WHERE id = ? AND item_type = ? AND deleted_at IS NOT NULL"
+    )
+    .bind(item_id)
+    .bind(item_type)
+    .execute(&mut *tx)
+    .await?;
+
+    index_item(&mut tx, item_type, item_id, crypto).await?;
+    tx.commit().await?;
+
+    Ok(result.rows_affected())
+}
+
+/// Supprime définitivement un élément et son historique
+async fn purge_item(
+    tx: &mut Transaction<'_, Sqlite>,
+    item_type: &str,
+    item_id: &str,
+) -> Result<u64, sqlx::Error> {
+    let result = sqlx::query("DELETE FROM vault_items WHERE id = ? AND item_type = ? AND deleted_at IS NOT NULL")
+        .bind(item_id)
+        .bind(item_type)
+        .execute(&mut **tx)
+        .await?;
+
+    if result.rows_affected() > 0 {
+        for dependent in ["secret_versions", "item_tags", "attachments", "item_shares", "item_leases"] {
+            sqlx::query(&format!("DELETE FROM {} WHERE item_type = ? AND item_id = ?", dependent))
+                .bind(item_type)
+                .bind(item_id)
+                .execute(&mut **tx)
+                .await?;
+        }
+        unindex_item(tx, item_type, item_id).await?;
+    }
+
+    Ok(result.rows_affected())
+}
+
+/// Vide la corbeille des éléments supprimés avant `cutoff` (tout si `None`)
+pub async fn purge_trash(
+    pool: &SqlitePool,
+    cutoff: Option<&str>,
+) -> Result<u64, sqlx::Error> {
+    let mut tx = pool.begin().await?;
+    let mut purged = 0;
+
+    let trashed: Vec<(String, String)> = sqlx::query_as(
+        "SELECT item_type, id FROM vault_items WHERE deleted_at IS NOT NULL AND (? IS NULL OR deleted_at < ?)"
+    )
+    .bind(cutoff)
+    .bind(cutoff)
+    .fetch_all(&mut *tx)
+    .await?;
+
+    for (item_type, id) in trashed {
+        purged += purge_item(&mut tx, &item_type, &id).await?;
+    }
+
+    tx.commit().await?;
+    Ok(purged)
+}
+
+/// Supprime définitivement un élément précis de la corbeille
+pub async fn purge_trashed_item(
+    pool: &SqlitePool,
+    item_type: &str,
+    item_id: &str,
+) -> Result<u64, sqlx::Error> {
+    let mut tx = pool.begin().await?;
+    let purged = purge_item(&mut tx, item_type, item_id).await?;
+    tx.commit().await?;
+    Ok(purged)
+}
+
+
+// ==================== RECHERCHE ====================
+
+/// Métadonnées déchiffrées d'un élément visible (hors corbeille)
+struct ItemMetadata {
+    owner: Option<String>,
+    group_name: Option<String>,
+    title: String,
+    url: String,
+    // Valeurs des champs non secrets du contenu (identifiant, titulaire, clé publique…)
+    fields: String,
+    tags: String,
+    notes: String,
+}
+
+// Propriétaire, groupe, titre, URL, contenu et notes tels que stockés (chiffrés)
+type MetadataRow = (Option<String>, Option<String>, String, String, String, Option<String>);
+
+async fn load_item_metadata(
+    conn: &mut SqliteConnection,
+    item_type: &str,
+    item_id: &str,
+    crypto: &CryptoService,
+) -> Result<Option<ItemMetadata>, sqlx::Error> {
+    let schema = schema_of(&mut *conn, item_type).await?;
+
+    let row: Option<MetadataRow> = sqlx::query_as(
+        "SELECT username, group_name, title, url, payload, notes FROM vault_items WHERE id = ? AND item_type = ? AND deleted_at IS NULL"
+    )
+    .bind(item_id)
+    .bind(item_type)
+    .fetch_optional(&mut *conn)
+    .await?;
+
+    let Some((owner, group_name, title, url, payload, notes)) = row else {
+        return Ok(None);
+    };
+
+    let tags: Vec<String> = sqlx::query_scalar("SELECT tag FROM item_tags WHERE item_type = ? AND item_id = ?")
+        .bind(item_type)
+        .bind(item_id)
+        .fetch_all(&mut *conn)
+        .await?;
+    let tags = tags
+        .iter()
+        .map(|tag| open_field(crypto, tag))
+        .collect::<Result<Vec<_>, _>>()?
+        .join(" ");
+
+    let fields = items::public_fields(&schema, &open_payload(crypto, &payload)?)
+        .values()
+        .filter_map(|value| value.as_str())
+        .collect::<Vec<_>>()
+        .join(" ");
+
+    Ok(Some(ItemMetadata {
+        owner,
+        group_name,
+        title: open_field(crypto, &title)?,
+        url: open_field(crypto, &url)?,
+        fields,
+        tags,
+        notes: open_notes(crypto, notes)?.unwrap_or_default(),
+    }))
+}
+
+/// Retire un élément de l'index de recherche
+async fn unindex_item(
+    conn: &mut SqliteConnection,
+    item_type: &str,
+    item_id: &str,
+) -> Result<(), sqlx::Error> {
+    sqlx::query("DELETE FROM vault_search WHERE item_type = ? AND item_id = ?")
+        .bind(item_type)
+        .bind(item_id)
+        .execute(&mut *conn)
+        .await?;
+
+    Ok(())
+}
+
+/// Remet un élément à jour dans l'index de recherche (ou l'en retire s'il n'est plus visible).
+/// Seuls des jetons aveugles sont indexés, jamais le texte en clair
+async fn index_item(
+    conn: &mut SqliteConnection,
+    item_type: &str,
+    item_id: &str,
+    crypto: &CryptoService,
+) -> Result<(), sqlx::Error> {
+    unindex_item(conn, item_type, item_id).await?;
+
+    let Some(item) = load_item_metadata(conn, item_type, item_id, crypto).await? else {
+        return Ok(());
+    };
+
+    sqlx::query(
+        "INSERT INTO vault_search (item_type, item_id, owner, group_name, title, url, fields, tags, notes)
+         VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?)"
+    )
+    .bind(item_type)
+    .bind(item_id)
+    .bind(&item.owner)
+    .bind(&item.group_name)
+    .bind(search::index_tokens(crypto, &item.title))
+    .bind(search::index_tokens(crypto, &item.url))
+    .bind(search::index_tokens(crypto, &item.fields))
+    .bind(search::index_tokens(crypto, &item.tags))
+    .bind(search::index_tokens(crypto, &item.notes))
+    .execute(&mut *conn)
+    .await?;
+
+    Ok(())
+}
+
+/// Reconstruit entièrement l'index de recherche
+pub async fn rebuild_search_index(pool: &SqlitePool, crypto: &CryptoService) -> Result<u64, sqlx::Error> {
+    let mut tx = pool.begin().await?;
+
+    sqlx::query("DELETE FROM vault_search")
+        .execute(&mut *tx)
+        .await?;
+
+    let visible: Vec<(String, String)> = sqlx::query_as("SELECT item_type, id FROM vault_items WHERE deleted_at IS NULL")
+        .fetch_all(&mut *tx)
+        .await?;
+
+    let mut indexed = 0;
+    for (item_type, id) in visible {
+        index_item(&mut tx, &item_type, &id, crypto).await?;
+        indexed += 1;
+    }
+
+    tx.commit().await?;
+    log::info!("Search index rebuilt with {} item(s)", indexed);
+    Ok(indexed)
+}
+
+/// Recherche dans les éléments personnels de l'utilisateur et ceux de ses groupes, classés par pertinence.
+/// Le surlignage est calculé après déchiffrement, l'index ne contenant que des jetons aveugles
+pub async fn search_items(
+    pool: &SqlitePool,
+    username: &str,
+    terms: &[String],
+    limit: i64,
+    crypto: &CryptoService,
+) -> Result<Vec<SearchResult>, sqlx::Error> {
+    let hits: Vec<(String, String, f64)> = sqlx::query_as(&format!(
+        r#"
+        SELECT item_type, item_id, bm25(vault_search, 0.0, 0.0, 0.0, 0.0, 10.0, 4.0, 4.0, 6.0, 1.0) AS rank
+        FROM vault_search
+        WHERE vault_search MATCH ?
+          AND (owner = ? OR group_name IN ({REACHABLE_GROUPS}))
+        ORDER BY rank
+        LIMIT ?
+        "#
+    ))
+    .bind(search::match_query(crypto, terms))
+    .bind(username)
+    .bind(username)
+    .bind(Utc::now().to_rfc3339())
+    .bind(limit)
+    .fetch_all(pool)
+    .await
+    .map_err(|e| {
+        log::error!("Database query failed for search_items: {:?}", e);
+        e
+    })?;
+
+    let mut conn = pool.acquire().await?;
+    let mut results = Vec::with_capacity(hits.len());
+
+    for (item_type, item_id, rank) in hits {
+        let Some(item) = load_item_metadata(&mut conn, &item_type, &item_id, crypto).await? else {
+            continue;
+        };
+
+        let snippet = [&item.title, &item.fields, &item.url, &item.tags, &item.notes]
+            .into_iter()
+            .find_map(|field| search::snippet(field, terms))
+            .unwrap_or_else(|| item.title.clone());
+
+        results.push(SearchResult {
+            title_highlight: search::highlight(&item.title, terms).unwrap_or_else(|| search::escape_html(&item.title)),
+            snippet,
+            item_type,
+            item_id,
+            group_name: item.group_name,
+            title: item.title,
+            url: item.url,
+            rank,
+        });
+    }
+
+    Ok(results)
+}
+
+// ==================== ÉCHÉANCES ====================
+
+/// Remplace la date d'expiration et l'intervalle de rotation (jours) d'un élément ; `None` les retire
+pub async fn set_item_deadlines(
+    pool: &SqlitePool,
+    item_type: &str,
+    item_id: &str,
+    expires_at: Option<&str>,
+    rotate_every: Option<i64>,
+) -> Result<u64, sqlx::Error> {
+    let expires_at = expires_at.map(expiry::parse_expires_at).transpose().map_err(sqlx::Error::Protocol)?;
+    let rotate_every = rotate_every.map(expiry::check_rotate_every).transpose().map_err(sqlx::Error::Protocol)?;
+
+    let result = sqlx::query(
+        "UPDATE vault_items SET expires_at = ?, rotate_every = ? WHERE id = ? AND item_type = ? AND deleted_at IS NULL"
+    )
+    .bind(&expires_at)
+    .bind(rotate_every)
+    .bind(item_id)
+    .bind(item_type)
+    .execute(pool)
+    .await?;
+
+    Ok(result.rows_affected())
+}
+
+// Type, identifiant, groupe, titre chiffré, expiration, intervalle de rotation, dernier renouvellement
+// et rotation demandée hors calendrier
+type DeadlineRow = (String, String, Option<String>, String, Option<String>, Option<i64>, String, Option<String>);
+
+/// Éléments personnels et des groupes de l'utilisateur expirés, à renouveler, ou dont une
+/// échéance tombe dans les `within_days` prochains jours ; les groupes sans élément concerné
+/// figurent avec une liste vide
+pub async fn get_expiring_items(
+    pool: &SqlitePool,
+    username: &str,
+    within_days: i64,
+    crypto: &CryptoService,
+) -> Result<ExpiringItemsResponse, sqlx::Error> {
+    let groups: Vec<(String, Option<i64>)> = sqlx::query_as(&format!(
+        "SELECT g.name, p.max_secret_age_days FROM ({REACHABLE_GROUPS}) g
+         LEFT JOIN group_policies p ON p.group_name = g.name
+         ORDER BY g.name"
+    ))
+    .bind(username)
+    .bind(Utc::now().to_rfc3339())
+    .fetch_all(pool)
+    .await?;
+
+    let rows: Vec<DeadlineRow> = sqlx::query_as(&format!(
+        "SELECT item_type, id, group_name, title, expires_at, rotate_every, COALESCE(rotated_at, updated_at, created_at),
+                rotation_requested_at
+         FROM vault_items
+         WHERE deleted_at IS NULL
+           AND ((username = ? AND group_name IS NULL)
+                OR group_name IN ({REACHABLE_GROUPS}))
+           AND (expires_at IS NOT NULL OR rotate_every IS NOT NULL OR rotation_requested_at IS NOT NULL
+                OR group_name IN (SELECT group_name FROM group_policies))"
+    ))
+    .bind(username)
+    .bind(username)
+    .bind(Utc::now().to_rfc3339())
+    .fetch_all(pool)
+    .await?;
+
+    let policies: HashMap<&str, i64> = groups
+        .iter()
+        .filter_map(|(group_name, max_age)| max_age.map(|days| (group_name.as_str(), days)))
+        .collect();
+
+    let mut by_group: HashMap<String, Vec<ExpiringItem>> = HashMap::new();
+    let mut personal = Vec::new();
+
+    for (item_type, id, group_name, title, expires_at, rotate_every, rotated_at, rotation_requested_at) in rows {
+        let max_age = group_name.as_deref().and_then(|g| policies.get(g).copied());
+        let rotate_every = expiry::effective_rotation(rotate_every, max_age);
+        // Une rotation demandée est due immédiatement
+        let rotation_due_at = rotate_every
+            .and_then(|days| expiry::rotation_due_at(&rotated_at, days))
+            .into_iter()
+            .chain(rotation_requested_at.clone())
+            .min();
+
+        let Some(status) = expiry::status(expires_at.as_deref(), rotation_due_at.as_deref(), within_days) else {
+            continue;
+        };
+
+        let item = ExpiringItem {
+            item_type,
+            id,
+            title: open_field(crypto, &title)?,
+            group_name: group_name.clone(),
+            status: status.to_string(),
+            expires_at,
+            rotate_every,
+            rotated_at,
+            rotation_due_at,
+            rotation_requested_at,
+        };
+
+        match group_name {
+            Some(group_name) => by_group.entry(group_name).or_default().push(item),
+            None => personal.push(item),
+        }
+    }
+
+    // Échéance la plus proche en premier (dates stockées en UTC, donc comparables en texte)
+    let next_deadline = |item: &ExpiringItem| {
+        [item.expires_at.as_deref(), item.rotation_due_at.as_deref()]
+            .into_iter()
+            .flatten()
+            .min()
+            .map(str::to_string)
+    };
+    personal.sort_by_key(next_deadline);
+
+    let groups = groups
+        .into_iter()
+        .map(|(group_name, max_secret_age_days)| {
+            let mut items = by_group.remove(&group_name).unwrap_or_default();
+            items.sort_by_key(next_deadline);
+            GroupExpiringItems { group_name, max_secret_age_days, items }
+        })
+        .collect();
+
+    Ok(ExpiringItemsResponse { within_days, personal, groups })
+}
+
+/// Liste les politiques d'âge maximal des secrets de groupe
+pub async fn get_group_policies(pool: &SqlitePool) -> Result<Vec<GroupPolicy>, sqlx::Error> {
+    sqlx::query_as::<_, GroupPolicy>(
+        "SELECT group_name, max_secret_age_days, updated_by, updated_at FROM group_policies ORDER BY group_name"
+    )
+    .fetch_all(pool)
+    .await
+}
+
+/// Politique d'un groupe, s'il en a une
+pub async fn get_group_policy(pool: &SqlitePool, group_name: &str) -> Result<Option<GroupPolicy>, sqlx::Error> {
+    sqlx::query_as::<_, GroupPolicy>(
+        "SELECT group_name, max_secret_age_days, updated_by, updated_at FROM group_policies WHERE group_name = ?"
+    )
+    .bind(group_name)
+    .fetch_optional(pool)
+    .await
+}
+
+/// Fixe l'âge maximal des secrets d'un groupe (remplace la politique existante) et l'inscrit au
+/// journal d'audit. Sans `may_relax` (propriétaire du groupe), la politique ne peut qu'être resserrée
+pub async fn set_group_policy(
+    pool: &SqlitePool,
+    group_name: &str,
+    max_secret_age_days: i64,
+    updated_by: &str,
+    may_relax: bool,
+) -> Result<GroupPolicy, sqlx::Error> {
+    let max_secret_age_days = expiry::check_rotate_every(max_secret_age_days)
+        .map_err(|_| sqlx::Error::Protocol(format!(
+            "max_secret_age_days must be between 1 and {} days", expiry::MAX_ROTATION_DAYS
+        )))?;
+
+    if !group_exists(pool, group_name).await? {
+        return Err(sqlx::Error::RowNotFound);
+    }
+
+    let mut tx = pool.begin().await?;
+
+    let current: Option<i64> = sqlx::query_scalar("SELECT max_secret_age_days FROM group_policies WHERE group_name = ?")
+        .bind(group_name)
+        .fetch_optional(&mut *tx)
+        .await?;
+
+    if !may_relax && current.is_some_and(|days| max_secret_age_days > days) {
+        return Err(sqlx::Error::Protocol("Group owners can only tighten the group policy".into()));
+    }
+
+    let policy = sqlx::query_as::<_, GroupPolicy>(
+        "INSERT INTO group_policies (group_name, max_secret_age_days, updated_by, updated_at) VALUES (?, ?, ?, ?)
+         ON CONFLICT(group_name) DO UPDATE SET
+             max_secret_age_days = excluded.max_secret_age_days,
+             updated_by = excluded.updated_by,
+             updated_at = excluded.updated_at
+         RETURNING group_name, max_secret_age_days, updated_by, updated_at"
+    )
+    .bind(group_name)
+    .bind(max_secret_age_days)
+    .bind(updated_by)
+    .bind(Utc::now().to_rfc3339())
+    .fetch_one(&mut *tx)
+    .await?;
+
+    let details = match current {
+        Some(days) => format!("max_secret_age_days: {} -> {}", days, max_secret_age_days),
+        None => format!("max_secret_age_days: {}", max_secret_age_days),
+    };
+    record_audit(&mut *tx, updated_by, "group_policy", Some("group"), Some(group_name), &details).await?;
+    tx.commit().await?;
+
+    Ok(policy)
+}
+
+/// Retire la politique d'un groupe et l'inscrit au journal d'audit ; renvoie le nombre de lignes supprimées
+pub async fn delete_group_policy(pool: &SqlitePool, group_name: &str, actor: &str) -> Result<u64, sqlx::Error> {
+    let mut tx = pool.begin().await?;
+
+    let result = sqlx::query("DELETE FROM group_policies WHERE group_name = ?")
+        .bind(group_name)
+        .execute(&mut *tx)
+        .await?;
+
+    if result.rows_affected() > 0 {
+        record_audit(&mut *tx, actor, "group_policy", Some("group"), Some(group_name), "policy removed").await?;
+    }
+    tx.commit().await?;
+
+    Ok(result.rows_affected())
+}
+
+// ==================== PIÈCES JOINTES ====================
+
+/// Enregistre une pièce jointe dont le contenu chiffré est prêt, si le quota du propriétaire
+/// de l'élément (utilisateur ou groupe) le permet ; `false` si le quota serait dépassé
+pub async fn insert_attachment(
+    pool: &SqlitePool,
+    attachment: &Attachment,
+    quota: u64,
+    crypto: &CryptoService,
+) -> Result<bool, sqlx::Error> {
+    let filename = crypto.seal_field(&attachment.filename)
+        .map_err(|e| sqlx::Error::Protocol(format!("Encryption failed: {}", e)))?;
+    let content_type = attachment.content_type
+        .as_deref()
+        .map(|value| crypto.seal_field(value))
+        .transpose()
+        .map_err(|e| sqlx::Error::Protocol(format!("Encryption failed: {}", e)))?;
+
+    let mut tx = pool.begin().await?;
+
+    let (owner, group_name): (Option<String>, Option<String>) = sqlx::query_as(
+        "SELECT username, group_name FROM vault_items WHERE id = ? AND item_type = ? AND deleted_at IS NULL"
+    )
+    .bind(&attachment.item_id)
+    .bind(&attachment.item_type)
+    .fetch_optional(&mut *tx)
+    .await?
+    .ok_or(sqlx::Error::RowNotFound)?;
+
+    let used = attachment_usage(&mut tx, owner.as_deref(), group_name.as_deref()).await?;
+    if used as u64 + attachment.size as u64 > quota {
+        return Ok(false);
+    }
+
+    sqlx::query(
+        "INSERT INTO attachments (id, item_type, item_id, filename, content_type, size, created_by, created_at)
+         VALUES (?, ?, ?, ?, ?, ?, ?, ?)"
+    )
+    .bind(&attachment.id)
+    .bind(&attachment.item_type)
+    .bind(&attachment.item_id)
+    .bind(&filename)
+    .bind(&content_type)
+    .bind(attachment.size)
+    .bind(&attachment.created_by)
+    .bind(&attachment.created_at)
+    .execute(&mut *tx)
+    .await?;
+
+    tx.commit().await?;
+    Ok(true)
+}
+
+/// Volume des pièces jointes des éléments d'un utilisateur ou d'un groupe, corbeille comprise
+async fn attachment_usage(
+    tx: &mut Transaction<'_, Sqlite>,
+    owner: Option<&str>,
+    group_name: Option<&str>,
+) -> Result<i64, sqlx::Error> {
+    sqlx::query_scalar(
+        "SELECT COALESCE(SUM(a.size), 0) FROM attachments a
+         JOIN vault_items v ON v.id = a.item_id AND v.item_type = a.item_type
+         WHERE v.username IS ? AND v.group_name IS ?"
+    )
+    .bind(owner)
+    .bind(group_name)
+    .fetch_one(&mut **tx)
+    .await
+}
+
+/// Volume déjà utilisé par le propriétaire d'un élément (utilisateur ou groupe)
+pub async fn attachment_usage_for_item(
+    pool: &SqlitePool,
+    item_type: &str,
+    item_id: &str,
+) -> Result<i64, sqlx::Error> {
+    let mut tx = pool.begin().await?;
+
+    let (owner, group_name): (Option<String>, Option<String>) = sqlx::query_as(
+        "SELECT username, group_name FROM vault_items WHERE id = ? AND item_type = ?"
+    )
+    .bind(item_id)
+    .bind(item_type)
+    .fetch_optional(&mut *tx)
+    .await?
+    .ok_or(sqlx::Error::RowNotFound)?;
+
+    let used = attachment_usage(&mut tx, owner.as_deref(), group_name.as_deref()).await?;
+    tx.commit().await?;
+    Ok(used)
+}
+
+fn open_attachment(crypto: &CryptoService, mut attachment: Attachment) -> Result<Attachment, sqlx::Error> {
+    attachment.filename = open_field(crypto, &attachment.filename)?;
+    attachment.content_type = open_notes(crypto, attachment.content_type.take())?;
+    Ok(attachment)
+}
+
+/// Liste les pièces jointes d'un élément
+pub async fn get_attachments(
+    pool: &SqlitePool,
+    item_type: &str,
+    item_id: &str,
+    crypto: &CryptoService,
+) -> Result<Vec<Attachment>, sqlx::Error> {
+    sqlx::query_as::<_, Attachment>(
+        "SELECT id, item_type, item_id, filename, content_type, size, created_by, created_at
+         FROM attachments WHERE item_type = ? AND item_id = ? ORDER BY created_at, id"
+    )
+    .bind(item_type)
+    .bind(item_id)
+    .fetch_all(pool)
+    .await?
+    .into_iter()
+    .map(|attachment| open_attachment(crypto, attachment))
+    .collect()
+}
+
+pub async fn get_attachment(
+    pool: &SqlitePool,
+    id: &str,
+    crypto: &CryptoService,
+) -> Result<Option<Attachment>, sqlx::Error> {
+    sqlx::query_as::<_, Attachment>(
+        "SELECT id, item_type, item_id, filename, content_type, size, created_by, created_at
+         FROM attachments WHERE id = ?"
+    )
+    .bind(id)
+    .fetch_optional(pool)
+    .await?
+    .map(|attachment| open_attachment(crypto, attachment))
+    .transpose()
+}
+
+pub async fn delete_attachment(pool: &SqlitePool, id: &str) -> Result<u64, sqlx::Error> {
+    let result = sqlx::query("DELETE FROM attachments WHERE id = ?")
+        .bind(id)
+        .execute(pool)
+        .await?;
+
+    Ok(result.rows_affected())
+}
+
+/// Identifiants de toutes les pièces jointes connues, pour repérer les fichiers orphelins
+pub async fn attachment_ids(pool: &SqlitePool) -> Result<HashSet<String>, sqlx::Error> {
+    let ids: Vec<String> = sqlx::query_scalar("SELECT id FROM attachments")
+        .fetch_all(pool)
+        .await?;
+
+    Ok(ids.into_iter().collect())
+}
+
+// ==================== DOSSIERS & TAGS ====================
+
+/// Vérifie qu'un dossier existe dans le périmètre donné (personnel ou de groupe)
+async fn folder_in_scope(
+    conn: &mut SqliteConnection,
+    folder_id: &str,
+    owner: Option<&str>,
+    group_name: Option<&str>,
+) -> Result<bool, sqlx::Error> {
+    let row = sqlx::query("SELECT 1 FROM folders WHERE id = ? AND owner IS ? AND group_name IS ?")
+        .bind(folder_id)
+        .bind(owner)
+        .bind(group_name)
+        .fetch_optional(&mut *conn)
+        .await?;
+
+    Ok(row.is_some())
+}
+
+/// Crée un dossier personnel (`owner`) ou de groupe (`group_name`)
+pub async fn create_folder(
+    pool: &SqlitePool,
+    name: &str,
+    parent_id: Option<&str>,
+    owner: Option<&str>,
+    group_name: Option<&str>,
+    crypto: &CryptoService,
+) -> Result<Folder, sqlx::Error> {
+    let mut conn = pool.acquire().await?;
+
+    if let Some(parent_id) = parent_id {
+        if !folder_in_scope(&mut conn, parent_id, owner, group_name).await? {
+            return Err(sqlx::Error::Protocol("Parent folder not found".into()));
+        }
+    }
+
+    let folder = Folder {
+        id: Uuid::new_v4().to_string(),
+        name: name.to_string(),
+        parent_id: parent_id.map(str::to_string),
+        owner: owner.map(str::to_string),
+        group_name: group_name.map(str::to_string),
+        created_at: Utc::now().to_rfc3339(),
+        updated_at: None,
+    };
+
+    let sealed_name = crypto.seal_field(name)
+        .map_err(|e| sqlx::Error::Protocol(format!("Encryption failed: {}", e)))?;
+
+    sqlx::query(
+        "INSERT INTO folders (id, name, parent_id, owner, group_name, created_at) VALUES (?, ?, ?, ?, ?, ?)"
+    )
+    .bind(&folder.id)
+    .bind(&sealed_name)
+    .bind(&folder.parent_id)
+    .bind(&folder.owner)
+    .bind(&folder.group_name)
+    .bind(&folder.created_at)
+    .execute(&mut *conn)
+    .await?;
+
+    Ok(folder)
+}
+
+/// Récupère un dossier (nom déchiffré)
+pub async fn get_folder(
+    pool: &SqlitePool,
+    folder_id: &str,
+    crypto: &CryptoService,
+) -> Result<Option<Folder>, sqlx::Error> {
+    let folder = sqlx::query_as::<_, Folder>(
+        "SELECT id, name, parent_id, owner, group_name, created_at, updated_at FROM folders WHERE id = ?"
+    )
+    .bind(folder_id)
+    .fetch_optional(pool)
+    .await?;
+
+    folder
+        .map(|mut folder| {
+            folder.name = open_field(crypto, &folder.name)?;
+            Ok(folder)
+        })
+        .transpose()
+}
+
+/// Liste les dossiers d'un périmètre ; la hiérarchie se reconstruit avec `parent_id`
+pub async fn get_folders(
+    pool: &SqlitePool,
+    owner: Option<&str>,
+    group_name: Option<&str>,
+    crypto: &CryptoService,
+) -> Result<Vec<Folder>, sqlx::Error> {
+    let mut folders = sqlx::query_as::<_, Folder>(
+        "SELECT id, name, parent_id, owner, group_name, created_at, updated_at
+         FROM folders WHERE owner IS ? AND group_name IS ? ORDER BY created_at, id"
+    )
+    .bind(owner)
+    .bind(group_name)
+    .fetch_all(pool)
+    .await
+    .map_err(|e| {
+        log::error!("Database query failed for get_folders: {:?}", e);
+        e
+    })?;
+
+    for folder in &mut folders {
+        folder.name = open_field(crypto, &folder.name)?;
+    }
+
+    Ok(folders)
+}
+
+/// Renomme un dossier
+pub async fn rename_folder(
+    pool: &SqlitePool,
+    folder_id: &str,
+    name: &str,
+    crypto: &CryptoService,
+) -> Result<u64, sqlx::Error> {
+    let sealed_name = crypto.seal_field(name)
+        .map_err(|e| sqlx::Error::Protocol(format!("Encryption failed: {}", e)))?;
+
+    let result = sqlx::query("UPDATE folders SET name = ?, updated_at = ? WHERE id = ?")
+        .bind(&sealed_name)
+        .bind(Utc::now().to_rfc3339())
+        .bind(folder_id)
+        .execute(pool)
+        .await?;
+
+    Ok(result.rows_affected())
+}
+
+/// Déplace un dossier sous un autre dossier du même périmètre (ou à la racine),
+/// en refusant de le placer sous l'un de ses propres descendants
+pub async fn move_folder(
+    pool: &SqlitePool,
+    folder: &Folder,
+    parent_id: Option<&str>,
+) -> Result<(), sqlx::Error> {
+    let mut tx = pool.begin().await?;
+
+    if let Some(parent_id) = parent_id {
+        if !folder_in_scope(&mut tx, parent_id, folder.owner.as_deref(), folder.group_name.as_deref()).await? {
+            return Err(sqlx::Error::Protocol("Parent folder not found".into()));
+        }
+
+        let cycle = sqlx::query(
+            "WITH RECURSIVE ancestors(id, parent_id) AS (
+                SELECT id, parent_id FROM folders WHERE id = ?
+                UNION ALL
+                SELECT f.id, f.parent_id FROM folders f JOIN ancestors a ON f.id = a.parent_id
+            )
+            SELECT 1 FROM ancestors WHERE id = ?"
+        )
+        .bind(parent_id)
+        .bind(&folder.id)
+        .fetch_optional(&mut *tx)
+        .await?;
+
+        if cycle.is_some() {
+            return Err(sqlx::Error::Protocol("A folder cannot be moved into itself or one of its subfolders".into()));
+        }
+    }
+
+    sqlx::query("UPDATE folders SET parent_id = ?, updated_at = ? WHERE id = ?")
+        .bind(parent_id)
+        .bind(Utc::now().to_rfc3339())
+        .bind(&folder.id)
+        .execute(&mut *tx)
+        .await?;
+
+    tx.commit().await?;
+    Ok(())
+}
+
+/// Supprime un dossier : ses sous-dossiers et ses éléments remontent dans le dossier parent
+pub async fn delete_folder(
+    pool: &SqlitePool,
+    folder: &Folder,
+) -> Result<u64, sqlx::Error> {
+    let mut tx = pool.begin().await?;
+
+    sqlx::query("UPDATE folders SET parent_id = ? WHERE parent_id = ?")
+        .bind(&folder.parent_id)
+        .bind(&folder.id)
+        .execute(&mut *tx)
+        .await?;
+
+    let items_moved = sqlx::query("UPDATE vault_items SET folder_id = ? WHERE folder_id = ?")
+        .bind(&folder.parent_id)
+        .bind(&folder.id)
+        .execute(&mut *tx)
+        .await?
+        .rows_affected();
+
+    sqlx::query("DELETE FROM folders WHERE id = ?")
+        .bind(&folder.id)
+        .execute(&mut *tx)
+        .await?;
+
+    tx.commit().await?;
+    Ok(items_moved)
+}
+
+/// Range un élément dans un dossier de son propre coffre (personnel ou de groupe), ou à la racine
+pub async fn move_item_to_folder(
+    pool: &SqlitePool,
+    item_type: &str,
+    item_id: &str,
+    folder_id: Option<&str>,
+) -> Result<u64, sqlx::Error> {
+    let result = sqlx::query(
+        "UPDATE vault_items SET folder_id = ?
+         WHERE id = ? AND item_type = ? AND deleted_at IS NULL
+           AND (? IS NULL OR EXISTS (
+               SELECT 1 FROM folders f
+               WHERE f.id = ? AND f.owner IS vault_items.username AND f.group_name IS vault_items.group_name
+           ))"
+    )
+    .bind(folder_id)
+    .bind(item_id)
+    .bind(item_type)
+    .bind(folder_id)
+    .bind(folder_id)
+    .execute(pool)
+    .await?;
+
+    Ok(result.rows_affected())
+}
+
+/// Remplace les tags d'un élément (dédoublonnés sans tenir compte de la casse) et met à jour l'index de recherche
+pub async fn set_item_tags(
+    pool: &SqlitePool,
+    item_type: &str,
+    item_id: &str,
+    tags: &[String],
+    crypto: &CryptoService,
+) -> Result<Vec<String>, sqlx::Error> {
+    let mut tx = pool.begin().await?;
+
+    sqlx::query("DELETE FROM item_tags WHERE item_type = ? AND item_id = ?")
+        .bind(item_type)
+        .bind(item_id)
+        .execute(&mut *tx)
+        .await?;
+
+    let mut stored: Vec<String> = Vec::new();
+    for tag in tags.iter().map(|t| t.trim()).filter(|t| !t.is_empty()) {
+        if stored.iter().any(|s| s.to_lowercase() == tag.to_lowercase()) {
+            continue;
+        }
+
+        let sealed_tag = crypto.seal_field(tag)
+            .map_err(|e| sqlx::Error::Protocol(format!("Encryption failed: {}", e)))?;
+
+        sqlx::query("INSERT INTO item_tags (item_type, item_id, tag, tag_index) VALUES (?, ?, ?, ?)")
+            .bind(item_type)
+            .bind(item_id)
+            .bind(&sealed_tag)
+            .bind(crypto.blind_index("tag", &tag.to_lowercase()))
+            .execute(&mut *tx)
+            .await?;
+
+        stored.push(tag.to_string());
+    }
+
+    index_item(&mut tx, item_type, item_id, crypto).await?;
+    tx.commit().await?;
+
+    Ok(stored)
+}
+
+/// Tags déchiffrés d'un ensemble d'éléments, par identifiant
+async fn load_tags(
+    pool: &SqlitePool,
+    item_ids: &[&str],
+    crypto: &CryptoService,
+) -> Result<HashMap<String, Vec<String>>, sqlx::Error> {
+    let mut tags: HashMap<String, Vec<String>> = HashMap::new();
+    if item_ids.is_empty() {
+        return Ok(tags);
+    }
+
+    let placeholders = vec!["?"; item_ids.len()].join(", ");
+    let sql = format!(
+        "SELECT item_id, tag FROM item_tags WHERE item_id IN ({}) ORDER BY rowid",
+        placeholders
+    );
+
+    let mut query = sqlx::query_as::<_, (String, String)>(&sql);
+    for id in item_ids {
+        query = query.bind(*id);
+    }
+
+    for (item_id, tag) in query.fetch_all(pool).await? {
+        tags.entry(item_id).or_default().push(open_field(crypto, &tag)?);
+    }
+
+    Ok(tags)
+}
+
+// ==================== PARTAGE ====================
+
+/// Partage un élément personnel avec un utilisateur, ou change le droit d'un partage existant,
+/// et l'inscrit au journal d'audit. `Protocol` si le destinataire est inconnu ou est le propriétaire
+pub async fn share_item(
+    pool: &SqlitePool,
+    item_type: &str,
+    item_id: &str,
+    username: &str,
+    permission: SharePermission,
+    shared_by: &str,
+) -> Result<ItemShare, sqlx::Error> {
+    if username == shared_by {
+        return Err(sqlx::Error::Protocol("An item cannot be shared with its owner".into()));
+    }
+    if !user_exists(pool, username).await? {
+        return Err(sqlx::Error::Protocol(format!("User '{}' does not exist", username)));
+    }
+
+    let mut tx = pool.begin().await?;
+
+    let share = sqlx::query_as::<_, ItemShare>(
+        "INSERT INTO item_shares (item_type, item_id, username, permission, shared_by, created_at) VALUES (?, ?, ?, ?, ?, ?)
+         ON CONFLICT(item_type, item_id, username) DO UPDATE SET
+             permission = excluded.permission,
+             shared_by = excluded.shared_by
+         RETURNING username, permission, shared_by, created_at"
+    )
+    .bind(item_type)
+    .bind(item_id)
+    .bind(username)
+    .bind(permission.as_str())
+    .bind(shared_by)
+    .bind(Utc::now().to_rfc3339())
+    .fetch_one(&mut *tx)
+    .await?;
+
+    let details = format!("{} {} shared with {} ({})", item_type, item_id, username, permission.as_str());
+    record_audit(&mut *tx, shared_by, "share_item", Some("item"), Some(item_id), &details).await?;
+    tx.commit().await?;
+
+    Ok(share)
+}
+
+/// Retire l'accès d'un utilisateur à un élément partagé et l'inscrit au journal d'audit ;
+/// renvoie le nombre de partages retirés
+pub async fn revoke_share(
+    pool: &SqlitePool,
+    item_type: &str,
+    item_id: &str,
+    username: &str,
+    actor: &str,
+) -> Result<u64, sqlx::Error> {
+    let mut tx = pool.begin().await?;
+
+    let result = sqlx::query("DELETE FROM item_shares WHERE item_type = ? AND item_id = ? AND username = ?")
+        .bind(item_type)
+        .bind(item_id)
+        .bind(username)
+        .execute(&mut *tx)
+        .await?;
+
+    if result.rows_affected() > 0 {
+        let details = format!("{} {} no longer shared with {}", item_type, item_id, username);
+        record_audit(&mut *tx, actor, "revoke_share", Some("item"), Some(item_id), &details).await?;
+    }
+    tx.commit().await?;
+
+    Ok(result.rows_affected())
+}
+
+/// Propriétaire d'un élément personnel et utilisateurs avec qui il est partagé
+pub async fn get_item_access(
+    pool: &SqlitePool,
+    item_type: &str,
+    item_id: &str,
+) -> Result<ItemAccessResponse, sqlx::Error> {
+    let owner: String = sqlx::query_scalar(
+        "SELECT username FROM vault_items WHERE id = ? AND item_type = ? AND group_name IS NULL AND deleted_at IS NULL"
+    )
+    .bind(item_id)
+    .bind(item_type)
+    .fetch_optional(pool)
+    .await?
+    .ok_or(sqlx::Error::RowNotFound)?;
+
+    let shares = sqlx::query_as::<_, ItemShare>(
+        "SELECT username, permission, shared_by, created_at FROM item_shares
+         WHERE item_type = ? AND item_id = ? ORDER BY username"
+    )
+    .bind(item_type)
+    .bind(item_id)
+    .fetch_all(pool)
+    .await?;
+
+    Ok(ItemAccessResponse {
+        item_type: item_type.to_string(),
+        item_id: item_id.to_string(),
+        owner,
+        shares,
+    })
+}
+
+/// Éléments d'autres utilisateurs partagés avec `username`, les plus récents en premier
+pub async fn get_shared_with_me(
+    pool: &SqlitePool,
+    username: &str,
+    crypto: &CryptoService,
+) -> Result<Vec<SharedItem>, sqlx::Error> {
+    let mut shared = sqlx::query_as::<_, SharedItem>(
+        "SELECT t.item_type, t.id, t.username AS owner, t.title, t.url, s.permission, s.shared_by, s.created_at AS shared_at
+         FROM item_shares s
+         JOIN vault_items t ON t.id = s.item_id AND t.item_type = s.item_type
+         WHERE s.username = ? AND t.deleted_at IS NULL
+         ORDER BY s.created_at DESC"
+    )
+    .bind(username)
+    .fetch_all(pool)
+    .await?;
+
+    for item in &mut shared {
+        item.title = open_field(crypto, &item.title)?;
+        item.url = open_field(crypto, &item.url)?;
+    }
+
+    Ok(shared)
 }
 
-pub async fn get_all_accounts(
+// ==================== LIENS À USAGE UNIQUE ====================
+
+/// Lien à enregistrer, contenu déjà chiffré
+pub struct NewSecretLink<'a> {
+    pub id: &'a str,
+    pub content: &'a str,
+    pub salt: &'a str,
+    pub passphrase_required: bool,
+    pub max_views: i64,
+    pub item_type: Option<&'a str>,
+    pub item_id: Option<&'a str>,
+    pub created_by: &'a str,
+    pub expires_at: &'a str,
+}
+
+// Contenu chiffré, sel, phrase secrète requise et fin de suspension d'un lien encore valide
+type SecretLinkRow = (String, String, bool, Option<String>);
+
+/// Enregistre un lien et l'inscrit au journal d'audit
+pub async fn insert_secret_link(pool: &SqlitePool, link: &NewSecretLink<'_>) -> Result<(), sqlx::Error> {
+    let mut tx = pool.begin().await?;
+
+    sqlx::query(
+        "INSERT INTO secret_links (id, content, salt, passphrase_required, max_views, item_type, item_id, created_by, created_at, expires_at)
+         VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?)"
+    )
+    .bind(link.id)
+    .bind(link.content)
+    .bind(link.salt)
+    .bind(link.passphrase_required)
+    .bind(link.max_views)
+    .bind(link.item_type)
+    .bind(link.item_id)
+    .bind(link.created_by)
+    .bind(Utc::now().to_rfc3339())
+    .bind(link.expires_at)
+    .execute(&mut *tx)
+    .await?;
+
+    let details = match (link.item_type, link.item_id) {
+        (Some(item_type), Some(item_id)) => format!("{} {}, {} view(s) until {}", item_type, item_id, link.max_views, link.expires_at),
+        _ => format!("ad-hoc secret, {} view(s) until {}", link.max_views, link.expires_at),
+    };
+    record_audit(&mut *tx, link.created_by, "create_secret_link", Some("secret_link"), Some(link.id), &details).await?;
+    tx.commit().await?;
+
+    Ok(())
+}
+
+/// Contenu chiffré d'un lien qui n'a ni expiré ni épuisé ses consultations
+pub async fn get_secret_link(pool: &SqlitePool, id: &str) -> Result<Option<SecretLinkRow>, sqlx::Error> {
+    let now = Utc::now().to_rfc3339();
+    sqlx::query_as(
+        "SELECT content, salt, passphrase_required, CASE WHEN locked_until > ? THEN locked_until END FROM secret_links
+         WHERE id = ? AND expires_at > ? AND views < max_views"
+    )
+    .bind(&now)
+    .bind(id)
+    .bind(&now)
+    .fetch_optional(pool)
+    .await
+}
+
+/// État public d'un lien valide : expiration, consultations restantes, phrase secrète requise et
+/// fin de la suspension des ouvertures en cours
+pub async fn get_secret_link_status(
     pool: &SqlitePool,
-) -> Result<Vec<User>, sqlx::Error> {
-    let rows = sqlx::query_as::<_, User>(
-        r#"
-            SELECT * FROM users;
-        "#
+    id: &str,
+) -> Result<Option<(String, i64, bool, Option<String>)>, sqlx::Error> {
+    let now = Utc::now().to_rfc3339();
+    sqlx::query_as(
+        "SELECT expires_at, max_views - views, passphrase_required, CASE WHEN locked_until > ? THEN locked_until END
+         FROM secret_links WHERE id = ? AND expires_at > ? AND views < max_views"
+    )
+    .bind(&now)
+    .bind(id)
+    .bind(&now)
+    .fetch_optional(pool)
+    .await
+}
+
+/// Décompte une consultation ; le lien est détruit à la dernière. Renvoie le nombre de
+/// consultations restantes, `None` si le lien n'est plus valide (consultation concurrente)
+pub async fn consume_secret_link(pool: &SqlitePool, id: &str) -> Result<Option<i64>, sqlx::Error> {
+    let mut tx = pool.begin().await?;
+
+    let remaining: Option<i64> = sqlx::query_scalar(
+        "UPDATE secret_links SET views = views + 1, failed_attempts = 0
+         WHERE id = ? AND expires_at > ? AND views < max_views
+         RETURNING max_views - views"
+    )
+    .bind(id)
+    .bind(Utc::now().to_rfc3339())
+    .fetch_optional(&mut *tx)
+    .await?;
+
+    let Some(remaining) = remaining else {
+        return Ok(None);
+    };
+
+    if remaining == 0 {
+        sqlx::query("DELETE FROM secret_links WHERE id = ?")
+            .bind(id)
+            .execute(&mut *tx)
+            .await?;
+    }
+
+    let details = if remaining == 0 { "last view, link destroyed".to_string() } else { format!("{} view(s) left", remaining) };
+    record_audit(&mut *tx, "anonymous", "open_secret_link", Some("secret_link"), Some(id), &details).await?;
+    tx.commit().await?;
+
+    Ok(Some(remaining))
+}
+
+/// Compte un essai de clé ou de phrase secrète erroné ; toutes les `MAX_FAILED_ATTEMPTS` erreurs,
+/// les ouvertures sont suspendues (voir `links::lockout`). Renvoie la fin de la suspension en
+/// cours, y compris celle qu'un essai concurrent vient de déclencher
+pub async fn record_secret_link_failure(pool: &SqlitePool, id: &str) -> Result<Option<String>, sqlx::Error> {
+    let now = Utc::now();
+    let mut tx = pool.begin().await?;
+
+    // Un essai arrivé pendant une suspension n'est pas décompté
+    let failures: Option<i64> = sqlx::query_scalar(
+        "UPDATE secret_links SET failed_attempts = failed_attempts + 1
+         WHERE id = ? AND (locked_until IS NULL OR locked_until <= ?)
+         RETURNING failed_attempts"
+    )
+    .bind(id)
+    .bind(now.to_rfc3339())
+    .fetch_optional(&mut *tx)
+    .await?;
+
+    let locked_until = match failures {
+        Some(failures) => match links::lockout(failures) {
+            Some(duration) => {
+                let until = (now + duration).to_rfc3339();
+                sqlx::query("UPDATE secret_links SET locked_until = ? WHERE id = ?")
+                    .bind(&until)
+                    .bind(id)
+                    .execute(&mut *tx)
+                    .await?;
+                let details = format!("{} failed attempts, locked until {}", failures, until);
+                record_audit(&mut *tx, "anonymous", "lock_secret_link", Some("secret_link"), Some(id), &details).await?;
+                Some(until)
+            }
+            None => None,
+        },
+        None => sqlx::query_scalar::<_, String>("SELECT locked_until FROM secret_links WHERE id = ? AND locked_until > ?")
+            .bind(id)
+            .bind(now.to_rfc3339())
+            .fetch_optional(&mut *tx)
+            .await?,
+    };
+    tx.commit().await?;
+
+    Ok(locked_until)
+}
+
+/// Liens encore valides créés par un utilisateur, les plus récents en premier
+pub async fn get_secret_links(pool: &SqlitePool, username: &str) -> Result<Vec<SecretLinkInfo>, sqlx::Error> {
+    sqlx::query_as::<_, SecretLinkInfo>(
+        "SELECT id, item_type, item_id, views, max_views, passphrase_required, created_at, expires_at
+         FROM secret_links WHERE created_by = ? AND expires_at > ? ORDER BY created_at DESC"
     )
+    .bind(username)
+    .bind(Utc::now().to_rfc3339())
     .fetch_all(pool)
     .await
-    .map_err(|e| {
-        log::error!("Database query failed for get_all_accounts: {:?}", e);
-        e
-    })?;
-    
-    Ok(rows)
 }
 
-pub async fn create_group(
+/// Détruit un lien avant son expiration (réservé à son créateur) et l'inscrit au journal d'audit
+pub async fn delete_secret_link(pool: &SqlitePool, id: &str, username: &str) -> Result<u64, sqlx::Error> {
+    let mut tx = pool.begin().await?;
+
+    let result = sqlx::query("DELETE FROM secret_links WHERE id = ? AND created_by = ?")
+        .bind(id)
+        .bind(username)
+        .execute(&mut *tx)
+        .await?;
+
+    if result.rows_affected() > 0 {
+        record_audit(&mut *tx, username, "revoke_secret_link", Some("secret_link"), Some(id), "").await?;
+    }
+    tx.commit().await?;
+
+    Ok(result.rows_affected())
+}
+
+/// Supprime les liens expirés
+pub async fn purge_expired_secret_links(pool: &SqlitePool) -> Result<u64, sqlx::Error> {
+    let result = sqlx::query("DELETE FROM secret_links WHERE expires_at <= ?")
+        .bind(Utc::now().to_rfc3339())
+        .execute(pool)
+        .await?;
+
+    Ok(result.rows_affected())
+}
+
+// ==================== DEMANDES D'ACCÈS ====================
+
+const ACCESS_REQUEST_COLUMNS: &str = "id, group_name, username, role, justification, duration_hours, status, created_at, \
+    decided_by, decided_at, decision_note, access_expires_at";
+
+/// Sélection des demandes à lister : celles d'un demandeur, celles des groupes dont un
+/// utilisateur est propriétaire (directement ou par héritage), ou toutes (administrateurs)
+pub enum AccessRequestScope<'a> {
+    Requester(&'a str),
+    Reviewer(&'a str),
+    All,
+}
+
+/// Propriétaires effectifs d'un groupe : ses propriétaires et ceux de ses groupes parents
+pub async fn group_owners(pool: &SqlitePool, group_name: &str) -> Result<Vec<String>, sqlx::Error> {
+    sqlx::query_scalar(&format!(
+        "{GROUP_LINEAGE} SELECT DISTINCT ug.username FROM user_groups ug JOIN lineage l ON ug.group_name = l.name
+         WHERE ug.role = 'owner' AND (ug.expires_at IS NULL OR ug.expires_at > ?) ORDER BY ug.username"
+    ))
+    .bind(group_name)
+    .bind(Utc::now().to_rfc3339())
+    .fetch_all(pool)
+    .await
+}
+
+/// Enregistre une demande d'adhésion et l'inscrit au journal d'audit. `RowNotFound` si le groupe
+/// n'existe pas ; `Protocol` si l'utilisateur a déjà ce rôle ou une demande en attente, ou s'il
+/// demande un accès temporaire à un groupe dont il est déjà membre
+pub async fn create_access_request(
     pool: &SqlitePool,
-    body: CreateGroupRequest,
-) -> Result<CreateGroupResponse, String> {
-    let group_name = body.group_name.trim();
-    
-    // Vérification du nom vide
-    if group_name.is_empty() {
-        return Err("Le nom du groupe ne peut pas être vide.".to_string());
+    group_name: &str,
+    username: &str,
+    role: GroupRole,
+    justification: &str,
+    duration_hours: Option<i64>,
+) -> Result<AccessRequest, sqlx::Error> {
+    if !group_exists(pool, group_name).await? {
+        return Err(sqlx::Error::RowNotFound);
     }
-    
-    // Vérification si le groupe existe déjà
-    let check = sqlx::query("SELECT id FROM user_groups WHERE group_name = ?")
-        .bind(group_name)
-        .fetch_optional(pool)
-        .await
-        .map_err(|e| e.to_string())?;
-    
-    if check.is_some() {
-        return Err("Le groupe existe déjà".to_string());
+    if group_role(pool, group_name, username).await?.is_some_and(|current| current >= role) {
+        return Err(sqlx::Error::Protocol(format!(
+            "You already have the {} role in group '{}'", role, group_name
+        )));
     }
-    
-    let group_id = Uuid::new_v4().to_string();
-    let created_at = Utc::now().to_rfc3339();
-    
-    // Insertion des utilisateurs dans le groupe
-    for username in &body.usernames {
-        let id = Uuid::new_v4().to_string();
-        sqlx::query("INSERT INTO user_groups (id, username, group_name, created_at) VALUES (?, ?, ?, ?)")
-            .bind(&id)
-            .bind(username)
-            .bind(group_name)
-            .bind(&created_at)
-            .execute(pool)
-            .await
-            .map_err(|e| e.to_string())?;
+
+    let mut tx = pool.begin().await?;
+
+    let member: Option<i64> = sqlx::query_scalar(
+        "SELECT 1 FROM user_groups WHERE group_name = ? AND username = ? AND (expires_at IS NULL OR expires_at > ?)"
+    )
+    .bind(group_name)
+    .bind(username)
+    .bind(Utc::now().to_rfc3339())
+    .fetch_optional(&mut *tx)
+    .await?;
+    if member.is_some() && duration_hours.is_some() {
+        return Err(sqlx::Error::Protocol(
+            "Temporary access can only be requested for a group you are not a member of".into(),
+        ));
     }
-    
-    Ok(CreateGroupResponse {
-        id: group_id,
-        created_at,
-        message: "Groupe créé avec succès".to_string(),
-    })
+
+    let request = sqlx::query_as::<_, AccessRequest>(&format!(
+        "INSERT INTO access_requests (id, group_name, username, role, justification, duration_hours, created_at)
+         VALUES (?, ?, ?, ?, ?, ?, ?)
+         ON CONFLICT(group_name, username) WHERE status = 'pending' DO NOTHING
+         RETURNING {ACCESS_REQUEST_COLUMNS}"
+    ))
+    .bind(Uuid::new_v4().to_string())
+    .bind(group_name)
+    .bind(username)
+    .bind(role.as_str())
+    .bind(justification)
+    .bind(duration_hours)
+    .bind(Utc::now().to_rfc3339())
+    .fetch_optional(&mut *tx)
+    .await?
+    .ok_or_else(|| sqlx::Error::Protocol(format!("A request for group '{}' is already pending", group_name)))?;
+
+    let details = match duration_hours {
+        Some(hours) => format!("{} for {}h: {}", role, hours, justification),
+        None => format!("{}: {}", role, justification),
+    };
+    record_audit(&mut *tx, username, "request_access", Some("group"), Some(group_name), &details).await?;
+    tx.commit().await?;
+
+    Ok(request)
 }
 
-pub async fn add_account(
-    pool: &SqlitePool, 
-    body: AddUserGroups
-) -> Result<AddResponseGroups, String> {
-    // Vérifie si le groupe existe
-    let group_exists = sqlx::query("SELECT group_name FROM user_groups WHERE group_name = ?")
-        .bind(&body.group_name)
+pub async fn get_access_request(pool: &SqlitePool, id: &str) -> Result<Option<AccessRequest>, sqlx::Error> {
+    sqlx::query_as::<_, AccessRequest>(&format!("SELECT {ACCESS_REQUEST_COLUMNS} FROM access_requests WHERE id = ?"))
+        .bind(id)
         .fetch_optional(pool)
         .await
-        .map_err(|e| e.to_string())?;
-    
-    if group_exists.is_none() {
-        return Err("Le groupe n'existe pas.".to_string());
+}
+
+/// Approuve ou refuse une demande en attente et inscrit la décision au journal d'audit.
+/// L'approbation ajoute le demandeur au groupe, pour `duration_hours` s'il est donné (à défaut la
+/// durée demandée), ou élève son rôle s'il en est déjà membre (l'adhésion devient alors permanente). `RowNotFound` si la demande
+/// n'existe pas, `Protocol` si elle a déjà été traitée
+pub async fn decide_access_request(
+    pool: &SqlitePool,
+    id: &str,
+    approve: bool,
+    decided_by: &str,
+    note: Option<&str>,
+    duration_hours: Option<i64>,
+) -> Result<AccessRequest, sqlx::Error> {
+    let mut tx = pool.begin().await?;
+
+    let request = sqlx::query_as::<_, AccessRequest>(&format!("SELECT {ACCESS_REQUEST_COLUMNS} FROM access_requests WHERE id = ?"))
+        .bind(id)
+        .fetch_optional(&mut *tx)
+        .await?
+        .ok_or(sqlx::Error::RowNotFound)?;
+    if request.status != "pending" {
+        return Err(sqlx::Error::Protocol(format!("This request is already {}", request.status)));
     }
-    
-    // Vérifie si l'utilisateur est déjà dans le groupe
-    let user_exists = sqlx::query("SELECT id FROM user_groups WHERE group_name = ? AND username = ?")
-        .bind(&body.group_name)
-        .bind(&body.username)
-        .fetch_optional(pool)
-        .await
-        .map_err(|e| e.to_string())?;
-    
-    if user_exists.is_some() {
-        return Err("L'utilisateur est déjà dans le groupe".to_string());
+
+    let now = Utc::now();
+    let mut access_expires_at = None;
+
+    if approve {
+        let role = parse_role(&request.role)?;
+        let current: Option<(String, bool)> = sqlx::query_as(
+            "SELECT role, expires_at IS NULL OR expires_at > ? FROM user_groups WHERE group_name = ? AND username = ?"
+        )
+        .bind(now.to_rfc3339())
+        .bind(&request.group_name)
+        .bind(&request.username)
+        .fetch_optional(&mut *tx)
+        .await?;
+
+        match current {
+            // Adhésion échue conservée (dernier membre du groupe) : elle est remplacée
+            Some((_, false)) => {
+                access_expires_at = duration_hours
+                    .or(request.duration_hours)
+                    .map(|hours| (now + chrono::Duration::hours(hours)).to_rfc3339());
+
+                sqlx::query("UPDATE user_groups SET role = ?, expires_at = ? WHERE group_name = ? AND username = ?")
+                    .bind(role.as_str())
+                    .bind(&access_expires_at)
+                    .bind(&request.group_name)
+                    .bind(&request.username)
+                    .execute(&mut *tx)
+                    .await?;
+            }
+            None => {
+                access_expires_at = duration_hours
+                    .or(request.duration_hours)
+                    .map(|hours| (now + chrono::Duration::hours(hours)).to_rfc3339());
+
+                sqlx::query("INSERT INTO user_groups (id, username, group_name, role, created_at, expires_at) VALUES (?, ?, ?, ?, ?, ?)")
+                    .bind(Uuid::new_v4().to_string())
+                    .bind(&request.username)
+                    .bind(&request.group_name)
+                    .bind(role.as_str())
+                    .bind(now.to_rfc3339())
+                    .bind(&access_expires_at)
+                    .execute(&mut *tx)
+                    .await?;
+            }
+            Some(_) if duration_hours.or(request.duration_hours).is_some() => {
+                return Err(sqlx::Error::Protocol(format!(
+                    "{} is already a member of group '{}', temporary access cannot be granted",
+                    request.username, request.group_name
+                )));
+            }
+            Some((current, true)) => {
+                // Une demande sans durée rend permanente une adhésion temporaire
+                let role = parse_role(&current)?.max(role);
+                sqlx::query("UPDATE user_groups SET role = ?, expires_at = NULL WHERE group_name = ? AND username = ?")
+                    .bind(role.as_str())
+                    .bind(&request.group_name)
+                    .bind(&request.username)
+                    .execute(&mut *tx)
+                    .await?;
+            }
+        }
     }
-    
-    // Ajoute l'utilisateur dans le groupe
-    let id = Uuid::new_v4().to_string();
-    let created_at = Utc::now().to_rfc3339();
-    
-    sqlx::query("INSERT INTO user_groups (id, username, group_name, created_at) VALUES (?, ?, ?, ?)")
-        .bind(&id)
-        .bind(&body.username)
-        .bind(&body.group_name)
-        .bind(&created_at)
-        .execute(pool)
+
+    let request = sqlx::query_as::<_, AccessRequest>(&format!(
+        "UPDATE access_requests SET status = ?, decided_by = ?, decided_at = ?, decision_note = ?, access_expires_at = ?
+         WHERE id = ? RETURNING {ACCESS_REQUEST_COLUMNS}"
+    ))
+    .bind(if approve { "approved" } else { "denied" })
+    .bind(decided_by)
+    .bind(now.to_rfc3339())
+    .bind(note)
+    .bind(&access_expires_at)
+    .bind(id)
+    .fetch_one(&mut *tx)
+    .await?;
+
+    let mut details = format!("{} ({}) requested by {}", request.id, request.role, request.username);
+    if let Some(expires_at) = &access_expires_at {
+        details.push_str(&format!(", until {}", expires_at));
+    }
+    if let Some(note) = note {
+        details.push_str(&format!(": {}", note));
+    }
+    let action = if approve { "approve_access" } else { "deny_access" };
+    record_audit(&mut *tx, decided_by, action, Some("group"), Some(&request.group_name), &details).await?;
+    tx.commit().await?;
+
+    Ok(request)
+}
+
+/// Annule une demande en attente de son auteur ; renvoie le nombre de demandes annulées
+pub async fn cancel_access_request(pool: &SqlitePool, id: &str, username: &str) -> Result<u64, sqlx::Error> {
+    let mut tx = pool.begin().await?;
+
+    let group_name: Option<String> = sqlx::query_scalar(
+        "UPDATE access_requests SET status = 'cancelled', decided_by = username, decided_at = ?
+         WHERE id = ? AND username = ? AND status = 'pending' RETURNING group_name"
+    )
+    .bind(Utc::now().to_rfc3339())
+    .bind(id)
+    .bind(username)
+    .fetch_optional(&mut *tx)
+    .await?;
+
+    let Some(group_name) = group_name else {
+        return Ok(0);
+    };
+
+    record_audit(&mut *tx, username, "cancel_access_request", Some("group"), Some(&group_name), id).await?;
+    tx.commit().await?;
+
+    Ok(1)
+}
+
+/// Historique des demandes d'accès, filtré par groupe et par statut
+pub async fn get_access_requests(
+    pool: &SqlitePool,
+    list: &ListQuery,
+    scope: AccessRequestScope<'_>,
+    group_name: Option<&str>,
+    status: Option<&str>,
+) -> Result<Page<AccessRequest>, sqlx::Error> {
+    let now = Utc::now().to_rfc3339();
+    let mut from_sql = String::from("FROM access_requests WHERE 1 = 1");
+    let mut binds: Vec<&str> = Vec::new();
+
+    match scope {
+        AccessRequestScope::Requester(username) => {
+            from_sql.push_str(" AND username = ?");
+            binds.push(username);
+        }
+        AccessRequestScope::Reviewer(username) => {
+            from_sql.push_str(&format!(" AND group_name IN ({OWNED_GROUPS})"));
+            binds.push(username);
+            binds.push(&now);
+        }
+        AccessRequestScope::All => {}
+    }
+    if let Some(group_name) = group_name {
+        from_sql.push_str(" AND group_name = ?");
+        binds.push(group_name);
+    }
+    if let Some(status) = status {
+        from_sql.push_str(" AND status = ?");
+        binds.push(status);
+    }
+
+    list.fetch_page::<AccessRequest>(pool, ACCESS_REQUEST_COLUMNS, &from_sql, &binds)
         .await
-        .map_err(|e| e.to_string())?;
-    
-    Ok(AddResponseGroups {
-        id,
-        username: body.username,
-        group_name: body.group_name,
-        created_at,
-        message: "Utilisateur ajouté au groupe avec succès".to_string(),
-    })
+        .map_err(|e| {
+            log::error!("Database query failed for get_access_requests: {:?}", e);
+            e
+        })
 }
 
-pub async fn fetch_groups(pool: &SqlitePool) -> Result<Vec<GetAllGroups>, sqlx::Error> {
-    let groups = sqlx::query_as::<_, GetAllGroups>(
-        r#"
-        SELECT 
-            group_name AS name,
-            COUNT(DISTINCT username) AS member_count,
-            MIN(created_at) AS created_at,
-            '' AS description
-        FROM user_groups
-        GROUP BY group_name
-        ORDER BY created_at DESC
-        "#
+/// Adhésion temporaire retirée à son échéance
+pub struct ExpiredMembership {
+    pub group_name: String,
+    pub username: String,
+    pub role: String,
+}
+
+/// Retire les adhésions temporaires échues et clôt les demandes qui les avaient accordées. Le dernier
+/// membre d'un groupe est conservé pour ne pas faire disparaître le groupe, mais son adhésion échue
+/// ne donne plus aucun droit
+pub async fn expire_memberships(pool: &SqlitePool) -> Result<Vec<ExpiredMembership>, sqlx::Error> {
+    let now = Utc::now().to_rfc3339();
+    let mut tx = pool.begin().await?;
+
+    let expired: Vec<(String, String, String)> = sqlx::query_as(
+        "DELETE FROM user_groups
+         WHERE expires_at IS NOT NULL AND expires_at <= ?
+           AND EXISTS (SELECT 1 FROM user_groups o
+                       WHERE o.group_name = user_groups.group_name AND o.username != user_groups.username)
+         RETURNING group_name, username, role"
+    )
+    .bind(&now)
+    .fetch_all(&mut *tx)
+    .await?;
+
+    for (group_name, username, role) in &expired {
+        sqlx::query(
+            "UPDATE access_requests SET status = 'expired'
+             WHERE group_name = ? AND username = ? AND status = 'approved' AND access_expires_at IS NOT NULL"
+        )
+        .bind(group_name)
+        .bind(username)
+        .execute(&mut *tx)
+        .await?;
+
+        let details = format!("{} ({})", username, role);
+        record_audit(&mut *tx, "scheduler", "membership_expired", Some("group"), Some(group_name), &details).await?;
+    }
+    tx.commit().await?;
+
+    Ok(expired
+        .into_iter()
+        .map(|(group_name, username, role)| ExpiredMembership { group_name, username, role })
+        .collect())
+}
+
+/// Fixe l'échéance d'une adhésion directe (`None` : permanente) et l'inscrit au journal d'audit.
+/// `RowNotFound` si l'utilisateur n'est pas membre du groupe
+pub async fn set_membership_expiry(
+    pool: &SqlitePool,
+    group_name: &str,
+    username: &str,
+    expires_at: Option<&str>,
+    actor: &str,
+) -> Result<GroupMember, sqlx::Error> {
+    let mut tx = pool.begin().await?;
+
+    let member = sqlx::query_as::<_, GroupMember>(
+        "UPDATE user_groups SET expires_at = ? WHERE group_name = ? AND username = ?
+         RETURNING username, role, created_at, expires_at"
+    )
+    .bind(expires_at)
+    .bind(group_name)
+    .bind(username)
+    .fetch_optional(&mut *tx)
+    .await?
+    .ok_or(sqlx::Error::RowNotFound)?;
+
+    // La demande qui avait accordé l'accès suit la nouvelle échéance
+    sqlx::query(
+        "UPDATE access_requests SET access_expires_at = ?
+         WHERE group_name = ? AND username = ? AND status = 'approved' AND access_expires_at IS NOT NULL"
+    )
+    .bind(expires_at)
+    .bind(group_name)
+    .bind(username)
+    .execute(&mut *tx)
+    .await?;
+
+    let details = format!("{} until {}", username, expires_at.unwrap_or("permanent"));
+    record_audit(&mut *tx, actor, "membership_expiry", Some("group"), Some(group_name), &details).await?;
+    tx.commit().await?;
+
+    Ok(member)
+}
+
+/// Adhésions temporaires échues (pas encore retirées) ou arrivant à échéance dans `within_days` jours
+pub async fn get_expiring_memberships(pool: &SqlitePool, within_days: i64) -> Result<Vec<ExpiringMembership>, sqlx::Error> {
+    sqlx::query_as::<_, ExpiringMembership>(
+        "SELECT group_name, username, role, created_at, expires_at FROM user_groups
+         WHERE expires_at IS NOT NULL AND expires_at <= ?
+         ORDER BY expires_at, group_name, username"
     )
+    .bind((Utc::now() + chrono::Duration::days(within_days)).to_rfc3339())
     .fetch_all(pool)
     .await
-    .map_err(|e| {
-        log::error!("Database query failed for fetch_groups: {:?}", e);
-        e
-    })?;
+}
+
+// ==================== EMPRUNTS ====================
+
+const LEASE_COLUMNS: &str = "id, item_type, item_id, group_name, username, reason, status, checked_out_at, expires_at, \
+    returned_at, returned_by";
+
+/// Clôt les emprunts arrivés à échéance sans retour (tous, ou ceux d'un élément) : le secret,
+/// resté entre les mains du titulaire, est signalé comme à renouveler
+async fn close_expired_leases(
+    tx: &mut Transaction<'_, Sqlite>,
+    item: Option<(&str, &str)>,
+) -> Result<Vec<ItemLease>, sqlx::Error> {
+    let now = Utc::now().to_rfc3339();
+    let (item_type, item_id) = item.unzip();
+
+    let expired = sqlx::query_as::<_, ItemLease>(&format!(
+        "UPDATE item_leases SET status = 'expired', returned_at = expires_at
+         WHERE status = 'active' AND expires_at <= ? AND (? IS NULL OR (item_type = ? AND item_id = ?))
+         RETURNING {LEASE_COLUMNS}"
+    ))
+    .bind(&now)
+    .bind(item_type)
+    .bind(item_type)
+    .bind(item_id)
+    .fetch_all(&mut **tx)
+    .await?;
+
+    for lease in &expired {
+        sqlx::query("UPDATE vault_items SET rotation_requested_at = COALESCE(rotation_requested_at, ?) WHERE id = ? AND item_type = ?")
+            .bind(&now)
+            .bind(&lease.item_id)
+            .bind(&lease.item_type)
+            .execute(&mut **tx)
+            .await?;
+
+        let details = format!("held by {} since {}", lease.username, lease.checked_out_at);
+        record_audit(&mut **tx, "scheduler", "lease_expired", Some(&lease.item_type), Some(&lease.item_id), &details).await?;
+    }
 
-    Ok(groups)
+    Ok(expired)
 }
 
-pub async fn delete_groups(pool: &SqlitePool, body: DeleteGroups) -> Result<DeleteGroups, String> {
-    // Vérifie si le groupe existe
-    let group_exists = sqlx::query("SELECT group_name FROM user_groups WHERE group_name = ?")
-        .bind(&body.group_name)
-        .fetch_optional(pool)
-        .await
-        .map_err(|e| e.to_string())?;
-    
-    if group_exists.is_none() {
-        return Err("Le groupe n'existe pas.".to_string());
-    }
-    
-    // Supprime tous les utilisateurs du groupe
-    let result = sqlx::query("DELETE FROM user_groups WHERE group_name = ?")
-        .bind(&body.group_name)
-        .execute(pool)
-        .await
-        .map_err(|e| e.to_string())?;
-    
-    if result.rows_affected() == 0 {
-        return Err("Aucun membre trouvé dans le groupe.".to_string());
-    }
-    
-    log::info!("Groupe '{}' supprimé avec {} membre(s)", body.group_name, result.rows_affected());
-    
-    Ok(body)
+/// Clôt tous les emprunts échus ; renvoie ceux qui viennent de l'être
+pub async fn expire_leases(pool: &SqlitePool) -> Result<Vec<ItemLease>, sqlx::Error> {
+    let mut tx = pool.begin().await?;
+    let expired = close_expired_leases(&mut tx, None).await?;
+    tx.commit().await?;
+
+    Ok(expired)
 }
 
-pub async fn get_groups_by_username(
+/// Emprunt en cours d'un élément, s'il n'est pas échu
+pub async fn active_lease(pool: &SqlitePool, item_type: &str, item_id: &str) -> Result<Option<ItemLease>, sqlx::Error> {
+    sqlx::query_as::<_, ItemLease>(&format!(
+        "SELECT {LEASE_COLUMNS} FROM item_leases
+         WHERE item_type = ? AND item_id = ? AND status = 'active' AND expires_at > ?"
+    ))
+    .bind(item_type)
+    .bind(item_id)
+    .bind(Utc::now().to_rfc3339())
+    .fetch_optional(pool)
+    .await
+}
+
+/// Emprunte un identifiant de groupe jusqu'à `expires_at` et l'inscrit au journal d'audit.
+/// `RowNotFound` si l'élément n'existe pas ; `Protocol` s'il est déjà emprunté
+pub async fn checkout_item(
     pool: &SqlitePool,
+    item_type: &str,
+    item_id: &str,
     username: &str,
-) -> Result<Vec<GetAllGroups>, sqlx::Error> {
-    let groups = sqlx::query_as::<_, GetAllGroups>(
-        r#"
-        SELECT 
-            group_name AS name,
-            COUNT(DISTINCT username) AS member_count,
-            MIN(created_at) AS created_at,
-            '' AS description
-        FROM user_groups
-        WHERE group_name IN (
-            SELECT DISTINCT group_name 
-            FROM user_groups 
-            WHERE username = ?
-        )
-        GROUP BY group_name
-        ORDER BY created_at DESC
-        "#
+    reason: Option<&str>,
+    expires_at: &str,
+) -> Result<ItemLease, sqlx::Error> {
+    let mut tx = pool.begin().await?;
+
+    let group_name: String = sqlx::query_scalar(
+        "SELECT group_name FROM vault_items WHERE id = ? AND item_type = ? AND group_name IS NOT NULL AND deleted_at IS NULL"
     )
+    .bind(item_id)
+    .bind(item_type)
+    .fetch_optional(&mut *tx)
+    .await?
+    .ok_or(sqlx::Error::RowNotFound)?;
+
+    close_expired_leases(&mut tx, Some((item_type, item_id))).await?;
+
+    let lease = sqlx::query_as::<_, ItemLease>(&format!(
+        "INSERT INTO item_leases (id, item_type, item_id, group_name, username, reason, checked_out_at, expires_at)
+         VALUES (?, ?, ?, ?, ?, ?, ?, ?)
+         ON CONFLICT(item_type, item_id) WHERE status = 'active' DO NOTHING
+         RETURNING {LEASE_COLUMNS}"
+    ))
+    .bind(Uuid::new_v4().to_string())
+    .bind(item_type)
+    .bind(item_id)
+    .bind(&group_name)
     .bind(username)
-    .fetch_all(pool)
-    .await
-    .map_err(|e| {
-        log::error!("Database query failed for get_groups_by_username: {:?}", e);
-        e
-    })?;
+    .bind(reason)
+    .bind(Utc::now().to_rfc3339())
+    .bind(expires_at)
+    .fetch_optional(&mut *tx)
+    .await?;
+
+    let Some(lease) = lease else {
+        let (holder, until): (String, String) = sqlx::query_as(
+            "SELECT username, expires_at FROM item_leases WHERE item_type = ? AND item_id = ? AND status = 'active'"
+        )
+        .bind(item_type)
+        .bind(item_id)
+        .fetch_one(&mut *tx)
+        .await?;
+        return Err(sqlx::Error::Protocol(format!("This credential is checked out by {} until {}", holder, until)));
+    };
 
-    Ok(groups)
+    let mut details = format!("until {}", expires_at);
+    if let Some(reason) = reason {
+        details.push_str(&format!(": {}", reason));
+    }
+    record_audit(&mut *tx, username, "checkout", Some(item_type), Some(item_id), &details).await?;
+    tx.commit().await?;
+
+    Ok(lease)
 }
 
-/// Ajoute un compte dans un groupe avec chiffrement
-pub async fn insert_account_in_group(
+/// Rend l'emprunt en cours d'un élément et l'inscrit au journal d'audit. Sans `force`, seul le
+/// titulaire peut le rendre ; avec `force` (propriétaire du groupe), l'emprunt est libéré.
+/// `remind` signale le secret comme à renouveler. `RowNotFound` s'il n'y a pas d'emprunt à rendre
+pub async fn checkin_item(
+    pool: &SqlitePool,
+    item_type: &str,
+    item_id: &str,
+    actor: &str,
+    force: bool,
+    remind: bool,
+) -> Result<ItemLease, sqlx::Error> {
+    let mut tx = pool.begin().await?;
+    let now = Utc::now().to_rfc3339();
+
+    close_expired_leases(&mut tx, Some((item_type, item_id))).await?;
+
+    let lease = sqlx::query_as::<_, ItemLease>(&format!(
+        "UPDATE item_leases SET status = CASE WHEN username = ? THEN 'returned' ELSE 'released' END,
+             returned_at = ?, returned_by = ?
+         WHERE item_type = ? AND item_id = ? AND status = 'active' AND (? OR username = ?)
+         RETURNING {LEASE_COLUMNS}"
+    ))
+    .bind(actor)
+    .bind(&now)
+    .bind(actor)
+    .bind(item_type)
+    .bind(item_id)
+    .bind(force)
+    .bind(actor)
+    .fetch_optional(&mut *tx)
+    .await?
+    .ok_or(sqlx::Error::RowNotFound)?;
+
+    if remind {
+        sqlx::query("UPDATE vault_items SET rotation_requested_at = COALESCE(rotation_requested_at, ?) WHERE id = ? AND item_type = ?")
+            .bind(&now)
+            .bind(item_id)
+            .bind(item_type)
+            .execute(&mut *tx)
+            .await?;
+    }
+
+    let (action, details) = if lease.status == "released" {
+        ("release_lease", format!("held by {}", lease.username))
+    } else {
+        ("checkin", format!("held since {}", lease.checked_out_at))
+    };
+    record_audit(&mut *tx, actor, action, Some(item_type), Some(item_id), &details).await?;
+    tx.commit().await?;
+
+    Ok(lease)
+}
+
+/// Emprunts d'un groupe, les plus récents d'abord, éventuellement limités à un élément ou aux emprunts en cours
+pub async fn get_leases(
     pool: &SqlitePool,
-    user_account: &str,
-    password_account: &str,
-    title: &str,
-    url: &str,
     group_name: &str,
-    crypto: &CryptoService,
-) -> Result<(String, String), sqlx::Error> {
-    // Vérifie si le groupe existe
-    let group_exists = sqlx::query("SELECT group_name FROM user_groups WHERE group_name = ?")
-        .bind(group_name)
-        .fetch_optional(pool)
+    item: Option<(&str, &str)>,
+    active_only: bool,
+) -> Result<Vec<ItemLease>, sqlx::Error> {
+    let (item_type, item_id) = item.unzip();
+
+    sqlx::query_as::<_, ItemLease>(&format!(
+        "SELECT {LEASE_COLUMNS} FROM item_leases
+         WHERE group_name = ? AND (? IS NULL OR (item_type = ? AND item_id = ?))
+           AND (NOT ? OR (status = 'active' AND expires_at > ?))
+         ORDER BY checked_out_at DESC
+         LIMIT 100"
+    ))
+    .bind(group_name)
+    .bind(item_type)
+    .bind(item_type)
+    .bind(item_id)
+    .bind(active_only)
+    .bind(Utc::now().to_rfc3339())
+    .fetch_all(pool)
+    .await
+}
+
+// ==================== ACCÈS D'URGENCE ====================
+
+const EMERGENCY_REQUEST_COLUMNS: &str = "id, target_type, target_name, requester, reason, status, created_at, grant_at, \
+    decided_by, decided_at, decision_note, override_requested_by, granted_at, access_expires_at";
+
+/// Sélection des demandes d'urgence à lister : celles d'un contact, celles visant le coffre d'un
+/// utilisateur ou les groupes dont il est propriétaire, ou toutes (administrateurs)
+pub enum EmergencyScope<'a> {
+    Requester(&'a str),
+    Reviewer(&'a str),
+    All,
+}
+
+/// Destinataires des notifications d'une demande d'urgence : le titulaire du coffre (ou les
+/// propriétaires effectifs du groupe), le demandeur et les administrateurs
+pub async fn emergency_recipients(pool: &SqlitePool, request: &EmergencyRequest) -> Result<Vec<String>, sqlx::Error> {
+    let mut recipients = match request.target_type.as_str() {
+        "group" => group_owners(pool, &request.target_name).await?,
+        _ => vec![request.target_name.clone()],
+    };
+    recipients.push(request.requester.clone());
+
+    let admins: Vec<String> = sqlx::query_scalar("SELECT admin_username FROM admin ORDER BY admin_username")
+        .fetch_all(pool)
         .await?;
-    
-    if group_exists.is_none() {
-        return Err(sqlx::Error::Protocol("Group does not exist".into()));
+    recipients.extend(admins);
+
+    recipients.sort();
+    recipients.dedup();
+    Ok(recipients)
+}
+
+/// Désigne (ou met à jour) un contact d'urgence et l'inscrit au journal d'audit.
+/// `Protocol` si le contact n'existe pas ou désigne le titulaire du coffre lui-même
+pub async fn set_emergency_contact(
+    pool: &SqlitePool,
+    target_type: &str,
+    target_name: &str,
+    contact: &str,
+    wait_hours: i64,
+    actor: &str,
+) -> Result<EmergencyContact, sqlx::Error> {
+    if target_type == "user" && contact == target_name {
+        return Err(sqlx::Error::Protocol("You cannot be your own emergency contact".into()));
+    }
+    if !user_exists(pool, contact).await? {
+        return Err(sqlx::Error::Protocol(format!("User '{}' does not exist", contact)));
     }
 
-    let id = Uuid::new_v4().to_string();
-    let created_at = Utc::now().to_rfc3339();
-    
-    // Chiffre le mot de passe
-    let encrypted_password = crypto.encrypt_and_encode(password_account)
-        .map_err(|e| sqlx::Error::Protocol(format!("Encryption failed: {}", e)))?;
-   
-    sqlx::query(
-        "INSERT INTO account_in_groups (id, title, user_account, password_account, url, group_name, created_at) 
-         VALUES (?, ?, ?, ?, ?, ?, ?)"
+    let mut tx = pool.begin().await?;
+
+    let entry = sqlx::query_as::<_, EmergencyContact>(
+        "INSERT INTO emergency_contacts (target_type, target_name, contact, wait_hours, added_by, created_at)
+         VALUES (?, ?, ?, ?, ?, ?)
+         ON CONFLICT(target_type, target_name, contact) DO UPDATE SET
+             wait_hours = excluded.wait_hours,
+             added_by = excluded.added_by
+         RETURNING target_type, target_name, contact, wait_hours, added_by, created_at"
     )
-    .bind(&id)
-    .bind(title)
-    .bind(user_account)
-    .bind(&encrypted_password)
-    .bind(url)
-    .bind(group_name)
-    .bind(&created_at)
-    .execute(pool)
+    .bind(target_type)
+    .bind(target_name)
+    .bind(contact)
+    .bind(wait_hours)
+    .bind(actor)
+    .bind(Utc::now().to_rfc3339())
+    .fetch_one(&mut *tx)
     .await?;
-   
-    Ok((id, created_at))
+
+    let details = format!("{} (wait {}h)", contact, wait_hours);
+    record_audit(&mut *tx, actor, "emergency_contact", Some(target_type), Some(target_name), &details).await?;
+    tx.commit().await?;
+
+    Ok(entry)
 }
 
-/// Ajoute une clé API dans un groupe avec chiffrement
-pub async fn insert_api_key_in_group(
+/// Retire un contact d'urgence ; ses demandes en attente sont annulées. Renvoie le nombre de contacts retirés
+pub async fn remove_emergency_contact(
     pool: &SqlitePool,
-    api_key: &str,
-    title: &str,
-    group_name: &str,
-    crypto: &CryptoService,
-) -> Result<(String, String), sqlx::Error> {
-    // Vérifie si le groupe existe
-    let group_exists = sqlx::query("SELECT group_name FROM user_groups WHERE group_name = ?")
-        .bind(group_name)
-        .fetch_optional(pool)
-        .await?;
-    
-    if group_exists.is_none() {
-        return Err(sqlx::Error::Protocol("Group does not exist".into()));
+    target_type: &str,
+    target_name: &str,
+    contact: &str,
+    actor: &str,
+) -> Result<u64, sqlx::Error> {
+    let mut tx = pool.begin().await?;
+
+    let removed = sqlx::query("DELETE FROM emergency_contacts WHERE target_type = ? AND target_name = ? AND contact = ?")
+        .bind(target_type)
+        .bind(target_name)
+        .bind(contact)
+        .execute(&mut *tx)
+        .await?
+        .rows_affected();
+    if removed == 0 {
+        return Ok(0);
     }
 
-    // Vérifie d'abord si une table existe pour les API keys de groupe
-    // Si elle n'existe pas, il faudra la créer dans init_tables
-    let id = Uuid::new_v4().to_string();
-    let created_at = Utc::now().to_rfc3339();
-    
-    // Chiffre la clé API
-    let encrypted_api_key = crypto.encrypt_and_encode(api_key)
-        .map_err(|e| sqlx::Error::Protocol(format!("Encryption failed: {}", e)))?;
-   
     sqlx::query(
-        "INSERT INTO api_key_in_groups (id, title, api_key, group_name, created_at) 
-         VALUES (?, ?, ?, ?, ?)"
+        "UPDATE emergency_requests SET status = 'cancelled', decided_by = ?, decided_at = ?
+         WHERE target_type = ? AND target_name = ? AND requester = ? AND status = 'pending'"
     )
-    .bind(&id)
-    .bind(title)
-    .bind(&encrypted_api_key)
-    .bind(group_name)
-    .bind(&created_at)
-    .execute(pool)
+    .bind(actor)
+    .bind(Utc::now().to_rfc3339())
+    .bind(target_type)
+    .bind(target_name)
+    .bind(contact)
+    .execute(&mut *tx)
     .await?;
-   
-    Ok((id, created_at))
+
+    record_audit(&mut *tx, actor, "remove_emergency_contact", Some(target_type), Some(target_name), contact).await?;
+    tx.commit().await?;
+
+    Ok(removed)
 }
 
-/// Récupère tous les comptes d'un groupe (avec mot de passe déchiffré)
+/// Contacts d'urgence d'un coffre ou d'un groupe
+pub async fn get_emergency_contacts(pool: &SqlitePool, target_type: &str, target_name: &str) -> Result<Vec<EmergencyContact>, sqlx::Error> {
+    sqlx::query_as::<_, EmergencyContact>(
+        "SELECT target_type, target_name, contact, wait_hours, added_by, created_at FROM emergency_contacts
+         WHERE target_type = ? AND target_name = ? ORDER BY contact"
+    )
+    .bind(target_type)
+    .bind(target_name)
+    .fetch_all(pool)
+    .await
+}
 
-pub async fn get_account_by_group_name(
-    pool: &SqlitePool,
-    group_name: &str,
-    crypto: &CryptoService,
-) -> Result<Vec<ResponseGetAccountInGroups>, sqlx::Error> {
-    let rows = sqlx::query_as::<_, ResponseGetAccountInGroups>(
-        r#"
-        SELECT group_name, title, user_account, password_account, url
-        FROM account_in_groups
-        WHERE group_name = ?
-        "#
+/// Coffres et groupes pour lesquels un utilisateur est contact d'urgence
+pub async fn get_emergency_designations(pool: &SqlitePool, contact: &str) -> Result<Vec<EmergencyContact>, sqlx::Error> {
+    sqlx::query_as::<_, EmergencyContact>(
+        "SELECT target_type, target_name, contact, wait_hours, added_by, created_at FROM emergency_contacts
+         WHERE contact = ? ORDER BY target_type, target_name"
     )
-    .bind(group_name)
+    .bind(contact)
     .fetch_all(pool)
     .await
-    .map_err(|e| {
-        log::error!("Database query failed for get_account_by_group_name: {:?}", e);
-        e
-    })?;
+}
 
-    let mut accounts = Vec::new();
+/// Enregistre une demande d'accès d'urgence d'un contact désigné ; l'accès sera accordé après le
+/// délai d'attente du contact, sauf refus. `RowNotFound` si le demandeur n'est pas contact
+/// d'urgence de la cible ; `Protocol` s'il y a déjà accès ou une demande en cours
+pub async fn create_emergency_request(
+    pool: &SqlitePool,
+    target_type: &str,
+    target_name: &str,
+    requester: &str,
+    reason: &str,
+) -> Result<EmergencyRequest, sqlx::Error> {
+    let wait_hours: i64 = sqlx::query_scalar(
+        "SELECT wait_hours FROM emergency_contacts WHERE target_type = ? AND target_name = ? AND contact = ?"
+    )
+    .bind(target_type)
+    .bind(target_name)
+    .bind(requester)
+    .fetch_optional(pool)
+    .await?
+    .ok_or(sqlx::Error::RowNotFound)?;
 
-    for mut row in rows {
-        match crypto.decode_and_decrypt(&row.password_account) {
-            Ok(decrypted) => {
-                row.password_account = decrypted;
-                accounts.push(row);
-            }
-            Err(e) => {
-                log::error!("Decryption failed for account '{}': {}", row.title, e);
-            }
-        }
+    if target_type == "group" && group_role(pool, target_name, requester).await?.is_some() {
+        return Err(sqlx::Error::Protocol(format!("You already have access to group '{}'", target_name)));
     }
 
-    Ok(accounts)
+    let mut tx = pool.begin().await?;
+    let now = Utc::now();
+
+    let request = sqlx::query_as::<_, EmergencyRequest>(&format!(
+        "INSERT INTO emergency_requests (id, target_type, target_name, requester, reason, created_at, grant_at)
+         VALUES (?, ?, ?, ?, ?, ?, ?)
+         ON CONFLICT(target_type, target_name, requester) WHERE status IN ('pending', 'granted') DO NOTHING
+         RETURNING {EMERGENCY_REQUEST_COLUMNS}"
+    ))
+    .bind(Uuid::new_v4().to_string())
+    .bind(target_type)
+    .bind(target_name)
+    .bind(requester)
+    .bind(reason)
+    .bind(now.to_rfc3339())
+    .bind((now + chrono::Duration::hours(wait_hours)).to_rfc3339())
+    .fetch_optional(&mut *tx)
+    .await?
+    .ok_or_else(|| sqlx::Error::Protocol("You already have a pending or granted emergency request for this target".into()))?;
+
+    let details = format!("{} (granted at {} unless denied): {}", request.id, request.grant_at, reason);
+    record_audit(&mut *tx, requester, "emergency_request", Some(target_type), Some(target_name), &details).await?;
+    tx.commit().await?;
+
+    Ok(request)
 }
 
-/// Récupère toutes les clés API d'un groupe (déchiffrées)
-pub async fn get_api_key_by_group_name(
+pub async fn get_emergency_request(pool: &SqlitePool, id: &str) -> Result<Option<EmergencyRequest>, sqlx::Error> {
+    sqlx::query_as::<_, EmergencyRequest>(&format!("SELECT {EMERGENCY_REQUEST_COLUMNS} FROM emergency_requests WHERE id = ?"))
+        .bind(id)
+        .fetch_optional(pool)
+        .await
+}
+
+/// Accès d'urgence en cours d'un contact sur le coffre personnel d'un utilisateur
+pub async fn active_emergency_access(pool: &SqlitePool, requester: &str, owner: &str) -> Result<Option<EmergencyRequest>, sqlx::Error> {
+    sqlx::query_as::<_, EmergencyRequest>(&format!(
+        "SELECT {EMERGENCY_REQUEST_COLUMNS} FROM emergency_requests
+         WHERE target_type = 'user' AND target_name = ? AND requester = ? AND status = 'granted' AND access_expires_at > ?"
+    ))
+    .bind(owner)
+    .bind(requester)
+    .bind(Utc::now().to_rfc3339())
+    .fetch_optional(pool)
+    .await
+}
+
+/// Accorde une demande en attente pour `access_hours` heures. Pour un groupe, le demandeur en
+/// devient membre en lecture seule jusqu'à l'échéance (voir `expire_memberships`)
+async fn grant_emergency_request(
+    tx: &mut Transaction<'_, Sqlite>,
+    id: &str,
+    granted_by: &str,
+    note: Option<&str>,
+    access_hours: i64,
+) -> Result<EmergencyRequest, sqlx::Error> {
+    let now = Utc::now();
+    let expires_at = (now + chrono::Duration::hours(access_hours)).to_rfc3339();
+
+    let request = sqlx::query_as::<_, EmergencyRequest>(&format!(
+        "UPDATE emergency_requests SET status = 'granted', decided_by = ?, decided_at = ?, decision_note = COALESCE(?, decision_note),
+             granted_at = ?, access_expires_at = ?
+         WHERE id = ? AND status = 'pending'
+         RETURNING {EMERGENCY_REQUEST_COLUMNS}"
+    ))
+    .bind(granted_by)
+    .bind(now.to_rfc3339())
+    .bind(note)
+    .bind(now.to_rfc3339())
+    .bind(&expires_at)
+    .bind(id)
+    .fetch_optional(&mut **tx)
+    .await?
+    .ok_or(sqlx::Error::RowNotFound)?;
+
+    if request.target_type == "group" {
+        // Un membre ajouté entre-temps garde son adhésion telle quelle
+        sqlx::query(
+            "INSERT INTO user_groups (id, username, group_name, role, created_at, expires_at) VALUES (?, ?, ?, 'viewer', ?, ?)
+             ON CONFLICT(username, group_name) DO NOTHING"
+        )
+        .bind(Uuid::new_v4().to_string())
+        .bind(&request.requester)
+        .bind(&request.target_name)
+        .bind(now.to_rfc3339())
+        .bind(&expires_at)
+        .execute(&mut **tx)
+        .await?;
+    }
+
+    let details = format!("{} for {} until {} (by {})", request.id, request.requester, expires_at, granted_by);
+    record_audit(&mut **tx, granted_by, "emergency_granted", Some(&request.target_type), Some(&request.target_name), &details).await?;
+
+    Ok(request)
+}
+
+/// Refuse ou accorde sans attendre une demande en attente (titulaire du coffre ou propriétaire du
+/// groupe). `RowNotFound` si la demande n'existe pas, `Protocol` si elle n'est plus en attente
+pub async fn decide_emergency_request(
     pool: &SqlitePool,
-    group_name: &str,
-    crypto: &CryptoService,
-) -> Result<Vec<ResponseGetApiKeyInGroups>, sqlx::Error> {
-    let rows = sqlx::query_as::<_, ResponseGetApiKeyInGroups>(
-        r#"
-        SELECT group_name, title, api_key
-        FROM api_key_in_groups
-        WHERE group_name = ?
-        "#
+    id: &str,
+    approve: bool,
+    actor: &str,
+    note: Option<&str>,
+    access_hours: i64,
+) -> Result<EmergencyRequest, sqlx::Error> {
+    let mut tx = pool.begin().await?;
+
+    let status: String = sqlx::query_scalar("SELECT status FROM emergency_requests WHERE id = ?")
+        .bind(id)
+        .fetch_optional(&mut *tx)
+        .await?
+        .ok_or(sqlx::Error::RowNotFound)?;
+    if status != "pending" {
+        return Err(sqlx::Error::Protocol(format!("This request is already {}", status)));
+    }
+
+    let request = if approve {
+        grant_emergency_request(&mut tx, id, actor, note, access_hours).await?
+    } else {
+        let request = sqlx::query_as::<_, EmergencyRequest>(&format!(
+            "UPDATE emergency_requests SET status = 'denied', decided_by = ?, decided_at = ?, decision_note = ?
+             WHERE id = ? RETURNING {EMERGENCY_REQUEST_COLUMNS}"
+        ))
+        .bind(actor)
+        .bind(Utc::now().to_rfc3339())
+        .bind(note)
+        .bind(id)
+        .fetch_one(&mut *tx)
+        .await?;
+
+        let details = format!("{} requested by {}{}", request.id, request.requester, note.map(|n| format!(": {}", n)).unwrap_or_default());
+        record_audit(&mut *tx, actor, "emergency_denied", Some(&request.target_type), Some(&request.target_name), &details).await?;
+        request
+    };
+    tx.commit().await?;
+
+    Ok(request)
+}
+
+/// Levée du délai d'attente par un administrateur : la première demande est enregistrée, l'accès
+/// n'est accordé qu'à l'approbation d'un second administrateur. Renvoie la demande et `true` si
+/// l'accès vient d'être accordé. `Protocol` si la demande n'est plus en attente ou si le même
+/// administrateur tente d'approuver sa propre levée
+pub async fn override_emergency_request(
+    pool: &SqlitePool,
+    id: &str,
+    admin: &str,
+    access_hours: i64,
+) -> Result<(EmergencyRequest, bool), sqlx::Error> {
+    let mut tx = pool.begin().await?;
+
+    let (status, first_admin): (String, Option<String>) = sqlx::query_as(
+        "SELECT status, override_requested_by FROM emergency_requests WHERE id = ?"
     )
-    .bind(group_name)
-    .fetch_all(pool)
-    .await
-    .map_err(|e| {
-        log::error!("Database query failed for get_api_key_by_group_name: {:?}", e);
-        e
-    })?;
+    .bind(id)
+    .fetch_optional(&mut *tx)
+    .await?
+    .ok_or(sqlx::Error::RowNotFound)?;
+    if status != "pending" {
+        return Err(sqlx::Error::Protocol(format!("This request is already {}", status)));
+    }
 
-    // Déchiffre chaque clé API
-    let mut api_keys = Vec::new();
+    let result = match first_admin {
+        None => {
+            let request = sqlx::query_as::<_, EmergencyRequest>(&format!(
+                "UPDATE emergency_requests SET override_requested_by = ? WHERE id = ? RETURNING {EMERGENCY_REQUEST_COLUMNS}"
+            ))
+            .bind(admin)
+            .bind(id)
+            .fetch_one(&mut *tx)
+            .await?;
 
-    for mut row in rows {
-        match crypto.decode_and_decrypt(&row.api_key) {
-            Ok(decrypted) => {
-                row.api_key = decrypted;
-                api_keys.push(row);
-            }
-            Err(e) => {
-                log::error!("Decryption failed for API key '{}': {}", row.title, e);
-            }
+            let details = format!("{} for {}, awaiting a second administrator", request.id, request.requester);
+            record_audit(&mut *tx, admin, "emergency_override_requested", Some(&request.target_type), Some(&request.target_name), &details).await?;
+            (request, false)
+        }
+        Some(first_admin) if first_admin == admin => {
+            return Err(sqlx::Error::Protocol("The override must be approved by a second administrator".into()));
         }
+        Some(first_admin) => {
+            let note = format!("override requested by {}, approved by {}", first_admin, admin);
+            (grant_emergency_request(&mut tx, id, admin, Some(&note), access_hours).await?, true)
+        }
+    };
+    tx.commit().await?;
+
+    Ok(result)
+}
+
+/// Met fin à une demande : annulation d'une demande en attente, ou révocation d'un accès accordé
+/// (le membre temporaire d'un groupe en est retiré). Renvoie la demande, ou `None` si elle n'est
+/// ni en attente ni accordée
+pub async fn end_emergency_request(pool: &SqlitePool, id: &str, actor: &str) -> Result<Option<EmergencyRequest>, sqlx::Error> {
+    let mut tx = pool.begin().await?;
+
+    let request = sqlx::query_as::<_, EmergencyRequest>(&format!(
+        "UPDATE emergency_requests
+         SET status = CASE status WHEN 'granted' THEN 'revoked' ELSE 'cancelled' END,
+             decided_by = CASE status WHEN 'granted' THEN decided_by ELSE ? END,
+             decided_at = CASE status WHEN 'granted' THEN decided_at ELSE ? END,
+             access_expires_at = CASE status WHEN 'granted' THEN ? ELSE access_expires_at END
+         WHERE id = ? AND status IN ('pending', 'granted')
+         RETURNING {EMERGENCY_REQUEST_COLUMNS}"
+    ))
+    .bind(actor)
+    .bind(Utc::now().to_rfc3339())
+    .bind(Utc::now().to_rfc3339())
+    .bind(id)
+    .fetch_optional(&mut *tx)
+    .await?;
+
+    let Some(request) = request else {
+        return Ok(None);
+    };
+
+    if request.status == "revoked" && request.target_type == "group" {
+        sqlx::query("DELETE FROM user_groups WHERE group_name = ? AND username = ? AND expires_at IS NOT NULL AND role = 'viewer'")
+            .bind(&request.target_name)
+            .bind(&request.requester)
+            .execute(&mut *tx)
+            .await?;
     }
 
-    Ok(api_keys)
+    let action = if request.status == "revoked" { "emergency_revoked" } else { "emergency_cancelled" };
+    let details = format!("{} requested by {}", request.id, request.requester);
+    record_audit(&mut *tx, actor, action, Some(&request.target_type), Some(&request.target_name), &details).await?;
+    tx.commit().await?;
+
+    Ok(Some(request))
 }
 
-pub async fn get_api_key_by_title_and_username(
+/// Accorde les demandes dont le délai d'attente est écoulé et clôt les accès arrivés à échéance ;
+/// renvoie les demandes accordées et celles clôturées
+pub async fn process_emergency_requests(
     pool: &SqlitePool,
-    title: &str,
-    username: &str,
+    access_hours: i64,
+) -> Result<(Vec<EmergencyRequest>, Vec<EmergencyRequest>), sqlx::Error> {
+    let now = Utc::now().to_rfc3339();
+    let mut tx = pool.begin().await?;
+
+    // Clôture d'abord : la transaction prend le verrou d'écriture avant toute lecture, qu'une
+    // écriture concurrente ferait sinon échouer (« database is locked »). Les adhésions
+    // temporaires des groupes sont retirées par `expire_memberships`
+    let expired = sqlx::query_as::<_, EmergencyRequest>(&format!(
+        "UPDATE emergency_requests SET status = 'expired'
+         WHERE status = 'granted' AND access_expires_at <= ?
+         RETURNING {EMERGENCY_REQUEST_COLUMNS}"
+    ))
+    .bind(&now)
+    .fetch_all(&mut *tx)
+    .await?;
+
+    for request in &expired {
+        let details = format!("{} for {}", request.id, request.requester);
+        record_audit(&mut *tx, "scheduler", "emergency_expired", Some(&request.target_type), Some(&request.target_name), &details).await?;
+    }
+
+    let due: Vec<String> = sqlx::query_scalar("SELECT id FROM emergency_requests WHERE status = 'pending' AND grant_at <= ?")
+        .bind(&now)
+        .fetch_all(&mut *tx)
+        .await?;
+
+    let mut granted = Vec::new();
+    for id in due {
+        granted.push(grant_emergency_request(&mut tx, &id, "scheduler", Some("waiting period elapsed"), access_hours).await?);
+    }
+    tx.commit().await?;
+
+    Ok((granted, expired))
+}
+
+/// Historique des demandes d'accès d'urgence, filtré par statut
+pub async fn get_emergency_requests(
+    pool: &SqlitePool,
+    list: &ListQuery,
+    scope: EmergencyScope<'_>,
+    status: Option<&str>,
+) -> Result<Page<EmergencyRequest>, sqlx::Error> {
+    let now = Utc::now().to_rfc3339();
+    let mut from_sql = String::from("FROM emergency_requests WHERE 1 = 1");
+    let mut binds: Vec<&str> = Vec::new();
+
+    match scope {
+        EmergencyScope::Requester(username) => {
+            from_sql.push_str(" AND requester = ?");
+            binds.push(username);
+        }
+        EmergencyScope::Reviewer(username) => {
+            from_sql.push_str(&format!(
+                " AND ((target_type = 'user' AND target_name = ?) OR (target_type = 'group' AND target_name IN ({OWNED_GROUPS})))"
+            ));
+            binds.push(username);
+            binds.push(username);
+            binds.push(&now);
+        }
+        EmergencyScope::All => {}
+    }
+    if let Some(status) = status {
+        from_sql.push_str(" AND status = ?");
+        binds.push(status);
+    }
+
+    list.fetch_page::<EmergencyRequest>(pool, EMERGENCY_REQUEST_COLUMNS, &from_sql, &binds)
+        .await
+        .map_err(|e| {
+            log::error!("Database query failed for get_emergency_requests: {:?}", e);
+            e
+        })
+}
+
+/// Éléments personnels du titulaire d'un coffre, tels que les voit un contact d'urgence
+pub async fn get_emergency_vault(
+    pool: &SqlitePool,
+    request: &EmergencyRequest,
     crypto: &CryptoService,
-) -> Result<Vec<ResponseGetApiKeyInTitle>, sqlx::Error> {
-    let rows = sqlx::query_as::<_, ResponseGetApiKeyInTitle>(
-        r#"
-        SELECT api_key
-        FROM add_api_key
-        WHERE title = ? AND username = ?
-        "#
+) -> Result<Vec<SharedItem>, sqlx::Error> {
+    let mut items = sqlx::query_as::<_, SharedItem>(
+        "SELECT item_type, id, username AS owner, title, url, 'read' AS permission, 'emergency' AS shared_by, ? AS shared_at
+         FROM vault_items
+         WHERE username = ? AND group_name IS NULL AND deleted_at IS NULL
+         ORDER BY created_at"
     )
-    .bind(username)
-    .bind(title)
+    .bind(request.granted_at.as_deref().unwrap_or_default())
+    .bind(&request.target_name)
     .fetch_all(pool)
-    .await
-    .map_err(|e| {
-        log::error!("Database query failed for get_api_key_by_group_name: {:?}", e);
-        e
-    })?;
+    .await?;
 
-    let mut api_keys = Vec::new();
+    for item in &mut items {
+        item.title = open_field(crypto, &item.title)?;
+        item.url = open_field(crypto, &item.url)?;
+    }
 
-    for mut row in rows {
-        match crypto.decode_and_decrypt(&row.api_key) {
-            Ok(decrypted) => {
-                row.api_key = decrypted;
-                api_keys.push(row);
-            }
-            Err(e) => {
-                log::error!("Decryption failed for API key '{}': {}", title, e);
-            }
-        }
+    Ok(items)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use sqlx::sqlite::SqlitePoolOptions;
+
+    async fn memory_pool() -> SqlitePool {
+        let pool = SqlitePoolOptions::new().max_connections(1).connect("sqlite::memory:").await.unwrap();
+        init_tables(&pool).await.unwrap();
+        pool
     }
 
-    Ok(api_keys)
-}
\ No newline at end of file
+    /// Adhésion échue mais conservée (dernier membre du groupe) : plus aucun accès au groupe
+    #[tokio::test]
+    async fn expired_membership_grants_no_access() {
+        let pool = memory_pool().await;
+        let crypto = CryptoService::new("membership test master password").unwrap();
+        let expired = (Utc::now() - chrono::Duration::hours(1)).to_rfc3339();
+
+        sqlx::query("INSERT INTO user_groups (id, username, group_name, role, created_at, expires_at) VALUES (?, 'carol', 'ops', 'owner', ?, ?)")
+            .bind(Uuid::new_v4().to_string())
+            .bind(Utc::now().to_rfc3339())
+            .bind(&expired)
+            .execute(&pool)
+            .await
+            .unwrap();
+        let fields = items::fields(&[("api_key", "s1")]);
+        let item = NewItem {
+            schema: &Schema::Kind(items::kind("api_key").expect("built-in item type")),
+            owner: Some("carol"),
+            group_name: Some("ops"),
+            title: "deploy token",
+            url: "",
+            notes: None,
+            fields: &fields,
+            expires_at: None,
+            rotate_every: None,
+            created_by: "carol",
+        };
+        insert_item(&pool, &item, &crypto).await.unwrap();
+        let terms = vec!["deploy".to_string()];
+
+        assert!(search_items(&pool, "carol", &terms, 10, &crypto).await.unwrap().is_empty());
+        assert!(get_expiring_items(&pool, "carol", 30, &crypto).await.unwrap().groups.is_empty());
+        assert!(group_role(&pool, "ops", "carol").await.unwrap().is_none());
+        assert!(group_owners(&pool, "ops").await.unwrap().is_empty());
+
+        // Témoin : la même adhésion, prolongée, redonne accès
+        sqlx::query("UPDATE user_groups SET expires_at = NULL").execute(&pool).await.unwrap();
+        assert_eq!(search_items(&pool, "carol", &terms, 10, &crypto).await.unwrap().len(), 1);
+        assert_eq!(get_expiring_items(&pool, "carol", 30, &crypto).await.unwrap().groups.len(), 1);
+    }
+}
@@ -1,11 +1,13 @@
 use actix_web::{web, HttpResponse, HttpRequest, HttpMessage};
-use serde::{Deserialize, Serialize};
 use sqlx::SqlitePool;
-use crate::models::{ClaimsAdmin, AddUser, DeleteUser, ErrorResponse, AddUserResponse, DeleteUserResponse};
+use crate::models::{ClaimsAdmin, DeleteUser, ErrorResponse, ItemTransfer};
+use crate::crypto::CryptoService;
 use crate::db;
+use crate::attachments;
+use crate::config::AppConfig;
 
-fn get_admin_id(reqAdmin: &HttpRequest) -> Result<String, HttpResponse> {
-    let extensions = reqAdmin.extensions();
+fn get_admin_id(req_admin: &HttpRequest) -> Result<String, HttpResponse> {
+    let extensions = req_admin.extensions();
     match extensions.get::<ClaimsAdmin>() {
         Some(c) => Ok(c.sub.clone()),
         None => Err(HttpResponse::Unauthorized().json(ErrorResponse {
@@ -14,10 +16,57 @@ fn get_admin_id(reqAdmin: &HttpRequest) -> Result<String, HttpResponse> {
     }
 }
 
+/// Détermine et valide la cible du transfert des éléments de l'utilisateur supprimé
+async fn resolve_transfer(
+    pool: &SqlitePool,
+    body: &DeleteUser,
+    username: &str,
+) -> Result<ItemTransfer, HttpResponse> {
+    match (&body.transfer_to_user, &body.transfer_to_group) {
+        (None, None) => Ok(ItemTransfer::Delete),
+        (Some(_), Some(_)) => Err(HttpResponse::BadRequest().json(ErrorResponse {
+            error: "Choose either transfer_to_user or transfer_to_group, not both".into(),
+        })),
+        (Some(new_owner), None) => {
+            if new_owner == username {
+                return Err(HttpResponse::BadRequest().json(ErrorResponse {
+                    error: "Cannot transfer items to the user being deleted".into(),
+                }));
+            }
+            match db::user_exists(pool, new_owner).await {
+                Ok(true) => Ok(ItemTransfer::ToUser(new_owner.clone())),
+                Ok(false) => Err(HttpResponse::BadRequest().json(ErrorResponse {
+                    error: format!("Target user '{}' does not exist", new_owner),
+                })),
+                Err(e) => {
+                    log::error!("Failed to check target user: {}", e);
+                    Err(HttpResponse::InternalServerError().json(ErrorResponse {
+                        error: "Failed to delete user".into(),
+                    }))
+                }
+            }
+        }
+        (None, Some(group_name)) => match db::group_exists(pool, group_name).await {
+            Ok(true) => Ok(ItemTransfer::ToGroup(group_name.clone())),
+            Ok(false) => Err(HttpResponse::BadRequest().json(ErrorResponse {
+                error: format!("Target group '{}' does not exist", group_name),
+            })),
+            Err(e) => {
+                log::error!("Failed to check target group: {}", e);
+                Err(HttpResponse::InternalServerError().json(ErrorResponse {
+                    error: "Failed to delete user".into(),
+                }))
+            }
+        },
+    }
+}
+
 pub async fn delete_user(
     req_admin: HttpRequest,
     pool: web::Data<SqlitePool>,
     body: web::Json<DeleteUser>,
+    config: web::Data<AppConfig>,
+    crypto: web::Data<CryptoService>,
 ) -> HttpResponse {
     // Vérifie que le token JWT appartient à un admin
     let admin_id = match get_admin_id(&req_admin) {
@@ -25,25 +74,100 @@ pub async fn delete_user(
         Err(response) => return response,
     };
 
-    match crate::db::delete_user(pool.get_ref(), &body.id).await {
-        Ok(rows_affected) => {
-            if rows_affected == 0 {
-                return HttpResponse::NotFound().json(ErrorResponse {
-                    error: "User not found".into(),
+    let username = match db::find_username_by_id(pool.get_ref(), &body.id).await {
+        Ok(Some(username)) => username,
+        Ok(None) => {
+            return HttpResponse::NotFound().json(ErrorResponse {
+                error: "User not found".into(),
+            });
+        }
+        Err(e) => {
+            log::error!("Failed to look up user {}: {}", body.id, e);
+            return HttpResponse::InternalServerError().json(ErrorResponse {
+                error: "Failed to delete user".into(),
+            });
+        }
+    };
+
+    // Un groupe ne doit pas perdre son dernier propriétaire : l'administrateur en nomme un autre d'abord
+    match db::sole_owner_groups(pool.get_ref(), &username).await {
+        Ok(groups) if groups.is_empty() => {}
+        Ok(groups) => {
+            return HttpResponse::Conflict().json(serde_json::json!({
+                "error": format!("{} is the last owner of: {}. Name another owner first", username, groups.join(", ")),
+                "groups": groups,
+            }));
+        }
+        Err(e) => {
+            log::error!("Failed to check group ownership of {}: {}", username, e);
+            return HttpResponse::InternalServerError().json(ErrorResponse {
+                error: "Failed to delete user".into(),
+            });
+        }
+    }
+
+    let transfer = match resolve_transfer(pool.get_ref(), &body, &username).await {
+        Ok(transfer) => transfer,
+        Err(response) => return response,
+    };
+
+    // Les titres étant uniques, un transfert vers un groupe échoue si l'un d'eux y existe déjà :
+    // on nomme les éléments en conflit plutôt que de laisser l'erreur SQL remonter
+    if let ItemTransfer::ToGroup(group_name) = &transfer {
+        match db::group_transfer_conflicts(pool.get_ref(), &username, crypto.get_ref()).await {
+            Ok(conflicts) if conflicts.is_empty() => {}
+            Ok(conflicts) => {
+                let names: Vec<String> = conflicts
+                    .iter()
+                    .map(|(item_type, title)| format!("{} '{}'", item_type, title))
+                    .collect();
+                log::warn!("Transfer of {}'s items to group '{}' blocked by: {}", username, group_name, names.join(", "));
+                return HttpResponse::Conflict().json(serde_json::json!({
+                    "error": format!("Items with the same title already exist: {}", names.join(", ")),
+                    "conflicts": conflicts
+                        .iter()
+                        .map(|(item_type, title)| serde_json::json!({ "item_type": item_type, "title": title }))
+                        .collect::<Vec<_>>(),
+                }));
+            }
+            Err(e) => {
+                log::error!("Failed to check transfer conflicts for {}: {}", username, e);
+                return HttpResponse::InternalServerError().json(ErrorResponse {
+                    error: "Failed to delete user".into(),
                 });
             }
+        }
+    }
 
-            log::info!("Admin {} deleted user {}", admin_id, body.id);
-            HttpResponse::Ok().json(DeleteUserResponse {
-                id: body.id.clone(),
-                message: "User deleted successfully".into(),
-            })
+    match crate::db::delete_user(pool.get_ref(), &body.id, &username, &transfer).await {
+        Ok(mut response) => {
+            log::info!(
+                "Admin {} deleted user {} ({:?}): {} account(s), {} API key(s), {} other item(s) deleted, {} account(s), {} API key(s), {} other item(s) transferred, {} membership(s) removed",
+                admin_id, body.id, transfer,
+                response.accounts_deleted, response.api_keys_deleted, response.other_items_deleted,
+                response.accounts_transferred, response.api_keys_transferred, response.other_items_transferred,
+                response.memberships_removed
+            );
+            if let Err(e) = attachments::sweep_orphans(pool.get_ref(), &config.attachments_dir).await {
+                log::error!("Attachment cleanup failed: {}", e);
+            }
+            response.message = "User deleted successfully".into();
+            HttpResponse::Ok().json(response)
         }
+        Err(sqlx::Error::RowNotFound) => HttpResponse::NotFound().json(ErrorResponse {
+            error: "User not found".into(),
+        }),
         Err(e) => {
+            if e.to_string().contains("UNIQUE constraint failed") {
+                return HttpResponse::Conflict().json(ErrorResponse {
+                    error: "An item with the same title already exists in the target group".into(),
+                });
+            }
+
             log::error!("Failed to delete user: {}", e);
             HttpResponse::InternalServerError().json(ErrorResponse {
                 error: "Failed to delete user".into(),
             })
         }
     }
-}
\ No newline at end of file
+}
@@ -0,0 +1,240 @@
+use std::path::Path;
+use chrono::Utc;
+use sqlx::{ConnectOptions, Connection};
+use tokio::fs;
+use crate::config::AppConfig;
+use crate::crypto::CryptoService;
+use crate::db;
+
+/// Clé SQLCipher brute (`x'…'`) d'une génération : SQLCipher l'utilise telle quelle, sans
+/// repasser par sa propre dérivation PBKDF2
+pub fn raw_key(crypto: &CryptoService, version: u32) -> Result<String, String> {
+    let key = crypto.database_key(version)?;
+    let hex: String = key.iter().map(|b| format!("{:02x}", b)).collect();
+    Ok(format!("x'{}'", hex))
+}
+
+/// Valeur de `PRAGMA key` pour une génération de clé
+pub fn key_pragma(crypto: &CryptoService, version: u32) -> Result<String, String> {
+    Ok(format!("\"{}\"", raw_key(crypto, version)?))
+}
+
+/// Clé de la base d'après la configuration ; `None` si le chiffrement est désactivé
+pub fn database_key(config: &AppConfig, crypto: &CryptoService) -> Result<Option<String>, String> {
+    if !config.database_encryption {
+        return Ok(None);
+    }
+    key_pragma(crypto, config.database_key_version).map(Some)
+}
+
+/// Ouvre une base, vérifie la clé puis son intégrité
+async fn open_checked(path: &Path, key: Option<&str>) -> Result<sqlx::SqliteConnection, String> {
+    let mut conn = db::connect_options(path, key)
+        .connect()
+        .await
+        .map_err(|e| format!("Cannot open {}: {}", path.display(), e))?;
+
+    db::check_access(&mut conn, key.is_some()).await.map_err(|e| e.to_string())?;
+    db::check_integrity(&mut conn).await.map_err(|e| e.to_string())?;
+
+    Ok(conn)
+}
+
+/// Convertit la base en clair en base chiffrée avec la génération de clé configurée (serveur
+/// arrêté). La base d'origine est conservée à côté avec le suffixe `.plaintext-<date>` : elle
+/// contient les données en clair et doit être effacée une fois la migration vérifiée.
+/// Renvoie le chemin de cette copie
+pub async fn encrypt_database(config: &AppConfig, crypto: &CryptoService) -> Result<String, String> {
+    let path = Path::new(&config.database_path);
+    let staged = format!("{}.encrypted", config.database_path);
+    if fs::metadata(&staged).await.is_ok() {
+        fs::remove_file(&staged).await.map_err(|e| e.to_string())?;
+    }
+
+    let mut conn = open_checked(path, None).await?;
+    let cipher_version: Option<String> = sqlx::query_scalar("PRAGMA cipher_version")
+        .fetch_optional(&mut conn)
+        .await
+        .map_err(|e| e.to_string())?;
+    if cipher_version.is_none() {
+        return Err("Database encryption requires a build with the `sqlcipher` feature".into());
+    }
+
+    // sqlcipher_export recopie le schéma et les données dans la base attachée, chiffrée.
+    // Le fichier est créé d'avance : la connexion n'a pas le droit de créer de base
+    let key = raw_key(crypto, config.database_key_version)?;
+    fs::File::create(&staged).await.map_err(|e| e.to_string())?;
+    let exported = async {
+        sqlx::query("ATTACH DATABASE ? AS encrypted KEY ?")
+            .bind(&staged)
+            .bind(&key)
+            .execute(&mut conn)
+            .await?;
+        sqlx::query("SELECT sqlcipher_export('encrypted')").execute(&mut conn).await?;
+        sqlx::query("DETACH DATABASE encrypted").execute(&mut conn).await?;
+        Ok::<_, sqlx::Error>(())
+    }
+    .await;
+    conn.close().await.ok();
+
+    let pragma = key_pragma(crypto, config.database_key_version)?;
+    let verified = match exported {
+        Ok(()) => open_checked(Path::new(&staged), Some(&pragma)).await,
+        Err(e) => Err(format!("Export to the encrypted database failed: {}", e)),
+    };
+    match verified {
+        Ok(conn) => {
+            conn.close().await.ok();
+        }
+        Err(e) => {
+            fs::remove_file(&staged).await.ok();
+            return Err(e);
+        }
+    }
+
+    let suffix = format!("plaintext-{}", Utc::now().format("%Y%m%dT%H%M%SZ"));
+    for file in ["", "-wal", "-shm"] {
+        let current = format!("{}{}", config.database_path, file);
+        if fs::metadata(&current).await.is_ok() {
+            fs::rename(&current, format!("{}.{}", current, suffix)).await.map_err(|e| e.to_string())?;
+        }
+    }
+    fs::rename(&staged, path).await.map_err(|e| e.to_string())?;
+
+    Ok(format!("{}.{}", config.database_path, suffix))
+}
+
+/// Rechiffre la base avec une nouvelle génération de clé (serveur arrêté) ; DATABASE_KEY_VERSION
+/// doit ensuite être mis à jour
+pub async fn rekey_database(config: &AppConfig, crypto: &CryptoService, new_version: u32) -> Result<(), String> {
+    if !config.database_encryption {
+        return Err("The database is not encrypted: set DATABASE_ENCRYPTION=true or run `encrypt-db` first".into());
+    }
+    if new_version == 0 || new_version == config.database_key_version {
+        return Err(format!(
+            "The new key version must be a positive number different from the current one ({})",
+            config.database_key_version
+        ));
+    }
+
+    let path = Path::new(&config.database_path);
+    let current = key_pragma(crypto, config.database_key_version)?;
+    let mut conn = open_checked(path, Some(&current)).await?;
+
+    let rekeyed = sqlx::query(&format!("PRAGMA rekey = {}", key_pragma(crypto, new_version)?))
+        .execute(&mut conn)
+        .await;
+    conn.close().await.ok();
+    rekeyed.map_err(|e| format!("Rekey failed: {}", e))?;
+
+    open_checked(path, Some(&key_pragma(crypto, new_version)?))
+        .await?
+        .close()
+        .await
+        .map_err(|e| e.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn service(master_password: &str) -> CryptoService {
+        CryptoService::new(master_password).unwrap()
+    }
+
+    /// Configuration pointant vers une base dans un répertoire temporaire propre au test
+    fn temp_config() -> (AppConfig, std::path::PathBuf) {
+        let dir = std::env::temp_dir().join(format!("vault-encryption-{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let mut config = AppConfig::from_env();
+        config.database_path = dir.join("vault.db").to_string_lossy().into_owned();
+        config.database_encryption = false;
+        config.database_key_version = 1;
+        (config, dir)
+    }
+
+    async fn create_plaintext_database(config: &AppConfig) {
+        let pool = db::connect(&config.database_path, true, None, false).await.unwrap();
+        sqlx::query("CREATE TABLE t (v TEXT)").execute(&pool).await.unwrap();
+        sqlx::query("INSERT INTO t (v) VALUES ('secret')").execute(&pool).await.unwrap();
+        pool.close().await;
+    }
+
+    #[test]
+    fn each_generation_has_its_own_key() {
+        let crypto = service("master");
+        let v1 = raw_key(&crypto, 1).unwrap();
+
+        assert_eq!(v1, raw_key(&crypto, 1).unwrap());
+        assert_ne!(v1, raw_key(&crypto, 2).unwrap());
+        assert_ne!(v1, raw_key(&service("other master"), 1).unwrap());
+        assert!(v1.starts_with("x'") && v1.ends_with('\'') && v1.len() == 67, "{}", v1);
+        assert_eq!(key_pragma(&crypto, 1).unwrap(), format!("\"{}\"", v1));
+    }
+
+    #[test]
+    fn the_key_follows_the_configuration() {
+        let crypto = service("master");
+        let mut config = AppConfig::from_env();
+
+        config.database_encryption = false;
+        assert_eq!(database_key(&config, &crypto).unwrap(), None);
+
+        config.database_encryption = true;
+        config.database_key_version = 3;
+        assert_eq!(database_key(&config, &crypto).unwrap(), Some(key_pragma(&crypto, 3).unwrap()));
+    }
+
+    #[actix_web::test]
+    async fn rekey_needs_an_encrypted_database_and_a_new_generation() {
+        let crypto = service("master");
+        let mut config = AppConfig::from_env();
+
+        config.database_encryption = false;
+        assert!(rekey_database(&config, &crypto, 2).await.is_err());
+
+        config.database_encryption = true;
+        config.database_key_version = 2;
+        assert!(rekey_database(&config, &crypto, 2).await.is_err());
+        assert!(rekey_database(&config, &crypto, 0).await.is_err());
+    }
+
+    #[cfg(not(feature = "sqlcipher"))]
+    #[actix_web::test]
+    async fn encryption_is_refused_without_sqlcipher() {
+        let (config, dir) = temp_config();
+        create_plaintext_database(&config).await;
+
+        let error = encrypt_database(&config, &service("master")).await.unwrap_err();
+        assert!(error.contains("sqlcipher"), "{}", error);
+        assert!(db::connect(&config.database_path, false, None, false).await.is_ok());
+
+        std::fs::remove_dir_all(dir).ok();
+    }
+
+    #[cfg(feature = "sqlcipher")]
+    #[actix_web::test]
+    async fn only_the_current_generation_opens_the_database() {
+        let (mut config, dir) = temp_config();
+        create_plaintext_database(&config).await;
+        let crypto = service("master");
+
+        let plaintext_copy = encrypt_database(&config, &crypto).await.unwrap();
+        assert!(std::path::Path::new(&plaintext_copy).exists());
+        assert!(db::connect(&config.database_path, false, None, false).await.is_err());
+
+        let v1 = key_pragma(&crypto, 1).unwrap();
+        let pool = db::connect(&config.database_path, false, Some(&v1), false).await.unwrap();
+        let value: String = sqlx::query_scalar("SELECT v FROM t").fetch_one(&pool).await.unwrap();
+        assert_eq!(value, "secret");
+        pool.close().await;
+
+        config.database_encryption = true;
+        rekey_database(&config, &crypto, 2).await.unwrap();
+        assert!(db::connect(&config.database_path, false, Some(&v1), false).await.is_err());
+        let v2 = key_pragma(&crypto, 2).unwrap();
+        assert!(db::connect(&config.database_path, false, Some(&v2), false).await.is_ok());
+
+        std::fs::remove_dir_all(dir).ok();
+    }
+}
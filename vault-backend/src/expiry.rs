@@ -0,0 +1,77 @@
+use chrono::{DateTime, Duration, NaiveDate, Utc};
+
+// Intervalle de rotation maximal accepté (jours)
+pub const MAX_ROTATION_DAYS: i64 = 3650;
+
+/// Normalise une date d'expiration : horodatage RFC 3339 ou date seule (minuit UTC)
+pub fn parse_expires_at(value: &str) -> Result<String, String> {
+    let value = value.trim();
+
+    if let Ok(date) = DateTime::parse_from_rfc3339(value) {
+        return Ok(date.with_timezone(&Utc).to_rfc3339());
+    }
+
+    NaiveDate::parse_from_str(value, "%Y-%m-%d")
+        .map(|date| date.and_hms_opt(0, 0, 0).unwrap_or_default().and_utc().to_rfc3339())
+        .map_err(|_| "expires_at must be an RFC 3339 timestamp or a YYYY-MM-DD date".to_string())
+}
+
+/// Vérifie un intervalle de rotation (jours)
+pub fn check_rotate_every(days: i64) -> Result<i64, String> {
+    if (1..=MAX_ROTATION_DAYS).contains(&days) {
+        Ok(days)
+    } else {
+        Err(format!("rotate_every must be between 1 and {} days", MAX_ROTATION_DAYS))
+    }
+}
+
+/// Intervalle de rotation applicable : le plus court entre celui de l'élément et l'âge
+/// maximal imposé par la politique de son groupe
+pub fn effective_rotation(rotate_every: Option<i64>, max_age: Option<i64>) -> Option<i64> {
+    match (rotate_every, max_age) {
+        (Some(days), Some(max)) => Some(days.min(max)),
+        (days, max) => days.or(max),
+    }
+}
+
+/// Date à laquelle le secret doit avoir été renouvelé
+pub fn rotation_due_at(rotated_at: &str, rotate_every: i64) -> Option<String> {
+    DateTime::parse_from_rfc3339(rotated_at)
+        .ok()
+        .map(|date| (date.with_timezone(&Utc) + Duration::days(rotate_every)).to_rfc3339())
+}
+
+/// État d'une échéance par rapport à maintenant et à la fenêtre d'alerte ;
+/// `None` si elle est encore lointaine
+fn deadline_status(due_at: &str, now: DateTime<Utc>, horizon: DateTime<Utc>) -> Option<bool> {
+    let due_at = DateTime::parse_from_rfc3339(due_at).ok()?.with_timezone(&Utc);
+    if due_at <= now {
+        Some(true)
+    } else if due_at <= horizon {
+        Some(false)
+    } else {
+        None
+    }
+}
+
+/// Statut le plus urgent d'un élément : `expired`, `rotation_overdue`, `expires_soon` ou
+/// `rotation_due` ; `None` si aucune échéance ne tombe dans la fenêtre
+pub fn status(
+    expires_at: Option<&str>,
+    rotation_due_at: Option<&str>,
+    within_days: i64,
+) -> Option<&'static str> {
+    let now = Utc::now();
+    let horizon = now + Duration::days(within_days);
+
+    let expiry = expires_at.and_then(|d| deadline_status(d, now, horizon));
+    let rotation = rotation_due_at.and_then(|d| deadline_status(d, now, horizon));
+
+    match (expiry, rotation) {
+        (Some(true), _) => Some("expired"),
+        (_, Some(true)) => Some("rotation_overdue"),
+        (Some(false), _) => Some("expires_soon"),
+        (_, Some(false)) => Some("rotation_due"),
+        _ => None,
+    }
+}
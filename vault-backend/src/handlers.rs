@@ -1,34 +1,75 @@
-use actix_web::{HttpMessage, HttpRequest, HttpResponse, body, web};
-use serde::{Deserialize, Serialize};
+use actix_web::{HttpMessage, HttpRequest, HttpResponse, web};
+use actix_web::http::header::{self, ContentDisposition};
 use sqlx::SqlitePool;
+use futures_util::StreamExt;
+use uuid::Uuid;
+use chrono::{DateTime, Utc};
 use crate::models::{
     Claims, ErrorResponse, AddApiKeyRequest, UsernameRequest, AccountInGroupResponse, ApiKeyInGroupResponse, RequestGetAccountInGroups, RequestGetApiKeyInTitle,
-    AddAccountRequest, DeleteRequest, AccountResponse, ApiKeyResponse, MeResponse, AddApiKeyInGroup, AddAccountInGroup, RequestGetApiKeyInGroups
+    AddAccountRequest, DeleteRequest, AccountResponse, ApiKeyResponse, MeResponse, AddApiKeyInGroup, AddAccountInGroup, RequestGetApiKeyInGroups,
+    UpdateAccountPasswordRequest, UpdateApiKeyRequest, UpdateSecretResponse, SecretVersionsRequest, SecretVersionRequest, RevealSecretVersionResponse, TrashItemRequest, RevealItemRequest, SearchRequest,
+    Folder, CreateFolderRequest, GetFoldersRequest, RenameFolderRequest, MoveFolderRequest, DeleteFolderResponse, MoveItemRequest, SetTagsRequest, SetTagsResponse,
+    AddItemRequest, AddItemResponse, GetItemsRequest, UpdateItemRequest,
+    Attachment, AttachmentUploadQuery, AttachmentsRequest, AttachmentRequest, SetDeadlinesRequest, ExpiringItemsQuery,
+    GroupRole, SetGroupRoleRequest, RemoveGroupMemberRequest, AddUserGroups, SetGroupPolicyRequest, SetGroupParentRequest,
+    ShareItemRequest, RevokeShareRequest, CreateSecretLinkRequest, CreateSecretLinkResponse, SecretLinkContent, OpenSecretLinkRequest,
+    CreateAccessRequest, DecideAccessRequest, AccessDecision, AccessRequestsQuery, UpdateMembershipRequest,
+    CheckoutRequest, CheckinRequest, CheckinRotation, LeasesRequest, CheckoutResponse, CheckinResponse,
+    SetEmergencyContactRequest, RemoveEmergencyContactRequest, EmergencyContactsRequest, CreateEmergencyRequest, DecideEmergencyRequest,
+    EmergencyRequestsQuery, EmergencyRequest
 };
-use crate::db;
+use crate::db::{self, NewItem};
+use crate::items::{self, Fields, Schema};
+use crate::search::search_terms;
 use crate::crypto::CryptoService;
+use crate::config::AppConfig;
+use crate::models::ListParams;
+use crate::pagination::{ListQuery, ListSource};
+use crate::attachments::{self, BlobWriter};
+use crate::multipart::Multipart;
+use crate::links::{self, LinkKey};
+use crate::notify::{Notification, Notifier};
+use crate::leases;
+
+/// Valide les paramètres de liste pour une source donnée
+fn list_query(params: &ListParams, source: &ListSource, crypto: &CryptoService) -> Result<ListQuery, HttpResponse> {
+    ListQuery::from_params(params, source, crypto).map_err(|error| {
+        HttpResponse::BadRequest().json(ErrorResponse { error })
+    })
+}
+
+/// Récupère le nom de l'utilisateur authentifié depuis les claims du JWT
+fn current_username(req: &HttpRequest) -> Result<String, HttpResponse> {
+    match req.extensions().get::<Claims>() {
+        Some(c) => Ok(c.username.clone()),
+        None => Err(HttpResponse::Unauthorized().json(ErrorResponse {
+            error: "Missing or invalid authentication".into(),
+        })),
+    }
+}
 
 // ==================== ACCOUNTS ====================
 
-/// Récupère tous les comptes d'un utilisateur (avec mot de passe déchiffré)
+/// Liste les comptes de l'utilisateur connecté (sans les mots de passe, voir `reveal_item`)
 pub async fn get_account(
-    _req: HttpRequest,
+    req: HttpRequest,
     pool: web::Data<SqlitePool>,
-    body: web::Json<UsernameRequest>,
+    query: web::Query<ListParams>,
     crypto: web::Data<CryptoService>,
 ) -> HttpResponse {
-    let username = &body.username;
+    let username = match current_username(&req) {
+        Ok(u) => u,
+        Err(response) => return response,
+    };
+    let list = match list_query(&query, &db::ITEM_LIST, crypto.get_ref()) {
+        Ok(list) => list,
+        Err(response) => return response,
+    };
 
-    match db::get_account_by_username(pool.get_ref(), username, crypto.get_ref()).await {
-        Ok(accounts) if !accounts.is_empty() => {
-            log::info!("User {} retrieved {} account(s)", username, accounts.len());
-            HttpResponse::Ok().json(accounts)
-        }
-        Ok(_) => {
-            log::warn!("No account found for user: {}", username);
-            HttpResponse::NotFound().json(ErrorResponse {
-                error: "No account found for this user".into(),
-            })
+    match db::get_account_by_username(pool.get_ref(), &username, &list, crypto.get_ref()).await {
+        Ok(page) => {
+            log::info!("User {} retrieved {} of {} account(s)", username, page.items.len(), page.total);
+            HttpResponse::Ok().json(page)
         }
         Err(e) => {
             log::error!("Failed to retrieve account for {}: {}", username, e);
@@ -41,17 +82,18 @@ pub async fn get_account(
 
 /// Ajoute un nouveau compte (chiffre le mot de passe)
 pub async fn add_account(
-    _req: HttpRequest,
+    req: HttpRequest,
     pool: web::Data<SqlitePool>,
     body: web::Json<AddAccountRequest>,
     crypto: web::Data<CryptoService>,
 ) -> HttpResponse {
+    // Le propriétaire et l'auteur de la première version sont ceux du token, jamais ceux du corps
+    let username = match current_username(&req) {
+        Ok(u) => u,
+        Err(response) => return response,
+    };
+
     // Validation des champs
-    if body.username.trim().is_empty() {
-        return HttpResponse::BadRequest().json(ErrorResponse {
-            error: "Username cannot be empty".into(),
-        });
-    }
     if body.title.trim().is_empty() {
         return HttpResponse::BadRequest().json(ErrorResponse {
             error: "Title cannot be empty".into(),
@@ -68,34 +110,41 @@ pub async fn add_account(
         });
     }
 
-    match db::insert_account(
-        pool.get_ref(),
-        &body.user_account,
-        &body.password_account,
-        &body.title,
-        &body.url,
-        &body.username,
-        crypto.get_ref()
-    ).await {
-        Ok((id, created_at)) => {
-            log::info!("Account '{}' added by user {}: {}", body.title, body.username, id);
+    let fields = items::fields(&[("user_account", &body.user_account), ("password", &body.password_account)]);
+    let item = NewItem {
+        schema: &Schema::Kind(items::kind("account").expect("built-in item type")),
+        owner: Some(&username),
+        group_name: None,
+        title: &body.title,
+        url: &body.url,
+        notes: body.notes.as_deref(),
+        fields: &fields,
+        expires_at: body.expires_at.as_deref(),
+        rotate_every: body.rotate_every,
+        created_by: &username,
+    };
+
+    match db::insert_item(pool.get_ref(), &item, crypto.get_ref()).await {
+        Ok((id, _, created_at)) => {
+            log::info!("Account '{}' added by user {}: {}", body.title, username, id);
             HttpResponse::Created().json(AccountResponse {
                 id,
                 created_at,
                 message: "Account stored successfully".into(),
             })
         }
+        Err(sqlx::Error::Protocol(msg)) => HttpResponse::BadRequest().json(ErrorResponse { error: msg }),
         Err(e) => {
             // Gestion des erreurs spécifiques
             let error_msg = e.to_string();
             if error_msg.contains("UNIQUE constraint failed") {
-                log::warn!("Duplicate account title '{}' for user {}", body.title, body.username);
+                log::warn!("Duplicate account title '{}' for user {}", body.title, username);
                 return HttpResponse::Conflict().json(ErrorResponse {
                     error: format!("An account with title '{}' already exists", body.title),
                 });
             }
             
-            log::error!("Failed to add account for {}: {}", body.username, e);
+            log::error!("Failed to add account for {}: {}", username, e);
             HttpResponse::InternalServerError().json(ErrorResponse {
                 error: "Failed to store account".into(),
             })
@@ -103,31 +152,36 @@ pub async fn add_account(
     }
 }
 
-/// Supprime un compte par son ID
+/// Place un compte dans la corbeille par son ID
 pub async fn delete_account(
-    _req: HttpRequest,
+    req: HttpRequest,
     pool: web::Data<SqlitePool>,
     body: web::Json<DeleteRequest>,
 ) -> HttpResponse {
+    let username = match current_username(&req) {
+        Ok(u) => u,
+        Err(response) => return response,
+    };
+
     if body.id.trim().is_empty() {
         return HttpResponse::BadRequest().json(ErrorResponse {
             error: "Account ID cannot be empty".into(),
         });
     }
 
-    match db::delete_account_by_title(pool.get_ref(), &body.id).await {
-        Ok(message) => {
-            if message.starts_with("0") {
-                log::warn!("Account not found for deletion: {}", body.id);
-                return HttpResponse::NotFound().json(ErrorResponse {
-                    error: "Account not found".into(),
-                });
-            }
-            log::info!("Account {} deleted successfully", body.id);
+    match db::trash_personal_item(pool.get_ref(), "account", &body.id, &username).await {
+        Ok(0) => {
+            log::warn!("Account not found for deletion: {}", body.id);
+            HttpResponse::NotFound().json(ErrorResponse {
+                error: "Account not found".into(),
+            })
+        }
+        Ok(affected) => {
+            log::info!("Account {} moved to trash by {}", body.id, username);
             HttpResponse::Ok().json(serde_json::json!({
-                "message": "Account deleted successfully",
+                "message": "Account moved to trash",
                 "id": body.id,
-                "details": message
+                "details": format!("{} row(s) affected", affected)
             }))
         }
         Err(e) => {
@@ -141,25 +195,26 @@ pub async fn delete_account(
 
 // ==================== API KEYS ====================
 
-/// Récupère toutes les clés API d'un utilisateur (déchiffrées)
+/// Liste les clés API de l'utilisateur connecté (sans les clés, voir `reveal_item`)
 pub async fn get_api_key(
-    _req: HttpRequest,
+    req: HttpRequest,
     pool: web::Data<SqlitePool>,
-    body: web::Json<UsernameRequest>,
+    query: web::Query<ListParams>,
     crypto: web::Data<CryptoService>,
 ) -> HttpResponse {
-    let username = &body.username;
+    let username = match current_username(&req) {
+        Ok(u) => u,
+        Err(response) => return response,
+    };
+    let list = match list_query(&query, &db::ITEM_LIST, crypto.get_ref()) {
+        Ok(list) => list,
+        Err(response) => return response,
+    };
 
-    match db::get_api_key_by_username(pool.get_ref(), username, crypto.get_ref()).await {
-        Ok(api_keys) if !api_keys.is_empty() => {
-            log::info!("User {} retrieved {} API key(s)", username, api_keys.len());
-            HttpResponse::Ok().json(api_keys)
-        }
-        Ok(_) => {
-            log::warn!("No API key found for user: {}", username);
-            HttpResponse::NotFound().json(ErrorResponse {
-                error: "No API key found for this user".into(),
-            })
+    match db::get_api_key_by_username(pool.get_ref(), &username, &list, crypto.get_ref()).await {
+        Ok(page) => {
+            log::info!("User {} retrieved {} of {} API key(s)", username, page.items.len(), page.total);
+            HttpResponse::Ok().json(page)
         }
         Err(e) => {
             log::error!("Failed to retrieve API key for {}: {}", username, e);
@@ -172,17 +227,18 @@ pub async fn get_api_key(
 
 /// Ajoute une nouvelle clé API (chiffrée)
 pub async fn add_api_key(
-    _req: HttpRequest,
+    req: HttpRequest,
     pool: web::Data<SqlitePool>,
     body: web::Json<AddApiKeyRequest>,
     crypto: web::Data<CryptoService>,
 ) -> HttpResponse {
+    // Le propriétaire et l'auteur de la première version sont ceux du token, jamais ceux du corps
+    let username = match current_username(&req) {
+        Ok(u) => u,
+        Err(response) => return response,
+    };
+
     // Validation des champs
-    if body.username.trim().is_empty() {
-        return HttpResponse::BadRequest().json(ErrorResponse {
-            error: "Username cannot be empty".into(),
-        });
-    }
     if body.title.trim().is_empty() {
         return HttpResponse::BadRequest().json(ErrorResponse {
             error: "Title cannot be empty".into(),
@@ -194,32 +250,41 @@ pub async fn add_api_key(
         });
     }
 
-    match db::insert_api_key(
-        pool.get_ref(), 
-        &body.api_key, 
-        &body.title, 
-        &body.username,
-        crypto.get_ref()
-    ).await {
-        Ok((id, created_at)) => {
-            log::info!("API key '{}' added by user {}: {}", body.title, body.username, id);
+    let fields = items::fields(&[("api_key", &body.api_key)]);
+    let item = NewItem {
+        schema: &Schema::Kind(items::kind("api_key").expect("built-in item type")),
+        owner: Some(&username),
+        group_name: None,
+        title: &body.title,
+        url: "",
+        notes: body.notes.as_deref(),
+        fields: &fields,
+        expires_at: body.expires_at.as_deref(),
+        rotate_every: body.rotate_every,
+        created_by: &username,
+    };
+
+    match db::insert_item(pool.get_ref(), &item, crypto.get_ref()).await {
+        Ok((id, _, created_at)) => {
+            log::info!("API key '{}' added by user {}: {}", body.title, username, id);
             HttpResponse::Created().json(ApiKeyResponse {
                 id,
                 created_at,
                 message: "API key stored successfully".into(),
             })
         }
+        Err(sqlx::Error::Protocol(msg)) => HttpResponse::BadRequest().json(ErrorResponse { error: msg }),
         Err(e) => {
             // Gestion des erreurs spécifiques
             let error_msg = e.to_string();
             if error_msg.contains("UNIQUE constraint failed") {
-                log::warn!("Duplicate API key title '{}' for user {}", body.title, body.username);
+                log::warn!("Duplicate API key title '{}' for user {}", body.title, username);
                 return HttpResponse::Conflict().json(ErrorResponse {
                     error: format!("An API key with title '{}' already exists", body.title),
                 });
             }
 
-            log::error!("Failed to add API key for {}: {}", body.username, e);
+            log::error!("Failed to add API key for {}: {}", username, e);
             HttpResponse::InternalServerError().json(ErrorResponse {
                 error: "Failed to store API key".into(),
             })
@@ -227,31 +292,36 @@ pub async fn add_api_key(
     }
 }
 
-/// Supprime une clé API par son ID
+/// Place une clé API dans la corbeille par son ID
 pub async fn delete_api_key(
-    _req: HttpRequest,
+    req: HttpRequest,
     pool: web::Data<SqlitePool>,
     body: web::Json<DeleteRequest>,
 ) -> HttpResponse {
+    let username = match current_username(&req) {
+        Ok(u) => u,
+        Err(response) => return response,
+    };
+
     if body.id.trim().is_empty() {
         return HttpResponse::BadRequest().json(ErrorResponse {
             error: "API key ID cannot be empty".into(),
         });
     }
 
-    match db::delete_api_key_by_title(pool.get_ref(), &body.id).await {
-        Ok(message) => {
-            if message.starts_with("0") {
-                log::warn!("API key not found for deletion: {}", body.id);
-                return HttpResponse::NotFound().json(ErrorResponse {
-                    error: "API key not found".into(),
-                });
-            }
-            log::info!("API key {} deleted successfully", body.id);
+    match db::trash_personal_item(pool.get_ref(), "api_key", &body.id, &username).await {
+        Ok(0) => {
+            log::warn!("API key not found for deletion: {}", body.id);
+            HttpResponse::NotFound().json(ErrorResponse {
+                error: "API key not found".into(),
+            })
+        }
+        Ok(affected) => {
+            log::info!("API key {} moved to trash by {}", body.id, username);
             HttpResponse::Ok().json(serde_json::json!({
-                "message": "API key deleted successfully",
+                "message": "API key moved to trash",
                 "id": body.id,
-                "details": message
+                "details": format!("{} row(s) affected", affected)
             }))
         }
         Err(e) => {
@@ -263,307 +333,3653 @@ pub async fn delete_api_key(
     }
 }
 
-// ==================== USER INFO ====================
+// ==================== ITEMS ====================
 
-/// Récupère les informations de l'utilisateur connecté
-pub async fn get_me(req: HttpRequest) -> HttpResponse {
-    let extensions = req.extensions();
+/// Décrit les types d'éléments disponibles et leurs champs
+pub async fn get_item_types() -> HttpResponse {
+    HttpResponse::Ok().json(items::KINDS)
+}
 
-    let claims = match extensions.get::<Claims>() {
-        Some(c) => c,
-        None => {
-            log::warn!("Unauthorized access attempt to /me endpoint");
-            return HttpResponse::Unauthorized().json(ErrorResponse {
-                error: "Missing or invalid authentication".into(),
-            });
+/// Liste les modèles d'éléments définis par les administrateurs ; leur nom sert de `kind`
+pub async fn get_item_templates(pool: web::Data<SqlitePool>) -> HttpResponse {
+    match db::get_templates(pool.get_ref()).await {
+        Ok(templates) => HttpResponse::Ok().json(templates),
+        Err(e) => {
+            log::error!("Failed to retrieve item templates: {}", e);
+            HttpResponse::InternalServerError().json(ErrorResponse {
+                error: "Failed to retrieve item templates".into(),
+            })
         }
-    };
-
-    log::debug!("User {} accessed /me endpoint", claims.username);
-    
-    let me = MeResponse {
-        username: claims.username.clone(),
-    };
-
-    HttpResponse::Ok().json(me)
+    }
 }
 
-/// Récupère les groupes d'un utilisateur
-pub async fn get_groups_by_name(
+/// Ajoute un élément de n'importe quel type, personnel ou dans un groupe dont l'utilisateur est membre
+pub async fn add_item(
     req: HttpRequest,
     pool: web::Data<SqlitePool>,
-    body: web::Json<UsernameRequest>,  // ✅ CHANGÉ: UsernameRequest au lieu de DeleteGroups
+    body: web::Json<AddItemRequest>,
+    crypto: web::Data<CryptoService>,
 ) -> HttpResponse {
-    let extensions = req.extensions();
-
-    let claims = match extensions.get::<Claims>() {
-        Some(c) => c,
-        None => {
-            log::warn!("Unauthorized access attempt to /get_groups_by_name endpoint");
-            return HttpResponse::Unauthorized().json(ErrorResponse {
-                error: "Missing or invalid authentication".into(),
-            });
-        }
+    let username = match current_username(&req) {
+        Ok(u) => u,
+        Err(response) => return response,
     };
 
-    let username = &body.username;
-    
-    if username.trim().is_empty() {
+    let schema = match resolve_kind(pool.get_ref(), &body.kind).await {
+        Ok(schema) => schema,
+        Err(response) => return response,
+    };
+    if body.title.trim().is_empty() {
         return HttpResponse::BadRequest().json(ErrorResponse {
-            error: "Username cannot be empty".into(),
+            error: "Title cannot be empty".into(),
         });
     }
 
-    log::debug!("User {} requesting their groups", claims.username);
+    let group_name = body.group_name.as_deref();
+    if let Err(response) = authorize_vault(pool.get_ref(), &username, group_name, GroupRole::Editor).await {
+        return response;
+    }
 
-    match db::get_groups_by_username(pool.get_ref(), username).await {  // ✅ Nouvelle fonction
-        Ok(groups) if !groups.is_empty() => {
-            log::info!("User '{}' has {} group(s)", username, groups.len());
-            HttpResponse::Ok().json(groups)
-        }
-        Ok(_) => {
-            log::warn!("No groups found for user: {}", username);
-            HttpResponse::NotFound().json(ErrorResponse {
-                error: "No groups found for this user".into(),
+    let item = NewItem {
+        schema: &schema,
+        owner: Some(&username),
+        group_name,
+        title: &body.title,
+        url: &body.url,
+        notes: body.notes.as_deref(),
+        fields: &body.fields,
+        expires_at: body.expires_at.as_deref(),
+        rotate_every: body.rotate_every,
+        created_by: &username,
+    };
+
+    match db::insert_item(pool.get_ref(), &item, crypto.get_ref()).await {
+        Ok((id, item_type, created_at)) => {
+            log::info!("User {} added {} {}", username, item_type, id);
+            HttpResponse::Created().json(AddItemResponse {
+                id,
+                item_type,
+                created_at,
+                message: "Item stored successfully".into(),
             })
         }
+        Err(sqlx::Error::Protocol(msg)) => HttpResponse::BadRequest().json(ErrorResponse { error: msg }),
         Err(e) => {
-            log::error!("Failed to retrieve groups for {}: {}", username, e);
+            if e.to_string().contains("UNIQUE constraint failed") {
+                return HttpResponse::Conflict().json(ErrorResponse {
+                    error: format!("An item of this type with title '{}' already exists", body.title),
+                });
+            }
+
+            log::error!("Failed to add {} for {}: {}", body.kind, username, e);
             HttpResponse::InternalServerError().json(ErrorResponse {
-                error: "Failed to retrieve groups".into(),
+                error: "Failed to store item".into(),
             })
         }
     }
 }
 
-// ==================== HEALTH CHECK ====================
-
-/// Endpoint de santé pour vérifier que l'API fonctionne
-pub async fn health_check() -> HttpResponse {
-    HttpResponse::Ok().json(serde_json::json!({
-        "status": "healthy",
-        "service": "password-manager-api",
-        "timestamp": chrono::Utc::now().to_rfc3339()
-    }))
-}
-
-
-/// Ajoute un compte dans un groupe (chiffré)
-pub async fn add_account_in_group(
-    _req: HttpRequest,
+/// Liste les éléments personnels, ou ceux d'un groupe, éventuellement d'un seul type (sans les champs secrets)
+pub async fn get_items(
+    req: HttpRequest,
     pool: web::Data<SqlitePool>,
-    body: web::Json<AddAccountInGroup>,
+    body: web::Json<GetItemsRequest>,
+    query: web::Query<ListParams>,
     crypto: web::Data<CryptoService>,
 ) -> HttpResponse {
-    // Validation des champs
-    if body.group_name.trim().is_empty() {
-        return HttpResponse::BadRequest().json(ErrorResponse {
-            error: "Group name cannot be empty".into(),
-        });
-    }
-    if body.title.trim().is_empty() {
-        return HttpResponse::BadRequest().json(ErrorResponse {
-            error: "Title cannot be empty".into(),
-        });
-    }
-    if body.user_account.trim().is_empty() {
-        return HttpResponse::BadRequest().json(ErrorResponse {
-            error: "User account cannot be empty".into(),
-        });
-    }
-    if body.password_account.trim().is_empty() {
-        return HttpResponse::BadRequest().json(ErrorResponse {
-            error: "Password cannot be empty".into(),
-        });
+    let username = match current_username(&req) {
+        Ok(u) => u,
+        Err(response) => return response,
+    };
+
+    let group_name = body.group_name.as_deref();
+    let item_type = match body.kind.as_deref() {
+        Some(name) => match resolve_kind(pool.get_ref(), name).await {
+            Ok(schema) => Some(items::item_type(schema.name(), group_name.is_some())),
+            Err(response) => return response,
+        },
+        None => None,
+    };
+
+    if let Err(response) = authorize_vault(pool.get_ref(), &username, group_name, GroupRole::Viewer).await {
+        return response;
     }
 
-    match db::insert_account_in_group(
-        pool.get_ref(),
-        &body.user_account,
-        &body.password_account,
-        &body.title,
-        &body.url,
-        &body.group_name,
-        crypto.get_ref()
-    ).await {
-        Ok((id, created_at)) => {
-            log::info!("Account '{}' added to group '{}': {}", body.title, body.group_name, id);
-            HttpResponse::Created().json(AccountInGroupResponse {
-                id,
-                created_at,
-                group_name: body.group_name.clone(),
-                message: "Account added to group successfully".into(),
-            })
+    let list = match list_query(&query, &db::ITEM_LIST, crypto.get_ref()) {
+        Ok(list) => list,
+        Err(response) => return response,
+    };
+
+    match db::get_items(pool.get_ref(), &username, group_name, item_type.as_deref(), &list, crypto.get_ref()).await {
+        Ok(page) => {
+            log::info!("User {} retrieved {} of {} item(s)", username, page.items.len(), page.total);
+            HttpResponse::Ok().json(page)
         }
         Err(e) => {
-            let error_msg = e.to_string();
-            if error_msg.contains("UNIQUE constraint failed") {
-                log::warn!("Duplicate account title '{}' in group '{}'", body.title, body.group_name);
-                return HttpResponse::Conflict().json(ErrorResponse {
-                    error: format!("An account with title '{}' already exists in this group", body.title),
-                });
-            }
-            
-            log::error!("Failed to add account to group '{}': {}", body.group_name, e);
+            log::error!("Failed to retrieve items for {}: {}", username, e);
             HttpResponse::InternalServerError().json(ErrorResponse {
-                error: "Failed to add account to group".into(),
+                error: "Failed to retrieve items".into(),
             })
         }
     }
 }
 
-/// Ajoute une clé API dans un groupe (chiffrée)
-pub async fn add_api_key_in_group(
-    _req: HttpRequest,
+/// Modifie les champs d'un élément ; le contenu obtenu est revalidé selon son type
+pub async fn update_item(
+    req: HttpRequest,
     pool: web::Data<SqlitePool>,
-    body: web::Json<AddApiKeyInGroup>,
+    body: web::Json<UpdateItemRequest>,
+    config: web::Data<AppConfig>,
     crypto: web::Data<CryptoService>,
 ) -> HttpResponse {
-    // Validation des champs
-    if body.group_name.trim().is_empty() {
+    let metadata = db::MetadataChanges {
+        title: body.title.as_deref(),
+        url: body.url.as_deref(),
+        notes: body.notes.as_deref(),
+    };
+    if metadata.is_empty() {
+        return update_fields(&req, pool.get_ref(), config.get_ref(), crypto.get_ref(), &body.item_type, &body.item_id, &body.fields, "Item updated successfully").await;
+    }
+
+    if body.item_id.trim().is_empty() {
         return HttpResponse::BadRequest().json(ErrorResponse {
-            error: "Group name cannot be empty".into(),
+            error: "Item ID cannot be empty".into(),
         });
     }
-    if body.title.trim().is_empty() {
+    if body.title.as_deref().is_some_and(|title| title.trim().is_empty()) {
         return HttpResponse::BadRequest().json(ErrorResponse {
             error: "Title cannot be empty".into(),
         });
     }
-    if body.api_key.trim().is_empty() {
-        return HttpResponse::BadRequest().json(ErrorResponse {
-            error: "API key cannot be empty".into(),
-        });
+
+    let username = match authorize_item(&req, pool.get_ref(), &body.item_type, &body.item_id, GroupRole::Editor).await {
+        Ok(u) => u,
+        Err(response) => return response,
+    };
+    if let Err(response) = check_lease(pool.get_ref(), &body.item_type, &body.item_id, &username).await {
+        return response;
     }
 
-    match db::insert_api_key_in_group(
+    // Champs et métadonnées sont enregistrés ensemble ou pas du tout
+    match db::update_item(
         pool.get_ref(),
-        &body.api_key,
-        &body.title,
-        &body.group_name,
-        crypto.get_ref()
-    ).await {
-        Ok((id, created_at)) => {
-            log::info!("API key '{}' added to group '{}': {}", body.title, body.group_name, id);
-            HttpResponse::Created().json(ApiKeyInGroupResponse {
-                id,
-                created_at,
-                group_name: body.group_name.clone(),
-                message: "API key added to group successfully".into(),
-            })
+        &body.item_type,
+        &body.item_id,
+        &body.fields,
+        &metadata,
+        &username,
+        config.max_secret_versions,
+        crypto.get_ref(),
+    )
+    .await
+    {
+        Ok((version, updated_at)) => {
+            log::info!("User {} updated {} {} (version {:?})", username, body.item_type, body.item_id, version);
+            let mut response = serde_json::json!({
+                "id": body.item_id,
+                "updated_at": updated_at,
+                "message": "Item updated successfully"
+            });
+            if let Some(version) = version {
+                response["version"] = version.into();
+            }
+            HttpResponse::Ok().json(response)
         }
+        Err(sqlx::Error::RowNotFound) => HttpResponse::NotFound().json(ErrorResponse {
+            error: "Item not found".into(),
+        }),
+        Err(sqlx::Error::Protocol(msg)) => HttpResponse::BadRequest().json(ErrorResponse { error: msg }),
+        Err(e) if e.to_string().contains("UNIQUE constraint failed") => HttpResponse::Conflict().json(ErrorResponse {
+            error: "An item with this title already exists".into(),
+        }),
         Err(e) => {
-            let error_msg = e.to_string();
-            if error_msg.contains("UNIQUE constraint failed") {
-                log::warn!("Duplicate API key title '{}' in group '{}'", body.title, body.group_name);
-                return HttpResponse::Conflict().json(ErrorResponse {
-                    error: format!("An API key with title '{}' already exists in this group", body.title),
-                });
-            }
-
-            log::error!("Failed to add API key to group '{}': {}", body.group_name, e);
+            log::error!("Failed to update {} {}: {}", body.item_type, body.item_id, e);
             HttpResponse::InternalServerError().json(ErrorResponse {
-                error: "Failed to add API key to group".into(),
+                error: "Failed to update item".into(),
             })
         }
     }
 }
 
-pub async fn get_account_in_group(
-    _req: HttpRequest,
+/// Place un élément dans la corbeille ; un élément de groupe demande le rôle d'éditeur,
+/// un élément personnel d'en être le propriétaire
+pub async fn delete_item(
+    req: HttpRequest,
     pool: web::Data<SqlitePool>,
-    body: web::Json<RequestGetAccountInGroups>,
-    crypto: web::Data<CryptoService>,
+    body: web::Json<TrashItemRequest>,
 ) -> HttpResponse {
-    let group_name = &body.group_name;
-    if group_name.trim().is_empty() {
+    trash_item(&req, pool.get_ref(), &body.item_type, &body.item_id).await
+}
+
+async fn trash_item(req: &HttpRequest, pool: &SqlitePool, item_type: &str, item_id: &str) -> HttpResponse {
+    if item_id.trim().is_empty() {
         return HttpResponse::BadRequest().json(ErrorResponse {
-            error: "Group name cannot be empty".into(),
+            error: "Item ID cannot be empty".into(),
         });
     }
 
-    match db::get_account_by_group_name(pool.get_ref(), group_name, crypto.get_ref()).await {
-        Ok(accounts) if !accounts.is_empty() => {
-            log::info!("Group '{}' retrieved {} account(s)", body.group_name, accounts.len());
-            HttpResponse::Ok().json(accounts)
-        }
+    // Un élément personnel partagé ne peut être supprimé que par son propriétaire
+    let in_group = items::split_item_type(item_type).1;
+    let required = if in_group { GroupRole::Editor } else { GroupRole::Owner };
+    let username = match authorize_item(req, pool, item_type, item_id, required).await {
+        Ok(u) => u,
+        Err(response) => return response,
+    };
+    if let Err(response) = check_lease(pool, item_type, item_id, &username).await {
+        return response;
+    }
+
+    let result = if in_group {
+        db::trash_group_item(pool, item_type, item_id, &username).await
+    } else {
+        db::trash_personal_item(pool, item_type, item_id, &username).await
+    };
+
+    match result {
+        Ok(0) => HttpResponse::NotFound().json(ErrorResponse {
+            error: "Item not found".into(),
+        }),
         Ok(_) => {
-            log::warn!("No accounts found for group: {}", body.group_name);
-            HttpResponse::NotFound().json(ErrorResponse {
-                error: "No accounts found for this group".into(),
-            })
+            log::info!("{} {} moved to trash by {}", item_type, item_id, username);
+            HttpResponse::Ok().json(serde_json::json!({
+                "message": "Item moved to trash",
+                "id": item_id,
+            }))
         }
         Err(e) => {
-            log::error!("Failed to retrieve accounts for group '{}': {}", body.group_name, e);
+            log::error!("Failed to delete {} {}: {}", item_type, item_id, e);
             HttpResponse::InternalServerError().json(ErrorResponse {
-                error: "Failed to retrieve accounts".into(),
+                error: "Failed to delete item".into(),
             })
         }
     }
 }
 
-pub async fn get_api_key_in_group(
-    _req: HttpRequest,
+/// Place un compte de groupe dans la corbeille
+pub async fn delete_account_in_group(
+    req: HttpRequest,
     pool: web::Data<SqlitePool>,
-    body: web::Json<RequestGetApiKeyInGroups>,
-    crypto: web::Data<CryptoService>,
+    body: web::Json<DeleteRequest>,
 ) -> HttpResponse {
-    let group_name = &body.group_name;
-    if group_name.trim().is_empty() {
-        return HttpResponse::BadRequest().json(ErrorResponse {
-            error: "Group name cannot be empty".into(),
-        });
-    }
-    match db::get_api_key_by_group_name(pool.get_ref(), group_name, crypto.get_ref()).await {
-        Ok(accounts) if !accounts.is_empty() => {
-            log::info!("Group '{}' retrieved {} account(s)", body.group_name, accounts.len());
-            HttpResponse::Ok().json(accounts)
-        }
-        Ok(_) => {
-            log::warn!("No accounts found for group: {}", body.group_name);
-            HttpResponse::NotFound().json(ErrorResponse {
-                error: "No accounts found for this group".into(),
+    trash_item(&req, pool.get_ref(), "account_group", &body.id).await
+}
+
+/// Place une clé API de groupe dans la corbeille
+pub async fn delete_api_key_in_group(
+    req: HttpRequest,
+    pool: web::Data<SqlitePool>,
+    body: web::Json<DeleteRequest>,
+) -> HttpResponse {
+    trash_item(&req, pool.get_ref(), "api_key_group", &body.id).await
+}
+
+// ==================== USER INFO ====================
+
+/// Récupère les informations de l'utilisateur connecté
+pub async fn get_me(req: HttpRequest) -> HttpResponse {
+    let extensions = req.extensions();
+
+    let claims = match extensions.get::<Claims>() {
+        Some(c) => c,
+        None => {
+            log::warn!("Unauthorized access attempt to /me endpoint");
+            return HttpResponse::Unauthorized().json(ErrorResponse {
+                error: "Missing or invalid authentication".into(),
+            });
+        }
+    };
+
+    log::debug!("User {} accessed /me endpoint", claims.username);
+    
+    let me = MeResponse {
+        username: claims.username.clone(),
+    };
+
+    HttpResponse::Ok().json(me)
+}
+
+/// Récupère les groupes de l'utilisateur connecté
+pub async fn get_groups_by_name(
+    req: HttpRequest,
+    pool: web::Data<SqlitePool>,
+    query: web::Query<ListParams>,
+    crypto: web::Data<CryptoService>,
+) -> HttpResponse {
+    let username = match current_username(&req) {
+        Ok(u) => u,
+        Err(response) => return response,
+    };
+
+    log::debug!("User {} requesting their groups", username);
+
+    let list = match list_query(&query, &db::GROUP_LIST, crypto.get_ref()) {
+        Ok(list) => list,
+        Err(response) => return response,
+    };
+
+    match db::get_groups_by_username(pool.get_ref(), &username, &list).await {
+        Ok(page) => {
+            log::info!("User '{}' has {} group(s)", username, page.total);
+            HttpResponse::Ok().json(page)
+        }
+        Err(e) => {
+            log::error!("Failed to retrieve groups for {}: {}", username, e);
+            HttpResponse::InternalServerError().json(ErrorResponse {
+                error: "Failed to retrieve groups".into(),
+            })
+        }
+    }
+}
+
+/// Liste les membres d'un groupe et leurs rôles (réservé aux membres)
+pub async fn get_group_members(
+    req: HttpRequest,
+    pool: web::Data<SqlitePool>,
+    body: web::Json<RequestGetAccountInGroups>,
+) -> HttpResponse {
+    let username = match current_username(&req) {
+        Ok(u) => u,
+        Err(response) => return response,
+    };
+
+    if let Err(response) = authorize_vault(pool.get_ref(), &username, Some(&body.group_name), GroupRole::Viewer).await {
+        return response;
+    }
+
+    match db::get_group_members(pool.get_ref(), &body.group_name).await {
+        Ok(members) => HttpResponse::Ok().json(members),
+        Err(e) => {
+            log::error!("Failed to retrieve members of group '{}': {}", body.group_name, e);
+            HttpResponse::InternalServerError().json(ErrorResponse {
+                error: "Failed to retrieve group members".into(),
+            })
+        }
+    }
+}
+
+/// Change le rôle d'un membre d'un groupe dont l'utilisateur est propriétaire
+pub async fn set_group_role(
+    req: HttpRequest,
+    pool: web::Data<SqlitePool>,
+    body: web::Json<SetGroupRoleRequest>,
+) -> HttpResponse {
+    let username = match current_username(&req) {
+        Ok(u) => u,
+        Err(response) => return response,
+    };
+
+    if let Err(response) = authorize_vault(pool.get_ref(), &username, Some(&body.group_name), GroupRole::Owner).await {
+        return response;
+    }
+    if let Err(response) = authorize_owner_grant(pool.get_ref(), &username, &body.group_name, body.role).await {
+        return response;
+    }
+
+    group_role_response(pool.get_ref(), &body, &username).await
+}
+
+/// Applique un changement de rôle (propriétaire du groupe ou administrateur)
+pub async fn group_role_response(pool: &SqlitePool, body: &SetGroupRoleRequest, actor: &str) -> HttpResponse {
+    match db::set_group_role(pool, &body.group_name, &body.username, body.role, actor).await {
+        Ok(previous) => {
+            log::info!("{} changed the role of {} in group '{}' from {} to {}", actor, body.username, body.group_name, previous, body.role);
+            HttpResponse::Ok().json(serde_json::json!({
+                "group_name": body.group_name,
+                "username": body.username,
+                "role": body.role,
+                "previous_role": previous,
+                "message": "Group role updated successfully"
+            }))
+        }
+        Err(sqlx::Error::RowNotFound) => HttpResponse::NotFound().json(ErrorResponse {
+            error: format!("{} is not a member of group '{}'", body.username, body.group_name),
+        }),
+        Err(sqlx::Error::Protocol(msg)) => HttpResponse::Conflict().json(ErrorResponse { error: msg }),
+        Err(e) => {
+            log::error!("Failed to change the role of {} in group '{}': {}", body.username, body.group_name, e);
+            HttpResponse::InternalServerError().json(ErrorResponse {
+                error: "Failed to update group role".into(),
+            })
+        }
+    }
+}
+
+/// Seuls les propriétaires nommés à la création du groupe peuvent nommer d'autres propriétaires ;
+/// les administrateurs passent par leurs propres routes
+async fn authorize_owner_grant(
+    pool: &SqlitePool,
+    username: &str,
+    group_name: &str,
+    role: GroupRole,
+) -> Result<(), HttpResponse> {
+    if role != GroupRole::Owner {
+        return Ok(());
+    }
+
+    match db::is_original_owner(pool, group_name, username).await {
+        Ok(true) => Ok(()),
+        Ok(false) => Err(HttpResponse::Forbidden().json(ErrorResponse {
+            error: "Only administrators and the group's original owners can grant the owner role".into(),
+        })),
+        Err(e) => {
+            log::error!("Failed to check original ownership of {} in '{}': {}", username, group_name, e);
+            Err(HttpResponse::InternalServerError().json(ErrorResponse {
+                error: "Failed to check access".into(),
+            }))
+        }
+    }
+}
+
+/// Ajoute un membre à un groupe dont l'utilisateur est propriétaire
+pub async fn add_group_member(
+    req: HttpRequest,
+    pool: web::Data<SqlitePool>,
+    body: web::Json<AddUserGroups>,
+) -> HttpResponse {
+    let username = match current_username(&req) {
+        Ok(u) => u,
+        Err(response) => return response,
+    };
+
+    if let Err(response) = authorize_vault(pool.get_ref(), &username, Some(&body.group_name), GroupRole::Owner).await {
+        return response;
+    }
+    if let Err(response) = authorize_owner_grant(pool.get_ref(), &username, &body.group_name, body.role).await {
+        return response;
+    }
+    if let Err(response) = check_access_hours("expires_in_hours", body.expires_in_hours) {
+        return response;
+    }
+    let expires_at = body.expires_in_hours.map(|hours| (Utc::now() + chrono::Duration::hours(hours)).to_rfc3339());
+
+    match db::add_group_member(pool.get_ref(), &body.group_name, &body.username, body.role, expires_at.as_deref(), &username).await {
+        Ok(member) => {
+            log::info!("{} added {} to group '{}' as {}", username, member.username, body.group_name, member.role);
+            HttpResponse::Created().json(member)
+        }
+        Err(sqlx::Error::RowNotFound) => HttpResponse::NotFound().json(ErrorResponse {
+            error: format!("Group '{}' does not exist", body.group_name),
+        }),
+        Err(sqlx::Error::Protocol(msg)) => HttpResponse::Conflict().json(ErrorResponse { error: msg }),
+        Err(e) => {
+            log::error!("Failed to add {} to group '{}': {}", body.username, body.group_name, e);
+            HttpResponse::InternalServerError().json(ErrorResponse {
+                error: "Failed to add group member".into(),
+            })
+        }
+    }
+}
+
+/// Prolonge l'adhésion temporaire d'un membre d'un groupe dont l'utilisateur est propriétaire,
+/// ou la rend permanente ; un propriétaire ne peut pas prolonger sa propre adhésion
+pub async fn update_group_membership(
+    req: HttpRequest,
+    pool: web::Data<SqlitePool>,
+    notifier: web::Data<Notifier>,
+    body: web::Json<UpdateMembershipRequest>,
+) -> HttpResponse {
+    let username = match current_username(&req) {
+        Ok(u) => u,
+        Err(response) => return response,
+    };
+
+    if body.username == username {
+        return HttpResponse::Forbidden().json(ErrorResponse {
+            error: "You cannot change the expiry of your own membership".into(),
+        });
+    }
+    if let Err(response) = authorize_vault(pool.get_ref(), &username, Some(&body.group_name), GroupRole::Owner).await {
+        return response;
+    }
+
+    membership_expiry_response(pool.get_ref(), notifier.get_ref(), &body, &username).await
+}
+
+/// Applique la nouvelle échéance d'une adhésion et prévient le membre
+/// (propriétaire du groupe ou administrateur)
+pub async fn membership_expiry_response(
+    pool: &SqlitePool,
+    notifier: &Notifier,
+    body: &UpdateMembershipRequest,
+    actor: &str,
+) -> HttpResponse {
+    let expires_at = match (body.expires_in_hours, body.permanent) {
+        (Some(hours), false) => {
+            if let Err(response) = check_access_hours("expires_in_hours", Some(hours)) {
+                return response;
+            }
+            Some((Utc::now() + chrono::Duration::hours(hours)).to_rfc3339())
+        }
+        (None, true) => None,
+        _ => {
+            return HttpResponse::BadRequest().json(ErrorResponse {
+                error: "Provide either expires_in_hours or permanent".into(),
+            });
+        }
+    };
+
+    match db::set_membership_expiry(pool, &body.group_name, &body.username, expires_at.as_deref(), actor).await {
+        Ok(member) => {
+            let until = member.expires_at.as_deref().unwrap_or("permanent");
+            notifier.send(Notification::new(
+                "membership_updated",
+                vec![member.username.clone()],
+                &body.group_name,
+                match &member.expires_at {
+                    Some(expires_at) => format!("Your access to group '{}' now expires at {}", body.group_name, expires_at),
+                    None => format!("Your access to group '{}' is now permanent", body.group_name),
+                },
+            ));
+
+            log::info!("{} set membership of {} in group {} until {}", actor, member.username, body.group_name, until);
+            HttpResponse::Ok().json(member)
+        }
+        Err(sqlx::Error::RowNotFound) => HttpResponse::NotFound().json(ErrorResponse {
+            error: format!("{} is not a member of group '{}'", body.username, body.group_name),
+        }),
+        Err(e) => {
+            log::error!("Failed to update membership of {} in group {}: {}", body.username, body.group_name, e);
+            HttpResponse::InternalServerError().json(ErrorResponse {
+                error: "Failed to update membership".into(),
+            })
+        }
+    }
+}
+
+/// Retire un membre d'un groupe dont l'utilisateur est propriétaire
+pub async fn remove_group_member(
+    req: HttpRequest,
+    pool: web::Data<SqlitePool>,
+    body: web::Json<RemoveGroupMemberRequest>,
+) -> HttpResponse {
+    let username = match current_username(&req) {
+        Ok(u) => u,
+        Err(response) => return response,
+    };
+
+    if let Err(response) = authorize_vault(pool.get_ref(), &username, Some(&body.group_name), GroupRole::Owner).await {
+        return response;
+    }
+
+    group_member_removal_response(pool.get_ref(), &body, &username).await
+}
+
+/// Applique le retrait d'un membre (propriétaire du groupe ou administrateur)
+pub async fn group_member_removal_response(pool: &SqlitePool, body: &RemoveGroupMemberRequest, actor: &str) -> HttpResponse {
+    match db::remove_group_member(pool, &body.group_name, &body.username, body.flag_rotation, actor).await {
+        Ok(flagged) => {
+            log::info!("{} removed {} from group '{}' ({} secret(s) flagged for rotation)", actor, body.username, body.group_name, flagged);
+            HttpResponse::Ok().json(serde_json::json!({
+                "group_name": body.group_name,
+                "username": body.username,
+                "flagged_for_rotation": flagged,
+                "message": "Member removed from group"
+            }))
+        }
+        Err(sqlx::Error::RowNotFound) => HttpResponse::NotFound().json(ErrorResponse {
+            error: format!("{} is not a member of group '{}'", body.username, body.group_name),
+        }),
+        Err(sqlx::Error::Protocol(msg)) => HttpResponse::Conflict().json(ErrorResponse { error: msg }),
+        Err(e) => {
+            log::error!("Failed to remove {} from group '{}': {}", body.username, body.group_name, e);
+            HttpResponse::InternalServerError().json(ErrorResponse {
+                error: "Failed to remove group member".into(),
+            })
+        }
+    }
+}
+
+/// Politique d'âge maximal des secrets d'un groupe (réservé aux membres)
+pub async fn get_group_policy(
+    req: HttpRequest,
+    pool: web::Data<SqlitePool>,
+    body: web::Json<RequestGetAccountInGroups>,
+) -> HttpResponse {
+    let username = match current_username(&req) {
+        Ok(u) => u,
+        Err(response) => return response,
+    };
+
+    if let Err(response) = authorize_vault(pool.get_ref(), &username, Some(&body.group_name), GroupRole::Viewer).await {
+        return response;
+    }
+
+    match db::get_group_policy(pool.get_ref(), &body.group_name).await {
+        Ok(Some(policy)) => HttpResponse::Ok().json(policy),
+        Ok(None) => HttpResponse::NotFound().json(ErrorResponse {
+            error: format!("No policy for group '{}'", body.group_name),
+        }),
+        Err(e) => {
+            log::error!("Failed to retrieve the policy of group '{}': {}", body.group_name, e);
+            HttpResponse::InternalServerError().json(ErrorResponse {
+                error: "Failed to retrieve group policy".into(),
+            })
+        }
+    }
+}
+
+/// Resserre la politique d'un groupe dont l'utilisateur est propriétaire ; l'assouplir ou la
+/// retirer reste réservé aux administrateurs
+pub async fn set_group_policy(
+    req: HttpRequest,
+    pool: web::Data<SqlitePool>,
+    body: web::Json<SetGroupPolicyRequest>,
+) -> HttpResponse {
+    let username = match current_username(&req) {
+        Ok(u) => u,
+        Err(response) => return response,
+    };
+
+    if let Err(response) = authorize_vault(pool.get_ref(), &username, Some(&body.group_name), GroupRole::Owner).await {
+        return response;
+    }
+
+    group_policy_response(pool.get_ref(), &body, &username, false).await
+}
+
+/// Applique un changement de politique de groupe (propriétaire du groupe ou administrateur)
+pub async fn group_policy_response(pool: &SqlitePool, body: &SetGroupPolicyRequest, actor: &str, may_relax: bool) -> HttpResponse {
+    let Some(max_secret_age_days) = body.max_secret_age_days else {
+        if !may_relax {
+            return HttpResponse::Forbidden().json(ErrorResponse {
+                error: "Only administrators can remove a group policy".into(),
+            });
+        }
+
+        return match db::delete_group_policy(pool, &body.group_name, actor).await {
+            Ok(0) => HttpResponse::NotFound().json(ErrorResponse {
+                error: format!("No policy for group '{}'", body.group_name),
+            }),
+            Ok(_) => {
+                log::info!("{} removed the policy of group {}", actor, body.group_name);
+                HttpResponse::Ok().json(serde_json::json!({
+                    "group_name": body.group_name,
+                    "message": "Group policy removed successfully"
+                }))
+            }
+            Err(e) => {
+                log::error!("Failed to remove the policy of group {}: {}", body.group_name, e);
+                HttpResponse::InternalServerError().json(ErrorResponse {
+                    error: "Failed to remove group policy".into(),
+                })
+            }
+        };
+    };
+
+    match db::set_group_policy(pool, &body.group_name, max_secret_age_days, actor, may_relax).await {
+        Ok(policy) => {
+            log::info!("{} set max secret age of group {} to {} day(s)", actor, policy.group_name, policy.max_secret_age_days);
+            HttpResponse::Ok().json(policy)
+        }
+        Err(sqlx::Error::Protocol(msg)) => HttpResponse::BadRequest().json(ErrorResponse { error: msg }),
+        Err(sqlx::Error::RowNotFound) => HttpResponse::NotFound().json(ErrorResponse {
+            error: format!("Group '{}' does not exist", body.group_name),
+        }),
+        Err(e) => {
+            log::error!("Failed to set the policy of group {}: {}", body.group_name, e);
+            HttpResponse::InternalServerError().json(ErrorResponse {
+                error: "Failed to update group policy".into(),
+            })
+        }
+    }
+}
+
+/// Rattache un groupe à un groupe parent ou le détache : l'utilisateur doit être propriétaire
+/// (directement ou par héritage) du groupe, du nouveau parent et de l'ancien parent
+pub async fn set_group_parent(
+    req: HttpRequest,
+    pool: web::Data<SqlitePool>,
+    body: web::Json<SetGroupParentRequest>,
+) -> HttpResponse {
+    let username = match current_username(&req) {
+        Ok(u) => u,
+        Err(response) => return response,
+    };
+
+    if let Err(response) = authorize_vault(pool.get_ref(), &username, Some(&body.group_name), GroupRole::Owner).await {
+        return response;
+    }
+
+    if let Some(parent) = &body.parent_group {
+        if let Err(response) = authorize_vault(pool.get_ref(), &username, Some(parent), GroupRole::Owner).await {
+            return response;
+        }
+    }
+
+    // Détacher un groupe retire l'accès hérité de l'ancien parent : réservé à ses propriétaires
+    match db::group_parent(pool.get_ref(), &body.group_name).await {
+        Ok(Some(previous)) if body.parent_group.as_deref() != Some(previous.as_str()) => {
+            if let Err(response) = authorize_vault(pool.get_ref(), &username, Some(&previous), GroupRole::Owner).await {
+                return response;
+            }
+        }
+        Ok(_) => {}
+        Err(e) => {
+            log::error!("Failed to load the parent of group {}: {}", body.group_name, e);
+            return HttpResponse::InternalServerError().json(ErrorResponse {
+                error: "Failed to update group parent".into(),
+            });
+        }
+    }
+
+    group_parent_response(pool.get_ref(), &body, &username).await
+}
+
+/// Applique un changement de groupe parent (propriétaire des groupes concernés ou administrateur)
+pub async fn group_parent_response(pool: &SqlitePool, body: &SetGroupParentRequest, actor: &str) -> HttpResponse {
+    match db::set_group_parent(pool, &body.group_name, body.parent_group.as_deref(), actor).await {
+        Ok(previous) => {
+            log::info!(
+                "{} moved group {} from {} to {}",
+                actor,
+                body.group_name,
+                previous.as_deref().unwrap_or("root"),
+                body.parent_group.as_deref().unwrap_or("root")
+            );
+            HttpResponse::Ok().json(serde_json::json!({
+                "group_name": body.group_name,
+                "parent_group": body.parent_group,
+                "previous_parent": previous,
+                "message": "Group parent updated successfully"
+            }))
+        }
+        Err(sqlx::Error::RowNotFound) => HttpResponse::NotFound().json(ErrorResponse {
+            error: "Group not found".into(),
+        }),
+        Err(sqlx::Error::Protocol(msg)) => HttpResponse::Conflict().json(ErrorResponse { error: msg }),
+        Err(e) => {
+            log::error!("Failed to set the parent of group {}: {}", body.group_name, e);
+            HttpResponse::InternalServerError().json(ErrorResponse {
+                error: "Failed to update group parent".into(),
+            })
+        }
+    }
+}
+
+// ==================== HEALTH CHECK ====================
+
+/// Endpoint de santé pour vérifier que l'API fonctionne
+pub async fn health_check() -> HttpResponse {
+    HttpResponse::Ok().json(serde_json::json!({
+        "status": "healthy",
+        "service": "password-manager-api",
+        "timestamp": chrono::Utc::now().to_rfc3339()
+    }))
+}
+
+
+/// Ajoute un compte dans un groupe (chiffré)
+pub async fn add_account_in_group(
+    req: HttpRequest,
+    pool: web::Data<SqlitePool>,
+    body: web::Json<AddAccountInGroup>,
+    crypto: web::Data<CryptoService>,
+) -> HttpResponse {
+    let username = match current_username(&req) {
+        Ok(u) => u,
+        Err(response) => return response,
+    };
+
+    // Validation des champs
+    if body.group_name.trim().is_empty() {
+        return HttpResponse::BadRequest().json(ErrorResponse {
+            error: "Group name cannot be empty".into(),
+        });
+    }
+    if body.title.trim().is_empty() {
+        return HttpResponse::BadRequest().json(ErrorResponse {
+            error: "Title cannot be empty".into(),
+        });
+    }
+    if body.user_account.trim().is_empty() {
+        return HttpResponse::BadRequest().json(ErrorResponse {
+            error: "User account cannot be empty".into(),
+        });
+    }
+    if body.password_account.trim().is_empty() {
+        return HttpResponse::BadRequest().json(ErrorResponse {
+            error: "Password cannot be empty".into(),
+        });
+    }
+
+    if let Err(response) = authorize_vault(pool.get_ref(), &username, Some(&body.group_name), GroupRole::Editor).await {
+        return response;
+    }
+
+    let fields = items::fields(&[("user_account", &body.user_account), ("password", &body.password_account)]);
+    let item = NewItem {
+        schema: &Schema::Kind(items::kind("account").expect("built-in item type")),
+        owner: None,
+        group_name: Some(&body.group_name),
+        title: &body.title,
+        url: &body.url,
+        notes: body.notes.as_deref(),
+        fields: &fields,
+        expires_at: body.expires_at.as_deref(),
+        rotate_every: body.rotate_every,
+        created_by: &username,
+    };
+
+    match db::insert_item(pool.get_ref(), &item, crypto.get_ref()).await {
+        Ok((id, _, created_at)) => {
+            log::info!("Account '{}' added to group '{}': {}", body.title, body.group_name, id);
+            HttpResponse::Created().json(AccountInGroupResponse {
+                id,
+                created_at,
+                group_name: body.group_name.clone(),
+                message: "Account added to group successfully".into(),
+            })
+        }
+        Err(e) => {
+            let error_msg = e.to_string();
+            if error_msg.contains("UNIQUE constraint failed") {
+                log::warn!("Duplicate account title '{}' in group '{}'", body.title, body.group_name);
+                return HttpResponse::Conflict().json(ErrorResponse {
+                    error: format!("An account with title '{}' already exists in this group", body.title),
+                });
+            }
+            
+            if let sqlx::Error::Protocol(msg) = e {
+                return HttpResponse::BadRequest().json(ErrorResponse { error: msg });
+            }
+
+            log::error!("Failed to add account to group '{}': {}", body.group_name, e);
+            HttpResponse::InternalServerError().json(ErrorResponse {
+                error: "Failed to add account to group".into(),
+            })
+        }
+    }
+}
+
+/// Ajoute une clé API dans un groupe (chiffrée)
+pub async fn add_api_key_in_group(
+    req: HttpRequest,
+    pool: web::Data<SqlitePool>,
+    body: web::Json<AddApiKeyInGroup>,
+    crypto: web::Data<CryptoService>,
+) -> HttpResponse {
+    let username = match current_username(&req) {
+        Ok(u) => u,
+        Err(response) => return response,
+    };
+
+    // Validation des champs
+    if body.group_name.trim().is_empty() {
+        return HttpResponse::BadRequest().json(ErrorResponse {
+            error: "Group name cannot be empty".into(),
+        });
+    }
+    if body.title.trim().is_empty() {
+        return HttpResponse::BadRequest().json(ErrorResponse {
+            error: "Title cannot be empty".into(),
+        });
+    }
+    if body.api_key.trim().is_empty() {
+        return HttpResponse::BadRequest().json(ErrorResponse {
+            error: "API key cannot be empty".into(),
+        });
+    }
+
+    if let Err(response) = authorize_vault(pool.get_ref(), &username, Some(&body.group_name), GroupRole::Editor).await {
+        return response;
+    }
+
+    let fields = items::fields(&[("api_key", &body.api_key)]);
+    let item = NewItem {
+        schema: &Schema::Kind(items::kind("api_key").expect("built-in item type")),
+        owner: None,
+        group_name: Some(&body.group_name),
+        title: &body.title,
+        url: "",
+        notes: body.notes.as_deref(),
+        fields: &fields,
+        expires_at: body.expires_at.as_deref(),
+        rotate_every: body.rotate_every,
+        created_by: &username,
+    };
+
+    match db::insert_item(pool.get_ref(), &item, crypto.get_ref()).await {
+        Ok((id, _, created_at)) => {
+            log::info!("API key '{}' added to group '{}': {}", body.title, body.group_name, id);
+            HttpResponse::Created().json(ApiKeyInGroupResponse {
+                id,
+                created_at,
+                group_name: body.group_name.clone(),
+                message: "API key added to group successfully".into(),
+            })
+        }
+        Err(e) => {
+            let error_msg = e.to_string();
+            if error_msg.contains("UNIQUE constraint failed") {
+                log::warn!("Duplicate API key title '{}' in group '{}'", body.title, body.group_name);
+                return HttpResponse::Conflict().json(ErrorResponse {
+                    error: format!("An API key with title '{}' already exists in this group", body.title),
+                });
+            }
+
+            if let sqlx::Error::Protocol(msg) = e {
+                return HttpResponse::BadRequest().json(ErrorResponse { error: msg });
+            }
+
+            log::error!("Failed to add API key to group '{}': {}", body.group_name, e);
+            HttpResponse::InternalServerError().json(ErrorResponse {
+                error: "Failed to add API key to group".into(),
+            })
+        }
+    }
+}
+
+pub async fn get_account_in_group(
+    req: HttpRequest,
+    pool: web::Data<SqlitePool>,
+    body: web::Json<RequestGetAccountInGroups>,
+    query: web::Query<ListParams>,
+    crypto: web::Data<CryptoService>,
+) -> HttpResponse {
+    let username = match current_username(&req) {
+        Ok(u) => u,
+        Err(response) => return response,
+    };
+
+    let group_name = &body.group_name;
+    if group_name.trim().is_empty() {
+        return HttpResponse::BadRequest().json(ErrorResponse {
+            error: "Group name cannot be empty".into(),
+        });
+    }
+    if let Err(response) = authorize_vault(pool.get_ref(), &username, Some(group_name), GroupRole::Viewer).await {
+        return response;
+    }
+    let list = match list_query(&query, &db::ITEM_LIST, crypto.get_ref()) {
+        Ok(list) => list,
+        Err(response) => return response,
+    };
+
+    match db::get_account_by_group_name(pool.get_ref(), group_name, &list, crypto.get_ref()).await {
+        Ok(page) => {
+            log::info!("Group '{}' retrieved {} of {} account(s)", body.group_name, page.items.len(), page.total);
+            HttpResponse::Ok().json(page)
+        }
+        Err(e) => {
+            log::error!("Failed to retrieve accounts for group '{}': {}", body.group_name, e);
+            HttpResponse::InternalServerError().json(ErrorResponse {
+                error: "Failed to retrieve accounts".into(),
+            })
+        }
+    }
+}
+
+pub async fn get_api_key_in_group(
+    req: HttpRequest,
+    pool: web::Data<SqlitePool>,
+    body: web::Json<RequestGetApiKeyInGroups>,
+    query: web::Query<ListParams>,
+    crypto: web::Data<CryptoService>,
+) -> HttpResponse {
+    let username = match current_username(&req) {
+        Ok(u) => u,
+        Err(response) => return response,
+    };
+
+    let group_name = &body.group_name;
+    if group_name.trim().is_empty() {
+        return HttpResponse::BadRequest().json(ErrorResponse {
+            error: "Group name cannot be empty".into(),
+        });
+    }
+    if let Err(response) = authorize_vault(pool.get_ref(), &username, Some(group_name), GroupRole::Viewer).await {
+        return response;
+    }
+    let list = match list_query(&query, &db::ITEM_LIST, crypto.get_ref()) {
+        Ok(list) => list,
+        Err(response) => return response,
+    };
+
+    match db::get_api_key_by_group_name(pool.get_ref(), group_name, &list, crypto.get_ref()).await {
+        Ok(page) => {
+            log::info!("Group '{}' retrieved {} of {} API key(s)", body.group_name, page.items.len(), page.total);
+            HttpResponse::Ok().json(page)
+        }
+        Err(e) => {
+            log::error!("Failed to retrieve accounts for group '{}': {}", body.group_name, e);
+            HttpResponse::InternalServerError().json(ErrorResponse {
+                error: "Failed to retrieve accounts".into(),
+            })
+        }
+    }
+}
+
+/// Déchiffre une clé API personnelle à partir de son titre (chaque lecture est auditée)
+pub async fn get_api_key_by_title(
+    req: HttpRequest,
+    pool: web::Data<SqlitePool>,
+    body: web::Json<RequestGetApiKeyInTitle>,
+    crypto: web::Data<CryptoService>,
+) -> HttpResponse {
+    let caller = match current_username(&req) {
+        Ok(u) => u,
+        Err(response) => return response,
+    };
+
+    let title = &body.title;
+    let username = &body.username;
+    if title.trim().is_empty() {
+        return HttpResponse::BadRequest().json(ErrorResponse {
+            error: "Group name cannot be empty".into(),
+        });        
+    }
+    if *username != caller {
+        log::warn!("User {} tried to read API key '{}' of {}", caller, title, username);
+        return HttpResponse::NotFound().json(ErrorResponse {
+            error: "No accounts found for this group".into(),
+        });
+    }
+    match db::get_api_key_by_title_and_username(pool.get_ref(), title, username, crypto.get_ref()).await {
+        Ok(accounts) if !accounts.is_empty() => {
+            for key in &accounts {
+                if let Err(e) = db::record_audit(pool.get_ref(), &caller, "reveal", Some("api_key"), Some(&key.id), title).await {
+                    log::error!("Failed to audit reveal of API key {}: {}", key.id, e);
+                    return HttpResponse::InternalServerError().json(ErrorResponse {
+                        error: "Failed to retrieve accounts".into(),
+                    });
+                }
+            }
+            log::info!("Group '{}' retrieved {} account(s)", username, accounts.len());
+            HttpResponse::Ok().json(accounts)
+        }
+        Ok(_) => {
+            log::warn!("No accounts found for group: {}", username);
+            HttpResponse::NotFound().json(ErrorResponse {
+                error: "No accounts found for this group".into(),
+            })
+        }
+        Err(e) => {
+            log::error!("Failed to retrieve accounts for group '{}': {}", title, e);
+            HttpResponse::InternalServerError().json(ErrorResponse {
+                error: "Failed to retrieve accounts".into(),
+            })
+        }
+    }
+
+}
+
+// ==================== UPDATES & VERSIONS ====================
+
+/// Modifie le contenu d'un élément après vérification de l'accès ; l'ancien contenu reste dans l'historique
+#[allow(clippy::too_many_arguments)]
+async fn update_fields(
+    req: &HttpRequest,
+    pool: &SqlitePool,
+    config: &AppConfig,
+    crypto: &CryptoService,
+    item_type: &str,
+    item_id: &str,
+    changes: &Fields,
+    message: &str,
+) -> HttpResponse {
+    if item_id.trim().is_empty() {
+        return HttpResponse::BadRequest().json(ErrorResponse {
+            error: "Item ID cannot be empty".into(),
+        });
+    }
+
+    let username = match authorize_item(req, pool, item_type, item_id, GroupRole::Editor).await {
+        Ok(u) => u,
+        Err(response) => return response,
+    };
+    if let Err(response) = check_lease(pool, item_type, item_id, &username).await {
+        return response;
+    }
+
+    match db::update_item_fields(pool, item_type, item_id, changes, &username, config.max_secret_versions, crypto).await {
+        Ok((version, updated_at)) => {
+            log::info!("User {} updated {} {} (version {})", username, item_type, item_id, version);
+            HttpResponse::Ok().json(UpdateSecretResponse {
+                id: item_id.to_string(),
+                version,
+                updated_at,
+                message: message.into(),
+            })
+        }
+        Err(sqlx::Error::RowNotFound) => HttpResponse::NotFound().json(ErrorResponse {
+            error: "Item not found".into(),
+        }),
+        Err(sqlx::Error::Protocol(msg)) => HttpResponse::BadRequest().json(ErrorResponse { error: msg }),
+        Err(e) => {
+            log::error!("Failed to update {} {}: {}", item_type, item_id, e);
+            HttpResponse::InternalServerError().json(ErrorResponse {
+                error: "Failed to update item".into(),
+            })
+        }
+    }
+}
+
+/// Remplace le secret principal d'un compte ou d'une clé API (endpoints historiques)
+async fn update_secret(
+    req: &HttpRequest,
+    pool: &SqlitePool,
+    config: &AppConfig,
+    crypto: &CryptoService,
+    item_type: &str,
+    item_id: &str,
+    secret: &str,
+) -> HttpResponse {
+    if secret.trim().is_empty() {
+        return HttpResponse::BadRequest().json(ErrorResponse {
+            error: "Secret cannot be empty".into(),
+        });
+    }
+
+    let Some((kind, _)) = items::parse_item_type(item_type) else {
+        return HttpResponse::BadRequest().json(ErrorResponse {
+            error: format!("Unknown item type '{}'", item_type),
+        });
+    };
+    let changes = items::fields(&[(kind.primary_secret, secret)]);
+
+    update_fields(req, pool, config, crypto, item_type, item_id, &changes, "Secret updated successfully").await
+}
+
+/// Modifie le mot de passe d'un compte personnel
+pub async fn update_account(
+    req: HttpRequest,
+    pool: web::Data<SqlitePool>,
+    body: web::Json<UpdateAccountPasswordRequest>,
+    config: web::Data<AppConfig>,
+    crypto: web::Data<CryptoService>,
+) -> HttpResponse {
+    update_secret(&req, pool.get_ref(), config.get_ref(), crypto.get_ref(), "account", &body.id, &body.password_account).await
+}
+
+/// Modifie une clé API personnelle
+pub async fn update_api_key(
+    req: HttpRequest,
+    pool: web::Data<SqlitePool>,
+    body: web::Json<UpdateApiKeyRequest>,
+    config: web::Data<AppConfig>,
+    crypto: web::Data<CryptoService>,
+) -> HttpResponse {
+    update_secret(&req, pool.get_ref(), config.get_ref(), crypto.get_ref(), "api_key", &body.id, &body.api_key).await
+}
+
+/// Modifie le mot de passe d'un compte de groupe
+pub async fn update_account_in_group(
+    req: HttpRequest,
+    pool: web::Data<SqlitePool>,
+    body: web::Json<UpdateAccountPasswordRequest>,
+    config: web::Data<AppConfig>,
+    crypto: web::Data<CryptoService>,
+) -> HttpResponse {
+    update_secret(&req, pool.get_ref(), config.get_ref(), crypto.get_ref(), "account_group", &body.id, &body.password_account).await
+}
+
+/// Modifie une clé API de groupe
+pub async fn update_api_key_in_group(
+    req: HttpRequest,
+    pool: web::Data<SqlitePool>,
+    body: web::Json<UpdateApiKeyRequest>,
+    config: web::Data<AppConfig>,
+    crypto: web::Data<CryptoService>,
+) -> HttpResponse {
+    update_secret(&req, pool.get_ref(), config.get_ref(), crypto.get_ref(), "api_key_group", &body.id, &body.api_key).await
+}
+
+/// Schéma et portée d'un type d'élément stocké (type intégré ou modèle) ; 400 si le type est inconnu
+async fn resolve_item_type(pool: &SqlitePool, item_type: &str) -> Result<(Schema, bool), HttpResponse> {
+    match db::item_schema(pool, item_type).await {
+        Ok(Some(resolved)) => Ok(resolved),
+        Ok(None) => Err(HttpResponse::BadRequest().json(ErrorResponse {
+            error: format!("Unknown item type '{}'", item_type),
+        })),
+        Err(e) => {
+            log::error!("Failed to resolve item type {}: {}", item_type, e);
+            Err(HttpResponse::InternalServerError().json(ErrorResponse {
+                error: "Failed to resolve item type".into(),
+            }))
+        }
+    }
+}
+
+/// Schéma d'un type de base (`account`, `note`, nom d'un modèle…), sans suffixe de portée
+async fn resolve_kind(pool: &SqlitePool, kind: &str) -> Result<Schema, HttpResponse> {
+    match resolve_item_type(pool, kind).await? {
+        (schema, false) => Ok(schema),
+        (_, true) => Err(HttpResponse::BadRequest().json(ErrorResponse {
+            error: format!("Unknown item kind '{}'", kind),
+        })),
+    }
+}
+
+/// Refus d'une action réservée à un rôle supérieur (dans le groupe ou sur l'élément partagé)
+fn role_required(required: GroupRole) -> HttpResponse {
+    HttpResponse::Forbidden().json(ErrorResponse {
+        error: format!("This action requires the {} role", required),
+    })
+}
+
+/// Vérifie le type d'élément et l'accès de l'utilisateur avant toute opération sur l'élément.
+/// Pour un élément de groupe, l'utilisateur doit y avoir au moins le rôle `required`
+async fn authorize_item(
+    req: &HttpRequest,
+    pool: &SqlitePool,
+    item_type: &str,
+    item_id: &str,
+    required: GroupRole,
+) -> Result<String, HttpResponse> {
+    let username = current_username(req)?;
+    resolve_item_type(pool, item_type).await?;
+
+    match db::item_role(pool, item_type, item_id, &username).await {
+        Ok(Some(role)) if role >= required => Ok(username),
+        Ok(Some(_)) => Err(role_required(required)),
+        Ok(None) => Err(HttpResponse::NotFound().json(ErrorResponse {
+            error: "Item not found".into(),
+        })),
+        Err(e) => {
+            log::error!("Failed to check access on {} {}: {}", item_type, item_id, e);
+            Err(HttpResponse::InternalServerError().json(ErrorResponse {
+                error: "Failed to check access".into(),
+            }))
+        }
+    }
+}
+
+/// Un identifiant de groupe emprunté ne peut être lu ou modifié que par le titulaire de l'emprunt
+async fn check_lease(pool: &SqlitePool, item_type: &str, item_id: &str, username: &str) -> Result<(), HttpResponse> {
+    if !item_type.ends_with("_group") {
+        return Ok(());
+    }
+
+    match db::active_lease(pool, item_type, item_id).await {
+        Ok(Some(lease)) if lease.username != username => Err(HttpResponse::Conflict().json(ErrorResponse {
+            error: format!("This credential is checked out by {} until {}", lease.username, lease.expires_at),
+        })),
+        Ok(_) => Ok(()),
+        Err(e) => {
+            log::error!("Failed to check lease on {} {}: {}", item_type, item_id, e);
+            Err(HttpResponse::InternalServerError().json(ErrorResponse {
+                error: "Failed to check access".into(),
+            }))
+        }
+    }
+}
+
+/// Liste les versions d'un secret (sans les valeurs)
+pub async fn get_secret_versions(
+    req: HttpRequest,
+    pool: web::Data<SqlitePool>,
+    body: web::Json<SecretVersionsRequest>,
+) -> HttpResponse {
+    if let Err(response) = authorize_item(&req, pool.get_ref(), &body.item_type, &body.item_id, GroupRole::Viewer).await {
+        return response;
+    }
+
+    match db::get_secret_versions(pool.get_ref(), &body.item_type, &body.item_id).await {
+        Ok(versions) => HttpResponse::Ok().json(versions),
+        Err(e) => {
+            log::error!("Failed to retrieve versions of {} {}: {}", body.item_type, body.item_id, e);
+            HttpResponse::InternalServerError().json(ErrorResponse {
+                error: "Failed to retrieve versions".into(),
+            })
+        }
+    }
+}
+
+/// Déchiffre une version précise d'un secret
+pub async fn reveal_secret_version(
+    req: HttpRequest,
+    pool: web::Data<SqlitePool>,
+    body: web::Json<SecretVersionRequest>,
+    crypto: web::Data<CryptoService>,
+) -> HttpResponse {
+    let username = match authorize_item(&req, pool.get_ref(), &body.item_type, &body.item_id, GroupRole::Viewer).await {
+        Ok(u) => u,
+        Err(response) => return response,
+    };
+    if let Err(response) = check_lease(pool.get_ref(), &body.item_type, &body.item_id, &username).await {
+        return response;
+    }
+
+    let schema = match resolve_item_type(pool.get_ref(), &body.item_type).await {
+        Ok((schema, _)) => schema,
+        Err(response) => return response,
+    };
+
+    match db::reveal_secret_version(pool.get_ref(), &body.item_type, &body.item_id, body.version, crypto.get_ref()).await {
+        Ok(Some((fields, info))) => {
+            let secret = items::text(&fields, schema.primary_secret()).to_string();
+            let details = format!("version {}", body.version);
+            if let Err(e) = db::record_audit(pool.get_ref(), &username, "reveal_version", Some(&body.item_type), Some(&body.item_id), &details).await {
+                log::error!("Failed to audit reveal of {} {}: {}", body.item_type, body.item_id, e);
+                return HttpResponse::InternalServerError().json(ErrorResponse {
+                    error: "Failed to retrieve version".into(),
+                });
+            }
+            log::info!("User {} revealed version {} of {} {}", username, body.version, body.item_type, body.item_id);
+            HttpResponse::Ok().json(RevealSecretVersionResponse {
+                item_id: body.item_id.clone(),
+                secret,
+                fields,
+                info,
+            })
+        }
+        Ok(None) => HttpResponse::NotFound().json(ErrorResponse {
+            error: "Version not found".into(),
+        }),
+        Err(e) => {
+            log::error!("Failed to reveal version {} of {} {}: {}", body.version, body.item_type, body.item_id, e);
+            HttpResponse::InternalServerError().json(ErrorResponse {
+                error: "Failed to retrieve version".into(),
+            })
+        }
+    }
+}
+
+/// Revient à une version antérieure d'un secret
+pub async fn restore_secret_version(
+    req: HttpRequest,
+    pool: web::Data<SqlitePool>,
+    body: web::Json<SecretVersionRequest>,
+    config: web::Data<AppConfig>,
+    crypto: web::Data<CryptoService>,
+) -> HttpResponse {
+    let username = match authorize_item(&req, pool.get_ref(), &body.item_type, &body.item_id, GroupRole::Editor).await {
+        Ok(u) => u,
+        Err(response) => return response,
+    };
+    if let Err(response) = check_lease(pool.get_ref(), &body.item_type, &body.item_id, &username).await {
+        return response;
+    }
+
+    match db::restore_secret_version(
+        pool.get_ref(),
+        &body.item_type,
+        &body.item_id,
+        body.version,
+        &username,
+        config.max_secret_versions,
+        crypto.get_ref(),
+    ).await {
+        Ok((version, updated_at)) => {
+            log::info!("User {} restored version {} of {} {} as version {}", username, body.version, body.item_type, body.item_id, version);
+            HttpResponse::Ok().json(UpdateSecretResponse {
+                id: body.item_id.clone(),
+                version,
+                updated_at,
+                message: format!("Version {} restored successfully", body.version),
+            })
+        }
+        Err(sqlx::Error::RowNotFound) => HttpResponse::NotFound().json(ErrorResponse {
+            error: "Version not found".into(),
+        }),
+        Err(e) => {
+            log::error!("Failed to restore version {} of {} {}: {}", body.version, body.item_type, body.item_id, e);
+            HttpResponse::InternalServerError().json(ErrorResponse {
+                error: "Failed to restore version".into(),
+            })
+        }
+    }
+}
+
+
+// ==================== REVEAL ====================
+
+/// Déchiffre le secret d'un seul élément après vérification de l'accès ; chaque lecture est auditée
+pub async fn reveal_item(
+    req: HttpRequest,
+    pool: web::Data<SqlitePool>,
+    body: web::Json<RevealItemRequest>,
+    crypto: web::Data<CryptoService>,
+) -> HttpResponse {
+    let username = match authorize_item(&req, pool.get_ref(), &body.item_type, &body.item_id, GroupRole::Viewer).await {
+        Ok(u) => u,
+        Err(response) => return response,
+    };
+    if let Err(response) = check_lease(pool.get_ref(), &body.item_type, &body.item_id, &username).await {
+        return response;
+    }
+
+    match db::reveal_item(pool.get_ref(), &body.item_type, &body.item_id, crypto.get_ref()).await {
+        Ok(Some(item)) => {
+            // Pas de secret sans trace dans le journal d'audit
+            if let Err(e) = db::record_audit(pool.get_ref(), &username, "reveal", Some(&body.item_type), Some(&body.item_id), &item.title).await {
+                log::error!("Failed to audit reveal of {} {}: {}", body.item_type, body.item_id, e);
+                return HttpResponse::InternalServerError().json(ErrorResponse {
+                    error: "Failed to reveal secret".into(),
+                });
+            }
+            log::info!("User {} revealed {} {}", username, body.item_type, body.item_id);
+            HttpResponse::Ok().json(item)
+        }
+        Ok(None) => HttpResponse::NotFound().json(ErrorResponse {
+            error: "Item not found".into(),
+        }),
+        Err(e) => {
+            log::error!("Failed to reveal {} {}: {}", body.item_type, body.item_id, e);
+            HttpResponse::InternalServerError().json(ErrorResponse {
+                error: "Failed to reveal secret".into(),
+            })
+        }
+    }
+}
+
+// ==================== SEARCH ====================
+
+const DEFAULT_SEARCH_LIMIT: i64 = 20;
+const MAX_SEARCH_LIMIT: i64 = 100;
+
+/// Recherche plein texte dans les éléments accessibles à l'utilisateur (titres, URLs, identifiants, tags, notes)
+pub async fn search(
+    req: HttpRequest,
+    pool: web::Data<SqlitePool>,
+    body: web::Json<SearchRequest>,
+    crypto: web::Data<CryptoService>,
+) -> HttpResponse {
+    let username = match current_username(&req) {
+        Ok(u) => u,
+        Err(response) => return response,
+    };
+
+    let terms = search_terms(&body.query);
+    if terms.is_empty() {
+        return HttpResponse::BadRequest().json(ErrorResponse {
+            error: "Search query cannot be empty".into(),
+        });
+    }
+
+    let limit = body.limit.unwrap_or(DEFAULT_SEARCH_LIMIT);
+    if !(1..=MAX_SEARCH_LIMIT).contains(&limit) {
+        return HttpResponse::BadRequest().json(ErrorResponse {
+            error: format!("limit must be between 1 and {}", MAX_SEARCH_LIMIT),
+        });
+    }
+
+    match db::search_items(pool.get_ref(), &username, &terms, limit, crypto.get_ref()).await {
+        Ok(results) => HttpResponse::Ok().json(results),
+        Err(e) => {
+            log::error!("Search failed for user {}: {}", username, e);
+            HttpResponse::InternalServerError().json(ErrorResponse {
+                error: "Search failed".into(),
+            })
+        }
+    }
+}
+
+// ==================== TRASH ====================
+
+/// Liste la corbeille personnelle de l'utilisateur connecté
+pub async fn get_trash(
+    req: HttpRequest,
+    pool: web::Data<SqlitePool>,
+    crypto: web::Data<CryptoService>,
+) -> HttpResponse {
+    let username = match current_username(&req) {
+        Ok(u) => u,
+        Err(response) => return response,
+    };
+
+    match db::get_trash_by_username(pool.get_ref(), &username, crypto.get_ref()).await {
+        Ok(items) => HttpResponse::Ok().json(items),
+        Err(e) => {
+            log::error!("Failed to retrieve trash for {}: {}", username, e);
+            HttpResponse::InternalServerError().json(ErrorResponse {
+                error: "Failed to retrieve trash".into(),
+            })
+        }
+    }
+}
+
+/// Liste la corbeille d'un groupe dont l'utilisateur est membre
+pub async fn get_trash_in_group(
+    req: HttpRequest,
+    pool: web::Data<SqlitePool>,
+    body: web::Json<RequestGetAccountInGroups>,
+    crypto: web::Data<CryptoService>,
+) -> HttpResponse {
+    let username = match current_username(&req) {
+        Ok(u) => u,
+        Err(response) => return response,
+    };
+
+    if let Err(response) = authorize_vault(pool.get_ref(), &username, Some(&body.group_name), GroupRole::Viewer).await {
+        return response;
+    }
+
+    match db::get_trash_by_group_name(pool.get_ref(), &body.group_name, crypto.get_ref()).await {
+        Ok(items) => HttpResponse::Ok().json(items),
+        Err(e) => {
+            log::error!("Failed to retrieve trash for group '{}': {}", body.group_name, e);
+            HttpResponse::InternalServerError().json(ErrorResponse {
+                error: "Failed to retrieve trash".into(),
+            })
+        }
+    }
+}
+
+/// Restaure un élément personnel ou de groupe depuis la corbeille
+pub async fn restore_from_trash(
+    req: HttpRequest,
+    pool: web::Data<SqlitePool>,
+    body: web::Json<TrashItemRequest>,
+    crypto: web::Data<CryptoService>,
+) -> HttpResponse {
+    let username = match current_username(&req) {
+        Ok(u) => u,
+        Err(response) => return response,
+    };
+
+    if let Err(response) = resolve_item_type(pool.get_ref(), &body.item_type).await {
+        return response;
+    }
+
+    match db::trashed_item_role(pool.get_ref(), &body.item_type, &body.item_id, &username).await {
+        Ok(Some(role)) if role >= GroupRole::Editor => {}
+        Ok(Some(_)) => return role_required(GroupRole::Editor),
+        Ok(None) => {
+            return HttpResponse::NotFound().json(ErrorResponse {
+                error: "Item not found in trash".into(),
+            });
+        }
+        Err(e) => {
+            log::error!("Failed to check access on {} {}: {}", body.item_type, body.item_id, e);
+            return HttpResponse::InternalServerError().json(ErrorResponse {
+                error: "Failed to restore item".into(),
+            });
+        }
+    }
+    if let Err(response) = check_lease(pool.get_ref(), &body.item_type, &body.item_id, &username).await {
+        return response;
+    }
+
+    match db::restore_trashed_item(pool.get_ref(), &body.item_type, &body.item_id, crypto.get_ref()).await {
+        Ok(0) => HttpResponse::NotFound().json(ErrorResponse {
+            error: "Item not found in trash".into(),
+        }),
+        Ok(_) => {
+            log::info!("User {} restored {} {} from trash", username, body.item_type, body.item_id);
+            HttpResponse::Ok().json(serde_json::json!({
+                "message": "Item restored successfully",
+                "id": body.item_id,
+            }))
+        }
+        Err(e) => {
+            let error_msg = e.to_string();
+            if error_msg.contains("UNIQUE constraint failed") {
+                return HttpResponse::Conflict().json(ErrorResponse {
+                    error: "An item with the same title already exists".into(),
+                });
+            }
+
+            log::error!("Failed to restore {} {}: {}", body.item_type, body.item_id, e);
+            HttpResponse::InternalServerError().json(ErrorResponse {
+                error: "Failed to restore item".into(),
+            })
+        }
+    }
+}
+
+// ==================== FOLDERS & TAGS ====================
+
+const MAX_FOLDER_NAME_LEN: usize = 128;
+const MAX_TAGS: usize = 20;
+const MAX_TAG_LEN: usize = 64;
+
+fn validate_folder_name(name: &str) -> Result<(), HttpResponse> {
+    if name.trim().is_empty() || name.chars().count() > MAX_FOLDER_NAME_LEN {
+        return Err(HttpResponse::BadRequest().json(ErrorResponse {
+            error: format!("Folder name must be between 1 and {} characters", MAX_FOLDER_NAME_LEN),
+        }));
+    }
+    Ok(())
+}
+
+/// Vérifie que l'utilisateur peut accéder au coffre visé : le sien, ou celui d'un groupe dont il
+/// est membre avec au moins le rôle `required`
+async fn authorize_vault(
+    pool: &SqlitePool,
+    username: &str,
+    group_name: Option<&str>,
+    required: GroupRole,
+) -> Result<(), HttpResponse> {
+    let Some(group_name) = group_name else {
+        return Ok(());
+    };
+
+    match db::group_role(pool, group_name, username).await {
+        Ok(Some(role)) if role >= required => Ok(()),
+        Ok(Some(_)) => Err(role_required(required)),
+        Ok(None) => Err(HttpResponse::Forbidden().json(ErrorResponse {
+            error: "You are not a member of this group".into(),
+        })),
+        Err(e) => {
+            log::error!("Failed to check membership of {} in '{}': {}", username, group_name, e);
+            Err(HttpResponse::InternalServerError().json(ErrorResponse {
+                error: "Failed to check access".into(),
+            }))
+        }
+    }
+}
+
+/// Charge un dossier et vérifie que l'utilisateur y a accès
+async fn authorize_folder(
+    req: &HttpRequest,
+    pool: &SqlitePool,
+    folder_id: &str,
+    required: GroupRole,
+    crypto: &CryptoService,
+) -> Result<(String, Folder), HttpResponse> {
+    let username = current_username(req)?;
+
+    let folder = match db::get_folder(pool, folder_id, crypto).await {
+        Ok(Some(folder)) => folder,
+        Ok(None) => {
+            return Err(HttpResponse::NotFound().json(ErrorResponse {
+                error: "Folder not found".into(),
+            }));
+        }
+        Err(e) => {
+            log::error!("Failed to load folder {}: {}", folder_id, e);
+            return Err(HttpResponse::InternalServerError().json(ErrorResponse {
+                error: "Failed to load folder".into(),
+            }));
+        }
+    };
+
+    let role = match (&folder.owner, &folder.group_name) {
+        (Some(owner), _) => (owner == &username).then_some(GroupRole::Owner),
+        (None, Some(group_name)) => match db::group_role(pool, group_name, &username).await {
+            Ok(role) => role,
+            Err(e) => {
+                log::error!("Failed to check membership of {} in '{}': {}", username, group_name, e);
+                return Err(HttpResponse::InternalServerError().json(ErrorResponse {
+                    error: "Failed to check access".into(),
+                }));
+            }
+        },
+        (None, None) => None,
+    };
+
+    match role {
+        Some(role) if role >= required => Ok((username, folder)),
+        Some(_) => Err(role_required(required)),
+        None => Err(HttpResponse::NotFound().json(ErrorResponse {
+            error: "Folder not found".into(),
+        })),
+    }
+}
+
+/// Crée un dossier personnel, ou de groupe si `group_name` est fourni
+pub async fn create_folder(
+    req: HttpRequest,
+    pool: web::Data<SqlitePool>,
+    body: web::Json<CreateFolderRequest>,
+    crypto: web::Data<CryptoService>,
+) -> HttpResponse {
+    let username = match current_username(&req) {
+        Ok(u) => u,
+        Err(response) => return response,
+    };
+
+    if let Err(response) = validate_folder_name(&body.name) {
+        return response;
+    }
+
+    let group_name = body.group_name.as_deref();
+    if let Err(response) = authorize_vault(pool.get_ref(), &username, group_name, GroupRole::Editor).await {
+        return response;
+    }
+
+    let owner = if group_name.is_none() { Some(username.as_str()) } else { None };
+
+    match db::create_folder(pool.get_ref(), body.name.trim(), body.parent_id.as_deref(), owner, group_name, crypto.get_ref()).await {
+        Ok(folder) => {
+            log::info!("Folder {} created by {}", folder.id, username);
+            HttpResponse::Created().json(folder)
+        }
+        Err(sqlx::Error::Protocol(msg)) => HttpResponse::BadRequest().json(ErrorResponse { error: msg }),
+        Err(e) => {
+            log::error!("Failed to create folder for {}: {}", username, e);
+            HttpResponse::InternalServerError().json(ErrorResponse {
+                error: "Failed to create folder".into(),
+            })
+        }
+    }
+}
+
+/// Liste les dossiers personnels, ou ceux d'un groupe
+pub async fn get_folders(
+    req: HttpRequest,
+    pool: web::Data<SqlitePool>,
+    body: web::Json<GetFoldersRequest>,
+    crypto: web::Data<CryptoService>,
+) -> HttpResponse {
+    let username = match current_username(&req) {
+        Ok(u) => u,
+        Err(response) => return response,
+    };
+
+    let group_name = body.group_name.as_deref();
+    if let Err(response) = authorize_vault(pool.get_ref(), &username, group_name, GroupRole::Viewer).await {
+        return response;
+    }
+
+    let owner = if group_name.is_none() { Some(username.as_str()) } else { None };
+
+    match db::get_folders(pool.get_ref(), owner, group_name, crypto.get_ref()).await {
+        Ok(folders) => HttpResponse::Ok().json(folders),
+        Err(e) => {
+            log::error!("Failed to retrieve folders for {}: {}", username, e);
+            HttpResponse::InternalServerError().json(ErrorResponse {
+                error: "Failed to retrieve folders".into(),
+            })
+        }
+    }
+}
+
+/// Renomme un dossier
+pub async fn rename_folder(
+    req: HttpRequest,
+    pool: web::Data<SqlitePool>,
+    body: web::Json<RenameFolderRequest>,
+    crypto: web::Data<CryptoService>,
+) -> HttpResponse {
+    if let Err(response) = validate_folder_name(&body.name) {
+        return response;
+    }
+
+    let (username, folder) = match authorize_folder(&req, pool.get_ref(), &body.id, GroupRole::Editor, crypto.get_ref()).await {
+        Ok(found) => found,
+        Err(response) => return response,
+    };
+
+    match db::rename_folder(pool.get_ref(), &folder.id, body.name.trim(), crypto.get_ref()).await {
+        Ok(_) => {
+            log::info!("Folder {} renamed by {}", folder.id, username);
+            HttpResponse::Ok().json(serde_json::json!({
+                "id": folder.id,
+                "message": "Folder renamed successfully"
+            }))
+        }
+        Err(e) => {
+            log::error!("Failed to rename folder {}: {}", folder.id, e);
+            HttpResponse::InternalServerError().json(ErrorResponse {
+                error: "Failed to rename folder".into(),
+            })
+        }
+    }
+}
+
+/// Déplace un dossier sous un autre dossier du même coffre, ou à la racine
+pub async fn move_folder(
+    req: HttpRequest,
+    pool: web::Data<SqlitePool>,
+    body: web::Json<MoveFolderRequest>,
+    crypto: web::Data<CryptoService>,
+) -> HttpResponse {
+    let (username, folder) = match authorize_folder(&req, pool.get_ref(), &body.id, GroupRole::Editor, crypto.get_ref()).await {
+        Ok(found) => found,
+        Err(response) => return response,
+    };
+
+    match db::move_folder(pool.get_ref(), &folder, body.parent_id.as_deref()).await {
+        Ok(()) => {
+            log::info!("Folder {} moved by {}", folder.id, username);
+            HttpResponse::Ok().json(serde_json::json!({
+                "id": folder.id,
+                "parent_id": body.parent_id,
+                "message": "Folder moved successfully"
+            }))
+        }
+        Err(sqlx::Error::Protocol(msg)) => HttpResponse::BadRequest().json(ErrorResponse { error: msg }),
+        Err(e) => {
+            log::error!("Failed to move folder {}: {}", folder.id, e);
+            HttpResponse::InternalServerError().json(ErrorResponse {
+                error: "Failed to move folder".into(),
+            })
+        }
+    }
+}
+
+/// Supprime un dossier sans toucher à son contenu, qui remonte d'un niveau
+pub async fn delete_folder(
+    req: HttpRequest,
+    pool: web::Data<SqlitePool>,
+    body: web::Json<DeleteRequest>,
+    crypto: web::Data<CryptoService>,
+) -> HttpResponse {
+    let (username, folder) = match authorize_folder(&req, pool.get_ref(), &body.id, GroupRole::Editor, crypto.get_ref()).await {
+        Ok(found) => found,
+        Err(response) => return response,
+    };
+
+    match db::delete_folder(pool.get_ref(), &folder).await {
+        Ok(items_moved) => {
+            log::info!("Folder {} deleted by {} ({} item(s) moved up)", folder.id, username, items_moved);
+            HttpResponse::Ok().json(DeleteFolderResponse {
+                id: folder.id,
+                items_moved,
+                message: "Folder deleted successfully".into(),
+            })
+        }
+        Err(e) => {
+            log::error!("Failed to delete folder {}: {}", folder.id, e);
+            HttpResponse::InternalServerError().json(ErrorResponse {
+                error: "Failed to delete folder".into(),
+            })
+        }
+    }
+}
+
+/// Range un élément dans un dossier (ou à la racine si `folder_id` est absent)
+pub async fn move_item(
+    req: HttpRequest,
+    pool: web::Data<SqlitePool>,
+    body: web::Json<MoveItemRequest>,
+) -> HttpResponse {
+    let username = match authorize_item(&req, pool.get_ref(), &body.item_type, &body.item_id, GroupRole::Editor).await {
+        Ok(u) => u,
+        Err(response) => return response,
+    };
+    if let Err(response) = check_lease(pool.get_ref(), &body.item_type, &body.item_id, &username).await {
+        return response;
+    }
+
+    match db::move_item_to_folder(pool.get_ref(), &body.item_type, &body.item_id, body.folder_id.as_deref()).await {
+        Ok(0) => HttpResponse::BadRequest().json(ErrorResponse {
+            error: "Folder not found in this item's vault".into(),
+        }),
+        Ok(_) => {
+            log::info!("User {} moved {} {} to folder {:?}", username, body.item_type, body.item_id, body.folder_id);
+            HttpResponse::Ok().json(serde_json::json!({
+                "item_type": body.item_type,
+                "item_id": body.item_id,
+                "folder_id": body.folder_id,
+                "message": "Item moved successfully"
+            }))
+        }
+        Err(e) => {
+            log::error!("Failed to move {} {}: {}", body.item_type, body.item_id, e);
+            HttpResponse::InternalServerError().json(ErrorResponse {
+                error: "Failed to move item".into(),
+            })
+        }
+    }
+}
+
+/// Remplace les tags d'un élément
+pub async fn set_item_tags(
+    req: HttpRequest,
+    pool: web::Data<SqlitePool>,
+    body: web::Json<SetTagsRequest>,
+    crypto: web::Data<CryptoService>,
+) -> HttpResponse {
+    if body.tags.len() > MAX_TAGS || body.tags.iter().any(|t| t.trim().chars().count() > MAX_TAG_LEN) {
+        return HttpResponse::BadRequest().json(ErrorResponse {
+            error: format!("At most {} tags of up to {} characters are allowed", MAX_TAGS, MAX_TAG_LEN),
+        });
+    }
+
+    let username = match authorize_item(&req, pool.get_ref(), &body.item_type, &body.item_id, GroupRole::Editor).await {
+        Ok(u) => u,
+        Err(response) => return response,
+    };
+    if let Err(response) = check_lease(pool.get_ref(), &body.item_type, &body.item_id, &username).await {
+        return response;
+    }
+
+    match db::set_item_tags(pool.get_ref(), &body.item_type, &body.item_id, &body.tags, crypto.get_ref()).await {
+        Ok(tags) => {
+            log::info!("User {} tagged {} {} ({} tag(s))", username, body.item_type, body.item_id, tags.len());
+            HttpResponse::Ok().json(SetTagsResponse {
+                item_type: body.item_type.clone(),
+                item_id: body.item_id.clone(),
+                tags,
+            })
+        }
+        Err(e) => {
+            log::error!("Failed to tag {} {}: {}", body.item_type, body.item_id, e);
+            HttpResponse::InternalServerError().json(ErrorResponse {
+                error: "Failed to update tags".into(),
+            })
+        }
+    }
+}
+
+// ==================== ÉCHÉANCES ====================
+
+// Fenêtre d'alerte par défaut et maximale des échéances (jours)
+const DEFAULT_EXPIRY_WINDOW_DAYS: i64 = 14;
+const MAX_EXPIRY_WINDOW_DAYS: i64 = 365;
+
+/// Fixe la date d'expiration et l'intervalle de rotation d'un élément (valeur absente : retirée)
+pub async fn set_item_deadlines(
+    req: HttpRequest,
+    pool: web::Data<SqlitePool>,
+    body: web::Json<SetDeadlinesRequest>,
+) -> HttpResponse {
+    let username = match authorize_item(&req, pool.get_ref(), &body.item_type, &body.item_id, GroupRole::Editor).await {
+        Ok(u) => u,
+        Err(response) => return response,
+    };
+    if let Err(response) = check_lease(pool.get_ref(), &body.item_type, &body.item_id, &username).await {
+        return response;
+    }
+
+    match db::set_item_deadlines(pool.get_ref(), &body.item_type, &body.item_id, body.expires_at.as_deref(), body.rotate_every).await {
+        Ok(0) => HttpResponse::NotFound().json(ErrorResponse {
+            error: "Item not found".into(),
+        }),
+        Ok(_) => {
+            log::info!("User {} updated deadlines of {} {}", username, body.item_type, body.item_id);
+            HttpResponse::Ok().json(serde_json::json!({
+                "item_type": body.item_type,
+                "item_id": body.item_id,
+                "message": "Deadlines updated successfully"
+            }))
+        }
+        Err(sqlx::Error::Protocol(msg)) => HttpResponse::BadRequest().json(ErrorResponse { error: msg }),
+        Err(e) => {
+            log::error!("Failed to update deadlines of {} {}: {}", body.item_type, body.item_id, e);
+            HttpResponse::InternalServerError().json(ErrorResponse {
+                error: "Failed to update deadlines".into(),
+            })
+        }
+    }
+}
+
+/// Liste les éléments expirés, à renouveler ou arrivant bientôt à échéance, pour l'utilisateur
+/// et pour chacun de ses groupes
+pub async fn get_expiring_items(
+    req: HttpRequest,
+    pool: web::Data<SqlitePool>,
+    query: web::Query<ExpiringItemsQuery>,
+    crypto: web::Data<CryptoService>,
+) -> HttpResponse {
+    let username = match current_username(&req) {
+        Ok(u) => u,
+        Err(response) => return response,
+    };
+
+    let within_days = query.within_days.unwrap_or(DEFAULT_EXPIRY_WINDOW_DAYS);
+    if !(0..=MAX_EXPIRY_WINDOW_DAYS).contains(&within_days) {
+        return HttpResponse::BadRequest().json(ErrorResponse {
+            error: format!("within_days must be between 0 and {}", MAX_EXPIRY_WINDOW_DAYS),
+        });
+    }
+
+    match db::get_expiring_items(pool.get_ref(), &username, within_days, crypto.get_ref()).await {
+        Ok(response) => HttpResponse::Ok().json(response),
+        Err(e) => {
+            log::error!("Failed to retrieve expiring items for {}: {}", username, e);
+            HttpResponse::InternalServerError().json(ErrorResponse {
+                error: "Failed to retrieve expiring items".into(),
+            })
+        }
+    }
+}
+
+// ==================== PIÈCES JOINTES ====================
+
+const MAX_FILENAME_LEN: usize = 255;
+
+/// Nom de fichier sans chemin ; `None` s'il est vide ou trop long
+fn clean_filename(filename: &str) -> Option<String> {
+    let name = filename.rsplit(['/', '\\']).next().unwrap_or("").trim();
+    if name.is_empty() || name.chars().count() > MAX_FILENAME_LEN {
+        None
+    } else {
+        Some(name.to_string())
+    }
+}
+
+/// Réponse d'erreur d'un envoi interrompu, après suppression du fichier partiel
+async fn abort_upload(config: &AppConfig, id: &str, response: HttpResponse) -> HttpResponse {
+    attachments::discard_upload(&config.attachments_dir, id).await;
+    response
+}
+
+/// Ajoute une pièce jointe à un élément. Le fichier (champ `file` d'un formulaire multipart)
+/// est chiffré bloc par bloc au fil de la réception, dans la limite de taille et du quota du
+/// propriétaire de l'élément (utilisateur ou groupe)
+pub async fn upload_attachment(
+    req: HttpRequest,
+    pool: web::Data<SqlitePool>,
+    query: web::Query<AttachmentUploadQuery>,
+    payload: web::Payload,
+    config: web::Data<AppConfig>,
+    crypto: web::Data<CryptoService>,
+) -> HttpResponse {
+    let username = match authorize_item(&req, pool.get_ref(), &query.item_type, &query.item_id, GroupRole::Editor).await {
+        Ok(u) => u,
+        Err(response) => return response,
+    };
+    if let Err(response) = check_lease(pool.get_ref(), &query.item_type, &query.item_id, &username).await {
+        return response;
+    }
+
+    let used = match db::attachment_usage_for_item(pool.get_ref(), &query.item_type, &query.item_id).await {
+        Ok(used) => used as u64,
+        Err(e) => {
+            log::error!("Failed to compute attachment usage for {} {}: {}", query.item_type, query.item_id, e);
+            return HttpResponse::InternalServerError().json(ErrorResponse {
+                error: "Failed to store attachment".into(),
+            });
+        }
+    };
+    let remaining = config.attachment_quota_bytes.saturating_sub(used);
+    let limit = config.attachment_max_bytes.min(remaining);
+    let too_large = || {
+        let error = if limit < config.attachment_max_bytes {
+            format!("Attachment quota exceeded ({} of {} bytes used)", used, config.attachment_quota_bytes)
+        } else {
+            format!("Attachment exceeds the maximum size of {} bytes", config.attachment_max_bytes)
+        };
+        HttpResponse::PayloadTooLarge().json(ErrorResponse { error })
+    };
+    if remaining == 0 {
+        return too_large();
+    }
+
+    let content_type = req.headers()
+        .get(header::CONTENT_TYPE)
+        .and_then(|value| value.to_str().ok())
+        .unwrap_or("");
+    let mut form = match Multipart::new(content_type, payload) {
+        Ok(form) => form,
+        Err(error) => return HttpResponse::BadRequest().json(ErrorResponse { error }),
+    };
+
+    let part = loop {
+        match form.next_part().await {
+            Ok(Some(part)) if part.name == "file" => break part,
+            Ok(Some(_)) => continue,
+            Ok(None) => {
+                return HttpResponse::BadRequest().json(ErrorResponse {
+                    error: "Missing 'file' part".into(),
+                });
+            }
+            Err(error) => return HttpResponse::BadRequest().json(ErrorResponse { error }),
+        }
+    };
+    let Some(filename) = part.filename.as_deref().and_then(clean_filename) else {
+        return HttpResponse::BadRequest().json(ErrorResponse {
+            error: format!("A file name of 1 to {} characters is required", MAX_FILENAME_LEN),
+        });
+    };
+
+    let id = Uuid::new_v4().to_string();
+    let mut writer = match BlobWriter::create(&config.attachments_dir, &id, crypto.get_ref()).await {
+        Ok(writer) => writer,
+        Err(e) => {
+            log::error!("Failed to create attachment file {}: {}", id, e);
+            return HttpResponse::InternalServerError().json(ErrorResponse {
+                error: "Failed to store attachment".into(),
+            });
+        }
+    };
+
+    loop {
+        match form.next_chunk().await {
+            Ok(Some(chunk)) => {
+                if writer.size() + chunk.len() as u64 > limit {
+                    drop(writer);
+                    return abort_upload(&config, &id, too_large()).await;
+                }
+                if let Err(e) = writer.write(&chunk).await {
+                    drop(writer);
+                    log::error!("Failed to write attachment {}: {}", id, e);
+                    return abort_upload(&config, &id, HttpResponse::InternalServerError().json(ErrorResponse {
+                        error: "Failed to store attachment".into(),
+                    })).await;
+                }
+            }
+            Ok(None) => break,
+            Err(error) => {
+                drop(writer);
+                return abort_upload(&config, &id, HttpResponse::BadRequest().json(ErrorResponse { error })).await;
+            }
+        }
+    }
+
+    let size = match writer.finish().await {
+        Ok(size) => size,
+        Err(e) => {
+            log::error!("Failed to write attachment {}: {}", id, e);
+            return abort_upload(&config, &id, HttpResponse::InternalServerError().json(ErrorResponse {
+                error: "Failed to store attachment".into(),
+            })).await;
+        }
+    };
+
+    let attachment = Attachment {
+        id: id.clone(),
+        item_type: query.item_type.clone(),
+        item_id: query.item_id.clone(),
+        filename,
+        content_type: part.content_type,
+        size: size as i64,
+        created_by: username.clone(),
+        created_at: Utc::now().to_rfc3339(),
+    };
+
+    // Le quota est revérifié à l'enregistrement, au cas où un autre envoi se serait terminé entre-temps
+    match db::insert_attachment(pool.get_ref(), &attachment, config.attachment_quota_bytes, crypto.get_ref()).await {
+        Ok(true) => {}
+        Ok(false) => return abort_upload(&config, &id, too_large()).await,
+        Err(sqlx::Error::RowNotFound) => {
+            return abort_upload(&config, &id, HttpResponse::NotFound().json(ErrorResponse {
+                error: "Item not found".into(),
+            })).await;
+        }
+        Err(e) => {
+            log::error!("Failed to record attachment {}: {}", id, e);
+            return abort_upload(&config, &id, HttpResponse::InternalServerError().json(ErrorResponse {
+                error: "Failed to store attachment".into(),
+            })).await;
+        }
+    }
+
+    if let Err(e) = attachments::commit(&config.attachments_dir, &id).await {
+        log::error!("Failed to commit attachment {}: {}", id, e);
+        if let Err(e) = db::delete_attachment(pool.get_ref(), &id).await {
+            log::error!("Failed to remove attachment record {}: {}", id, e);
+        }
+        return abort_upload(&config, &id, HttpResponse::InternalServerError().json(ErrorResponse {
+            error: "Failed to store attachment".into(),
+        })).await;
+    }
+
+    log::info!("User {} attached {} ({} bytes) to {} {}", username, id, size, query.item_type, query.item_id);
+    HttpResponse::Created().json(attachment)
+}
+
+/// Liste les pièces jointes d'un élément (sans leur contenu)
+pub async fn get_attachments(
+    req: HttpRequest,
+    pool: web::Data<SqlitePool>,
+    body: web::Json<AttachmentsRequest>,
+    crypto: web::Data<CryptoService>,
+) -> HttpResponse {
+    if let Err(response) = authorize_item(&req, pool.get_ref(), &body.item_type, &body.item_id, GroupRole::Viewer).await {
+        return response;
+    }
+
+    match db::get_attachments(pool.get_ref(), &body.item_type, &body.item_id, crypto.get_ref()).await {
+        Ok(attachments) => HttpResponse::Ok().json(attachments),
+        Err(e) => {
+            log::error!("Failed to retrieve attachments of {} {}: {}", body.item_type, body.item_id, e);
+            HttpResponse::InternalServerError().json(ErrorResponse {
+                error: "Failed to retrieve attachments".into(),
+            })
+        }
+    }
+}
+
+/// Pièce jointe accessible à l'utilisateur (via l'élément auquel elle est rattachée)
+async fn authorize_attachment(
+    req: &HttpRequest,
+    pool: &SqlitePool,
+    id: &str,
+    required: GroupRole,
+    crypto: &CryptoService,
+) -> Result<(String, Attachment), HttpResponse> {
+    let attachment = match db::get_attachment(pool, id, crypto).await {
+        Ok(Some(attachment)) => attachment,
+        Ok(None) => {
+            return Err(HttpResponse::NotFound().json(ErrorResponse {
+                error: "Attachment not found".into(),
+            }));
+        }
+        Err(e) => {
+            log::error!("Failed to load attachment {}: {}", id, e);
+            return Err(HttpResponse::InternalServerError().json(ErrorResponse {
+                error: "Failed to load attachment".into(),
+            }));
+        }
+    };
+
+    let username = authorize_item(req, pool, &attachment.item_type, &attachment.item_id, required).await?;
+    check_lease(pool, &attachment.item_type, &attachment.item_id, &username).await?;
+    Ok((username, attachment))
+}
+
+/// Télécharge une pièce jointe, déchiffrée à la volée
+pub async fn download_attachment(
+    req: HttpRequest,
+    pool: web::Data<SqlitePool>,
+    body: web::Json<AttachmentRequest>,
+    config: web::Data<AppConfig>,
+    crypto: web::Data<CryptoService>,
+) -> HttpResponse {
+    let (username, attachment) = match authorize_attachment(&req, pool.get_ref(), &body.id, GroupRole::Viewer, crypto.get_ref()).await {
+        Ok(found) => found,
+        Err(response) => return response,
+    };
+
+    let mut stream = match attachments::open(&config.attachments_dir, &attachment.id, crypto.get_ref().clone()).await {
+        Ok(stream) => Box::pin(stream),
+        Err(e) => {
+            log::error!("Failed to open attachment {}: {}", attachment.id, e);
+            return HttpResponse::InternalServerError().json(ErrorResponse {
+                error: "Failed to read attachment".into(),
+            });
+        }
+    };
+
+    // Le premier bloc est vérifié avant l'envoi des en-têtes : un fichier altéré donne une
+    // erreur propre plutôt qu'une connexion interrompue
+    let first = match stream.next().await {
+        Some(Err(_)) => {
+            return HttpResponse::InternalServerError().json(ErrorResponse {
+                error: "Failed to read attachment".into(),
+            });
+        }
+        first => first,
+    };
+
+    // Pas de contenu sans trace dans le journal d'audit
+    if let Err(e) = db::record_audit(pool.get_ref(), &username, "download", Some(&attachment.item_type), Some(&attachment.item_id), &attachment.filename).await {
+        log::error!("Failed to audit download of attachment {}: {}", attachment.id, e);
+        return HttpResponse::InternalServerError().json(ErrorResponse {
+            error: "Failed to read attachment".into(),
+        });
+    }
+
+    log::info!("User {} downloaded attachment {} of {} {}", username, attachment.id, attachment.item_type, attachment.item_id);
+    HttpResponse::Ok()
+        .content_type(attachment.content_type.as_deref().unwrap_or("application/octet-stream"))
+        .insert_header(ContentDisposition::attachment(attachment.filename))
+        .streaming(futures_util::stream::iter(first).chain(stream))
+}
+
+/// Supprime une pièce jointe et son fichier chiffré
+pub async fn delete_attachment(
+    req: HttpRequest,
+    pool: web::Data<SqlitePool>,
+    body: web::Json<AttachmentRequest>,
+    config: web::Data<AppConfig>,
+    crypto: web::Data<CryptoService>,
+) -> HttpResponse {
+    let (username, attachment) = match authorize_attachment(&req, pool.get_ref(), &body.id, GroupRole::Editor, crypto.get_ref()).await {
+        Ok(found) => found,
+        Err(response) => return response,
+    };
+
+    if let Err(e) = db::delete_attachment(pool.get_ref(), &attachment.id).await {
+        log::error!("Failed to delete attachment {}: {}", attachment.id, e);
+        return HttpResponse::InternalServerError().json(ErrorResponse {
+            error: "Failed to delete attachment".into(),
+        });
+    }
+
+    // Un fichier resté sur disque sera retiré par le nettoyage périodique
+    if let Err(e) = attachments::remove(&config.attachments_dir, &attachment.id).await {
+        log::warn!("Failed to remove attachment file {}: {}", attachment.id, e);
+    }
+
+    log::info!("User {} deleted attachment {} of {} {}", username, attachment.id, attachment.item_type, attachment.item_id);
+    HttpResponse::Ok().json(serde_json::json!({
+        "id": attachment.id,
+        "message": "Attachment deleted successfully"
+    }))
+}
+
+// ==================== PARTAGE ====================
+
+/// Vérifie qu'un élément est personnel et que l'utilisateur en est le propriétaire
+async fn authorize_shared_item(
+    req: &HttpRequest,
+    pool: &SqlitePool,
+    item_type: &str,
+    item_id: &str,
+) -> Result<String, HttpResponse> {
+    if items::split_item_type(item_type).1 {
+        return Err(HttpResponse::BadRequest().json(ErrorResponse {
+            error: "Only personal items can be shared, group items follow group membership".into(),
+        }));
+    }
+
+    authorize_item(req, pool, item_type, item_id, GroupRole::Owner).await
+}
+
+/// Partage un élément personnel avec un utilisateur, en lecture seule ou en modification
+pub async fn share_item(
+    req: HttpRequest,
+    pool: web::Data<SqlitePool>,
+    body: web::Json<ShareItemRequest>,
+) -> HttpResponse {
+    let username = match authorize_shared_item(&req, pool.get_ref(), &body.item_type, &body.item_id).await {
+        Ok(u) => u,
+        Err(response) => return response,
+    };
+
+    match db::share_item(pool.get_ref(), &body.item_type, &body.item_id, &body.username, body.permission, &username).await {
+        Ok(share) => {
+            log::info!("User {} shared {} {} with {} ({})", username, body.item_type, body.item_id, share.username, share.permission);
+            HttpResponse::Ok().json(share)
+        }
+        Err(sqlx::Error::Protocol(msg)) => HttpResponse::BadRequest().json(ErrorResponse { error: msg }),
+        Err(e) => {
+            log::error!("Failed to share {} {}: {}", body.item_type, body.item_id, e);
+            HttpResponse::InternalServerError().json(ErrorResponse {
+                error: "Failed to share item".into(),
+            })
+        }
+    }
+}
+
+/// Retire l'accès d'un utilisateur à un élément partagé ; le propriétaire peut retirer
+/// n'importe quel partage, un destinataire seulement le sien
+pub async fn revoke_share(
+    req: HttpRequest,
+    pool: web::Data<SqlitePool>,
+    body: web::Json<RevokeShareRequest>,
+) -> HttpResponse {
+    let username = match current_username(&req) {
+        Ok(u) => u,
+        Err(response) => return response,
+    };
+
+    let required = if body.username == username { GroupRole::Viewer } else { GroupRole::Owner };
+    if let Err(response) = authorize_item(&req, pool.get_ref(), &body.item_type, &body.item_id, required).await {
+        return response;
+    }
+
+    match db::revoke_share(pool.get_ref(), &body.item_type, &body.item_id, &body.username, &username).await {
+        Ok(0) => HttpResponse::NotFound().json(ErrorResponse {
+            error: format!("Item is not shared with {}", body.username),
+        }),
+        Ok(_) => {
+            log::info!("User {} revoked the access of {} to {} {}", username, body.username, body.item_type, body.item_id);
+            HttpResponse::Ok().json(serde_json::json!({
+                "item_id": body.item_id,
+                "username": body.username,
+                "message": "Share revoked successfully"
+            }))
+        }
+        Err(e) => {
+            log::error!("Failed to revoke share of {} {}: {}", body.item_type, body.item_id, e);
+            HttpResponse::InternalServerError().json(ErrorResponse {
+                error: "Failed to revoke share".into(),
+            })
+        }
+    }
+}
+
+/// Liste les utilisateurs ayant accès à un élément personnel (réservé au propriétaire)
+pub async fn get_item_access(
+    req: HttpRequest,
+    pool: web::Data<SqlitePool>,
+    body: web::Json<RevealItemRequest>,
+) -> HttpResponse {
+    if let Err(response) = authorize_shared_item(&req, pool.get_ref(), &body.item_type, &body.item_id).await {
+        return response;
+    }
+
+    match db::get_item_access(pool.get_ref(), &body.item_type, &body.item_id).await {
+        Ok(access) => HttpResponse::Ok().json(access),
+        Err(sqlx::Error::RowNotFound) => HttpResponse::NotFound().json(ErrorResponse {
+            error: "Item not found".into(),
+        }),
+        Err(e) => {
+            log::error!("Failed to retrieve access to {} {}: {}", body.item_type, body.item_id, e);
+            HttpResponse::InternalServerError().json(ErrorResponse {
+                error: "Failed to retrieve item access".into(),
+            })
+        }
+    }
+}
+
+/// Liste les éléments d'autres utilisateurs partagés avec l'utilisateur connecté
+pub async fn get_shared_with_me(
+    req: HttpRequest,
+    pool: web::Data<SqlitePool>,
+    crypto: web::Data<CryptoService>,
+) -> HttpResponse {
+    let username = match current_username(&req) {
+        Ok(u) => u,
+        Err(response) => return response,
+    };
+
+    match db::get_shared_with_me(pool.get_ref(), &username, crypto.get_ref()).await {
+        Ok(shared) => HttpResponse::Ok().json(shared),
+        Err(e) => {
+            log::error!("Failed to retrieve items shared with {}: {}", username, e);
+            HttpResponse::InternalServerError().json(ErrorResponse {
+                error: "Failed to retrieve shared items".into(),
+            })
+        }
+    }
+}
+
+// ==================== LIENS À USAGE UNIQUE ====================
+
+/// Crée un lien à usage unique vers un secret ponctuel ou un élément que l'utilisateur peut modifier.
+/// La clé du lien n'est renvoyée qu'ici : le serveur n'en garde aucune trace
+pub async fn create_secret_link(
+    req: HttpRequest,
+    pool: web::Data<SqlitePool>,
+    body: web::Json<CreateSecretLinkRequest>,
+    config: web::Data<AppConfig>,
+    crypto: web::Data<CryptoService>,
+) -> HttpResponse {
+    let username = match current_username(&req) {
+        Ok(u) => u,
+        Err(response) => return response,
+    };
+
+    let max_views = body.max_views.unwrap_or(1);
+    if !(1..=links::MAX_VIEWS).contains(&max_views) {
+        return HttpResponse::BadRequest().json(ErrorResponse {
+            error: format!("max_views must be between 1 and {}", links::MAX_VIEWS),
+        });
+    }
+    let hours = body.expires_in_hours.unwrap_or(24);
+    if !(1..=links::MAX_LIFETIME_HOURS).contains(&hours) {
+        return HttpResponse::BadRequest().json(ErrorResponse {
+            error: format!("expires_in_hours must be between 1 and {}", links::MAX_LIFETIME_HOURS),
+        });
+    }
+    let passphrase = body.passphrase.clone().filter(|p| !p.is_empty());
+
+    let content = match (&body.secret, &body.item_type, &body.item_id) {
+        (Some(secret), None, None) if !secret.is_empty() => SecretLinkContent {
+            title: body.title.clone(),
+            secret: secret.clone(),
+            fields: None,
+        },
+        (None, Some(item_type), Some(item_id)) => {
+            // Un lien fait sortir le secret du coffre : il faut être propriétaire de l'élément
+            // personnel, ou éditeur du groupe. Un partage (rôle d'éditeur au plus) ou un accès
+            // d'urgence (lecture seule) ne suffit donc pas
+            let required = if items::split_item_type(item_type).1 { GroupRole::Editor } else { GroupRole::Owner };
+            if let Err(response) = authorize_item(&req, pool.get_ref(), item_type, item_id, required).await {
+                return response;
+            }
+            if let Err(response) = check_lease(pool.get_ref(), item_type, item_id, &username).await {
+                return response;
+            }
+            match db::reveal_item(pool.get_ref(), item_type, item_id, crypto.get_ref()).await {
+                Ok(Some(item)) => SecretLinkContent {
+                    title: Some(item.title),
+                    secret: item.secret,
+                    fields: Some(item.fields),
+                },
+                Ok(None) => {
+                    return HttpResponse::NotFound().json(ErrorResponse {
+                        error: "Item not found".into(),
+                    });
+                }
+                Err(e) => {
+                    log::error!("Failed to read {} {} for a secret link: {}", item_type, item_id, e);
+                    return HttpResponse::InternalServerError().json(ErrorResponse {
+                        error: "Failed to create secret link".into(),
+                    });
+                }
+            }
+        }
+        _ => {
+            return HttpResponse::BadRequest().json(ErrorResponse {
+                error: "Provide either a secret or an item_type and item_id".into(),
+            });
+        }
+    };
+
+    let id = Uuid::new_v4().to_string();
+    let key = LinkKey::generate();
+    let salt = links::generate_salt();
+    let expires_at = (Utc::now() + chrono::Duration::hours(hours)).to_rfc3339();
+
+    let sealed = match serde_json::to_vec(&content) {
+        Ok(json) => {
+            let (id, key, salt, passphrase) = (id.clone(), key.clone(), salt.clone(), passphrase.clone());
+            web::block(move || links::seal(&id, &key, passphrase.as_deref(), &salt, &json)).await
+        }
+        Err(e) => Ok(Err(e.to_string())),
+    };
+    let content = match sealed.map_err(|e| e.to_string()).and_then(|sealed| sealed) {
+        Ok(content) => content,
+        Err(e) => {
+            log::error!("Failed to encrypt secret link: {}", e);
+            return HttpResponse::InternalServerError().json(ErrorResponse {
+                error: "Failed to create secret link".into(),
+            });
+        }
+    };
+
+    let link = db::NewSecretLink {
+        id: &id,
+        content: &content,
+        salt: &salt,
+        passphrase_required: passphrase.is_some(),
+        max_views,
+        item_type: body.item_type.as_deref(),
+        item_id: body.item_id.as_deref(),
+        created_by: &username,
+        expires_at: &expires_at,
+    };
+    if let Err(e) = db::insert_secret_link(pool.get_ref(), &link).await {
+        log::error!("Failed to store secret link: {}", e);
+        return HttpResponse::InternalServerError().json(ErrorResponse {
+            error: "Failed to create secret link".into(),
+        });
+    }
+
+    log::info!("User {} created secret link {} ({} view(s), expires {})", username, id, max_views, expires_at);
+    let key = key.encode();
+    HttpResponse::Created().json(CreateSecretLinkResponse {
+        link: format!("{}/{}#{}", config.secret_link_base_url, id, key),
+        id,
+        key,
+        max_views,
+        expires_at,
+        passphrase_required: passphrase.is_some(),
+    })
+}
+
+/// Liste les liens encore valides créés par l'utilisateur connecté
+pub async fn get_secret_links(
+    req: HttpRequest,
+    pool: web::Data<SqlitePool>,
+) -> HttpResponse {
+    let username = match current_username(&req) {
+        Ok(u) => u,
+        Err(response) => return response,
+    };
+
+    match db::get_secret_links(pool.get_ref(), &username).await {
+        Ok(links) => HttpResponse::Ok().json(links),
+        Err(e) => {
+            log::error!("Failed to retrieve secret links of {}: {}", username, e);
+            HttpResponse::InternalServerError().json(ErrorResponse {
+                error: "Failed to retrieve secret links".into(),
+            })
+        }
+    }
+}
+
+/// Détruit un lien avant son expiration
+pub async fn delete_secret_link(
+    req: HttpRequest,
+    pool: web::Data<SqlitePool>,
+    body: web::Json<DeleteRequest>,
+) -> HttpResponse {
+    let username = match current_username(&req) {
+        Ok(u) => u,
+        Err(response) => return response,
+    };
+
+    match db::delete_secret_link(pool.get_ref(), &body.id, &username).await {
+        Ok(0) => HttpResponse::NotFound().json(ErrorResponse {
+            error: "Secret link not found".into(),
+        }),
+        Ok(_) => {
+            log::info!("User {} revoked secret link {}", username, body.id);
+            HttpResponse::Ok().json(serde_json::json!({
+                "id": body.id,
+                "message": "Secret link revoked"
+            }))
+        }
+        Err(e) => {
+            log::error!("Failed to revoke secret link {}: {}", body.id, e);
+            HttpResponse::InternalServerError().json(ErrorResponse {
+                error: "Failed to revoke secret link".into(),
+            })
+        }
+    }
+}
+
+fn secret_link_gone() -> HttpResponse {
+    HttpResponse::NotFound().json(ErrorResponse {
+        error: "This link has expired or has already been used".into(),
+    })
+}
+
+/// Ouvertures suspendues après une série d'essais erronés ; `Retry-After` indique l'attente en secondes
+fn secret_link_locked(until: &str) -> HttpResponse {
+    let wait = DateTime::parse_from_rfc3339(until)
+        .map(|until| (until.with_timezone(&Utc) - Utc::now()).num_seconds().max(1))
+        .unwrap_or(60);
+    HttpResponse::TooManyRequests()
+        .insert_header((header::RETRY_AFTER, wait.to_string()))
+        .json(ErrorResponse {
+            error: format!("Too many failed attempts, this link can be opened again after {}", until),
+        })
+}
+
+/// État d'un lien sans le consommer (accès public) : la page d'ouverture sait ainsi s'il faut
+/// demander une phrase secrète
+pub async fn get_secret_link_status(
+    pool: web::Data<SqlitePool>,
+    id: web::Path<String>,
+) -> HttpResponse {
+    match db::get_secret_link_status(pool.get_ref(), &id).await {
+        Ok(Some((expires_at, views_left, passphrase_required, locked_until))) => HttpResponse::Ok().json(serde_json::json!({
+            "expires_at": expires_at,
+            "views_left": views_left,
+            "passphrase_required": passphrase_required,
+            "locked_until": locked_until,
+        })),
+        Ok(None) => secret_link_gone(),
+        Err(e) => {
+            log::error!("Failed to read secret link {}: {}", id, e);
+            HttpResponse::InternalServerError().json(ErrorResponse {
+                error: "Failed to read secret link".into(),
+            })
+        }
+    }
+}
+
+/// Ouvre un lien (accès public) avec la clé tirée du fragment de l'URL ; chaque ouverture
+/// réussie consomme une consultation et la dernière détruit le lien
+pub async fn open_secret_link(
+    pool: web::Data<SqlitePool>,
+    id: web::Path<String>,
+    body: web::Json<OpenSecretLinkRequest>,
+) -> HttpResponse {
+    let id = id.into_inner();
+    let Some(key) = LinkKey::decode(&body.key) else {
+        return HttpResponse::BadRequest().json(ErrorResponse {
+            error: "Invalid link key".into(),
+        });
+    };
+
+    let (sealed, salt, passphrase_required, locked_until) = match db::get_secret_link(pool.get_ref(), &id).await {
+        Ok(Some(link)) => link,
+        Ok(None) => return secret_link_gone(),
+        Err(e) => {
+            log::error!("Failed to read secret link {}: {}", id, e);
+            return HttpResponse::InternalServerError().json(ErrorResponse {
+                error: "Failed to open secret link".into(),
+            });
+        }
+    };
+
+    if let Some(until) = locked_until {
+        return secret_link_locked(&until);
+    }
+
+    let passphrase = body.passphrase.clone().filter(|p| !p.is_empty());
+    if passphrase_required && passphrase.is_none() {
+        return HttpResponse::Unauthorized().json(ErrorResponse {
+            error: "This link requires a passphrase".into(),
+        });
+    }
+
+    let opened = {
+        let id = id.clone();
+        web::block(move || links::open(&id, &key, passphrase.as_deref(), &salt, &sealed)).await
+    };
+    let content = match opened {
+        Ok(Ok(content)) => content,
+        Ok(Err(_)) => {
+            return match db::record_secret_link_failure(pool.get_ref(), &id).await {
+                Ok(Some(until)) => {
+                    log::warn!("Secret link {} locked until {} after too many failed attempts", id, until);
+                    secret_link_locked(&until)
+                }
+                Ok(None) => HttpResponse::Forbidden().json(ErrorResponse {
+                    error: "Invalid link key or passphrase".into(),
+                }),
+                Err(e) => {
+                    log::error!("Failed to record failed attempt on secret link {}: {}", id, e);
+                    HttpResponse::InternalServerError().json(ErrorResponse {
+                        error: "Failed to open secret link".into(),
+                    })
+                }
+            };
+        }
+        Err(e) => {
+            log::error!("Failed to decrypt secret link {}: {}", id, e);
+            return HttpResponse::InternalServerError().json(ErrorResponse {
+                error: "Failed to open secret link".into(),
+            });
+        }
+    };
+
+    let content: SecretLinkContent = match serde_json::from_slice(&content) {
+        Ok(content) => content,
+        Err(e) => {
+            log::error!("Invalid content in secret link {}: {}", id, e);
+            return HttpResponse::InternalServerError().json(ErrorResponse {
+                error: "Failed to open secret link".into(),
+            });
+        }
+    };
+
+    // La consultation n'est rendue qu'une fois décomptée : deux ouvertures simultanées
+    // ne peuvent pas dépasser le nombre autorisé
+    match db::consume_secret_link(pool.get_ref(), &id).await {
+        Ok(Some(views_left)) => {
+            log::info!("Secret link {} opened ({} view(s) left)", id, views_left);
+            HttpResponse::Ok()
+                .insert_header((header::CACHE_CONTROL, "no-store"))
+                .json(serde_json::json!({
+                    "title": content.title,
+                    "secret": content.secret,
+                    "fields": content.fields,
+                    "views_left": views_left,
+                }))
+        }
+        Ok(None) => secret_link_gone(),
+        Err(e) => {
+            log::error!("Failed to consume secret link {}: {}", id, e);
+            HttpResponse::InternalServerError().json(ErrorResponse {
+                error: "Failed to open secret link".into(),
+            })
+        }
+    }
+}
+
+// ==================== DEMANDES D'ACCÈS ====================
+
+// Durée maximale d'un accès temporaire accordé sur demande (heures)
+const MAX_ACCESS_HOURS: i64 = 24 * 90;
+const MAX_JUSTIFICATION_LEN: usize = 1000;
+const ACCESS_REQUEST_STATUSES: [&str; 5] = ["pending", "approved", "denied", "cancelled", "expired"];
+
+/// Durée d'un accès temporaire (heures), bornée par `MAX_ACCESS_HOURS`
+pub fn check_access_hours(field: &str, hours: Option<i64>) -> Result<(), HttpResponse> {
+    match hours {
+        Some(hours) if !(1..=MAX_ACCESS_HOURS).contains(&hours) => Err(HttpResponse::BadRequest().json(ErrorResponse {
+            error: format!("{} must be between 1 and {}", field, MAX_ACCESS_HOURS),
+        })),
+        _ => Ok(()),
+    }
+}
+
+/// Demande l'adhésion à un groupe (ou un rôle plus élevé) ; les propriétaires du groupe sont prévenus
+pub async fn create_access_request(
+    req: HttpRequest,
+    pool: web::Data<SqlitePool>,
+    notifier: web::Data<Notifier>,
+    body: web::Json<CreateAccessRequest>,
+) -> HttpResponse {
+    let username = match current_username(&req) {
+        Ok(u) => u,
+        Err(response) => return response,
+    };
+
+    let justification = body.justification.trim();
+    if justification.is_empty() || justification.chars().count() > MAX_JUSTIFICATION_LEN {
+        return HttpResponse::BadRequest().json(ErrorResponse {
+            error: format!("justification is required ({} characters at most)", MAX_JUSTIFICATION_LEN),
+        });
+    }
+    if let Err(response) = check_access_hours("duration_hours", body.duration_hours) {
+        return response;
+    }
+
+    let request = match db::create_access_request(
+        pool.get_ref(),
+        &body.group_name,
+        &username,
+        body.role,
+        justification,
+        body.duration_hours,
+    )
+    .await
+    {
+        Ok(request) => request,
+        Err(sqlx::Error::RowNotFound) => {
+            return HttpResponse::NotFound().json(ErrorResponse {
+                error: format!("Group '{}' does not exist", body.group_name),
+            });
+        }
+        Err(sqlx::Error::Protocol(msg)) => return HttpResponse::Conflict().json(ErrorResponse { error: msg }),
+        Err(e) => {
+            log::error!("Failed to record access request of {} to group '{}': {}", username, body.group_name, e);
+            return HttpResponse::InternalServerError().json(ErrorResponse {
+                error: "Failed to create access request".into(),
+            });
+        }
+    };
+
+    match db::group_owners(pool.get_ref(), &request.group_name).await {
+        Ok(owners) => notifier.send(Notification::new(
+            "access_requested",
+            owners,
+            &request.group_name,
+            format!(
+                "{} requests {} access to group '{}': {}",
+                request.username, request.role, request.group_name, request.justification
+            ),
+        )),
+        Err(e) => log::error!("Failed to load owners of group '{}': {}", request.group_name, e),
+    }
+
+    log::info!("User {} requested {} access to group {}", username, request.role, request.group_name);
+    HttpResponse::Created().json(request)
+}
+
+/// Historique des demandes : celles de l'utilisateur (`scope=mine`) ou celles des groupes dont il
+/// est propriétaire (`scope=review`)
+pub async fn get_access_requests(
+    req: HttpRequest,
+    pool: web::Data<SqlitePool>,
+    params: web::Query<ListParams>,
+    query: web::Query<AccessRequestsQuery>,
+    crypto: web::Data<CryptoService>,
+) -> HttpResponse {
+    let username = match current_username(&req) {
+        Ok(u) => u,
+        Err(response) => return response,
+    };
+
+    let scope = match query.scope.as_deref().unwrap_or("mine") {
+        "mine" => db::AccessRequestScope::Requester(&username),
+        "review" => db::AccessRequestScope::Reviewer(&username),
+        other => {
+            return HttpResponse::BadRequest().json(ErrorResponse {
+                error: format!("Unknown scope '{}' (expected mine or review)", other),
+            });
+        }
+    };
+
+    access_requests_response(pool.get_ref(), &params, &query, scope, crypto.get_ref()).await
+}
+
+/// Liste paginée des demandes d'accès (utilisateurs et administrateurs)
+pub async fn access_requests_response(
+    pool: &SqlitePool,
+    params: &ListParams,
+    query: &AccessRequestsQuery,
+    scope: db::AccessRequestScope<'_>,
+    crypto: &CryptoService,
+) -> HttpResponse {
+    if let Some(status) = query.status.as_deref().filter(|status| !ACCESS_REQUEST_STATUSES.contains(status)) {
+        return HttpResponse::BadRequest().json(ErrorResponse {
+            error: format!("Unknown status '{}' (expected {})", status, ACCESS_REQUEST_STATUSES.join(", ")),
+        });
+    }
+
+    let list = match list_query(params, &db::ACCESS_REQUEST_LIST, crypto) {
+        Ok(list) => list,
+        Err(response) => return response,
+    };
+
+    match db::get_access_requests(pool, &list, scope, query.group_name.as_deref(), query.status.as_deref()).await {
+        Ok(page) => HttpResponse::Ok().json(page),
+        Err(e) => {
+            log::error!("Failed to list access requests: {}", e);
+            HttpResponse::InternalServerError().json(ErrorResponse {
+                error: "Failed to retrieve access requests".into(),
+            })
+        }
+    }
+}
+
+/// Approuve ou refuse une demande d'accès à un groupe dont l'utilisateur est propriétaire
+pub async fn decide_access_request(
+    req: HttpRequest,
+    pool: web::Data<SqlitePool>,
+    notifier: web::Data<Notifier>,
+    body: web::Json<DecideAccessRequest>,
+) -> HttpResponse {
+    let username = match current_username(&req) {
+        Ok(u) => u,
+        Err(response) => return response,
+    };
+
+    let request = match db::get_access_request(pool.get_ref(), &body.id).await {
+        Ok(Some(request)) => request,
+        Ok(None) => {
+            return HttpResponse::NotFound().json(ErrorResponse {
+                error: "Access request not found".into(),
+            });
+        }
+        Err(e) => {
+            log::error!("Failed to load access request {}: {}", body.id, e);
+            return HttpResponse::InternalServerError().json(ErrorResponse {
+                error: "Failed to load access request".into(),
+            });
+        }
+    };
+
+    if request.username == username {
+        return HttpResponse::Forbidden().json(ErrorResponse {
+            error: "You cannot decide on your own access request".into(),
+        });
+    }
+    if let Err(response) = authorize_vault(pool.get_ref(), &username, Some(&request.group_name), GroupRole::Owner).await {
+        return response;
+    }
+    if body.decision == AccessDecision::Approve {
+        let role = GroupRole::parse(&request.role).unwrap_or_default();
+        if let Err(response) = authorize_owner_grant(pool.get_ref(), &username, &request.group_name, role).await {
+            return response;
+        }
+    }
+
+    access_decision_response(pool.get_ref(), notifier.get_ref(), &body, &username).await
+}
+
+/// Applique la décision sur une demande d'accès et prévient le demandeur
+/// (propriétaire du groupe ou administrateur)
+pub async fn access_decision_response(
+    pool: &SqlitePool,
+    notifier: &Notifier,
+    body: &DecideAccessRequest,
+    actor: &str,
+) -> HttpResponse {
+    if let Err(response) = check_access_hours("duration_hours", body.duration_hours) {
+        return response;
+    }
+    let approve = body.decision == AccessDecision::Approve;
+    let note = body.note.as_deref().map(str::trim).filter(|note| !note.is_empty());
+
+    match db::decide_access_request(pool, &body.id, approve, actor, note, body.duration_hours).await {
+        Ok(request) => {
+            let outcome = if approve { "approved" } else { "denied" };
+            let mut message = format!("Your {} access request to group '{}' was {} by {}", request.role, request.group_name, outcome, actor);
+            if let Some(expires_at) = &request.access_expires_at {
+                message.push_str(&format!(" (access until {})", expires_at));
+            }
+            if let Some(note) = note {
+                message.push_str(&format!(": {}", note));
+            }
+            notifier.send(Notification::new(
+                if approve { "access_approved" } else { "access_denied" },
+                vec![request.username.clone()],
+                &request.group_name,
+                message,
+            ));
+
+            log::info!("{} {} access request {} of {} to group {}", actor, outcome, request.id, request.username, request.group_name);
+            HttpResponse::Ok().json(request)
+        }
+        Err(sqlx::Error::RowNotFound) => HttpResponse::NotFound().json(ErrorResponse {
+            error: "Access request not found".into(),
+        }),
+        Err(sqlx::Error::Protocol(msg)) => HttpResponse::Conflict().json(ErrorResponse { error: msg }),
+        Err(e) => {
+            log::error!("Failed to decide access request {}: {}", body.id, e);
+            HttpResponse::InternalServerError().json(ErrorResponse {
+                error: "Failed to decide access request".into(),
+            })
+        }
+    }
+}
+
+/// Annule une demande d'accès en attente de l'utilisateur
+pub async fn cancel_access_request(
+    req: HttpRequest,
+    pool: web::Data<SqlitePool>,
+    body: web::Json<DeleteRequest>,
+) -> HttpResponse {
+    let username = match current_username(&req) {
+        Ok(u) => u,
+        Err(response) => return response,
+    };
+
+    match db::cancel_access_request(pool.get_ref(), &body.id, &username).await {
+        Ok(0) => HttpResponse::NotFound().json(ErrorResponse {
+            error: "No pending access request with this id".into(),
+        }),
+        Ok(_) => {
+            log::info!("User {} cancelled access request {}", username, body.id);
+            HttpResponse::Ok().json(serde_json::json!({
+                "id": body.id,
+                "message": "Access request cancelled"
+            }))
+        }
+        Err(e) => {
+            log::error!("Failed to cancel access request {}: {}", body.id, e);
+            HttpResponse::InternalServerError().json(ErrorResponse {
+                error: "Failed to cancel access request".into(),
             })
         }
+    }
+}
+
+// ==================== EMPRUNTS ====================
+
+/// Emprunte un identifiant de groupe : l'utilisateur en reçoit le secret et en a l'usage exclusif
+/// jusqu'au retour ou à l'échéance de l'emprunt
+pub async fn checkout_item(
+    req: HttpRequest,
+    pool: web::Data<SqlitePool>,
+    body: web::Json<CheckoutRequest>,
+    crypto: web::Data<CryptoService>,
+) -> HttpResponse {
+    if !body.item_type.ends_with("_group") {
+        return HttpResponse::BadRequest().json(ErrorResponse {
+            error: "Only group credentials can be checked out".into(),
+        });
+    }
+    let username = match authorize_item(&req, pool.get_ref(), &body.item_type, &body.item_id, GroupRole::Viewer).await {
+        Ok(u) => u,
+        Err(response) => return response,
+    };
+
+    let minutes = body.duration_minutes.unwrap_or(leases::DEFAULT_LEASE_MINUTES);
+    if !(1..=leases::MAX_LEASE_MINUTES).contains(&minutes) {
+        return HttpResponse::BadRequest().json(ErrorResponse {
+            error: format!("duration_minutes must be between 1 and {}", leases::MAX_LEASE_MINUTES),
+        });
+    }
+    let reason = body.reason.as_deref().map(str::trim).filter(|reason| !reason.is_empty());
+    if reason.is_some_and(|reason| reason.chars().count() > leases::MAX_REASON_LEN) {
+        return HttpResponse::BadRequest().json(ErrorResponse {
+            error: format!("reason cannot exceed {} characters", leases::MAX_REASON_LEN),
+        });
+    }
+    let expires_at = (Utc::now() + chrono::Duration::minutes(minutes)).to_rfc3339();
+
+    let lease = match db::checkout_item(pool.get_ref(), &body.item_type, &body.item_id, &username, reason, &expires_at).await {
+        Ok(lease) => lease,
+        Err(sqlx::Error::RowNotFound) => {
+            return HttpResponse::NotFound().json(ErrorResponse {
+                error: "Item not found".into(),
+            });
+        }
+        Err(sqlx::Error::Protocol(msg)) => return HttpResponse::Conflict().json(ErrorResponse { error: msg }),
         Err(e) => {
-            log::error!("Failed to retrieve accounts for group '{}': {}", body.group_name, e);
+            log::error!("Failed to check out {} {}: {}", body.item_type, body.item_id, e);
+            return HttpResponse::InternalServerError().json(ErrorResponse {
+                error: "Failed to check out item".into(),
+            });
+        }
+    };
+
+    match db::reveal_item(pool.get_ref(), &body.item_type, &body.item_id, crypto.get_ref()).await {
+        Ok(Some(item)) => {
+            log::info!("User {} checked out {} {} until {}", username, body.item_type, body.item_id, lease.expires_at);
+            HttpResponse::Ok().json(CheckoutResponse { lease, item })
+        }
+        Ok(None) => HttpResponse::NotFound().json(ErrorResponse {
+            error: "Item not found".into(),
+        }),
+        Err(e) => {
+            log::error!("Failed to reveal checked out {} {}: {}", body.item_type, body.item_id, e);
             HttpResponse::InternalServerError().json(ErrorResponse {
-                error: "Failed to retrieve accounts".into(),
+                error: "Failed to reveal secret".into(),
             })
         }
     }
 }
 
-pub async fn get_api_key_by_title(
-    _req: HttpRequest,
+/// Rend un identifiant emprunté. Un propriétaire du groupe peut libérer l'emprunt d'un autre membre.
+/// Au retour, le secret peut être signalé comme à renouveler (`remind`) ou remplacé par un mot de
+/// passe généré (`rotate`, comptes uniquement), renvoyé pour être reporté sur le système cible
+pub async fn checkin_item(
+    req: HttpRequest,
     pool: web::Data<SqlitePool>,
-    body: web::Json<RequestGetApiKeyInTitle>,
+    notifier: web::Data<Notifier>,
+    body: web::Json<CheckinRequest>,
+    config: web::Data<AppConfig>,
     crypto: web::Data<CryptoService>,
 ) -> HttpResponse {
-    let title = &body.title;
-    let username = &body.username;
-    if title.trim().is_empty() {
+    let username = match authorize_item(&req, pool.get_ref(), &body.item_type, &body.item_id, GroupRole::Viewer).await {
+        Ok(u) => u,
+        Err(response) => return response,
+    };
+
+    let holder = match db::active_lease(pool.get_ref(), &body.item_type, &body.item_id).await {
+        Ok(Some(lease)) => lease.username,
+        Ok(None) => {
+            return HttpResponse::NotFound().json(ErrorResponse {
+                error: "This item is not checked out".into(),
+            });
+        }
+        Err(e) => {
+            log::error!("Failed to load the lease on {} {}: {}", body.item_type, body.item_id, e);
+            return HttpResponse::InternalServerError().json(ErrorResponse {
+                error: "Failed to check in item".into(),
+            });
+        }
+    };
+
+    // Libérer l'emprunt d'un autre membre ou renouveler le secret demande plus que la lecture
+    let force = holder != username;
+    let required = match (force, body.rotation) {
+        (true, _) => GroupRole::Owner,
+        (false, CheckinRotation::Rotate) => GroupRole::Editor,
+        (false, _) => GroupRole::Viewer,
+    };
+    if let Err(response) = authorize_item(&req, pool.get_ref(), &body.item_type, &body.item_id, required).await {
+        return response;
+    }
+
+    let kind = items::parse_item_type(&body.item_type).map(|(kind, _)| kind);
+    if body.rotation == CheckinRotation::Rotate && kind.map(|kind| kind.primary_secret) != Some("password") {
         return HttpResponse::BadRequest().json(ErrorResponse {
-            error: "Group name cannot be empty".into(),
-        });        
+            error: "Automatic rotation is only available for accounts".into(),
+        });
     }
-    match db::get_api_key_by_title_and_username(pool.get_ref(), title, username, crypto.get_ref()).await {
-        Ok(accounts) if !accounts.is_empty() => {
-            log::info!("Group '{}' retrieved {} account(s)", username, accounts.len());
-            HttpResponse::Ok().json(accounts)
+
+    let remind = body.rotation != CheckinRotation::Keep;
+    let lease = match db::checkin_item(pool.get_ref(), &body.item_type, &body.item_id, &username, force, remind).await {
+        Ok(lease) => lease,
+        Err(sqlx::Error::RowNotFound) => {
+            return HttpResponse::NotFound().json(ErrorResponse {
+                error: "This item is not checked out".into(),
+            });
+        }
+        Err(e) => {
+            log::error!("Failed to check in {} {}: {}", body.item_type, body.item_id, e);
+            return HttpResponse::InternalServerError().json(ErrorResponse {
+                error: "Failed to check in item".into(),
+            });
+        }
+    };
+
+    if force {
+        notifier.send(Notification::new(
+            "lease_released",
+            vec![lease.username.clone()],
+            &lease.group_name,
+            format!("Your checkout of {} {} was ended by {}", lease.item_type, lease.item_id, username),
+        ));
+    }
+
+    let mut response = CheckinResponse { lease, rotated_secret: None, version: None };
+
+    if body.rotation == CheckinRotation::Rotate {
+        // En cas d'échec, le secret reste signalé comme à renouveler
+        let password = leases::generate_password();
+        let changes = items::fields(&[("password", &password)]);
+        match db::update_item_fields(pool.get_ref(), &body.item_type, &body.item_id, &changes, &username, config.max_secret_versions, crypto.get_ref()).await {
+            Ok((version, _)) => {
+                response.rotated_secret = Some(password);
+                response.version = Some(version);
+            }
+            Err(e) => log::error!("Failed to rotate {} {} after check-in: {}", body.item_type, body.item_id, e),
+        }
+    }
+
+    log::info!("User {} checked in {} {} ({:?})", username, body.item_type, body.item_id, body.rotation);
+    HttpResponse::Ok().json(response)
+}
+
+/// Emprunts en cours et historique des emprunts d'un groupe
+pub async fn get_leases(
+    req: HttpRequest,
+    pool: web::Data<SqlitePool>,
+    body: web::Json<LeasesRequest>,
+) -> HttpResponse {
+    let username = match current_username(&req) {
+        Ok(u) => u,
+        Err(response) => return response,
+    };
+
+    if let Err(response) = authorize_vault(pool.get_ref(), &username, Some(&body.group_name), GroupRole::Viewer).await {
+        return response;
+    }
+
+    let item = match (&body.item_type, &body.item_id) {
+        (Some(item_type), Some(item_id)) => Some((item_type.as_str(), item_id.as_str())),
+        (None, None) => None,
+        _ => {
+            return HttpResponse::BadRequest().json(ErrorResponse {
+                error: "item_type and item_id must be given together".into(),
+            });
+        }
+    };
+
+    match db::get_leases(pool.get_ref(), &body.group_name, item, body.active_only).await {
+        Ok(leases) => HttpResponse::Ok().json(leases),
+        Err(e) => {
+            log::error!("Failed to list leases of group {}: {}", body.group_name, e);
+            HttpResponse::InternalServerError().json(ErrorResponse {
+                error: "Failed to retrieve leases".into(),
+            })
+        }
+    }
+}
+
+// ==================== ACCÈS D'URGENCE ====================
+
+const MAX_EMERGENCY_WAIT_HOURS: i64 = 24 * 30;
+const EMERGENCY_REQUEST_STATUSES: [&str; 6] = ["pending", "granted", "denied", "cancelled", "revoked", "expired"];
+
+/// Cible d'un contact d'urgence : le coffre de l'utilisateur, ou un groupe dont il est propriétaire
+async fn emergency_target<'a>(
+    pool: &SqlitePool,
+    username: &'a str,
+    group_name: Option<&'a str>,
+) -> Result<(&'static str, &'a str), HttpResponse> {
+    match group_name {
+        Some(group_name) => {
+            authorize_vault(pool, username, Some(group_name), GroupRole::Owner).await?;
+            Ok(("group", group_name))
+        }
+        None => Ok(("user", username)),
+    }
+}
+
+/// Vérifie que l'utilisateur peut refuser ou révoquer une demande d'urgence : titulaire du coffre
+/// visé, ou propriétaire du groupe visé
+async fn authorize_emergency_review(pool: &SqlitePool, username: &str, request: &EmergencyRequest) -> Result<(), HttpResponse> {
+    match request.target_type.as_str() {
+        "group" => authorize_vault(pool, username, Some(&request.target_name), GroupRole::Owner).await,
+        _ if request.target_name == username => Ok(()),
+        _ => Err(HttpResponse::Forbidden().json(ErrorResponse {
+            error: "This emergency request does not target your vault".into(),
+        })),
+    }
+}
+
+async fn load_emergency_request(pool: &SqlitePool, id: &str) -> Result<EmergencyRequest, HttpResponse> {
+    match db::get_emergency_request(pool, id).await {
+        Ok(Some(request)) => Ok(request),
+        Ok(None) => Err(HttpResponse::NotFound().json(ErrorResponse {
+            error: "Emergency request not found".into(),
+        })),
+        Err(e) => {
+            log::error!("Failed to load emergency request {}: {}", id, e);
+            Err(HttpResponse::InternalServerError().json(ErrorResponse {
+                error: "Failed to load emergency request".into(),
+            }))
+        }
+    }
+}
+
+/// Prévient le titulaire (ou les propriétaires du groupe), le demandeur et les administrateurs
+pub async fn notify_emergency(pool: &SqlitePool, notifier: &Notifier, event: &'static str, request: &EmergencyRequest, message: String) {
+    log::warn!("🚨 [{}] {} {} / {}: {}", event, request.target_type, request.target_name, request.id, message);
+
+    match db::emergency_recipients(pool, request).await {
+        Ok(recipients) => notifier.send(Notification::new(event, recipients, &request.target_name, message)),
+        Err(e) => log::error!("Failed to load recipients of emergency request {}: {}", request.id, e),
+    }
+}
+
+/// Désigne un contact d'urgence pour le coffre de l'utilisateur ou un groupe dont il est propriétaire
+pub async fn set_emergency_contact(
+    req: HttpRequest,
+    pool: web::Data<SqlitePool>,
+    config: web::Data<AppConfig>,
+    notifier: web::Data<Notifier>,
+    body: web::Json<SetEmergencyContactRequest>,
+) -> HttpResponse {
+    let username = match current_username(&req) {
+        Ok(u) => u,
+        Err(response) => return response,
+    };
+    let (target_type, target_name) = match emergency_target(pool.get_ref(), &username, body.group_name.as_deref()).await {
+        Ok(target) => target,
+        Err(response) => return response,
+    };
+
+    let wait_hours = body.wait_hours.unwrap_or(config.emergency_default_wait_hours);
+    if !(1..=MAX_EMERGENCY_WAIT_HOURS).contains(&wait_hours) {
+        return HttpResponse::BadRequest().json(ErrorResponse {
+            error: format!("wait_hours must be between 1 and {}", MAX_EMERGENCY_WAIT_HOURS),
+        });
+    }
+
+    match db::set_emergency_contact(pool.get_ref(), target_type, target_name, &body.contact, wait_hours, &username).await {
+        Ok(contact) => {
+            notifier.send(Notification::new(
+                "emergency_contact_added",
+                vec![contact.contact.clone()],
+                target_name,
+                format!(
+                    "{} designated you as emergency contact for {} '{}' (waiting period {}h)",
+                    username, target_type, target_name, wait_hours
+                ),
+            ));
+            log::info!("User {} designated {} as emergency contact for {} {}", username, contact.contact, target_type, target_name);
+            HttpResponse::Ok().json(contact)
+        }
+        Err(sqlx::Error::Protocol(msg)) => HttpResponse::BadRequest().json(ErrorResponse { error: msg }),
+        Err(e) => {
+            log::error!("Failed to set emergency contact {} for {} {}: {}", body.contact, target_type, target_name, e);
+            HttpResponse::InternalServerError().json(ErrorResponse {
+                error: "Failed to set emergency contact".into(),
+            })
         }
+    }
+}
+
+/// Retire un contact d'urgence ; ses demandes en attente sont annulées
+pub async fn remove_emergency_contact(
+    req: HttpRequest,
+    pool: web::Data<SqlitePool>,
+    body: web::Json<RemoveEmergencyContactRequest>,
+) -> HttpResponse {
+    let username = match current_username(&req) {
+        Ok(u) => u,
+        Err(response) => return response,
+    };
+    let (target_type, target_name) = match emergency_target(pool.get_ref(), &username, body.group_name.as_deref()).await {
+        Ok(target) => target,
+        Err(response) => return response,
+    };
+
+    match db::remove_emergency_contact(pool.get_ref(), target_type, target_name, &body.contact, &username).await {
+        Ok(0) => HttpResponse::NotFound().json(ErrorResponse {
+            error: format!("'{}' is not an emergency contact of {} '{}'", body.contact, target_type, target_name),
+        }),
         Ok(_) => {
-            log::warn!("No accounts found for group: {}", username);
-            HttpResponse::NotFound().json(ErrorResponse {
-                error: "No accounts found for this group".into(),
+            log::info!("User {} removed emergency contact {} from {} {}", username, body.contact, target_type, target_name);
+            HttpResponse::Ok().json(serde_json::json!({
+                "contact": body.contact,
+                "message": "Emergency contact removed"
+            }))
+        }
+        Err(e) => {
+            log::error!("Failed to remove emergency contact {} from {} {}: {}", body.contact, target_type, target_name, e);
+            HttpResponse::InternalServerError().json(ErrorResponse {
+                error: "Failed to remove emergency contact".into(),
+            })
+        }
+    }
+}
+
+/// Contacts d'urgence du coffre de l'utilisateur ou d'un groupe dont il est propriétaire
+pub async fn get_emergency_contacts(
+    req: HttpRequest,
+    pool: web::Data<SqlitePool>,
+    body: web::Json<EmergencyContactsRequest>,
+) -> HttpResponse {
+    let username = match current_username(&req) {
+        Ok(u) => u,
+        Err(response) => return response,
+    };
+    let (target_type, target_name) = match emergency_target(pool.get_ref(), &username, body.group_name.as_deref()).await {
+        Ok(target) => target,
+        Err(response) => return response,
+    };
+
+    match db::get_emergency_contacts(pool.get_ref(), target_type, target_name).await {
+        Ok(contacts) => HttpResponse::Ok().json(contacts),
+        Err(e) => {
+            log::error!("Failed to list emergency contacts of {} {}: {}", target_type, target_name, e);
+            HttpResponse::InternalServerError().json(ErrorResponse {
+                error: "Failed to retrieve emergency contacts".into(),
             })
         }
+    }
+}
+
+/// Coffres et groupes pour lesquels l'utilisateur est contact d'urgence
+pub async fn get_emergency_designations(req: HttpRequest, pool: web::Data<SqlitePool>) -> HttpResponse {
+    let username = match current_username(&req) {
+        Ok(u) => u,
+        Err(response) => return response,
+    };
+
+    match db::get_emergency_designations(pool.get_ref(), &username).await {
+        Ok(designations) => HttpResponse::Ok().json(designations),
         Err(e) => {
-            log::error!("Failed to retrieve accounts for group '{}': {}", title, e);
+            log::error!("Failed to list emergency designations of {}: {}", username, e);
             HttpResponse::InternalServerError().json(ErrorResponse {
-                error: "Failed to retrieve accounts".into(),
+                error: "Failed to retrieve emergency designations".into(),
+            })
+        }
+    }
+}
+
+/// Demande d'accès d'urgence d'un contact désigné : accordée automatiquement à l'issue du délai
+/// d'attente, sauf refus du titulaire ou d'un propriétaire du groupe
+pub async fn create_emergency_request(
+    req: HttpRequest,
+    pool: web::Data<SqlitePool>,
+    notifier: web::Data<Notifier>,
+    body: web::Json<CreateEmergencyRequest>,
+) -> HttpResponse {
+    let username = match current_username(&req) {
+        Ok(u) => u,
+        Err(response) => return response,
+    };
+
+    if !matches!(body.target_type.as_str(), "user" | "group") {
+        return HttpResponse::BadRequest().json(ErrorResponse {
+            error: format!("Unknown target_type '{}' (expected user or group)", body.target_type),
+        });
+    }
+    let reason = body.reason.trim();
+    if reason.is_empty() || reason.chars().count() > MAX_JUSTIFICATION_LEN {
+        return HttpResponse::BadRequest().json(ErrorResponse {
+            error: format!("reason is required ({} characters at most)", MAX_JUSTIFICATION_LEN),
+        });
+    }
+
+    let request = match db::create_emergency_request(pool.get_ref(), &body.target_type, &body.target_name, &username, reason).await {
+        Ok(request) => request,
+        Err(sqlx::Error::RowNotFound) => {
+            return HttpResponse::Forbidden().json(ErrorResponse {
+                error: format!("You are not an emergency contact of {} '{}'", body.target_type, body.target_name),
+            });
+        }
+        Err(sqlx::Error::Protocol(msg)) => return HttpResponse::Conflict().json(ErrorResponse { error: msg }),
+        Err(e) => {
+            log::error!("Failed to record emergency request of {} to {} '{}': {}", username, body.target_type, body.target_name, e);
+            return HttpResponse::InternalServerError().json(ErrorResponse {
+                error: "Failed to create emergency request".into(),
+            });
+        }
+    };
+
+    let message = format!(
+        "{} requested emergency access to {} '{}', granted automatically at {} unless denied: {}",
+        request.requester, request.target_type, request.target_name, request.grant_at, request.reason
+    );
+    notify_emergency(pool.get_ref(), notifier.get_ref(), "emergency_requested", &request, message).await;
+
+    HttpResponse::Created().json(request)
+}
+
+/// Historique des demandes d'urgence : celles de l'utilisateur (`scope=mine`) ou celles visant son
+/// coffre et ses groupes (`scope=review`)
+pub async fn get_emergency_requests(
+    req: HttpRequest,
+    pool: web::Data<SqlitePool>,
+    params: web::Query<ListParams>,
+    query: web::Query<EmergencyRequestsQuery>,
+    crypto: web::Data<CryptoService>,
+) -> HttpResponse {
+    let username = match current_username(&req) {
+        Ok(u) => u,
+        Err(response) => return response,
+    };
+
+    let scope = match query.scope.as_deref().unwrap_or("mine") {
+        "mine" => db::EmergencyScope::Requester(&username),
+        "review" => db::EmergencyScope::Reviewer(&username),
+        other => {
+            return HttpResponse::BadRequest().json(ErrorResponse {
+                error: format!("Unknown scope '{}' (expected mine or review)", other),
+            });
+        }
+    };
+
+    emergency_requests_response(pool.get_ref(), &params, &query, scope, crypto.get_ref()).await
+}
+
+/// Liste paginée des demandes d'urgence (utilisateurs et administrateurs)
+pub async fn emergency_requests_response(
+    pool: &SqlitePool,
+    params: &ListParams,
+    query: &EmergencyRequestsQuery,
+    scope: db::EmergencyScope<'_>,
+    crypto: &CryptoService,
+) -> HttpResponse {
+    if let Some(status) = query.status.as_deref().filter(|status| !EMERGENCY_REQUEST_STATUSES.contains(status)) {
+        return HttpResponse::BadRequest().json(ErrorResponse {
+            error: format!("Unknown status '{}' (expected {})", status, EMERGENCY_REQUEST_STATUSES.join(", ")),
+        });
+    }
+
+    let list = match list_query(params, &db::EMERGENCY_REQUEST_LIST, crypto) {
+        Ok(list) => list,
+        Err(response) => return response,
+    };
+
+    match db::get_emergency_requests(pool, &list, scope, query.status.as_deref()).await {
+        Ok(page) => HttpResponse::Ok().json(page),
+        Err(e) => {
+            log::error!("Failed to list emergency requests: {}", e);
+            HttpResponse::InternalServerError().json(ErrorResponse {
+                error: "Failed to retrieve emergency requests".into(),
+            })
+        }
+    }
+}
+
+/// Refuse une demande d'urgence pendant le délai d'attente, ou l'accorde sans attendre
+pub async fn decide_emergency_request(
+    req: HttpRequest,
+    pool: web::Data<SqlitePool>,
+    config: web::Data<AppConfig>,
+    notifier: web::Data<Notifier>,
+    body: web::Json<DecideEmergencyRequest>,
+) -> HttpResponse {
+    let username = match current_username(&req) {
+        Ok(u) => u,
+        Err(response) => return response,
+    };
+    let request = match load_emergency_request(pool.get_ref(), &body.id).await {
+        Ok(request) => request,
+        Err(response) => return response,
+    };
+
+    if request.requester == username {
+        return HttpResponse::Forbidden().json(ErrorResponse {
+            error: "You cannot decide on your own emergency request".into(),
+        });
+    }
+    if let Err(response) = authorize_emergency_review(pool.get_ref(), &username, &request).await {
+        return response;
+    }
+
+    let approve = body.decision == AccessDecision::Approve;
+    let note = body.note.as_deref().map(str::trim).filter(|note| !note.is_empty());
+
+    match db::decide_emergency_request(pool.get_ref(), &body.id, approve, &username, note, config.emergency_access_hours).await {
+        Ok(request) => {
+            let mut message = match &request.access_expires_at {
+                Some(expires_at) if approve => format!(
+                    "{} granted emergency access of {} to {} '{}' until {}",
+                    username, request.requester, request.target_type, request.target_name, expires_at
+                ),
+                _ => format!(
+                    "{} denied emergency access of {} to {} '{}'",
+                    username, request.requester, request.target_type, request.target_name
+                ),
+            };
+            if let Some(note) = note {
+                message.push_str(&format!(": {}", note));
+            }
+            let event = if approve { "emergency_granted" } else { "emergency_denied" };
+            notify_emergency(pool.get_ref(), notifier.get_ref(), event, &request, message).await;
+
+            HttpResponse::Ok().json(request)
+        }
+        Err(sqlx::Error::RowNotFound) => HttpResponse::NotFound().json(ErrorResponse {
+            error: "Emergency request not found".into(),
+        }),
+        Err(sqlx::Error::Protocol(msg)) => HttpResponse::Conflict().json(ErrorResponse { error: msg }),
+        Err(e) => {
+            log::error!("Failed to decide emergency request {}: {}", body.id, e);
+            HttpResponse::InternalServerError().json(ErrorResponse {
+                error: "Failed to decide emergency request".into(),
+            })
+        }
+    }
+}
+
+/// Annule une demande d'urgence en attente (demandeur) ou met fin à un accès accordé
+/// (demandeur, titulaire du coffre ou propriétaire du groupe)
+pub async fn end_emergency_request(
+    req: HttpRequest,
+    pool: web::Data<SqlitePool>,
+    notifier: web::Data<Notifier>,
+    body: web::Json<DeleteRequest>,
+) -> HttpResponse {
+    let username = match current_username(&req) {
+        Ok(u) => u,
+        Err(response) => return response,
+    };
+    let request = match load_emergency_request(pool.get_ref(), &body.id).await {
+        Ok(request) => request,
+        Err(response) => return response,
+    };
+
+    if request.requester != username {
+        if request.status == "pending" {
+            return HttpResponse::Forbidden().json(ErrorResponse {
+                error: "Deny the request instead of cancelling it".into(),
+            });
+        }
+        if let Err(response) = authorize_emergency_review(pool.get_ref(), &username, &request).await {
+            return response;
+        }
+    }
+
+    match db::end_emergency_request(pool.get_ref(), &body.id, &username).await {
+        Ok(Some(request)) => {
+            let (event, outcome) = match request.status.as_str() {
+                "revoked" => ("emergency_revoked", "ended"),
+                _ => ("emergency_cancelled", "cancelled"),
+            };
+            let message = format!(
+                "{} {} emergency access of {} to {} '{}'",
+                username, outcome, request.requester, request.target_type, request.target_name
+            );
+            notify_emergency(pool.get_ref(), notifier.get_ref(), event, &request, message).await;
+
+            HttpResponse::Ok().json(request)
+        }
+        Ok(None) => HttpResponse::Conflict().json(ErrorResponse {
+            error: format!("This request is already {}", request.status),
+        }),
+        Err(e) => {
+            log::error!("Failed to end emergency request {}: {}", body.id, e);
+            HttpResponse::InternalServerError().json(ErrorResponse {
+                error: "Failed to end emergency request".into(),
             })
         }
     }
+}
+
+/// Éléments personnels d'un utilisateur dont le contact a obtenu l'accès d'urgence ; chaque
+/// consultation est auditée, les secrets se révèlent ensuite par `reveal/item`
+pub async fn get_emergency_vault(
+    req: HttpRequest,
+    pool: web::Data<SqlitePool>,
+    body: web::Json<UsernameRequest>,
+    crypto: web::Data<CryptoService>,
+) -> HttpResponse {
+    let username = match current_username(&req) {
+        Ok(u) => u,
+        Err(response) => return response,
+    };
+
+    let request = match db::active_emergency_access(pool.get_ref(), &username, &body.username).await {
+        Ok(Some(request)) => request,
+        Ok(None) => {
+            return HttpResponse::Forbidden().json(ErrorResponse {
+                error: format!("You have no emergency access to the vault of '{}'", body.username),
+            });
+        }
+        Err(e) => {
+            log::error!("Failed to check emergency access of {} to {}: {}", username, body.username, e);
+            return HttpResponse::InternalServerError().json(ErrorResponse {
+                error: "Failed to check access".into(),
+            });
+        }
+    };
+
+    if let Err(e) = db::record_audit(pool.get_ref(), &username, "emergency_vault_viewed", Some("user"), Some(&body.username), &request.id).await {
+        log::error!("Failed to audit emergency access of {} to {}: {}", username, body.username, e);
+        return HttpResponse::InternalServerError().json(ErrorResponse {
+            error: "Failed to retrieve emergency vault".into(),
+        });
+    }
 
-}
\ No newline at end of file
+    match db::get_emergency_vault(pool.get_ref(), &request, crypto.get_ref()).await {
+        Ok(items) => {
+            log::warn!("🚨 User {} opened the vault of {} through emergency request {}", username, body.username, request.id);
+            HttpResponse::Ok().json(items)
+        }
+        Err(e) => {
+            log::error!("Failed to retrieve emergency vault of {} for {}: {}", body.username, username, e);
+            HttpResponse::InternalServerError().json(ErrorResponse {
+                error: "Failed to retrieve emergency vault".into(),
+            })
+        }
+    }
+}
@@ -1,15 +1,25 @@
 use actix_web::{web, HttpResponse, HttpRequest, HttpMessage};
+use actix_web::http::header::ContentDisposition;
 use sqlx::SqlitePool;
-use crate::models::{ClaimsAdmin, ErrorResponse,CreateGroupRequest,AddUserGroups,DeleteGroups};
+use crate::models::{ClaimsAdmin, ErrorResponse,CreateGroupRequest,AddUserGroups,DeleteGroups,PurgeTrashRequest,PurgeTrashResponse,ItemTemplateRequest,DeleteTemplateRequest,SetGroupPolicyRequest,BackupRequest,SetGroupRoleRequest,RemoveGroupMemberRequest,SetGroupParentRequest,DecideAccessRequest,AccessRequestsQuery,UpdateMembershipRequest,ExpiringMembershipsQuery,DeleteRequest,EmergencyRequestsQuery,EmergencyOverrideResponse};
 use crate::db;
+use crate::handlers::{group_role_response, group_member_removal_response, group_policy_response, group_parent_response, access_requests_response, access_decision_response, membership_expiry_response, check_access_hours, emergency_requests_response, notify_emergency};
+use crate::notify::Notifier;
+use crate::attachments;
+use crate::backup::{self, BackupKey, BackupLock};
+use crate::config::AppConfig;
+use crate::models::ListParams;
+use crate::pagination::ListQuery;
+use crate::crypto::CryptoService;
 
 pub async fn get_users(
     req: HttpRequest,
     pool: web::Data<SqlitePool>,
+    query: web::Query<ListParams>,
+    crypto: web::Data<CryptoService>,
 ) -> HttpResponse {
     // ✅ Récupération sécurisée des claims depuis les extensions
-    let extensions = req.extensions();
-    let claims = match extensions.get::<ClaimsAdmin>() {
+    let claims = match req.extensions().get::<ClaimsAdmin>().cloned() {
         Some(c) => c,
         None => {
             return HttpResponse::Unauthorized().json(ErrorResponse {
@@ -27,12 +37,14 @@ pub async fn get_users(
         });
     }
 
+    let list = match ListQuery::from_params(&query, &db::USER_LIST, crypto.get_ref()) {
+        Ok(list) => list,
+        Err(error) => return HttpResponse::BadRequest().json(ErrorResponse { error }),
+    };
+
     // ✅ Lecture en base
-    match db::get_all_accounts(pool.get_ref()).await {
-        Ok(accounts) if !accounts.is_empty() => HttpResponse::Ok().json(accounts),
-        Ok(_) => HttpResponse::NotFound().json(ErrorResponse {
-            error: "No accounts found".into(),
-        }),
+    match db::get_all_accounts(pool.get_ref(), &list).await {
+        Ok(page) => HttpResponse::Ok().json(page),
         Err(e) => {
             eprintln!("❌ Database error while fetching users: {:?}", e);
             HttpResponse::InternalServerError().json(ErrorResponse {
@@ -48,8 +60,7 @@ pub async fn create_groups(
     body: web::Json<CreateGroupRequest>,
 ) -> HttpResponse {
     // ✅ Vérifie le token admin
-    let extensions = req.extensions();
-    let claims = match extensions.get::<ClaimsAdmin>() {
+    let claims = match req.extensions().get::<ClaimsAdmin>().cloned() {
         Some(c) => c,
         None => {
             return HttpResponse::Unauthorized().json(ErrorResponse {
@@ -73,8 +84,7 @@ pub async fn add_groups(
     pool: web::Data<SqlitePool>,
     body: web::Json<AddUserGroups>,
 ) -> HttpResponse {
-    let extensions = req.extensions();
-    let claims = match extensions.get::<ClaimsAdmin>() {
+    let claims = match req.extensions().get::<ClaimsAdmin>().cloned() {
         Some(c) => c,
         None => {
             return HttpResponse::Unauthorized().json(ErrorResponse {
@@ -85,6 +95,10 @@ pub async fn add_groups(
 
     println!("🔐 Admin connecté : {}", claims.admin_username);
 
+    if let Err(response) = check_access_hours("expires_in_hours", body.expires_in_hours) {
+        return response;
+    }
+
     match db::add_account(pool.get_ref(), body.into_inner()).await {
         Ok(message) => HttpResponse::Ok().json(serde_json::json!({ "message": message })),
         Err(err) => HttpResponse::BadRequest().json(err),
@@ -95,9 +109,10 @@ pub async fn add_groups(
 pub async fn get_groups(
     req: HttpRequest,
     pool: web::Data<SqlitePool>,
+    query: web::Query<ListParams>,
+    crypto: web::Data<CryptoService>,
 ) -> HttpResponse {
-    let extensions = req.extensions();
-    let claims = match extensions.get::<ClaimsAdmin>() {
+    let claims = match req.extensions().get::<ClaimsAdmin>().cloned() {
         Some(c) => c,
         None => {
             return HttpResponse::Unauthorized().json(ErrorResponse {
@@ -108,7 +123,12 @@ pub async fn get_groups(
 
     println!("🔐 Admin connecté : {}", claims.admin_username);
 
-    match db::fetch_groups(pool.get_ref()).await {
+    let list = match ListQuery::from_params(&query, &db::GROUP_LIST, crypto.get_ref()) {
+        Ok(list) => list,
+        Err(error) => return HttpResponse::BadRequest().json(ErrorResponse { error }),
+    };
+
+    match db::fetch_groups(pool.get_ref(), &list).await {
         Ok(groups) => HttpResponse::Ok().json(groups),
         Err(err) => HttpResponse::InternalServerError().json(ErrorResponse {
             error: format!("Database error: {}", err),
@@ -121,8 +141,7 @@ pub async fn delete_groups(
     pool: web::Data<SqlitePool>,
     body: web::Json<DeleteGroups>,
 ) -> HttpResponse {
-    let extensions = req.extensions();
-    let claims = match extensions.get::<ClaimsAdmin>() {
+    let claims = match req.extensions().get::<ClaimsAdmin>().cloned() {
         Some(c) => c,
         None => {
             return HttpResponse::Unauthorized().json(ErrorResponse {
@@ -139,4 +158,610 @@ pub async fn delete_groups(
             error: format!("Database error: {}", err),
         }),
     }
-}
\ No newline at end of file
+}
+
+/// Supprime définitivement un élément de la corbeille, ou toute la corbeille si `all` est demandé explicitement
+pub async fn purge_trash(
+    req: HttpRequest,
+    pool: web::Data<SqlitePool>,
+    body: web::Json<PurgeTrashRequest>,
+    config: web::Data<AppConfig>,
+) -> HttpResponse {
+    let claims = match req.extensions().get::<ClaimsAdmin>().cloned() {
+        Some(c) => c,
+        None => {
+            return HttpResponse::Unauthorized().json(ErrorResponse {
+                error: "Unauthorized: no valid admin token found".into(),
+            });
+        }
+    };
+
+    if claims.role != "admin" {
+        return HttpResponse::Forbidden().json(ErrorResponse {
+            error: "Access denied: insufficient permissions".into(),
+        });
+    }
+
+    // Vider toute la corbeille doit être demandé explicitement : un corps vide ne purge rien
+    let result = match (&body.item_type, &body.item_id, body.all) {
+        (Some(item_type), Some(item_id), false) => {
+            match db::item_schema(pool.get_ref(), item_type).await {
+                Ok(Some(_)) => {}
+                Ok(None) => {
+                    return HttpResponse::BadRequest().json(ErrorResponse {
+                        error: format!("Unknown item type '{}'", item_type),
+                    });
+                }
+                Err(e) => {
+                    log::error!("Failed to load item type '{}': {}", item_type, e);
+                    return HttpResponse::InternalServerError().json(ErrorResponse {
+                        error: "Failed to purge trash".into(),
+                    });
+                }
+            }
+            db::purge_trashed_item(pool.get_ref(), item_type, item_id).await
+        }
+        (None, None, true) => db::purge_trash(pool.get_ref(), None).await,
+        (None, None, false) => {
+            return HttpResponse::BadRequest().json(ErrorResponse {
+                error: "Provide item_type and item_id, or set all to true to empty the whole trash".into(),
+            });
+        }
+        (_, _, true) => {
+            return HttpResponse::BadRequest().json(ErrorResponse {
+                error: "all cannot be combined with item_type or item_id".into(),
+            });
+        }
+        _ => {
+            return HttpResponse::BadRequest().json(ErrorResponse {
+                error: "item_type and item_id must be provided together".into(),
+            });
+        }
+    };
+
+    match result {
+        Ok(purged) => {
+            log::info!("Admin {} permanently purged {} item(s) from trash", claims.admin_username, purged);
+            if let Err(e) = attachments::sweep_orphans(pool.get_ref(), &config.attachments_dir).await {
+                log::error!("Attachment cleanup failed: {}", e);
+            }
+            HttpResponse::Ok().json(PurgeTrashResponse {
+                purged,
+                message: "Trash purged successfully".into(),
+            })
+        }
+        Err(e) => {
+            log::error!("Trash purge requested by {} failed: {}", claims.admin_username, e);
+            HttpResponse::InternalServerError().json(ErrorResponse {
+                error: "Failed to purge trash".into(),
+            })
+        }
+    }
+}
+
+
+/// Consulte le journal d'audit (révélations de secrets, modifications...)
+pub async fn get_audit_log(
+    req: HttpRequest,
+    pool: web::Data<SqlitePool>,
+    query: web::Query<ListParams>,
+    crypto: web::Data<CryptoService>,
+) -> HttpResponse {
+    let claims = match req.extensions().get::<ClaimsAdmin>().cloned() {
+        Some(c) => c,
+        None => {
+            return HttpResponse::Unauthorized().json(ErrorResponse {
+                error: "Unauthorized: no valid admin token found".into(),
+            });
+        }
+    };
+
+    if claims.role != "admin" {
+        return HttpResponse::Forbidden().json(ErrorResponse {
+            error: "Access denied: insufficient permissions".into(),
+        });
+    }
+
+    let list = match ListQuery::from_params(&query, &db::AUDIT_LIST, crypto.get_ref()) {
+        Ok(list) => list,
+        Err(error) => return HttpResponse::BadRequest().json(ErrorResponse { error }),
+    };
+
+    match db::get_audit_log(pool.get_ref(), &list).await {
+        Ok(page) => HttpResponse::Ok().json(page),
+        Err(err) => HttpResponse::InternalServerError().json(ErrorResponse {
+            error: format!("Database error: {}", err),
+        }),
+    }
+}
+
+/// Vérifie le jeton et le rôle administrateur ; renvoie le nom de l'administrateur
+fn require_admin(req: &HttpRequest) -> Result<String, HttpResponse> {
+    let claims = req.extensions().get::<ClaimsAdmin>().cloned().ok_or_else(|| {
+        HttpResponse::Unauthorized().json(ErrorResponse {
+            error: "Unauthorized: no valid admin token found".into(),
+        })
+    })?;
+
+    if claims.role != "admin" {
+        return Err(HttpResponse::Forbidden().json(ErrorResponse {
+            error: "Access denied: insufficient permissions".into(),
+        }));
+    }
+
+    Ok(claims.admin_username)
+}
+
+/// Liste les modèles d'éléments
+pub async fn get_templates(
+    req: HttpRequest,
+    pool: web::Data<SqlitePool>,
+) -> HttpResponse {
+    if let Err(response) = require_admin(&req) {
+        return response;
+    }
+
+    match db::get_templates(pool.get_ref()).await {
+        Ok(templates) => HttpResponse::Ok().json(templates),
+        Err(e) => HttpResponse::InternalServerError().json(ErrorResponse {
+            error: format!("Database error: {}", e),
+        }),
+    }
+}
+
+/// Crée un modèle d'élément (champs, formats, champs secrets et obligatoires, motifs)
+pub async fn create_template(
+    req: HttpRequest,
+    pool: web::Data<SqlitePool>,
+    body: web::Json<ItemTemplateRequest>,
+) -> HttpResponse {
+    let admin = match require_admin(&req) {
+        Ok(admin) => admin,
+        Err(response) => return response,
+    };
+
+    if body.label.trim().is_empty() {
+        return HttpResponse::BadRequest().json(ErrorResponse {
+            error: "Label cannot be empty".into(),
+        });
+    }
+
+    match db::create_template(pool.get_ref(), &body.name, &body.label, &body.fields, &admin).await {
+        Ok(template) => {
+            log::info!("Admin {} created item template {}", admin, template.name);
+            HttpResponse::Created().json(template)
+        }
+        Err(sqlx::Error::Protocol(msg)) => HttpResponse::BadRequest().json(ErrorResponse { error: msg }),
+        Err(e) if e.to_string().contains("UNIQUE constraint failed") => HttpResponse::Conflict().json(ErrorResponse {
+            error: format!("Template '{}' already exists", body.name),
+        }),
+        Err(e) => HttpResponse::InternalServerError().json(ErrorResponse {
+            error: format!("Database error: {}", e),
+        }),
+    }
+}
+
+/// Remplace le libellé et les champs d'un modèle existant
+pub async fn update_template(
+    req: HttpRequest,
+    pool: web::Data<SqlitePool>,
+    body: web::Json<ItemTemplateRequest>,
+    crypto: web::Data<CryptoService>,
+) -> HttpResponse {
+    let admin = match require_admin(&req) {
+        Ok(admin) => admin,
+        Err(response) => return response,
+    };
+
+    if body.label.trim().is_empty() {
+        return HttpResponse::BadRequest().json(ErrorResponse {
+            error: "Label cannot be empty".into(),
+        });
+    }
+
+    match db::update_template(pool.get_ref(), &body.name, &body.label, &body.fields, crypto.get_ref()).await {
+        Ok(template) => {
+            log::info!("Admin {} updated item template {}", admin, template.name);
+            HttpResponse::Ok().json(template)
+        }
+        Err(sqlx::Error::Protocol(msg)) => HttpResponse::BadRequest().json(ErrorResponse { error: msg }),
+        Err(sqlx::Error::RowNotFound) => HttpResponse::NotFound().json(ErrorResponse {
+            error: format!("Template '{}' not found", body.name),
+        }),
+        Err(e) => HttpResponse::InternalServerError().json(ErrorResponse {
+            error: format!("Database error: {}", e),
+        }),
+    }
+}
+
+/// Supprime un modèle qui n'est plus utilisé par aucun élément (corbeille comprise)
+pub async fn delete_template(
+    req: HttpRequest,
+    pool: web::Data<SqlitePool>,
+    body: web::Json<DeleteTemplateRequest>,
+) -> HttpResponse {
+    let admin = match require_admin(&req) {
+        Ok(admin) => admin,
+        Err(response) => return response,
+    };
+
+    match db::count_template_items(pool.get_ref(), &body.name).await {
+        Ok(0) => {}
+        Ok(count) => {
+            return HttpResponse::Conflict().json(ErrorResponse {
+                error: format!("Template '{}' is still used by {} item(s)", body.name, count),
+            });
+        }
+        Err(e) => {
+            return HttpResponse::InternalServerError().json(ErrorResponse {
+                error: format!("Database error: {}", e),
+            });
+        }
+    }
+
+    match db::delete_template(pool.get_ref(), &body.name).await {
+        Ok(0) => HttpResponse::NotFound().json(ErrorResponse {
+            error: format!("Template '{}' not found", body.name),
+        }),
+        Ok(_) => {
+            log::info!("Admin {} deleted item template {}", admin, body.name);
+            HttpResponse::Ok().json(serde_json::json!({
+                "name": body.name,
+                "message": "Template deleted successfully"
+            }))
+        }
+        Err(e) => HttpResponse::InternalServerError().json(ErrorResponse {
+            error: format!("Database error: {}", e),
+        }),
+    }
+}
+
+/// Change le rôle d'un membre de n'importe quel groupe
+pub async fn set_group_role(
+    req: HttpRequest,
+    pool: web::Data<SqlitePool>,
+    body: web::Json<SetGroupRoleRequest>,
+) -> HttpResponse {
+    let admin = match require_admin(&req) {
+        Ok(admin) => admin,
+        Err(response) => return response,
+    };
+
+    group_role_response(pool.get_ref(), &body, &admin).await
+}
+
+/// Retire un membre de n'importe quel groupe
+pub async fn remove_group_member(
+    req: HttpRequest,
+    pool: web::Data<SqlitePool>,
+    body: web::Json<RemoveGroupMemberRequest>,
+) -> HttpResponse {
+    let admin = match require_admin(&req) {
+        Ok(admin) => admin,
+        Err(response) => return response,
+    };
+
+    group_member_removal_response(pool.get_ref(), &body, &admin).await
+}
+
+/// Liste les politiques d'âge maximal des secrets de groupe
+pub async fn get_group_policies(
+    req: HttpRequest,
+    pool: web::Data<SqlitePool>,
+) -> HttpResponse {
+    if let Err(response) = require_admin(&req) {
+        return response;
+    }
+
+    match db::get_group_policies(pool.get_ref()).await {
+        Ok(policies) => HttpResponse::Ok().json(policies),
+        Err(e) => HttpResponse::InternalServerError().json(ErrorResponse {
+            error: format!("Database error: {}", e),
+        }),
+    }
+}
+
+/// Fixe l'âge maximal des secrets d'un groupe, ou retire la politique si aucune valeur n'est donnée.
+/// Les éléments du groupe sont à renouveler au plus tard à cet âge, quel que soit leur propre intervalle
+pub async fn set_group_policy(
+    req: HttpRequest,
+    pool: web::Data<SqlitePool>,
+    body: web::Json<SetGroupPolicyRequest>,
+) -> HttpResponse {
+    let admin = match require_admin(&req) {
+        Ok(admin) => admin,
+        Err(response) => return response,
+    };
+
+    group_policy_response(pool.get_ref(), &body, &admin, true).await
+}
+
+/// Rattache un groupe à un groupe parent, ou en fait un groupe racine si aucun parent n'est donné.
+/// Les membres du parent accèdent aux éléments du groupe avec leur rôle dans le parent
+pub async fn set_group_parent(
+    req: HttpRequest,
+    pool: web::Data<SqlitePool>,
+    body: web::Json<SetGroupParentRequest>,
+) -> HttpResponse {
+    let admin = match require_admin(&req) {
+        Ok(admin) => admin,
+        Err(response) => return response,
+    };
+
+    group_parent_response(pool.get_ref(), &body, &admin).await
+}
+
+/// Historique de toutes les demandes d'accès aux groupes, filtrable par groupe et par statut
+pub async fn get_access_requests(
+    req: HttpRequest,
+    pool: web::Data<SqlitePool>,
+    params: web::Query<ListParams>,
+    query: web::Query<AccessRequestsQuery>,
+    crypto: web::Data<CryptoService>,
+) -> HttpResponse {
+    if let Err(response) = require_admin(&req) {
+        return response;
+    }
+
+    access_requests_response(pool.get_ref(), &params, &query, db::AccessRequestScope::All, crypto.get_ref()).await
+}
+
+/// Approuve ou refuse une demande d'accès, quel que soit le groupe
+pub async fn decide_access_request(
+    req: HttpRequest,
+    pool: web::Data<SqlitePool>,
+    notifier: web::Data<Notifier>,
+    body: web::Json<DecideAccessRequest>,
+) -> HttpResponse {
+    let admin = match require_admin(&req) {
+        Ok(admin) => admin,
+        Err(response) => return response,
+    };
+
+    access_decision_response(pool.get_ref(), notifier.get_ref(), &body, &admin).await
+}
+
+/// Historique de toutes les demandes d'accès d'urgence
+pub async fn get_emergency_requests(
+    req: HttpRequest,
+    pool: web::Data<SqlitePool>,
+    params: web::Query<ListParams>,
+    query: web::Query<EmergencyRequestsQuery>,
+    crypto: web::Data<CryptoService>,
+) -> HttpResponse {
+    if let Err(response) = require_admin(&req) {
+        return response;
+    }
+
+    emergency_requests_response(pool.get_ref(), &params, &query, db::EmergencyScope::All, crypto.get_ref()).await
+}
+
+/// Lève le délai d'attente d'une demande d'urgence : l'accès n'est accordé qu'une fois la levée
+/// approuvée par un second administrateur
+pub async fn override_emergency_request(
+    req: HttpRequest,
+    pool: web::Data<SqlitePool>,
+    config: web::Data<AppConfig>,
+    notifier: web::Data<Notifier>,
+    body: web::Json<DeleteRequest>,
+) -> HttpResponse {
+    let admin = match require_admin(&req) {
+        Ok(admin) => admin,
+        Err(response) => return response,
+    };
+
+    match db::override_emergency_request(pool.get_ref(), &body.id, &admin, config.emergency_access_hours).await {
+        Ok((request, granted)) => {
+            let message = if granted {
+                format!(
+                    "Administrators {} and {} overrode the waiting period: {} has emergency access to {} '{}' until {}",
+                    request.override_requested_by.as_deref().unwrap_or_default(),
+                    admin,
+                    request.requester,
+                    request.target_type,
+                    request.target_name,
+                    request.access_expires_at.as_deref().unwrap_or_default()
+                )
+            } else {
+                format!(
+                    "Administrator {} requested to override the waiting period of {} on {} '{}', a second administrator must approve",
+                    admin, request.requester, request.target_type, request.target_name
+                )
+            };
+            let event = if granted { "emergency_granted" } else { "emergency_override_requested" };
+            notify_emergency(pool.get_ref(), notifier.get_ref(), event, &request, message.clone()).await;
+
+            HttpResponse::Ok().json(EmergencyOverrideResponse { request, granted, message })
+        }
+        Err(sqlx::Error::RowNotFound) => HttpResponse::NotFound().json(ErrorResponse {
+            error: "Emergency request not found".into(),
+        }),
+        Err(sqlx::Error::Protocol(msg)) => HttpResponse::Conflict().json(ErrorResponse { error: msg }),
+        Err(e) => {
+            log::error!("Failed to override emergency request {}: {}", body.id, e);
+            HttpResponse::InternalServerError().json(ErrorResponse {
+                error: "Failed to override emergency request".into(),
+            })
+        }
+    }
+}
+
+/// Prolonge une adhésion temporaire ou la rend permanente
+pub async fn update_group_membership(
+    req: HttpRequest,
+    pool: web::Data<SqlitePool>,
+    notifier: web::Data<Notifier>,
+    body: web::Json<UpdateMembershipRequest>,
+) -> HttpResponse {
+    let admin = match require_admin(&req) {
+        Ok(admin) => admin,
+        Err(response) => return response,
+    };
+
+    membership_expiry_response(pool.get_ref(), notifier.get_ref(), &body, &admin).await
+}
+
+/// Adhésions temporaires arrivant à échéance dans les `within_days` prochains jours (7 par défaut),
+/// et celles déjà échues que la tâche d'expiration n'a pas encore retirées
+pub async fn get_expiring_memberships(
+    req: HttpRequest,
+    pool: web::Data<SqlitePool>,
+    query: web::Query<ExpiringMembershipsQuery>,
+) -> HttpResponse {
+    if let Err(response) = require_admin(&req) {
+        return response;
+    }
+
+    let within_days = query.within_days.unwrap_or(7);
+    if !(0..=365).contains(&within_days) {
+        return HttpResponse::BadRequest().json(ErrorResponse {
+            error: "within_days must be between 0 and 365".into(),
+        });
+    }
+
+    match db::get_expiring_memberships(pool.get_ref(), within_days).await {
+        Ok(memberships) => HttpResponse::Ok().json(memberships),
+        Err(e) => {
+            log::error!("Failed to list expiring memberships: {}", e);
+            HttpResponse::InternalServerError().json(ErrorResponse {
+                error: "Failed to retrieve expiring memberships".into(),
+            })
+        }
+    }
+}
+
+/// Clé des sauvegardes ; sans BACKUP_KEY les sauvegardes sont désactivées
+fn backup_key(config: &AppConfig) -> Result<&BackupKey, HttpResponse> {
+    config.backup_key.as_ref().ok_or_else(|| {
+        HttpResponse::ServiceUnavailable().json(ErrorResponse {
+            error: "Backups are disabled: BACKUP_KEY is not set".into(),
+        })
+    })
+}
+
+/// Liste les archives du répertoire des sauvegardes, de la plus récente à la plus ancienne
+pub async fn get_backups(
+    req: HttpRequest,
+    config: web::Data<AppConfig>,
+) -> HttpResponse {
+    if let Err(response) = require_admin(&req) {
+        return response;
+    }
+
+    match backup::list(config.get_ref()).await {
+        Ok(backups) => HttpResponse::Ok().json(backups),
+        Err(e) => HttpResponse::InternalServerError().json(ErrorResponse {
+            error: format!("Failed to list backups: {}", e),
+        }),
+    }
+}
+
+/// Sauvegarde immédiate : instantané cohérent de la base et des pièces jointes, chiffré avec la
+/// clé de sauvegarde, puis application de la rétention
+pub async fn create_backup(
+    req: HttpRequest,
+    pool: web::Data<SqlitePool>,
+    config: web::Data<AppConfig>,
+    crypto: web::Data<CryptoService>,
+) -> HttpResponse {
+    let admin = match require_admin(&req) {
+        Ok(admin) => admin,
+        Err(response) => return response,
+    };
+    let key = match backup_key(config.get_ref()) {
+        Ok(key) => key,
+        Err(response) => return response,
+    };
+
+    let Some(lock) = BackupLock::acquire() else {
+        return HttpResponse::Conflict().json(ErrorResponse {
+            error: "A backup is already running".into(),
+        });
+    };
+
+    match backup::create(&lock, pool.get_ref(), config.get_ref(), crypto.get_ref(), key, &admin).await {
+        Ok(file) => {
+            log::info!("Admin {} created backup {} ({} bytes)", admin, file.name, file.size);
+            if let Err(e) = db::record_audit(pool.get_ref(), &admin, "backup", Some("backup"), Some(&file.name), "").await {
+                log::error!("Failed to audit backup {}: {}", file.name, e);
+            }
+            HttpResponse::Created().json(file)
+        }
+        Err(e) => {
+            log::error!("Backup failed: {}", e);
+            HttpResponse::InternalServerError().json(ErrorResponse {
+                error: format!("Backup failed: {}", e),
+            })
+        }
+    }
+}
+
+/// Déchiffre et vérifie entièrement une archive (empreintes, clé maître, intégrité de la base)
+/// sans rien restaurer
+pub async fn verify_backup(
+    req: HttpRequest,
+    body: web::Json<BackupRequest>,
+    config: web::Data<AppConfig>,
+    crypto: web::Data<CryptoService>,
+) -> HttpResponse {
+    if let Err(response) = require_admin(&req) {
+        return response;
+    }
+    let key = match backup_key(config.get_ref()) {
+        Ok(key) => key,
+        Err(response) => return response,
+    };
+
+    let path = match backup::archive_path(config.get_ref(), &body.name) {
+        Some(path) if path.is_file() => path,
+        _ => {
+            return HttpResponse::NotFound().json(ErrorResponse {
+                error: format!("Backup '{}' not found", body.name),
+            });
+        }
+    };
+
+    match backup::verify(config.get_ref(), crypto.get_ref(), key, &path).await {
+        Ok(report) => HttpResponse::Ok().json(report),
+        Err(error) => {
+            log::warn!("Backup {} failed verification: {}", body.name, error);
+            HttpResponse::UnprocessableEntity().json(ErrorResponse { error })
+        }
+    }
+}
+
+/// Télécharge une archive telle quelle (chiffrée), pour la conserver hors du volume de données
+pub async fn download_backup(
+    req: HttpRequest,
+    pool: web::Data<SqlitePool>,
+    body: web::Json<BackupRequest>,
+    config: web::Data<AppConfig>,
+) -> HttpResponse {
+    let admin = match require_admin(&req) {
+        Ok(admin) => admin,
+        Err(response) => return response,
+    };
+
+    let stream = match backup::archive_path(config.get_ref(), &body.name) {
+        Some(path) if path.is_file() => backup::open(&path).await,
+        _ => Err(std::io::ErrorKind::NotFound.into()),
+    };
+
+    match stream {
+        Ok(stream) => {
+            if let Err(e) = db::record_audit(pool.get_ref(), &admin, "download", Some("backup"), Some(&body.name), "").await {
+                log::error!("Failed to audit download of backup {}: {}", body.name, e);
+            }
+            HttpResponse::Ok()
+                .content_type("application/octet-stream")
+                .insert_header(ContentDisposition::attachment(body.name.clone()))
+                .streaming(stream)
+        }
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => HttpResponse::NotFound().json(ErrorResponse {
+            error: format!("Backup '{}' not found", body.name),
+        }),
+        Err(e) => HttpResponse::InternalServerError().json(ErrorResponse {
+            error: format!("Failed to read backup: {}", e),
+        }),
+    }
+}
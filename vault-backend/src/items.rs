@@ -0,0 +1,478 @@
+use chrono::NaiveDate;
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+
+/// Contenu d'un élément : champs propres à son type, chiffrés ensemble en JSON
+pub type Fields = Map<String, Value>;
+
+/// Contrôle de format d'un champ ; l'erreur décrit la valeur attendue
+pub type FieldCheck = fn(&str) -> Result<(), String>;
+
+/// Description d'un champ d'un type d'élément
+#[derive(Serialize)]
+pub struct FieldSpec {
+    pub name: &'static str,
+    pub required: bool,
+    // Un champ secret n'apparaît jamais dans les listes, seulement via /reveal
+    pub secret: bool,
+    #[serde(skip)]
+    pub check: Option<FieldCheck>,
+}
+
+/// Type d'élément du coffre
+#[derive(Serialize)]
+pub struct ItemKind {
+    pub name: &'static str,
+    pub label: &'static str,
+    pub fields: &'static [FieldSpec],
+    // Champ renvoyé comme `secret` par les endpoints historiques (mot de passe, clé API…)
+    pub primary_secret: &'static str,
+}
+
+const fn field(name: &'static str, required: bool, secret: bool) -> FieldSpec {
+    FieldSpec { name, required, secret, check: None }
+}
+
+const fn checked(name: &'static str, required: bool, secret: bool, check: FieldCheck) -> FieldSpec {
+    FieldSpec { name, required, secret, check: Some(check) }
+}
+
+pub const KINDS: &[ItemKind] = &[
+    ItemKind {
+        name: "account",
+        label: "Account",
+        fields: &[field("user_account", true, false), field("password", true, true)],
+        primary_secret: "password",
+    },
+    ItemKind {
+        name: "api_key",
+        label: "API key",
+        fields: &[field("api_key", true, true)],
+        primary_secret: "api_key",
+    },
+    ItemKind {
+        name: "note",
+        label: "Secure note",
+        fields: &[field("content", true, true)],
+        primary_secret: "content",
+    },
+    ItemKind {
+        name: "ssh_key",
+        label: "SSH key pair",
+        fields: &[
+            checked("private_key", true, true, check_private_key),
+            checked("public_key", false, false, check_ssh_public_key),
+            field("passphrase", false, true),
+        ],
+        primary_secret: "private_key",
+    },
+    ItemKind {
+        name: "certificate",
+        label: "TLS certificate",
+        fields: &[
+            checked("certificate", true, false, check_certificate),
+            checked("private_key", false, true, check_private_key),
+            checked("chain", false, false, check_certificate),
+        ],
+        primary_secret: "private_key",
+    },
+    ItemKind {
+        name: "card",
+        label: "Payment card",
+        fields: &[
+            field("cardholder", true, false),
+            checked("number", true, true, check_card_number),
+            checked("expiry", true, false, check_card_expiry),
+            checked("cvv", false, true, check_cvv),
+            field("brand", false, false),
+        ],
+        primary_secret: "number",
+    },
+    ItemKind {
+        name: "identity",
+        label: "Identity",
+        fields: &[
+            field("full_name", true, false),
+            checked("email", false, false, check_email),
+            field("phone", false, false),
+            field("address", false, false),
+            checked("birth_date", false, false, check_date),
+            field("document_type", false, false),
+            field("document_number", false, true),
+        ],
+        primary_secret: "document_number",
+    },
+];
+
+// Longueur maximale d'un nom de modèle ou de champ
+const MAX_NAME_LEN: usize = 40;
+
+/// Format de valeur d'un champ de modèle ; toutes les valeurs restent stockées en texte
+#[derive(Serialize, Deserialize, Clone, Copy, Default, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum FieldType {
+    #[default]
+    Text,
+    Multiline,
+    Number,
+    Boolean,
+    Email,
+    Url,
+    Date,
+}
+
+/// Champ d'un modèle d'élément défini par un administrateur
+#[derive(Serialize, Deserialize, Clone)]
+pub struct TemplateField {
+    pub name: String,
+    #[serde(rename = "type", default)]
+    pub field_type: FieldType,
+    #[serde(default)]
+    pub required: bool,
+    #[serde(default)]
+    pub secret: bool,
+    // Expression régulière que doit respecter la valeur entière
+    #[serde(default)]
+    pub pattern: Option<String>,
+}
+
+/// Modèle d'élément : type personnalisé (chaîne de connexion, client OAuth…) créé par un administrateur
+pub struct Template {
+    pub name: String,
+    pub fields: Vec<TemplateField>,
+}
+
+/// Schéma du contenu d'un élément : type intégré ou modèle
+pub enum Schema {
+    Kind(&'static ItemKind),
+    Template(Template),
+}
+
+impl Schema {
+    pub fn name(&self) -> &str {
+        match self {
+            Schema::Kind(kind) => kind.name,
+            Schema::Template(template) => &template.name,
+        }
+    }
+
+    /// Champ renvoyé comme `secret` : pour un modèle, son premier champ secret
+    pub fn primary_secret(&self) -> &str {
+        match self {
+            Schema::Kind(kind) => kind.primary_secret,
+            Schema::Template(template) => template.fields
+                .iter()
+                .find(|f| f.secret)
+                .map(|f| f.name.as_str())
+                .unwrap_or(""),
+        }
+    }
+
+    fn has_field(&self, name: &str) -> bool {
+        match self {
+            Schema::Kind(kind) => kind.fields.iter().any(|f| f.name == name),
+            Schema::Template(template) => template.fields.iter().any(|f| f.name == name),
+        }
+    }
+
+    fn is_public(&self, name: &str) -> bool {
+        match self {
+            Schema::Kind(kind) => kind.fields.iter().any(|f| f.name == name && !f.secret),
+            Schema::Template(template) => template.fields.iter().any(|f| f.name == name && !f.secret),
+        }
+    }
+
+    fn is_secret(&self, name: &str) -> bool {
+        self.has_field(name) && !self.is_public(name)
+    }
+
+    fn required_fields(&self) -> Vec<&str> {
+        match self {
+            Schema::Kind(kind) => kind.fields.iter().filter(|f| f.required).map(|f| f.name).collect(),
+            Schema::Template(template) => template.fields
+                .iter()
+                .filter(|f| f.required)
+                .map(|f| f.name.as_str())
+                .collect(),
+        }
+    }
+
+    /// Contrôle le format d'une valeur non vide d'un champ connu
+    fn check(&self, name: &str, value: &str) -> Result<(), String> {
+        match self {
+            Schema::Kind(kind) => match kind.fields.iter().find(|f| f.name == name).and_then(|f| f.check) {
+                Some(check) => check(value),
+                None => Ok(()),
+            },
+            Schema::Template(template) => match template.fields.iter().find(|f| f.name == name) {
+                Some(field) => check_template_value(field, value),
+                None => Ok(()),
+            },
+        }
+    }
+}
+
+pub fn kind(name: &str) -> Option<&'static ItemKind> {
+    KINDS.iter().find(|k| k.name == name)
+}
+
+/// Décompose un type d'élément intégré (`account`, `note_group`…) en type de base et portée (groupe ou non)
+pub fn parse_item_type(item_type: &str) -> Option<(&'static ItemKind, bool)> {
+    let (name, in_group) = split_item_type(item_type);
+    kind(name).map(|k| (k, in_group))
+}
+
+/// Sépare un type d'élément stocké en nom de type (intégré ou modèle) et portée
+pub fn split_item_type(item_type: &str) -> (&str, bool) {
+    match item_type.strip_suffix("_group") {
+        Some(name) => (name, true),
+        None => (item_type, false),
+    }
+}
+
+/// Type d'élément stocké pour un nom de type et une portée
+pub fn item_type(name: &str, in_group: bool) -> String {
+    if in_group {
+        format!("{}_group", name)
+    } else {
+        name.to_string()
+    }
+}
+
+/// Valide un contenu complet : champs connus, textuels, obligatoires renseignés et formats respectés.
+/// Les champs vides sont retirés
+pub fn validate(schema: &Schema, fields: &Fields) -> Result<Fields, String> {
+    let mut valid = Fields::new();
+
+    for (name, value) in fields {
+        if !schema.has_field(name) {
+            return Err(format!("Unknown field '{}' for type '{}'", name, schema.name()));
+        }
+
+        let text = match value {
+            Value::String(s) => s,
+            Value::Null => continue,
+            _ => return Err(format!("Field '{}' must be a string", name)),
+        };
+
+        if text.trim().is_empty() {
+            continue;
+        }
+
+        schema.check(name, text).map_err(|e| format!("Invalid '{}': {}", name, e))?;
+
+        valid.insert(name.clone(), Value::String(text.clone()));
+    }
+
+    if let Some(missing) = schema.required_fields().into_iter().find(|f| !valid.contains_key(*f)) {
+        return Err(format!("Field '{}' is required for type '{}'", missing, schema.name()));
+    }
+
+    Ok(valid)
+}
+
+/// Applique une modification partielle (`null` efface un champ) puis valide le résultat.
+/// Les champs retirés du schéma depuis l'enregistrement sont abandonnés
+pub fn merge(schema: &Schema, current: &Fields, changes: &Fields) -> Result<Fields, String> {
+    let mut merged: Fields = current
+        .iter()
+        .filter(|(name, _)| schema.has_field(name))
+        .map(|(name, value)| (name.clone(), value.clone()))
+        .collect();
+    for (name, value) in changes {
+        merged.insert(name.clone(), value.clone());
+    }
+    validate(schema, &merged)
+}
+
+/// Champs pouvant figurer dans une liste (sans les secrets)
+pub fn public_fields(schema: &Schema, fields: &Fields) -> Fields {
+    fields
+        .iter()
+        .filter(|(name, _)| schema.is_public(name))
+        .map(|(name, value)| (name.clone(), value.clone()))
+        .collect()
+}
+
+/// Indique si un champ secret diffère entre deux contenus (rotation du secret)
+pub fn secrets_changed(schema: &Schema, before: &Fields, after: &Fields) -> bool {
+    before
+        .keys()
+        .chain(after.keys())
+        .filter(|name| schema.is_secret(name))
+        .any(|name| before.get(name) != after.get(name))
+}
+
+fn valid_name(name: &str) -> bool {
+    let mut chars = name.chars();
+    matches!(chars.next(), Some(c) if c.is_ascii_lowercase())
+        && chars.all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '_')
+        && name.len() <= MAX_NAME_LEN
+}
+
+/// Vérifie la définition d'un modèle : nom libre, champs uniques et bien nommés, motifs compilables
+pub fn check_template(name: &str, fields: &[TemplateField]) -> Result<(), String> {
+    if !valid_name(name) || name.len() < 2 {
+        return Err(format!(
+            "Template name must be 2 to {} lowercase letters, digits or underscores, starting with a letter",
+            MAX_NAME_LEN
+        ));
+    }
+    if name.ends_with("_group") {
+        return Err("Template name cannot end with '_group'".into());
+    }
+    if kind(name).is_some() {
+        return Err(format!("'{}' is a built-in item type", name));
+    }
+    if fields.is_empty() {
+        return Err("A template needs at least one field".into());
+    }
+
+    for (i, field) in fields.iter().enumerate() {
+        if !valid_name(&field.name) {
+            return Err(format!("Invalid field name '{}'", field.name));
+        }
+        if fields[..i].iter().any(|f| f.name == field.name) {
+            return Err(format!("Duplicate field '{}'", field.name));
+        }
+        if let Some(pattern) = &field.pattern {
+            Regex::new(pattern).map_err(|e| format!("Invalid pattern for '{}': {}", field.name, e))?;
+        }
+    }
+
+    Ok(())
+}
+
+/// Contrôle une valeur selon le format et le motif d'un champ de modèle
+fn check_template_value(field: &TemplateField, value: &str) -> Result<(), String> {
+    match field.field_type {
+        FieldType::Text | FieldType::Multiline => Ok(()),
+        FieldType::Number => value.trim().parse::<f64>()
+            .map(|_| ())
+            .map_err(|_| "expected a number".to_string()),
+        FieldType::Boolean => match value {
+            "true" | "false" => Ok(()),
+            _ => Err("expected 'true' or 'false'".into()),
+        },
+        FieldType::Email => check_email(value),
+        FieldType::Url => check_url(value),
+        FieldType::Date => check_date(value),
+    }?;
+
+    if let Some(pattern) = &field.pattern {
+        // Le motif porte sur la valeur entière
+        let regex = Regex::new(&format!("^(?:{})$", pattern))
+            .map_err(|e| format!("invalid pattern: {}", e))?;
+        if !regex.is_match(value) {
+            return Err(format!("does not match pattern '{}'", pattern));
+        }
+    }
+
+    Ok(())
+}
+
+/// Construit un contenu à partir de paires (champ, valeur)
+pub fn fields(pairs: &[(&str, &str)]) -> Fields {
+    pairs
+        .iter()
+        .map(|(name, value)| (name.to_string(), Value::String(value.to_string())))
+        .collect()
+}
+
+/// Valeur textuelle d'un champ ("" si absent)
+pub fn text<'a>(fields: &'a Fields, name: &str) -> &'a str {
+    fields.get(name).and_then(Value::as_str).unwrap_or("")
+}
+
+fn check_private_key(value: &str) -> Result<(), String> {
+    let value = value.trim();
+    if value.starts_with("-----BEGIN") && value.contains("PRIVATE KEY-----") && value.contains("-----END") {
+        Ok(())
+    } else {
+        Err("expected a PEM or OpenSSH private key".into())
+    }
+}
+
+fn check_ssh_public_key(value: &str) -> Result<(), String> {
+    let mut parts = value.split_whitespace();
+    match (parts.next(), parts.next()) {
+        (Some(algo), Some(_)) if algo.starts_with("ssh-") || algo.starts_with("ecdsa-") || algo.starts_with("sk-") => Ok(()),
+        _ => Err("expected an OpenSSH public key (e.g. 'ssh-ed25519 AAAA...')".into()),
+    }
+}
+
+fn check_certificate(value: &str) -> Result<(), String> {
+    let value = value.trim();
+    if value.starts_with("-----BEGIN CERTIFICATE-----") && value.ends_with("-----END CERTIFICATE-----") {
+        Ok(())
+    } else {
+        Err("expected a PEM certificate".into())
+    }
+}
+
+fn check_card_number(value: &str) -> Result<(), String> {
+    let digits: Vec<u32> = value
+        .chars()
+        .filter(|c| *c != ' ' && *c != '-')
+        .map(|c| c.to_digit(10).ok_or_else(|| "only digits, spaces and dashes are allowed".to_string()))
+        .collect::<Result<_, _>>()?;
+
+    if !(12..=19).contains(&digits.len()) {
+        return Err("expected 12 to 19 digits".into());
+    }
+
+    // Clé de Luhn
+    let sum: u32 = digits
+        .iter()
+        .rev()
+        .enumerate()
+        .map(|(i, &d)| if i % 2 == 1 { if d * 2 > 9 { d * 2 - 9 } else { d * 2 } } else { d })
+        .sum();
+
+    if sum.is_multiple_of(10) {
+        Ok(())
+    } else {
+        Err("checksum does not match".into())
+    }
+}
+
+fn check_card_expiry(value: &str) -> Result<(), String> {
+    let (month, year) = value.trim().split_once('/').ok_or("expected MM/YY or MM/YYYY")?;
+    let month: u32 = month.parse().map_err(|_| "invalid month")?;
+    let valid_year = matches!(year.len(), 2 | 4) && year.chars().all(|c| c.is_ascii_digit());
+
+    if (1..=12).contains(&month) && valid_year {
+        Ok(())
+    } else {
+        Err("expected MM/YY or MM/YYYY".into())
+    }
+}
+
+fn check_cvv(value: &str) -> Result<(), String> {
+    if matches!(value.len(), 3 | 4) && value.chars().all(|c| c.is_ascii_digit()) {
+        Ok(())
+    } else {
+        Err("expected 3 or 4 digits".into())
+    }
+}
+
+fn check_email(value: &str) -> Result<(), String> {
+    match value.split_once('@') {
+        Some((local, domain)) if !local.is_empty() && domain.contains('.') => Ok(()),
+        _ => Err("expected an email address".into()),
+    }
+}
+
+fn check_url(value: &str) -> Result<(), String> {
+    match value.split_once("://") {
+        Some((scheme, rest)) if !scheme.is_empty() && !rest.is_empty() => Ok(()),
+        _ => Err("expected a URL with a scheme (e.g. 'https://...')".into()),
+    }
+}
+
+fn check_date(value: &str) -> Result<(), String> {
+    NaiveDate::parse_from_str(value, "%Y-%m-%d")
+        .map(|_| ())
+        .map_err(|_| "expected a date formatted as YYYY-MM-DD".into())
+}
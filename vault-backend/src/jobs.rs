@@ -0,0 +1,221 @@
+use std::time::Duration as StdDuration;
+use chrono::{Duration, Utc};
+use sqlx::SqlitePool;
+use crate::config::AppConfig;
+use crate::attachments;
+use crate::backup::{self, BackupLock};
+use crate::crypto::CryptoService;
+use crate::db;
+use crate::handlers::notify_emergency;
+use crate::notify::{Notification, Notifier};
+use crate::replica::{self, Replicator};
+
+/// Lance la purge périodique des éléments restés trop longtemps dans la corbeille,
+/// suivie du nettoyage des pièces jointes devenues orphelines et des liens expirés
+pub fn spawn_trash_purge(pool: SqlitePool, config: AppConfig) {
+    tokio::spawn(async move {
+        let mut interval = tokio::time::interval(StdDuration::from_secs(config.trash_purge_interval_secs));
+
+        loop {
+            interval.tick().await;
+
+            let cutoff = (Utc::now() - Duration::days(config.trash_retention_days)).to_rfc3339();
+            match db::purge_trash(&pool, Some(&cutoff)).await {
+                Ok(0) => {}
+                Ok(purged) => log::info!("🗑️  Purged {} item(s) deleted before {}", purged, cutoff),
+                Err(e) => log::error!("Trash purge failed: {}", e),
+            }
+
+            match attachments::sweep_orphans(&pool, &config.attachments_dir).await {
+                Ok(0) => {}
+                Ok(removed) => log::info!("🗑️  Removed {} orphaned attachment file(s)", removed),
+                Err(e) => log::error!("Attachment cleanup failed: {}", e),
+            }
+
+            match db::purge_expired_secret_links(&pool).await {
+                Ok(0) => {}
+                Ok(purged) => log::info!("🗑️  Purged {} expired secret link(s)", purged),
+                Err(e) => log::error!("Secret link purge failed: {}", e),
+            }
+        }
+    });
+}
+
+/// Lance le retrait des adhésions temporaires arrivées à échéance ; chaque membre retiré est prévenu
+pub fn spawn_membership_expiry(pool: SqlitePool, config: AppConfig, notifier: Notifier) {
+    tokio::spawn(async move {
+        let mut interval = tokio::time::interval(StdDuration::from_secs(config.membership_expiry_interval_secs));
+
+        loop {
+            interval.tick().await;
+
+            match db::expire_memberships(&pool).await {
+                Ok(expired) => {
+                    for membership in expired {
+                        log::info!("⏳ Membership of {} in group {} expired", membership.username, membership.group_name);
+                        notifier.send(Notification::new(
+                            "membership_expired",
+                            vec![membership.username],
+                            &membership.group_name,
+                            format!("Your {} access to group '{}' has expired", membership.role, membership.group_name),
+                        ));
+                    }
+                }
+                Err(e) => log::error!("Membership expiry failed: {}", e),
+            }
+        }
+    });
+}
+
+/// Lance la clôture des emprunts d'identifiants échus ; le titulaire est prévenu que le secret
+/// est à renouveler
+pub fn spawn_lease_expiry(pool: SqlitePool, config: AppConfig, notifier: Notifier) {
+    tokio::spawn(async move {
+        let mut interval = tokio::time::interval(StdDuration::from_secs(config.lease_expiry_interval_secs));
+
+        loop {
+            interval.tick().await;
+
+            match db::expire_leases(&pool).await {
+                Ok(expired) => {
+                    for lease in expired {
+                        log::info!("⏳ Checkout of {} {} by {} expired", lease.item_type, lease.item_id, lease.username);
+                        notifier.send(Notification::new(
+                            "lease_expired",
+                            vec![lease.username],
+                            &lease.group_name,
+                            format!(
+                                "Your checkout of {} {} expired without check-in, the secret is flagged for rotation",
+                                lease.item_type, lease.item_id
+                            ),
+                        ));
+                    }
+                }
+                Err(e) => log::error!("Lease expiry failed: {}", e),
+            }
+        }
+    });
+}
+
+/// Lance l'attribution automatique des accès d'urgence dont le délai d'attente est écoulé, et la
+/// clôture des accès arrivés à échéance
+pub fn spawn_emergency_access(pool: SqlitePool, config: AppConfig, notifier: Notifier) {
+    tokio::spawn(async move {
+        let mut interval = tokio::time::interval(StdDuration::from_secs(config.emergency_check_interval_secs));
+
+        loop {
+            interval.tick().await;
+
+            match db::process_emergency_requests(&pool, config.emergency_access_hours).await {
+                Ok((granted, expired)) => {
+                    for request in granted {
+                        let message = format!(
+                            "Waiting period elapsed without denial: {} has emergency access to {} '{}' until {}",
+                            request.requester,
+                            request.target_type,
+                            request.target_name,
+                            request.access_expires_at.as_deref().unwrap_or_default()
+                        );
+                        notify_emergency(&pool, &notifier, "emergency_granted", &request, message).await;
+                    }
+                    for request in expired {
+                        let message = format!(
+                            "Emergency access of {} to {} '{}' has expired",
+                            request.requester, request.target_type, request.target_name
+                        );
+                        notify_emergency(&pool, &notifier, "emergency_expired", &request, message).await;
+                    }
+                }
+                Err(e) => log::error!("Emergency access processing failed: {}", e),
+            }
+        }
+    });
+}
+
+/// Lance les sauvegardes planifiées ; la rétention est appliquée après chaque archive
+pub fn spawn_backups(pool: SqlitePool, config: AppConfig, crypto: CryptoService) {
+    let Some(key) = config.backup_key.clone() else {
+        log::warn!("⚠️  BACKUP_KEY not set, backups are disabled");
+        return;
+    };
+    if config.backup_interval_hours == 0 {
+        return;
+    }
+
+    tokio::spawn(async move {
+        let period = StdDuration::from_secs(config.backup_interval_hours * 3600);
+        let mut interval = tokio::time::interval_at(tokio::time::Instant::now() + period, period);
+
+        loop {
+            interval.tick().await;
+
+            let Some(lock) = BackupLock::acquire() else {
+                log::warn!("Scheduled backup skipped: another backup is running");
+                continue;
+            };
+
+            match backup::create(&lock, &pool, &config, &crypto, &key, "scheduler").await {
+                Ok(file) => {
+                    log::info!("💾 Scheduled backup {} created ({} bytes)", file.name, file.size);
+                    if let Err(e) = db::record_audit(&pool, "scheduler", "backup", Some("backup"), Some(&file.name), "").await {
+                        log::error!("Failed to audit backup {}: {}", file.name, e);
+                    }
+                }
+                Err(e) => log::error!("Scheduled backup failed: {}", e),
+            }
+        }
+    });
+}
+
+/// Lance la réplication continue vers REPLICA_URL. Après un échec, les cycles s'espacent
+/// (jusqu'à une minute) et l'erreur n'est journalisée qu'à son premier occurrence
+pub fn spawn_replication(config: AppConfig, crypto: CryptoService) {
+    let Some(key) = config.backup_key.clone() else {
+        return;
+    };
+    match replica::Store::from_config(&config) {
+        Ok(Some(_)) => {}
+        Ok(None) => return,
+        Err(e) => {
+            // Normalement écarté au démarrage par main
+            log::error!("Invalid replica configuration, replication disabled: {}", e);
+            return;
+        }
+    }
+
+    tokio::spawn(async move {
+        let period = StdDuration::from_secs(config.replica_sync_interval_secs);
+        let mut replicator = loop {
+            match Replicator::new(&config, &crypto, &key).await {
+                Ok(replicator) => break replicator,
+                Err(e) => {
+                    log::error!("Replication cannot start: {}", e);
+                    tokio::time::sleep(StdDuration::from_secs(60)).await;
+                }
+            }
+        };
+        log::info!("🔁 Replicating the database to {}", config.replica_url.as_deref().unwrap_or_default());
+
+        let mut last_error: Option<String> = None;
+        let mut delay = period;
+        loop {
+            tokio::time::sleep(delay).await;
+
+            match replicator.sync().await {
+                Ok(()) => {
+                    if last_error.take().is_some() {
+                        log::info!("🔁 Replication resumed");
+                    }
+                    delay = period;
+                }
+                Err(e) => {
+                    if last_error.as_ref() != Some(&e) {
+                        log::error!("Replication failed: {}", e);
+                    }
+                    last_error = Some(e);
+                    delay = (delay * 2).min(StdDuration::from_secs(60));
+                }
+            }
+        }
+    });
+}
@@ -0,0 +1,18 @@
+use rand::Rng;
+
+// Durée d'un emprunt d'identifiant de groupe (minutes)
+pub const DEFAULT_LEASE_MINUTES: i64 = 60;
+pub const MAX_LEASE_MINUTES: i64 = 24 * 60;
+pub const MAX_REASON_LEN: usize = 500;
+
+// Mots de passe générés au retour d'un emprunt : ~150 bits d'entropie, sans caractères ambigus
+const GENERATED_PASSWORD_LEN: usize = 24;
+const PASSWORD_ALPHABET: &[u8] = b"ABCDEFGHJKLMNPQRSTUVWXYZabcdefghijkmnopqrstuvwxyz23456789-_.!@#%+=";
+
+/// Nouveau mot de passe aléatoire, à reporter sur le système cible par le titulaire de l'emprunt
+pub fn generate_password() -> String {
+    let mut rng = rand::thread_rng();
+    (0..GENERATED_PASSWORD_LEN)
+        .map(|_| PASSWORD_ALPHABET[rng.gen_range(0..PASSWORD_ALPHABET.len())] as char)
+        .collect()
+}
@@ -0,0 +1,98 @@
+use argon2::Params;
+use chrono::Duration;
+use base64::{Engine as _, engine::general_purpose};
+use rand::RngCore;
+use crate::crypto::CryptoService;
+
+// Bornes des liens à usage unique : nombre de consultations et durée de vie (heures)
+pub const MAX_VIEWS: i64 = 100;
+pub const MAX_LIFETIME_HOURS: i64 = 24 * 30;
+// Essais de clé ou de phrase secrète erronés avant suspension des ouvertures du lien. Le lien
+// n'est jamais détruit par des échecs : quiconque en connaît l'adresse pourrait sinon le faire
+pub const MAX_FAILED_ATTEMPTS: i64 = 5;
+const LOCKOUT_BASE_MINUTES: i64 = 15;
+const LOCKOUT_MAX_MINUTES: i64 = 24 * 60;
+
+// Coût Argon2id modéré : la clé du lien porte déjà 256 bits d'entropie, la dérivation ne
+// protège que la phrase secrète facultative
+const KDF_MEMORY_KIB: u32 = 19 * 1024;
+const KDF_ITERATIONS: u32 = 2;
+
+/// Durée de suspension après `failures` essais erronés : aucune avant `MAX_FAILED_ATTEMPTS`,
+/// puis doublée à chaque nouvelle série, dans la limite d'une journée
+pub fn lockout(failures: i64) -> Option<Duration> {
+    if failures <= 0 || failures % MAX_FAILED_ATTEMPTS != 0 {
+        return None;
+    }
+    let doublings = (failures / MAX_FAILED_ATTEMPTS - 1).min(10) as u32;
+    let minutes = (LOCKOUT_BASE_MINUTES << doublings).min(LOCKOUT_MAX_MINUTES);
+    Some(Duration::minutes(minutes))
+}
+
+/// Clé d'un lien, générée à la création et transmise uniquement dans le fragment de l'URL :
+/// le serveur ne la conserve pas et ne peut donc pas relire le secret seul
+#[derive(Clone)]
+pub struct LinkKey([u8; 32]);
+
+impl LinkKey {
+    pub fn generate() -> Self {
+        let mut key = [0u8; 32];
+        rand::thread_rng().fill_bytes(&mut key);
+        Self(key)
+    }
+
+    pub fn encode(&self) -> String {
+        general_purpose::URL_SAFE_NO_PAD.encode(self.0)
+    }
+
+    pub fn decode(value: &str) -> Option<Self> {
+        let bytes = general_purpose::URL_SAFE_NO_PAD.decode(value.trim()).ok()?;
+        bytes.try_into().ok().map(Self)
+    }
+}
+
+/// Sel aléatoire de la dérivation, conservé avec le lien (hexadécimal)
+pub fn generate_salt() -> String {
+    let mut salt = [0u8; 16];
+    rand::thread_rng().fill_bytes(&mut salt);
+    salt.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+/// Chiffreur du lien : clé dérivée de la clé du lien et de la phrase secrète éventuelle
+fn cipher(key: &LinkKey, passphrase: Option<&str>, salt: &str) -> Result<CryptoService, String> {
+    let params = Params::new(KDF_MEMORY_KIB, KDF_ITERATIONS, 1, Some(32))
+        .map_err(|e| format!("Invalid key derivation parameters: {}", e))?;
+    let secret = format!("{}\n{}", key.encode(), passphrase.unwrap_or(""));
+
+    CryptoService::with_salt(&secret, salt.as_bytes(), params)
+}
+
+/// Chiffre le contenu d'un lien ; l'identifiant du lien est authentifié avec le contenu
+pub fn seal(id: &str, key: &LinkKey, passphrase: Option<&str>, salt: &str, content: &[u8]) -> Result<String, String> {
+    let sealed = cipher(key, passphrase, salt)?.encrypt_chunk(id, 0, true, content)?;
+    Ok(general_purpose::STANDARD.encode(sealed))
+}
+
+/// Déchiffre le contenu d'un lien ; échoue si la clé ou la phrase secrète est erronée
+pub fn open(id: &str, key: &LinkKey, passphrase: Option<&str>, salt: &str, sealed: &str) -> Result<Vec<u8>, String> {
+    let sealed = general_purpose::STANDARD
+        .decode(sealed)
+        .map_err(|e| format!("Invalid link content: {}", e))?;
+
+    cipher(key, passphrase, salt)?.decrypt_chunk(id, 0, true, &sealed)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn lockout_doubles_after_each_series_of_failures() {
+        assert_eq!(lockout(0), None);
+        assert_eq!(lockout(MAX_FAILED_ATTEMPTS - 1), None);
+        assert_eq!(lockout(MAX_FAILED_ATTEMPTS), Some(Duration::minutes(LOCKOUT_BASE_MINUTES)));
+        assert_eq!(lockout(MAX_FAILED_ATTEMPTS + 1), None);
+        assert_eq!(lockout(2 * MAX_FAILED_ATTEMPTS), Some(Duration::minutes(2 * LOCKOUT_BASE_MINUTES)));
+        assert_eq!(lockout(100 * MAX_FAILED_ATTEMPTS), Some(Duration::minutes(LOCKOUT_MAX_MINUTES)));
+    }
+}
@@ -1,6 +1,4 @@
 use actix_web::{web, App, HttpServer, middleware};
-use sqlx::sqlite::{SqlitePool, SqliteConnectOptions};
-use std::str::FromStr;
 use actix_cors::Cors;
 use actix_web::http::header;
 
@@ -13,18 +11,54 @@ mod auth_admin;
 mod delete_user;
 mod crypto;
 mod handlers_admin;
+mod config;
+mod expiry;
+mod attachments;
+mod multipart;
+mod backup;
+mod cli;
+mod encryption;
+mod s3;
+mod replica;
+mod jobs;
+mod pagination;
+mod search;
+mod items;
+mod links;
+mod notify;
+mod leases;
+
+#[cfg(test)]
+mod tests;
 
 use auth::{register, login, verify_token};
 use auth_admin::{register_admin, login_admin, verify_token_admin};
 use handlers::{add_api_key, delete_api_key, add_account, delete_account, get_account,
      get_api_key, get_me, get_groups_by_name, add_api_key_in_group, add_account_in_group,
-     get_account_in_group, get_api_key_in_group, get_api_key_by_title
+     get_account_in_group, get_api_key_in_group, get_api_key_by_title, health_check,
+     update_account, update_api_key, update_account_in_group, update_api_key_in_group,
+     get_secret_versions, reveal_secret_version, restore_secret_version,
+     get_trash, get_trash_in_group, restore_from_trash, reveal_item, search,
+     create_folder, get_folders, rename_folder, move_folder, delete_folder, move_item, set_item_tags,
+     get_item_types, get_item_templates, add_item, get_items, update_item, delete_item,
+     upload_attachment, get_attachments, download_attachment, delete_attachment,
+     set_item_deadlines, get_expiring_items, get_group_members, set_group_role, remove_group_member,
+     add_group_member, get_group_policy, set_group_policy as set_owned_group_policy,
+     share_item, revoke_share, get_item_access, get_shared_with_me,
+     create_secret_link, get_secret_links, delete_secret_link, get_secret_link_status, open_secret_link,
+     delete_account_in_group, delete_api_key_in_group, set_group_parent as set_owned_group_parent,
+     create_access_request, get_access_requests, decide_access_request, cancel_access_request, update_group_membership,
+     checkout_item, checkin_item, get_leases,
+     set_emergency_contact, remove_emergency_contact, get_emergency_contacts, get_emergency_designations,
+     create_emergency_request, get_emergency_requests, decide_emergency_request, end_emergency_request, get_emergency_vault
     };
 use middleware_mod::auth_middleware::AuthMiddleware;
 use middleware_mod::auth_middleware_admin::AuthMiddlewareAdmin;
 use delete_user::{delete_user};
-use handlers_admin::{get_users, create_groups,add_groups, get_groups,delete_groups}; 
+use handlers_admin::{get_users, create_groups,add_groups, get_groups,delete_groups, purge_trash, get_audit_log, get_templates, create_template, update_template, delete_template, get_group_policies, set_group_policy, set_group_role as admin_set_group_role, remove_group_member as admin_remove_group_member, set_group_parent, get_access_requests as admin_get_access_requests, decide_access_request as admin_decide_access_request, update_group_membership as admin_update_group_membership, get_expiring_memberships, get_emergency_requests as admin_get_emergency_requests, override_emergency_request, get_backups, create_backup, verify_backup, download_backup}; 
 use crypto::CryptoService;  
+use config::AppConfig;
+use notify::Notifier;
 
 #[actix_web::main]
 async fn main() -> std::io::Result<()> {
@@ -44,17 +78,48 @@ async fn main() -> std::io::Result<()> {
         .expect("❌ Failed to initialize crypto service");
     
     log::info!("✅ Crypto service initialized successfully");
+
+    let mut config = AppConfig::from_env();
+
+    if config.backup_key.as_ref().is_some_and(|key| key.matches(&master_password)) {
+        log::error!("❌ BACKUP_KEY must differ from MASTER_PASSWORD, backups disabled");
+        config.backup_key = None;
+    }
+
+    if config.replica_url.is_some() && config.backup_key.is_none() {
+        log::error!("❌ REPLICA_URL requires a valid BACKUP_KEY, replication disabled");
+        config.replica_url = None;
+    }
+
+    // Commandes d'exploitation : `vault-backend backup | verify | restore | encrypt-db | rekey-db | replica-list | replica-restore`
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    if !args.is_empty() {
+        if let Err(e) = cli::run(&args, &config, &crypto).await {
+            log::error!("❌ {}", e);
+            std::process::exit(1);
+        }
+        return Ok(());
+    }
+
+    // Une configuration de réplica invalide arrête le démarrage au lieu de faire échouer le job en silence
+    if let Err(e) = replica::Store::from_config(&config) {
+        log::error!("❌ Invalid replica configuration: {}", e);
+        std::process::exit(1);
+    }
     
     // Create data directory if it doesn't exist
-    std::fs::create_dir_all("data").ok();
+    if let Some(parent) = std::path::Path::new(&config.database_path).parent() {
+        std::fs::create_dir_all(parent).ok();
+    }
+    std::fs::create_dir_all(&config.attachments_dir)
+        .expect("Failed to create attachments directory");
+    std::fs::create_dir_all(&config.backup_dir)
+        .expect("Failed to create backup directory");
     
     // Database connection
-    let database_url = "sqlite:data/vault.db?mode=rwc";
-    let connect_options = SqliteConnectOptions::from_str(database_url)
-        .expect("Failed to parse database URL")
-        .create_if_missing(true);
-    
-    let pool = SqlitePool::connect_with(connect_options)
+    let database_key = encryption::database_key(&config, &crypto)
+        .expect("Failed to derive the database key");
+    let pool = db::connect(&config.database_path, true, database_key.as_deref(), config.replica_url.is_some())
         .await
         .expect("Failed to connect to database");
     
@@ -62,8 +127,21 @@ async fn main() -> std::io::Result<()> {
     db::init_tables(&pool)
         .await
         .expect("Failed to initialize database tables");
+
+    db::migrate_legacy_items(&pool, &crypto)
+        .await
+        .expect("Failed to migrate vault items");
     
     log::info!("✅ Database initialized successfully");
+
+    let notifier = Notifier::from_config(&config);
+
+    jobs::spawn_trash_purge(pool.clone(), config.clone());
+    jobs::spawn_membership_expiry(pool.clone(), config.clone(), notifier.clone());
+    jobs::spawn_lease_expiry(pool.clone(), config.clone(), notifier.clone());
+    jobs::spawn_emergency_access(pool.clone(), config.clone(), notifier.clone());
+    jobs::spawn_backups(pool.clone(), config.clone(), crypto.clone());
+    jobs::spawn_replication(config.clone(), crypto.clone());
     
     HttpServer::new(move || {
         let cors = Cors::default()
@@ -76,9 +154,13 @@ async fn main() -> std::io::Result<()> {
         App::new()
             .app_data(web::Data::new(pool.clone()))
             .app_data(web::Data::new(crypto.clone()))  
+            .app_data(web::Data::new(config.clone()))
+            .app_data(web::Data::new(notifier.clone()))
             .wrap(cors)
             .wrap(middleware::Logger::default())
            
+            .route("/api/health", web::get().to(health_check))
+           
             .service(
                 web::scope("/api/auth")
                     .route("/register", web::post().to(register))
@@ -86,6 +168,13 @@ async fn main() -> std::io::Result<()> {
                     .route("/verify", web::get().to(verify_token))
             )
            
+            // Liens à usage unique : ouverture sans compte, la clé tient lieu d'autorisation
+            .service(
+                web::scope("/api/links")
+                    .route("/{id}", web::get().to(get_secret_link_status))
+                    .route("/{id}/open", web::post().to(open_secret_link))
+            )
+           
             .service(
                 web::scope("/api/admin/auth")
                     .route("/register", web::post().to(register_admin))
@@ -97,6 +186,12 @@ async fn main() -> std::io::Result<()> {
                 web::scope("/api/secure")
                     .wrap(AuthMiddleware)
                     .route("/me", web::post().to(get_me))
+                    .route("/item-types", web::get().to(get_item_types))
+                    .route("/item-templates", web::get().to(get_item_templates))
+                    .route("/add/item", web::post().to(add_item))
+                    .route("/get/items", web::post().to(get_items))
+                    .route("/update/item", web::put().to(update_item))
+                    .route("/delete/item", web::delete().to(delete_item))
                     .route("/add/api-key", web::post().to(add_api_key))
                     .route("/delete/api-key", web::delete().to(delete_api_key))
                     .route("/add/account", web::post().to(add_account))
@@ -104,11 +199,69 @@ async fn main() -> std::io::Result<()> {
                     .route("/get/account", web::post().to(get_account))
                     .route("/get/api-key", web::post().to(get_api_key))
                     .route("/get/groups-by-name", web::post().to(get_groups_by_name))
+                    .route("/get/group-members", web::post().to(get_group_members))
+                    .route("/add/group-member", web::post().to(add_group_member))
+                    .route("/update/group-role", web::put().to(set_group_role))
+                    .route("/update/group-membership", web::put().to(update_group_membership))
+                    .route("/delete/group-member", web::delete().to(remove_group_member))
+                    .route("/get/group-policy", web::post().to(get_group_policy))
+                    .route("/set/group-policy", web::put().to(set_owned_group_policy))
+                    .route("/set/group-parent", web::put().to(set_owned_group_parent))
+                    .route("/add/access-request", web::post().to(create_access_request))
+                    .route("/get/access-requests", web::get().to(get_access_requests))
+                    .route("/decide/access-request", web::put().to(decide_access_request))
+                    .route("/delete/access-request", web::delete().to(cancel_access_request))
+                    .route("/add/emergency-contact", web::post().to(set_emergency_contact))
+                    .route("/delete/emergency-contact", web::delete().to(remove_emergency_contact))
+                    .route("/get/emergency-contacts", web::post().to(get_emergency_contacts))
+                    .route("/get/emergency-designations", web::get().to(get_emergency_designations))
+                    .route("/add/emergency-request", web::post().to(create_emergency_request))
+                    .route("/get/emergency-requests", web::get().to(get_emergency_requests))
+                    .route("/decide/emergency-request", web::put().to(decide_emergency_request))
+                    .route("/delete/emergency-request", web::delete().to(end_emergency_request))
+                    .route("/get/emergency-vault", web::post().to(get_emergency_vault))
                     .route("/add/account/groups", web::post().to(add_account_in_group))
                     .route("/add/api-key/groups", web::post().to(add_api_key_in_group))
+                    .route("/delete/account/groups", web::delete().to(delete_account_in_group))
+                    .route("/delete/api-key/groups", web::delete().to(delete_api_key_in_group))
                     .route("/get/api-key-by-title", web::post().to(get_api_key_by_title))
                     .route("/get/account/groups", web::post().to(get_account_in_group))
                     .route("/get/api-key/groups", web::post().to(get_api_key_in_group))
+                    .route("/update/account", web::put().to(update_account))
+                    .route("/update/api-key", web::put().to(update_api_key))
+                    .route("/update/account/groups", web::put().to(update_account_in_group))
+                    .route("/update/api-key/groups", web::put().to(update_api_key_in_group))
+                    .route("/get/versions", web::post().to(get_secret_versions))
+                    .route("/get/version", web::post().to(reveal_secret_version))
+                    .route("/restore/version", web::post().to(restore_secret_version))
+                    .route("/reveal", web::post().to(reveal_item))
+                    .route("/search", web::post().to(search))
+                    .route("/add/folder", web::post().to(create_folder))
+                    .route("/get/folders", web::post().to(get_folders))
+                    .route("/rename/folder", web::put().to(rename_folder))
+                    .route("/move/folder", web::put().to(move_folder))
+                    .route("/delete/folder", web::delete().to(delete_folder))
+                    .route("/move/item", web::put().to(move_item))
+                    .route("/update/tags", web::put().to(set_item_tags))
+                    .route("/get/trash", web::post().to(get_trash))
+                    .route("/get/trash/groups", web::post().to(get_trash_in_group))
+                    .route("/restore/trash", web::post().to(restore_from_trash))
+                    .route("/update/deadlines", web::put().to(set_item_deadlines))
+                    .route("/get/expiring", web::get().to(get_expiring_items))
+                    .route("/add/attachment", web::post().to(upload_attachment))
+                    .route("/get/attachments", web::post().to(get_attachments))
+                    .route("/download/attachment", web::post().to(download_attachment))
+                    .route("/delete/attachment", web::delete().to(delete_attachment))
+                    .route("/share/item", web::post().to(share_item))
+                    .route("/delete/share", web::delete().to(revoke_share))
+                    .route("/get/item-access", web::post().to(get_item_access))
+                    .route("/get/shared-with-me", web::get().to(get_shared_with_me))
+                    .route("/checkout/item", web::post().to(checkout_item))
+                    .route("/checkin/item", web::post().to(checkin_item))
+                    .route("/get/leases", web::post().to(get_leases))
+                    .route("/add/secret-link", web::post().to(create_secret_link))
+                    .route("/get/secret-links", web::get().to(get_secret_links))
+                    .route("/delete/secret-link", web::delete().to(delete_secret_link))
             )
            
             .service(
@@ -120,6 +273,27 @@ async fn main() -> std::io::Result<()> {
                     .route("/add/groups", web::post().to(add_groups))
                     .route("/create/groups", web::post().to(create_groups))
                     .route("/delete/groups", web::delete().to(delete_groups))
+                    .route("/purge/trash", web::delete().to(purge_trash))
+                    .route("/get/audit", web::get().to(get_audit_log))
+                    .route("/get/group-policies", web::get().to(get_group_policies))
+                    .route("/set/group-policy", web::put().to(set_group_policy))
+                    .route("/set/group-parent", web::put().to(set_group_parent))
+                    .route("/get/access-requests", web::get().to(admin_get_access_requests))
+                    .route("/decide/access-request", web::put().to(admin_decide_access_request))
+                    .route("/get/emergency-requests", web::get().to(admin_get_emergency_requests))
+                    .route("/override/emergency-request", web::post().to(override_emergency_request))
+                    .route("/update/group-role", web::put().to(admin_set_group_role))
+                    .route("/update/group-membership", web::put().to(admin_update_group_membership))
+                    .route("/get/expiring-memberships", web::get().to(get_expiring_memberships))
+                    .route("/delete/group-member", web::delete().to(admin_remove_group_member))
+                    .route("/get/backups", web::get().to(get_backups))
+                    .route("/create/backup", web::post().to(create_backup))
+                    .route("/verify/backup", web::post().to(verify_backup))
+                    .route("/download/backup", web::post().to(download_backup))
+                    .route("/get/templates", web::get().to(get_templates))
+                    .route("/create/template", web::post().to(create_template))
+                    .route("/update/template", web::put().to(update_template))
+                    .route("/delete/template", web::delete().to(delete_template))
             )
     })
     .bind("0.0.0.0:8000")?
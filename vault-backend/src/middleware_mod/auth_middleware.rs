@@ -1,12 +1,14 @@
 use actix_web::{
     dev::{ServiceRequest, ServiceResponse, Transform, Service, forward_ready},
     Error, HttpResponse, HttpMessage,
-    body::EitherBody,
+    body::EitherBody, web,
 };
+use sqlx::SqlitePool;
 use futures_util::future::{LocalBoxFuture, Ready, ready};
 use std::rc::Rc;
 use crate::auth::{extract_token_from_header, verify_jwt};
 use crate::models::ErrorResponse;
+use crate::db;
 
 pub struct AuthMiddleware;
 
@@ -50,6 +52,26 @@ where
             match extract_token_from_header(req.request()) {
                 Ok(token) => match verify_jwt(&token) {
                     Ok(claims) => {
+                        // Refuse les tokens révoqués (utilisateur supprimé, accès retiré...)
+                        if let Some(pool) = req.app_data::<web::Data<SqlitePool>>() {
+                            match db::is_session_revoked(pool.get_ref(), &claims.sub, claims.iat).await {
+                                Ok(false) => {}
+                                Ok(true) => {
+                                    let resp = HttpResponse::Unauthorized().json(ErrorResponse {
+                                        error: "Session has been revoked".into(),
+                                    });
+                                    return Ok(req.into_response(resp).map_into_right_body());
+                                }
+                                Err(e) => {
+                                    log::error!("Failed to check session revocation: {}", e);
+                                    let resp = HttpResponse::InternalServerError().json(ErrorResponse {
+                                        error: "Failed to verify session".into(),
+                                    });
+                                    return Ok(req.into_response(resp).map_into_right_body());
+                                }
+                            }
+                        }
+
                         req.extensions_mut().insert(claims);
                         match srv.call(req).await {
                             Ok(res) => Ok(res.map_into_left_body()),
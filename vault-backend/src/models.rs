@@ -1,5 +1,6 @@
 use serde::{Deserialize, Serialize};
 use sqlx::FromRow;
+use crate::items::{Fields, TemplateField};
 
 #[derive(Debug, Serialize, Deserialize)]
 pub struct LoginRequest {
@@ -51,6 +52,14 @@ pub struct User {
 }
 
 
+/// Utilisateur tel qu'exposé aux listes admin (sans hash du mot de passe)
+#[derive(Debug, Serialize, sqlx::FromRow)]
+pub struct UserSummary {
+    pub id: String,
+    pub username: String,
+    pub created_at: String,
+}
+
 #[derive(Debug, Serialize, Deserialize, sqlx::FromRow)]
 pub struct Admin {
     pub id: String,
@@ -84,9 +93,14 @@ pub struct ErrorResponse {
 
 #[derive(Deserialize)]
 pub struct AddApiKeyRequest {
-    pub username: String,
     pub api_key: String,
     pub title: String,
+    #[serde(default)]
+    pub notes: Option<String>,
+    #[serde(default)]
+    pub expires_at: Option<String>, // RFC 3339 ou YYYY-MM-DD
+    #[serde(default)]
+    pub rotate_every: Option<i64>, // jours
 }
 
 #[derive(Deserialize)]
@@ -94,14 +108,18 @@ pub struct UsernameRequest {
     pub username: String,
 }
 
-
 #[derive(Deserialize)]
 pub struct AddAccountRequest {
-    pub username: String,
     pub user_account: String,
     pub password_account: String,
     pub title: String,
     pub url: String,
+    #[serde(default)]
+    pub notes: Option<String>,
+    #[serde(default)]
+    pub expires_at: Option<String>, // RFC 3339 ou YYYY-MM-DD
+    #[serde(default)]
+    pub rotate_every: Option<i64>, // jours
 }
 
 #[derive(Serialize)]
@@ -116,10 +134,18 @@ pub struct GetAccountResponse {
     pub id: String,
     pub username: String,
     pub title: String,
+    #[sqlx(skip)]
     pub user_account: String,
-    pub password_account: String,
     pub url: String,
+    // Contenu chiffré, dont seul `user_account` est exposé
+    #[serde(skip)]
+    pub payload: String,
+    pub notes: Option<String>,
+    pub folder_id: Option<String>,
+    #[sqlx(skip)]
+    pub tags: Vec<String>,
     pub created_at: String,
+    pub updated_at: Option<String>,
 }
 
 #[derive(FromRow, Serialize, Deserialize)]
@@ -127,9 +153,12 @@ pub struct GetApiKeyResponse {
     pub id: String,
     pub username: String,
     pub title: String,
-    #[serde(rename = "apiKey")]  // ✅ Seulement pour JSON, pas pour SQL
-    pub api_key: String,
+    pub notes: Option<String>,
+    pub folder_id: Option<String>,
+    #[sqlx(skip)]
+    pub tags: Vec<String>,
     pub created_at: String,
+    pub updated_at: Option<String>,
 }
 
 #[derive(Deserialize)]
@@ -141,6 +170,8 @@ pub struct DeleteRequest {
 pub struct CreateGroupRequest {
     pub group_name: String,
     pub usernames: Vec<String>, // liste des utilisateurs à inclure
+    #[serde(default)]
+    pub owners: Vec<String>, // membres de `usernames` nommés propriétaires (les autres sont éditeurs)
 }
 
 #[derive(Serialize)]
@@ -154,6 +185,106 @@ pub struct CreateGroupResponse {
 pub struct AddUserGroups {
     pub username: String,
     pub group_name: String,
+    #[serde(default)]
+    pub role: GroupRole,
+    #[serde(default)]
+    pub expires_in_hours: Option<i64>, // adhésion temporaire ; absent : permanente
+}
+
+/// Rôle d'un membre dans un groupe, du plus restreint au plus étendu : lecture des éléments,
+/// modification des éléments, gestion des rôles
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum GroupRole {
+    Viewer,
+    #[default]
+    Editor,
+    Owner,
+}
+
+impl GroupRole {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            GroupRole::Viewer => "viewer",
+            GroupRole::Editor => "editor",
+            GroupRole::Owner => "owner",
+        }
+    }
+
+    pub fn parse(value: &str) -> Option<Self> {
+        match value {
+            "viewer" => Some(GroupRole::Viewer),
+            "editor" => Some(GroupRole::Editor),
+            "owner" => Some(GroupRole::Owner),
+            _ => None,
+        }
+    }
+}
+
+impl std::fmt::Display for GroupRole {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+#[derive(Deserialize)]
+pub struct SetGroupRoleRequest {
+    pub group_name: String,
+    pub username: String,
+    pub role: GroupRole,
+}
+
+#[derive(Deserialize)]
+pub struct RemoveGroupMemberRequest {
+    pub group_name: String,
+    pub username: String,
+    #[serde(default)]
+    pub flag_rotation: bool, // signale les secrets du groupe comme à renouveler
+}
+
+#[derive(Debug, Serialize, FromRow)]
+pub struct GroupMember {
+    pub username: String,
+    pub role: String,
+    pub created_at: String,
+    #[sqlx(default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub inherited_from: Option<String>, // groupe parent d'où vient le rôle
+    #[sqlx(default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub expires_at: Option<String>, // adhésion temporaire
+}
+
+/// Prolonge une adhésion temporaire (`expires_in_hours` à partir de maintenant) ou la rend permanente
+#[derive(Deserialize)]
+pub struct UpdateMembershipRequest {
+    pub group_name: String,
+    pub username: String,
+    #[serde(default)]
+    pub expires_in_hours: Option<i64>,
+    #[serde(default)]
+    pub permanent: bool,
+}
+
+#[derive(Deserialize)]
+pub struct ExpiringMembershipsQuery {
+    pub within_days: Option<i64>, // absent : 7
+}
+
+/// Adhésion temporaire échue ou arrivant à échéance
+#[derive(Debug, Serialize, FromRow)]
+pub struct ExpiringMembership {
+    pub group_name: String,
+    pub username: String,
+    pub role: String,
+    pub created_at: String,
+    pub expires_at: String,
+}
+
+#[derive(Deserialize)]
+pub struct SetGroupParentRequest {
+    pub group_name: String,
+    pub parent_group: Option<String>, // absent : le groupe devient un groupe racine
 }
 
 #[derive(Serialize,Deserialize)]
@@ -167,6 +298,9 @@ pub struct GetAllGroups {
     pub member_count: i64,
     pub created_at: String,
     pub description: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub role: Option<String>, // rôle de l'utilisateur connecté (absent pour les administrateurs)
+    pub parent_group: Option<String>,
 }
 #[derive(Debug, Serialize)]
 pub struct AddResponseGroups {
@@ -174,6 +308,8 @@ pub struct AddResponseGroups {
     pub username: String,
     pub group_name: String,
     pub created_at: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub expires_at: Option<String>,
     pub message: String,
 }
 #[derive(Serialize)]
@@ -183,27 +319,35 @@ pub struct ApiKeyResponse {
     pub message: String,
 }
 
-#[derive(Deserialize)]
-pub struct AddUser {
-    pub username: String,
-    pub password: String,
-}
-
 #[derive(Deserialize)]
 pub struct DeleteUser {
     pub id: String,
+    // Sans cible, les éléments de l'utilisateur sont supprimés
+    pub transfer_to_user: Option<String>,
+    pub transfer_to_group: Option<String>,
 }
 
-#[derive(serde::Serialize)]
-pub struct DeleteUserResponse {
-    pub id: String,
-    pub message: String,
+/// Devenir des éléments personnels d'un utilisateur supprimé
+#[derive(Debug)]
+pub enum ItemTransfer {
+    Delete,
+    ToUser(String),
+    ToGroup(String),
 }
 
-#[derive(Serialize)]
-pub struct AddUserResponse {
+#[derive(serde::Serialize, Default)]
+pub struct DeleteUserResponse {
     pub id: String,
-    pub created_at: String,
+    pub username: String,
+    pub accounts_deleted: u64,
+    pub api_keys_deleted: u64,
+    pub other_items_deleted: u64,
+    pub accounts_transferred: u64,
+    pub api_keys_transferred: u64,
+    pub other_items_transferred: u64,
+    pub transferred_to: Option<String>,
+    pub memberships_removed: u64,
+    pub sessions_revoked: bool,
     pub message: String,
 }
 
@@ -219,6 +363,12 @@ pub struct AddAccountInGroup {
     pub user_account: String,
     pub password_account: String,
     pub url: String,
+    #[serde(default)]
+    pub notes: Option<String>,
+    #[serde(default)]
+    pub expires_at: Option<String>, // RFC 3339 ou YYYY-MM-DD
+    #[serde(default)]
+    pub rotate_every: Option<i64>, // jours
 }
 
 #[derive(Debug, Serialize)]
@@ -234,6 +384,12 @@ pub struct AddApiKeyInGroup {
     pub group_name: String,
     pub title: String,
     pub api_key: String,
+    #[serde(default)]
+    pub notes: Option<String>,
+    #[serde(default)]
+    pub expires_at: Option<String>, // RFC 3339 ou YYYY-MM-DD
+    #[serde(default)]
+    pub rotate_every: Option<i64>, // jours
 }
 
 #[derive(Debug, Serialize)]
@@ -251,11 +407,20 @@ pub struct RequestGetAccountInGroups {
 
 #[derive(Debug, Serialize, sqlx::FromRow)]
 pub struct ResponseGetAccountInGroups {
+    pub id: String,
     pub group_name: String,
     pub title: String,
+    #[sqlx(skip)]
     pub user_account: String,
-    pub password_account: String,
     pub url: String,
+    #[serde(skip)]
+    pub payload: String,
+    pub notes: Option<String>,
+    pub folder_id: Option<String>,
+    #[sqlx(skip)]
+    pub tags: Vec<String>,
+    pub created_at: String,
+    pub updated_at: Option<String>,
 }
 
 #[derive(Deserialize)]
@@ -265,9 +430,15 @@ pub struct RequestGetApiKeyInGroups {
 
 #[derive(Serialize, sqlx::FromRow)]
 pub struct ResponseGetApiKeyInGroups {
+    pub id: String,
     pub group_name: String,
     pub title: String,
-    pub api_key: String,
+    pub notes: Option<String>,
+    pub folder_id: Option<String>,
+    #[sqlx(skip)]
+    pub tags: Vec<String>,
+    pub created_at: String,
+    pub updated_at: Option<String>,
 }
 
 #[derive(Deserialize)]
@@ -276,7 +447,763 @@ pub struct RequestGetApiKeyInTitle {
     pub username: String,
 }
 
-#[derive(Serialize, sqlx::FromRow)]
+#[derive(Serialize)]
 pub struct ResponseGetApiKeyInTitle {
+    pub id: String,
     pub api_key: String,
-}
\ No newline at end of file
+}
+
+/// Création d'un élément de n'importe quel type (voir `GET /item-types`)
+#[derive(Deserialize)]
+pub struct AddItemRequest {
+    pub kind: String, // account, api_key, note, ssh_key, certificate, card, identity
+    pub group_name: Option<String>, // absent : élément personnel
+    pub title: String,
+    #[serde(default)]
+    pub url: String,
+    #[serde(default)]
+    pub notes: Option<String>,
+    pub fields: Fields,
+    #[serde(default)]
+    pub expires_at: Option<String>, // RFC 3339 ou YYYY-MM-DD
+    #[serde(default)]
+    pub rotate_every: Option<i64>, // jours
+}
+
+#[derive(Serialize)]
+pub struct AddItemResponse {
+    pub id: String,
+    pub item_type: String,
+    pub created_at: String,
+    pub message: String,
+}
+
+#[derive(Deserialize)]
+pub struct GetItemsRequest {
+    pub group_name: Option<String>,
+    pub kind: Option<String>,
+}
+
+/// Élément tel qu'exposé aux listes : métadonnées et champs non secrets
+#[derive(Serialize, FromRow)]
+pub struct VaultItemSummary {
+    pub id: String,
+    pub item_type: String,
+    pub username: Option<String>,
+    pub group_name: Option<String>,
+    pub title: String,
+    pub url: String,
+    #[sqlx(skip)]
+    pub fields: Fields,
+    #[serde(skip)]
+    pub payload: String,
+    pub notes: Option<String>,
+    pub folder_id: Option<String>,
+    #[sqlx(skip)]
+    pub tags: Vec<String>,
+    pub created_at: String,
+    pub updated_at: Option<String>,
+    pub expires_at: Option<String>,
+    pub rotate_every: Option<i64>, // jours
+    pub rotated_at: String,
+}
+
+#[derive(Deserialize)]
+pub struct UpdateItemRequest {
+    pub item_type: String,
+    pub item_id: String,
+    #[serde(default)]
+    pub fields: Fields, // champs modifiés ; `null` efface un champ facultatif
+    // Métadonnées modifiées ; des notes vides les effacent
+    #[serde(default)]
+    pub title: Option<String>,
+    #[serde(default)]
+    pub url: Option<String>,
+    #[serde(default)]
+    pub notes: Option<String>,
+}
+
+#[derive(Deserialize)]
+pub struct UpdateAccountPasswordRequest {
+    pub id: String,
+    pub password_account: String,
+}
+
+#[derive(Deserialize)]
+pub struct UpdateApiKeyRequest {
+    pub id: String,
+    pub api_key: String,
+}
+
+#[derive(Serialize)]
+pub struct UpdateSecretResponse {
+    pub id: String,
+    pub version: i64,
+    pub updated_at: String,
+    pub message: String,
+}
+
+#[derive(Deserialize)]
+pub struct SecretVersionsRequest {
+    pub item_type: String, // account, note, ssh_key_group… (type de base, suffixé de _group pour un groupe)
+    pub item_id: String,
+}
+
+#[derive(Deserialize)]
+pub struct SecretVersionRequest {
+    pub item_type: String,
+    pub item_id: String,
+    pub version: i64,
+}
+
+#[derive(Debug, Serialize, sqlx::FromRow)]
+pub struct SecretVersionInfo {
+    pub version: i64,
+    pub action: String,
+    pub restored_from: Option<i64>,
+    pub created_by: String,
+    pub created_at: String,
+    pub is_current: bool,
+}
+
+#[derive(Serialize)]
+pub struct RevealSecretVersionResponse {
+    pub item_id: String,
+    pub secret: String,
+    pub fields: Fields,
+    #[serde(flatten)]
+    pub info: SecretVersionInfo,
+}
+
+#[derive(Debug, Serialize, sqlx::FromRow)]
+pub struct TrashItem {
+    pub item_type: String,
+    pub id: String,
+    pub title: String,
+    pub group_name: Option<String>,
+    pub deleted_at: String,
+    pub deleted_by: Option<String>,
+}
+
+#[derive(Deserialize)]
+pub struct TrashItemRequest {
+    pub item_type: String,
+    pub item_id: String,
+}
+
+/// Purge d'un élément précis (`item_type` et `item_id`), ou de toute la corbeille avec `all: true`
+#[derive(Deserialize)]
+pub struct PurgeTrashRequest {
+    pub item_type: Option<String>,
+    pub item_id: Option<String>,
+    #[serde(default)]
+    pub all: bool,
+}
+
+#[derive(Serialize)]
+pub struct PurgeTrashResponse {
+    pub purged: u64,
+    pub message: String,
+}
+
+/// Paramètres de pagination, tri et filtrage des listes (query string)
+#[derive(Debug, Deserialize, Default)]
+pub struct ListParams {
+    pub limit: Option<i64>,
+    pub cursor: Option<String>,
+    pub sort: Option<String>,  // title, created_at, updated_at
+    pub order: Option<String>, // asc, desc
+    pub url_host: Option<String>,
+    pub title: Option<String>,        // correspondance exacte (index aveugle pour les titres chiffrés)
+    pub title_prefix: Option<String>, // uniquement pour les titres en clair
+    pub folder_id: Option<String>,    // "root" pour les éléments hors dossier
+    pub include_subfolders: Option<bool>,
+    pub tag: Option<String>,
+    pub created_after: Option<String>,
+    pub created_before: Option<String>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct Page<T> {
+    pub items: Vec<T>,
+    pub total: i64,
+    pub next_cursor: Option<String>,
+}
+
+#[derive(Deserialize)]
+pub struct RevealItemRequest {
+    pub item_type: String,
+    pub item_id: String,
+}
+
+#[derive(Serialize)]
+pub struct RevealItemResponse {
+    pub id: String,
+    pub item_type: String,
+    pub title: String,
+    pub secret: String, // champ secret principal du type (mot de passe, clé API, clé privée…)
+    pub fields: Fields,
+}
+
+#[derive(Debug, Serialize, sqlx::FromRow)]
+pub struct AuditEntry {
+    pub id: String,
+    pub actor: String,
+    pub action: String,
+    pub target_type: Option<String>,
+    pub target_id: Option<String>,
+    pub details: String,
+    pub created_at: String,
+}
+#[derive(Deserialize)]
+pub struct SearchRequest {
+    pub query: String,
+    pub limit: Option<i64>,
+}
+
+#[derive(Serialize)]
+pub struct SearchResult {
+    pub item_type: String,
+    pub item_id: String,
+    pub group_name: Option<String>,
+    pub title: String,
+    pub url: String,
+    pub title_highlight: String, // HTML : texte échappé, correspondances entre <mark>
+    pub snippet: String,         // idem
+    pub rank: f64,
+}
+
+#[derive(Debug, Serialize, sqlx::FromRow)]
+pub struct Folder {
+    pub id: String,
+    pub name: String,
+    pub parent_id: Option<String>,
+    pub owner: Option<String>,
+    pub group_name: Option<String>,
+    pub created_at: String,
+    pub updated_at: Option<String>,
+}
+
+#[derive(Deserialize)]
+pub struct CreateFolderRequest {
+    pub name: String,
+    pub parent_id: Option<String>,
+    pub group_name: Option<String>, // absent : dossier personnel
+}
+
+#[derive(Deserialize)]
+pub struct GetFoldersRequest {
+    pub group_name: Option<String>,
+}
+
+#[derive(Deserialize)]
+pub struct RenameFolderRequest {
+    pub id: String,
+    pub name: String,
+}
+
+#[derive(Deserialize)]
+pub struct MoveFolderRequest {
+    pub id: String,
+    pub parent_id: Option<String>, // absent : racine
+}
+
+#[derive(Serialize)]
+pub struct DeleteFolderResponse {
+    pub id: String,
+    pub items_moved: u64,
+    pub message: String,
+}
+
+#[derive(Deserialize)]
+pub struct MoveItemRequest {
+    pub item_type: String,
+    pub item_id: String,
+    pub folder_id: Option<String>, // absent : racine
+}
+
+#[derive(Deserialize)]
+pub struct SetTagsRequest {
+    pub item_type: String,
+    pub item_id: String,
+    pub tags: Vec<String>,
+}
+
+#[derive(Serialize)]
+pub struct SetTagsResponse {
+    pub item_type: String,
+    pub item_id: String,
+    pub tags: Vec<String>,
+}
+
+/// Modèle d'élément défini par un administrateur
+#[derive(Serialize, FromRow)]
+pub struct ItemTemplate {
+    pub name: String,
+    pub label: String,
+    #[sqlx(skip)]
+    pub fields: Vec<TemplateField>,
+    // Définition des champs telle que stockée (JSON)
+    #[serde(skip)]
+    pub definition: String,
+    pub created_by: String,
+    pub created_at: String,
+    pub updated_at: Option<String>,
+}
+
+#[derive(Deserialize)]
+pub struct ItemTemplateRequest {
+    pub name: String,
+    pub label: String,
+    pub fields: Vec<TemplateField>,
+}
+
+#[derive(Deserialize)]
+pub struct DeleteTemplateRequest {
+    pub name: String,
+}
+
+/// Pièce jointe d'un élément (nom et type de contenu déchiffrés)
+#[derive(Serialize, FromRow)]
+pub struct Attachment {
+    pub id: String,
+    pub item_type: String,
+    pub item_id: String,
+    pub filename: String,
+    pub content_type: Option<String>,
+    pub size: i64,
+    pub created_by: String,
+    pub created_at: String,
+}
+
+#[derive(Deserialize)]
+pub struct AttachmentUploadQuery {
+    pub item_type: String,
+    pub item_id: String,
+}
+
+#[derive(Deserialize)]
+pub struct AttachmentsRequest {
+    pub item_type: String,
+    pub item_id: String,
+}
+
+#[derive(Deserialize)]
+pub struct AttachmentRequest {
+    pub id: String,
+}
+
+/// Date d'expiration et intervalle de rotation d'un élément ; une valeur absente est retirée
+#[derive(Deserialize)]
+pub struct SetDeadlinesRequest {
+    pub item_type: String,
+    pub item_id: String,
+    #[serde(default)]
+    pub expires_at: Option<String>, // RFC 3339 ou YYYY-MM-DD
+    #[serde(default)]
+    pub rotate_every: Option<i64>, // jours
+}
+
+#[derive(Deserialize)]
+pub struct ExpiringItemsQuery {
+    pub within_days: Option<i64>, // absent : 14
+}
+
+/// Élément expiré, à renouveler ou arrivant à échéance
+#[derive(Serialize)]
+pub struct ExpiringItem {
+    pub item_type: String,
+    pub id: String,
+    pub title: String,
+    pub group_name: Option<String>,
+    pub status: String, // expired, rotation_overdue, expires_soon, rotation_due
+    pub expires_at: Option<String>,
+    pub rotate_every: Option<i64>, // intervalle applicable, politique du groupe comprise
+    pub rotated_at: String,
+    pub rotation_due_at: Option<String>,
+    pub rotation_requested_at: Option<String>, // départ d'un membre du groupe
+}
+
+#[derive(Serialize)]
+pub struct GroupExpiringItems {
+    pub group_name: String,
+    pub max_secret_age_days: Option<i64>,
+    pub items: Vec<ExpiringItem>,
+}
+
+#[derive(Serialize)]
+pub struct ExpiringItemsResponse {
+    pub within_days: i64,
+    pub personal: Vec<ExpiringItem>,
+    pub groups: Vec<GroupExpiringItems>,
+}
+
+/// Âge maximal des secrets d'un groupe
+#[derive(Serialize, FromRow)]
+pub struct GroupPolicy {
+    pub group_name: String,
+    pub max_secret_age_days: i64,
+    pub updated_by: String,
+    pub updated_at: String,
+}
+
+#[derive(Deserialize)]
+pub struct SetGroupPolicyRequest {
+    pub group_name: String,
+    pub max_secret_age_days: Option<i64>, // absent : retire la politique
+}
+
+/// Archive de sauvegarde chiffrée du répertoire des sauvegardes
+#[derive(Serialize)]
+pub struct BackupFile {
+    pub name: String,
+    pub size: u64,
+    pub created_at: String,
+}
+
+#[derive(Deserialize)]
+pub struct BackupRequest {
+    pub name: String,
+}
+
+/// Droit accordé sur un élément personnel partagé : lecture seule ou modification
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SharePermission {
+    #[default]
+    Read,
+    Edit,
+}
+
+impl SharePermission {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            SharePermission::Read => "read",
+            SharePermission::Edit => "edit",
+        }
+    }
+
+    /// Rôle équivalent pour les contrôles d'accès aux éléments
+    pub fn role(&self) -> GroupRole {
+        match self {
+            SharePermission::Read => GroupRole::Viewer,
+            SharePermission::Edit => GroupRole::Editor,
+        }
+    }
+}
+
+#[derive(Deserialize)]
+pub struct ShareItemRequest {
+    pub item_type: String, // type personnel uniquement
+    pub item_id: String,
+    pub username: String,
+    #[serde(default)]
+    pub permission: SharePermission,
+}
+
+#[derive(Deserialize)]
+pub struct RevokeShareRequest {
+    pub item_type: String,
+    pub item_id: String,
+    pub username: String,
+}
+
+/// Utilisateur ayant accès à un élément partagé
+#[derive(Serialize, FromRow)]
+pub struct ItemShare {
+    pub username: String,
+    pub permission: String,
+    pub shared_by: String,
+    pub created_at: String,
+}
+
+/// Qui a accès à un élément : son propriétaire et les utilisateurs avec qui il est partagé
+#[derive(Serialize)]
+pub struct ItemAccessResponse {
+    pub item_type: String,
+    pub item_id: String,
+    pub owner: String,
+    pub shares: Vec<ItemShare>,
+}
+
+/// Élément partagé avec l'utilisateur connecté (métadonnées déchiffrées)
+#[derive(Serialize, FromRow)]
+pub struct SharedItem {
+    pub item_type: String,
+    pub id: String,
+    pub owner: String,
+    pub title: String,
+    pub url: String,
+    pub permission: String,
+    pub shared_by: String,
+    pub shared_at: String,
+}
+
+/// Lien à usage unique vers un secret ponctuel (`secret`) ou un élément existant (`item_type`, `item_id`)
+#[derive(Deserialize)]
+pub struct CreateSecretLinkRequest {
+    #[serde(default)]
+    pub secret: Option<String>,
+    #[serde(default)]
+    pub title: Option<String>, // libellé d'un secret ponctuel, chiffré avec lui
+    #[serde(default)]
+    pub item_type: Option<String>,
+    #[serde(default)]
+    pub item_id: Option<String>,
+    #[serde(default)]
+    pub max_views: Option<i64>, // 1 par défaut
+    #[serde(default)]
+    pub expires_in_hours: Option<i64>, // 24 par défaut
+    #[serde(default)]
+    pub passphrase: Option<String>,
+}
+
+#[derive(Serialize)]
+pub struct CreateSecretLinkResponse {
+    pub id: String,
+    pub key: String, // à transmettre uniquement dans le fragment de l'URL
+    pub link: String,
+    pub max_views: i64,
+    pub expires_at: String,
+    pub passphrase_required: bool,
+}
+
+/// Contenu chiffré d'un lien, rendu tel quel au destinataire
+#[derive(Serialize, Deserialize)]
+pub struct SecretLinkContent {
+    pub title: Option<String>,
+    pub secret: String,
+    pub fields: Option<Fields>,
+}
+
+#[derive(Deserialize)]
+pub struct OpenSecretLinkRequest {
+    pub key: String,
+    #[serde(default)]
+    pub passphrase: Option<String>,
+}
+
+/// Lien actif tel que le voit son créateur (sans le contenu)
+#[derive(Serialize, FromRow)]
+pub struct SecretLinkInfo {
+    pub id: String,
+    pub item_type: Option<String>,
+    pub item_id: Option<String>,
+    pub views: i64,
+    pub max_views: i64,
+    pub passphrase_required: bool,
+    pub created_at: String,
+    pub expires_at: String,
+}
+
+// ==================== DEMANDES D'ACCÈS ====================
+
+/// Demande d'adhésion à un groupe ; `duration_hours` limite l'accès accordé dans le temps
+#[derive(Deserialize)]
+pub struct CreateAccessRequest {
+    pub group_name: String,
+    #[serde(default = "default_requested_role")]
+    pub role: GroupRole,
+    pub justification: String,
+    #[serde(default)]
+    pub duration_hours: Option<i64>,
+}
+
+fn default_requested_role() -> GroupRole {
+    GroupRole::Viewer
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum AccessDecision {
+    Approve,
+    Deny,
+}
+
+#[derive(Deserialize)]
+pub struct DecideAccessRequest {
+    pub id: String,
+    pub decision: AccessDecision,
+    #[serde(default)]
+    pub note: Option<String>,
+    #[serde(default)]
+    pub duration_hours: Option<i64>, // remplace la durée demandée lors de l'approbation
+}
+
+#[derive(Deserialize)]
+pub struct AccessRequestsQuery {
+    pub scope: Option<String>, // mine (par défaut) : mes demandes ; review : celles de mes groupes
+    pub group_name: Option<String>,
+    pub status: Option<String>, // pending, approved, denied, cancelled, expired
+}
+
+#[derive(Debug, Serialize, FromRow)]
+pub struct AccessRequest {
+    pub id: String,
+    pub group_name: String,
+    pub username: String,
+    pub role: String,
+    pub justification: String,
+    pub duration_hours: Option<i64>,
+    pub status: String,
+    pub created_at: String,
+    pub decided_by: Option<String>,
+    pub decided_at: Option<String>,
+    pub decision_note: Option<String>,
+    pub access_expires_at: Option<String>,
+}
+
+// ==================== EMPRUNTS ====================
+
+/// Emprunt exclusif d'un identifiant de groupe pour `duration_minutes` (60 par défaut)
+#[derive(Deserialize)]
+pub struct CheckoutRequest {
+    pub item_type: String,
+    pub item_id: String,
+    #[serde(default)]
+    pub duration_minutes: Option<i64>,
+    #[serde(default)]
+    pub reason: Option<String>,
+}
+
+/// Suite donnée au secret au retour d'un emprunt
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum CheckinRotation {
+    #[default]
+    Keep,   // le secret reste inchangé
+    Remind, // le secret est signalé comme à renouveler
+    Rotate, // un nouveau mot de passe est généré et enregistré comme nouvelle version
+}
+
+#[derive(Deserialize)]
+pub struct CheckinRequest {
+    pub item_type: String,
+    pub item_id: String,
+    #[serde(default)]
+    pub rotation: CheckinRotation,
+}
+
+#[derive(Deserialize)]
+pub struct LeasesRequest {
+    pub group_name: String,
+    #[serde(default)]
+    pub item_type: Option<String>,
+    #[serde(default)]
+    pub item_id: Option<String>,
+    #[serde(default)]
+    pub active_only: bool,
+}
+
+#[derive(Debug, Serialize, FromRow)]
+pub struct ItemLease {
+    pub id: String,
+    pub item_type: String,
+    pub item_id: String,
+    pub group_name: String,
+    pub username: String,
+    pub reason: Option<String>,
+    pub status: String, // active, returned, released (retour forcé), expired
+    pub checked_out_at: String,
+    pub expires_at: String,
+    pub returned_at: Option<String>,
+    pub returned_by: Option<String>,
+}
+
+#[derive(Serialize)]
+pub struct CheckoutResponse {
+    pub lease: ItemLease,
+    pub item: RevealItemResponse,
+}
+
+#[derive(Serialize)]
+pub struct CheckinResponse {
+    pub lease: ItemLease,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub rotated_secret: Option<String>, // nouveau mot de passe, à reporter sur le système cible
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub version: Option<i64>,
+}
+
+// ==================== ACCÈS D'URGENCE ====================
+
+/// Désigne un contact d'urgence pour le coffre personnel de l'utilisateur, ou pour un groupe
+/// dont il est propriétaire (`group_name`)
+#[derive(Deserialize)]
+pub struct SetEmergencyContactRequest {
+    pub contact: String,
+    #[serde(default)]
+    pub group_name: Option<String>,
+    #[serde(default)]
+    pub wait_hours: Option<i64>, // délai avant accès automatique ; défaut : EMERGENCY_DEFAULT_WAIT_HOURS
+}
+
+#[derive(Deserialize)]
+pub struct RemoveEmergencyContactRequest {
+    pub contact: String,
+    #[serde(default)]
+    pub group_name: Option<String>,
+}
+
+#[derive(Deserialize)]
+pub struct EmergencyContactsRequest {
+    #[serde(default)]
+    pub group_name: Option<String>,
+}
+
+#[derive(Debug, Serialize, FromRow)]
+pub struct EmergencyContact {
+    pub target_type: String, // user, group
+    pub target_name: String,
+    pub contact: String,
+    pub wait_hours: i64,
+    pub added_by: String,
+    pub created_at: String,
+}
+
+#[derive(Deserialize)]
+pub struct CreateEmergencyRequest {
+    pub target_type: String,
+    pub target_name: String,
+    pub reason: String,
+}
+
+#[derive(Deserialize)]
+pub struct DecideEmergencyRequest {
+    pub id: String,
+    pub decision: AccessDecision,
+    #[serde(default)]
+    pub note: Option<String>,
+}
+
+#[derive(Deserialize)]
+pub struct EmergencyRequestsQuery {
+    pub scope: Option<String>, // mine (par défaut) : mes demandes ; review : celles visant mon coffre ou mes groupes
+    pub status: Option<String>, // pending, granted, denied, cancelled, revoked, expired
+}
+
+#[derive(Debug, Serialize, FromRow)]
+pub struct EmergencyRequest {
+    pub id: String,
+    pub target_type: String,
+    pub target_name: String,
+    pub requester: String,
+    pub reason: String,
+    pub status: String,
+    pub created_at: String,
+    pub grant_at: String, // accès accordé automatiquement à cette date sans refus
+    pub decided_by: Option<String>,
+    pub decided_at: Option<String>,
+    pub decision_note: Option<String>,
+    pub override_requested_by: Option<String>, // premier administrateur d'une levée du délai
+    pub granted_at: Option<String>,
+    pub access_expires_at: Option<String>,
+}
+
+/// Levée du délai d'attente par un administrateur : effective à la seconde approbation
+#[derive(Serialize)]
+pub struct EmergencyOverrideResponse {
+    pub request: EmergencyRequest,
+    pub granted: bool,
+    pub message: String,
+}
@@ -0,0 +1,319 @@
+use std::fmt::Display;
+use actix_web::web::{Bytes, Payload};
+use futures_util::{Stream, StreamExt};
+
+// Taille maximale des en-têtes d'une partie
+const MAX_HEADER_SIZE: usize = 8 * 1024;
+
+/// En-têtes utiles d'une partie de formulaire
+pub struct PartInfo {
+    pub name: String,
+    pub filename: Option<String>,
+    pub content_type: Option<String>,
+}
+
+enum State {
+    Preamble,
+    Headers,
+    Body,
+    End,
+}
+
+/// Lecteur multipart/form-data en flux : le contenu des parties est rendu par morceaux,
+/// sans jamais charger un fichier entier en mémoire. Lit le corps d'une requête, ou tout autre
+/// flux d'octets
+pub struct Multipart<S = Payload> {
+    payload: S,
+    buffer: Vec<u8>,
+    // "\r\n--<boundary>" : le premier délimiteur n'est pas précédé de CRLF
+    delimiter: Vec<u8>,
+    state: State,
+}
+
+impl<S, E> Multipart<S>
+where
+    S: Stream<Item = Result<Bytes, E>> + Unpin,
+    E: Display,
+{
+    pub fn new(content_type: &str, payload: S) -> Result<Self, String> {
+        let (mime, params) = content_type.split_once(';').unwrap_or((content_type, ""));
+        if !mime.trim().eq_ignore_ascii_case("multipart/form-data") {
+            return Err("Expected a multipart/form-data request".into());
+        }
+
+        let boundary = params
+            .split(';')
+            .filter_map(|param| param.trim().split_once('='))
+            .find(|(key, _)| key.eq_ignore_ascii_case("boundary"))
+            .map(|(_, value)| value.trim_matches('"'))
+            .filter(|boundary| !boundary.is_empty())
+            .ok_or("Missing multipart boundary")?;
+
+        Ok(Self {
+            payload,
+            buffer: Vec::new(),
+            delimiter: format!("\r\n--{}", boundary).into_bytes(),
+            state: State::Preamble,
+        })
+    }
+
+    /// Lit un morceau de plus depuis le client ; `false` en fin de corps
+    async fn fill(&mut self) -> Result<bool, String> {
+        match self.payload.next().await {
+            Some(Ok(bytes)) => {
+                self.buffer.extend_from_slice(&bytes);
+                Ok(true)
+            }
+            Some(Err(e)) => Err(format!("Failed to read request body: {}", e)),
+            None => Ok(false),
+        }
+    }
+
+    /// Passe à la partie suivante et renvoie ses en-têtes ; `None` après la dernière.
+    /// Le contenu restant de la partie courante est ignoré
+    pub async fn next_part(&mut self) -> Result<Option<PartInfo>, String> {
+        loop {
+            match self.state {
+                State::End => return Ok(None),
+                State::Body => while self.next_chunk().await?.is_some() {},
+                State::Preamble => {
+                    let opening = self.delimiter[2..].to_vec();
+                    loop {
+                        if let Some(pos) = find(&self.buffer, &opening) {
+                            self.buffer.drain(..pos + opening.len());
+                            break;
+                        }
+                        if self.buffer.len() > MAX_HEADER_SIZE || !self.fill().await? {
+                            return Err("Multipart boundary not found".into());
+                        }
+                    }
+                    self.state = self.after_delimiter().await?;
+                }
+                State::Headers => {
+                    let headers = loop {
+                        // Une partie sans en-têtes commence directement par la ligne vide
+                        if self.buffer.starts_with(b"\r\n") {
+                            self.buffer.drain(..2);
+                            break String::new();
+                        }
+                        if let Some(pos) = find(&self.buffer, b"\r\n\r\n") {
+                            if pos > MAX_HEADER_SIZE {
+                                return Err("Multipart part headers too large".into());
+                            }
+                            let headers = String::from_utf8_lossy(&self.buffer[..pos]).into_owned();
+                            self.buffer.drain(..pos + 4);
+                            break headers;
+                        }
+                        if self.buffer.len() > MAX_HEADER_SIZE {
+                            return Err("Multipart part headers too large".into());
+                        }
+                        if !self.fill().await? {
+                            return Err("Unexpected end of multipart body".into());
+                        }
+                    };
+                    self.state = State::Body;
+                    return Ok(Some(parse_headers(&headers)));
+                }
+            }
+        }
+    }
+
+    /// Morceau suivant du contenu de la partie courante ; `None` à la fin de la partie
+    pub async fn next_chunk(&mut self) -> Result<Option<Bytes>, String> {
+        if !matches!(self.state, State::Body) {
+            return Ok(None);
+        }
+
+        loop {
+            if let Some(pos) = find(&self.buffer, &self.delimiter) {
+                let data: Vec<u8> = self.buffer.drain(..pos).collect();
+                self.buffer.drain(..self.delimiter.len());
+                self.state = self.after_delimiter().await?;
+                return Ok(if data.is_empty() { None } else { Some(Bytes::from(data)) });
+            }
+
+            // Garde de quoi reconnaître un délimiteur à cheval sur deux morceaux
+            let keep = self.delimiter.len();
+            if self.buffer.len() > keep {
+                let data: Vec<u8> = self.buffer.drain(..self.buffer.len() - keep).collect();
+                return Ok(Some(Bytes::from(data)));
+            }
+
+            if !self.fill().await? {
+                return Err("Unexpected end of multipart body".into());
+            }
+        }
+    }
+
+    /// Après un délimiteur : `--` termine le corps, CRLF annonce une nouvelle partie
+    async fn after_delimiter(&mut self) -> Result<State, String> {
+        while self.buffer.len() < 2 {
+            if !self.fill().await? {
+                return Err("Unexpected end of multipart body".into());
+            }
+        }
+
+        let marker: Vec<u8> = self.buffer.drain(..2).collect();
+        match marker.as_slice() {
+            b"--" => Ok(State::End),
+            b"\r\n" => Ok(State::Headers),
+            _ => Err("Malformed multipart delimiter".into()),
+        }
+    }
+}
+
+fn find(haystack: &[u8], needle: &[u8]) -> Option<usize> {
+    haystack.windows(needle.len()).position(|window| window == needle)
+}
+
+fn parse_headers(headers: &str) -> PartInfo {
+    let mut info = PartInfo { name: String::new(), filename: None, content_type: None };
+
+    for line in headers.split("\r\n") {
+        let Some((key, value)) = line.split_once(':') else {
+            continue;
+        };
+
+        if key.trim().eq_ignore_ascii_case("content-disposition") {
+            for param in value.split(';').skip(1) {
+                let Some((key, value)) = param.trim().split_once('=') else {
+                    continue;
+                };
+                let value = value.trim().trim_matches('"').to_string();
+                match key.trim().to_ascii_lowercase().as_str() {
+                    "name" => info.name = value,
+                    "filename" => info.filename = Some(value),
+                    _ => {}
+                }
+            }
+        } else if key.trim().eq_ignore_ascii_case("content-type") {
+            info.content_type = Some(value.trim().to_string());
+        }
+    }
+
+    info
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::convert::Infallible;
+    use futures_util::stream::{self, Iter};
+    use std::vec::IntoIter;
+
+    type TestStream = Iter<IntoIter<Result<Bytes, Infallible>>>;
+
+    const CONTENT_TYPE: &str = "multipart/form-data; boundary=XyZ";
+
+    fn form(chunk_size: usize, body: &[u8]) -> Multipart<TestStream> {
+        let chunks: Vec<_> = body.chunks(chunk_size).map(|c| Ok(Bytes::copy_from_slice(c))).collect();
+        Multipart::new(CONTENT_TYPE, stream::iter(chunks)).unwrap()
+    }
+
+    async fn read_part(form: &mut Multipart<TestStream>) -> Result<Vec<u8>, String> {
+        let mut data = Vec::new();
+        while let Some(chunk) = form.next_chunk().await? {
+            data.extend_from_slice(&chunk);
+        }
+        Ok(data)
+    }
+
+    fn two_parts() -> Vec<u8> {
+        b"preamble\r\n--XyZ\r\n\
+          Content-Disposition: form-data; name=\"note\"\r\n\r\n\
+          hello\r\n--XyZ\r\n\
+          Content-Disposition: form-data; name=\"file\"; filename=\"a.bin\"\r\n\
+          Content-Type: application/octet-stream\r\n\r\n\
+          \r\n--Xy not a boundary \r\n--XyY\r\n--XyZ--\r\n"
+            .to_vec()
+    }
+
+    #[tokio::test]
+    async fn parses_parts_whatever_the_chunk_boundaries() {
+        let body = two_parts();
+
+        for chunk_size in 1..=body.len() {
+            let mut form = form(chunk_size, &body);
+
+            let part = form.next_part().await.unwrap().unwrap();
+            assert_eq!(part.name, "note");
+            assert_eq!(part.filename, None);
+            assert_eq!(read_part(&mut form).await.unwrap(), b"hello", "chunk size {}", chunk_size);
+
+            let part = form.next_part().await.unwrap().unwrap();
+            assert_eq!(part.name, "file");
+            assert_eq!(part.filename.as_deref(), Some("a.bin"));
+            assert_eq!(part.content_type.as_deref(), Some("application/octet-stream"));
+            assert_eq!(read_part(&mut form).await.unwrap(), b"\r\n--Xy not a boundary \r\n--XyY", "chunk size {}", chunk_size);
+
+            assert!(form.next_part().await.unwrap().is_none());
+        }
+    }
+
+    #[tokio::test]
+    async fn skips_unread_part_content() {
+        let mut form = form(7, &two_parts());
+
+        assert_eq!(form.next_part().await.unwrap().unwrap().name, "note");
+        assert_eq!(form.next_part().await.unwrap().unwrap().name, "file");
+        assert!(form.next_part().await.unwrap().is_none());
+    }
+
+    #[tokio::test]
+    async fn streams_large_parts_in_bounded_chunks() {
+        let content: Vec<u8> = (0..1_000_000u32).map(|i| (i % 251) as u8).collect();
+        let mut body = b"--XyZ\r\nContent-Disposition: form-data; name=\"file\"; filename=\"big\"\r\n\r\n".to_vec();
+        body.extend_from_slice(&content);
+        body.extend_from_slice(b"\r\n--XyZ--");
+
+        let mut form = form(4096, &body);
+        form.next_part().await.unwrap().unwrap();
+
+        let mut data = Vec::new();
+        while let Some(chunk) = form.next_chunk().await.unwrap() {
+            assert!(chunk.len() <= 4096 + "\r\n--XyZ".len());
+            assert!(form.buffer.len() <= 2 * 4096);
+            data.extend_from_slice(&chunk);
+        }
+        assert_eq!(data, content);
+    }
+
+    #[tokio::test]
+    async fn rejects_missing_or_oversized_headers() {
+        let mut truncated = form(5, b"--XyZ\r\nContent-Disposition: form-data; name=\"file\"\r\n");
+        assert_eq!(truncated.next_part().await.err().unwrap(), "Unexpected end of multipart body");
+
+        let mut body = b"--XyZ\r\nX-Padding: ".to_vec();
+        body.resize(body.len() + MAX_HEADER_SIZE + 1, b'a');
+        body.extend_from_slice(b"\r\n\r\ndata\r\n--XyZ--");
+        let mut oversized = form(1024, &body);
+        assert_eq!(oversized.next_part().await.err().unwrap(), "Multipart part headers too large");
+
+        let mut nameless = form(16, b"--XyZ\r\n\r\ndata\r\n--XyZ--");
+        let part = nameless.next_part().await.unwrap().unwrap();
+        assert_eq!((part.name.as_str(), part.filename), ("", None));
+    }
+
+    #[tokio::test]
+    async fn rejects_truncated_or_malformed_bodies() {
+        let mut no_boundary = form(8, b"just some bytes without any delimiter");
+        assert_eq!(no_boundary.next_part().await.err().unwrap(), "Multipart boundary not found");
+
+        let mut unterminated = form(8, b"--XyZ\r\nContent-Disposition: form-data; name=\"a\"\r\n\r\nno closing delimiter");
+        unterminated.next_part().await.unwrap().unwrap();
+        assert_eq!(read_part(&mut unterminated).await.err().unwrap(), "Unexpected end of multipart body");
+
+        let mut malformed = form(8, b"--XyZ!!");
+        assert_eq!(malformed.next_part().await.err().unwrap(), "Malformed multipart delimiter");
+    }
+
+    #[test]
+    fn rejects_invalid_content_types() {
+        let empty = || stream::iter(Vec::<Result<Bytes, Infallible>>::new());
+
+        assert!(Multipart::new("application/json", empty()).is_err());
+        assert!(Multipart::new("multipart/form-data", empty()).is_err());
+        assert!(Multipart::new("multipart/form-data; boundary=\"\"", empty()).is_err());
+        assert!(Multipart::new("Multipart/Form-Data; charset=utf-8; Boundary=\"abc\"", empty()).is_ok());
+    }
+}
@@ -0,0 +1,83 @@
+use std::time::Duration as StdDuration;
+use chrono::Utc;
+use reqwest::{header, Client};
+use serde::Serialize;
+use crate::config::AppConfig;
+
+const WEBHOOK_TIMEOUT: StdDuration = StdDuration::from_secs(10);
+
+/// Événement à porter à la connaissance d'utilisateurs (propriétaires d'un groupe, demandeur...)
+#[derive(Debug, Clone, Serialize)]
+pub struct Notification {
+    pub event: &'static str,
+    pub recipients: Vec<String>,
+    pub target: String, // groupe ou élément concerné
+    pub message: String,
+    pub created_at: String,
+}
+
+impl Notification {
+    pub fn new(event: &'static str, recipients: Vec<String>, target: &str, message: String) -> Self {
+        Self {
+            event,
+            recipients,
+            target: target.to_string(),
+            message,
+            created_at: Utc::now().to_rfc3339(),
+        }
+    }
+}
+
+/// Canal des notifications : le journal du serveur, ou un webhook (NOTIFY_WEBHOOK_URL) qui reçoit
+/// chaque notification en JSON et se charge de la relayer (messagerie, chat...)
+#[derive(Clone)]
+pub enum Notifier {
+    Log,
+    Webhook { client: Client, url: String },
+}
+
+impl Notifier {
+    pub fn from_config(config: &AppConfig) -> Self {
+        match &config.notify_webhook_url {
+            Some(url) => Notifier::Webhook { client: Client::new(), url: url.clone() },
+            None => Notifier::Log,
+        }
+    }
+
+    /// Envoie la notification en arrière-plan : un échec est journalisé sans bloquer l'appelant
+    pub fn send(&self, notification: Notification) {
+        if notification.recipients.is_empty() {
+            return;
+        }
+
+        match self {
+            Notifier::Log => log::info!(
+                "🔔 [{}] to {}: {}",
+                notification.event,
+                notification.recipients.join(", "),
+                notification.message
+            ),
+            Notifier::Webhook { client, url } => {
+                let (client, url) = (client.clone(), url.clone());
+                tokio::spawn(async move {
+                    let body = match serde_json::to_vec(&notification) {
+                        Ok(body) => body,
+                        Err(e) => return log::error!("Failed to encode {} notification: {}", notification.event, e),
+                    };
+
+                    let result = client
+                        .post(&url)
+                        .header(header::CONTENT_TYPE, "application/json")
+                        .timeout(WEBHOOK_TIMEOUT)
+                        .body(body)
+                        .send()
+                        .await
+                        .and_then(|response| response.error_for_status());
+                    if let Err(e) = result {
+                        log::error!("Failed to deliver {} notification: {}", notification.event, e);
+                    }
+                });
+            }
+        }
+    }
+}
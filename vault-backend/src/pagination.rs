@@ -0,0 +1,255 @@
+use base64::{Engine as _, engine::general_purpose};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::sqlite::SqliteRow;
+use sqlx::{FromRow, Row, SqlitePool};
+use crate::crypto::CryptoService;
+use crate::models::{ListParams, Page};
+use crate::search::url_host;
+
+const DEFAULT_LIMIT: i64 = 50;
+const MAX_LIMIT: i64 = 200;
+
+/// Colonnes d'une source listable utilisées pour le tri et les filtres.
+/// Un titre chiffré n'a pas de `title_column` : il ne se trie pas et ne se filtre
+/// qu'à l'identique, via son index aveugle
+pub struct ListSource {
+    pub id_column: &'static str,
+    pub title_column: Option<&'static str>,
+    pub title_index_column: Option<&'static str>,
+    pub created_column: &'static str,
+    pub updated_column: &'static str,
+    pub url_index_column: Option<&'static str>,
+    // Éléments du coffre : active les filtres par dossier et par tag
+    pub vault_items: bool,
+}
+
+/// Position dans une liste : valeur de la clé de tri et identifiant du dernier élément renvoyé
+#[derive(Serialize, Deserialize)]
+struct Cursor {
+    k: String,
+    id: String,
+}
+
+/// Requête de liste validée (tri, filtres, curseur) prête à être appliquée à une source
+pub struct ListQuery {
+    sort_expr: String,
+    descending: bool,
+    limit: i64,
+    filters: Vec<String>,
+    filter_binds: Vec<String>,
+    cursor: Option<Cursor>,
+    id_column: &'static str,
+}
+
+impl ListQuery {
+    pub fn from_params(params: &ListParams, source: &ListSource, crypto: &CryptoService) -> Result<Self, String> {
+        let sort_expr = match params.sort.as_deref().unwrap_or("created_at") {
+            "title" => source.title_column
+                .ok_or_else(|| "Sorting by title is not available for encrypted titles".to_string())?
+                .to_string(),
+            "created_at" => source.created_column.to_string(),
+            "updated_at" => format!("COALESCE({}, {})", source.updated_column, source.created_column),
+            other => return Err(format!("Unknown sort key '{}' (expected title, created_at or updated_at)", other)),
+        };
+
+        let descending = match params.order.as_deref() {
+            None => params.sort.as_deref() != Some("title"),
+            Some("asc") => false,
+            Some("desc") => true,
+            Some(other) => return Err(format!("Unknown sort order '{}' (expected asc or desc)", other)),
+        };
+
+        let limit = params.limit.unwrap_or(DEFAULT_LIMIT);
+        if !(1..=MAX_LIMIT).contains(&limit) {
+            return Err(format!("limit must be between 1 and {}", MAX_LIMIT));
+        }
+
+        let mut filters = Vec::new();
+        let mut filter_binds = Vec::new();
+
+        if let Some(title) = params.title.as_deref().filter(|t| !t.is_empty()) {
+            match (source.title_index_column, source.title_column) {
+                (Some(index_column), _) => {
+                    filters.push(format!("{} = ?", index_column));
+                    filter_binds.push(crypto.blind_index("title", title));
+                }
+                (None, Some(title_column)) => {
+                    filters.push(format!("{} = ?", title_column));
+                    filter_binds.push(title.to_string());
+                }
+                (None, None) => return Err("title filter is not supported for this list".to_string()),
+            }
+        }
+
+        if let Some(prefix) = params.title_prefix.as_deref().filter(|p| !p.is_empty()) {
+            let title_column = source.title_column
+                .ok_or_else(|| "title_prefix is not available for encrypted titles (use title for an exact match)".to_string())?;
+            filters.push(format!("{} LIKE ? ESCAPE '\\'", title_column));
+            filter_binds.push(format!("{}%", escape_like(prefix)));
+        }
+
+        if let Some(host) = params.url_host.as_deref().filter(|h| !h.is_empty()) {
+            let index_column = source.url_index_column
+                .ok_or_else(|| "url_host filter is not supported for this list".to_string())?;
+            let host = url_host(host).ok_or_else(|| "url_host must be a host name".to_string())?;
+            filters.push(format!("{} = ?", index_column));
+            filter_binds.push(crypto.blind_index("url_host", &host));
+        }
+
+        if let Some(folder_id) = params.folder_id.as_deref().filter(|f| !f.is_empty()) {
+            if !source.vault_items {
+                return Err("folder_id filter is not supported for this list".to_string());
+            }
+            if folder_id == "root" {
+                filters.push("folder_id IS NULL".to_string());
+            } else if params.include_subfolders.unwrap_or(false) {
+                filters.push(
+                    "folder_id IN (WITH RECURSIVE tree(id) AS (SELECT ? UNION ALL SELECT f.id FROM folders f JOIN tree ON f.parent_id = tree.id) SELECT id FROM tree)"
+                        .to_string(),
+                );
+                filter_binds.push(folder_id.to_string());
+            } else {
+                filters.push("folder_id = ?".to_string());
+                filter_binds.push(folder_id.to_string());
+            }
+        }
+
+        if let Some(tag) = params.tag.as_deref().map(str::trim).filter(|t| !t.is_empty()) {
+            if !source.vault_items {
+                return Err("tag filter is not supported for this list".to_string());
+            }
+            filters.push(format!(
+                "(item_type, {}) IN (SELECT item_type, item_id FROM item_tags WHERE tag_index = ?)",
+                source.id_column
+            ));
+            filter_binds.push(crypto.blind_index("tag", &tag.to_lowercase()));
+        }
+
+        if let Some(after) = params.created_after.as_deref() {
+            filters.push(format!("{} >= ?", source.created_column));
+            filter_binds.push(normalize_timestamp(after, "created_after")?);
+        }
+
+        if let Some(before) = params.created_before.as_deref() {
+            filters.push(format!("{} < ?", source.created_column));
+            filter_binds.push(normalize_timestamp(before, "created_before")?);
+        }
+
+        let cursor = match params.cursor.as_deref() {
+            Some(encoded) => Some(decode_cursor(encoded)?),
+            None => None,
+        };
+
+        Ok(Self {
+            sort_expr,
+            descending,
+            limit,
+            filters,
+            filter_binds,
+            cursor,
+            id_column: source.id_column,
+        })
+    }
+
+    /// Conditions de filtrage à ajouter après la clause WHERE de la source
+    fn filter_sql(&self) -> String {
+        self.filters.iter().map(|f| format!(" AND {}", f)).collect()
+    }
+
+    /// Exécute la requête paginée : `from_sql` contient la clause FROM et un WHERE
+    /// dont les paramètres sont fournis par `base_binds`, `columns` les colonnes sélectionnées
+    pub async fn fetch_page<T>(
+        &self,
+        pool: &SqlitePool,
+        columns: &str,
+        from_sql: &str,
+        base_binds: &[&str],
+    ) -> Result<Page<T>, sqlx::Error>
+    where
+        T: for<'r> FromRow<'r, SqliteRow> + Send + Unpin,
+    {
+        let filter_sql = self.filter_sql();
+
+        let count_sql = format!("SELECT COUNT(*) {}{}", from_sql, filter_sql);
+        let mut count_query = sqlx::query_scalar::<_, i64>(&count_sql);
+        for bind in base_binds {
+            count_query = count_query.bind(*bind);
+        }
+        for bind in &self.filter_binds {
+            count_query = count_query.bind(bind);
+        }
+        let total = count_query.fetch_one(pool).await?;
+
+        let direction = if self.descending { "DESC" } else { "ASC" };
+        let comparison = if self.descending { "<" } else { ">" };
+        let cursor_sql = match self.cursor {
+            Some(_) => format!(
+                " AND ({sort} {cmp} ? OR ({sort} = ? AND {id} {cmp} ?))",
+                sort = self.sort_expr,
+                cmp = comparison,
+                id = self.id_column
+            ),
+            None => String::new(),
+        };
+
+        let page_sql = format!(
+            "SELECT {}, {} AS sort_key, {} AS cursor_id {}{}{} ORDER BY {} {}, {} {} LIMIT ?",
+            columns, self.sort_expr, self.id_column, from_sql, filter_sql, cursor_sql,
+            self.sort_expr, direction, self.id_column, direction
+        );
+
+        let mut page_query = sqlx::query(&page_sql);
+        for bind in base_binds {
+            page_query = page_query.bind(*bind);
+        }
+        for bind in &self.filter_binds {
+            page_query = page_query.bind(bind);
+        }
+        if let Some(cursor) = &self.cursor {
+            page_query = page_query.bind(&cursor.k).bind(&cursor.k).bind(&cursor.id);
+        }
+        // Un élément de plus pour savoir s'il reste une page
+        let rows = page_query.bind(self.limit + 1).fetch_all(pool).await?;
+
+        let has_more = rows.len() as i64 > self.limit;
+        let mut items = Vec::with_capacity(rows.len());
+        let mut next_cursor = None;
+
+        for row in rows.iter().take(self.limit as usize) {
+            items.push(T::from_row(row)?);
+            if has_more {
+                next_cursor = Some(encode_cursor(&Cursor {
+                    k: row.try_get::<Option<String>, _>("sort_key")?.unwrap_or_default(),
+                    id: row.try_get("cursor_id")?,
+                }));
+            }
+        }
+
+        Ok(Page { items, total, next_cursor })
+    }
+}
+
+fn escape_like(value: &str) -> String {
+    value.replace('\\', "\\\\").replace('%', "\\%").replace('_', "\\_")
+}
+
+/// Ramène une date RFC 3339 en UTC pour qu'elle se compare aux dates stockées
+fn normalize_timestamp(value: &str, field: &str) -> Result<String, String> {
+    DateTime::parse_from_rfc3339(value)
+        .map(|d| d.with_timezone(&Utc).to_rfc3339())
+        .map_err(|_| format!("{} must be an RFC 3339 timestamp", field))
+}
+
+fn encode_cursor(cursor: &Cursor) -> String {
+    let json = serde_json::to_vec(cursor).unwrap_or_default();
+    general_purpose::URL_SAFE_NO_PAD.encode(json)
+}
+
+fn decode_cursor(encoded: &str) -> Result<Cursor, String> {
+    general_purpose::URL_SAFE_NO_PAD
+        .decode(encoded)
+        .ok()
+        .and_then(|bytes| serde_json::from_slice(&bytes).ok())
+        .ok_or_else(|| "Invalid cursor".to_string())
+}
@@ -0,0 +1,883 @@
+use std::collections::HashSet;
+use std::io;
+use std::path::{Path, PathBuf};
+use argon2::Params;
+use chrono::{DateTime, Duration, NaiveDateTime, Utc};
+use rand::RngCore;
+use serde::{Deserialize, Serialize};
+use sqlx::{ConnectOptions, Connection, Row, SqliteConnection};
+use tokio::fs;
+use tokio::io::{AsyncReadExt, AsyncSeekExt};
+use uuid::Uuid;
+use crate::backup::{self, BackupKey};
+use crate::config::AppConfig;
+use crate::crypto::CryptoService;
+use crate::db;
+use crate::encryption;
+use crate::s3::S3Client;
+
+// Journal WAL de SQLite : en-tête de 32 octets puis trames [en-tête 24 octets][page]
+const WAL_HEADER_LEN: u64 = 32;
+const FRAME_HEADER_LEN: usize = 24;
+const WAL_MAGIC_LE: u32 = 0x377f0682;
+const WAL_MAGIC_BE: u32 = 0x377f0683;
+
+const FORMAT_VERSION: u32 = 1;
+const SALT_LEN: usize = 16;
+const TIME_FORMAT: &str = "%Y%m%dT%H%M%S%.3fZ";
+const CONFIG_OBJECT: &str = "replica.json";
+const GENERATIONS: &str = "generations/";
+const ATTACHMENTS: &str = "attachments/";
+const KEY_CHECK: &[u8] = b"frozpass/replica";
+
+fn parse_time(value: &str) -> Option<DateTime<Utc>> {
+    NaiveDateTime::parse_from_str(value, TIME_FORMAT).ok().map(|time| time.and_utc())
+}
+
+fn to_hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+fn from_hex(value: &str) -> Option<Vec<u8>> {
+    (0..value.len())
+        .step_by(2)
+        .map(|i| value.get(i..i + 2).and_then(|byte| u8::from_str_radix(byte, 16).ok()))
+        .collect()
+}
+
+// ==================== STOCKAGE ====================
+
+/// Emplacement du réplica : un répertoire (disque local, montage réseau) ou un bucket compatible S3
+pub enum Store {
+    Local(PathBuf),
+    S3 { client: S3Client, prefix: String },
+}
+
+impl Store {
+    /// REPLICA_URL : `s3://<bucket>/<préfixe>` ou chemin d'un répertoire ; `None` si la réplication est désactivée
+    pub fn from_config(config: &AppConfig) -> Result<Option<Self>, String> {
+        let Some(url) = &config.replica_url else {
+            return Ok(None);
+        };
+
+        let Some(rest) = url.strip_prefix("s3://") else {
+            let path = url.strip_prefix("file://").unwrap_or(url);
+            return Ok(Some(Store::Local(PathBuf::from(path))));
+        };
+
+        let (bucket, prefix) = rest.split_once('/').unwrap_or((rest, ""));
+        let prefix = match prefix.trim_matches('/') {
+            "" => String::new(),
+            prefix => format!("{}/", prefix),
+        };
+        let credentials = config.replica_s3_credentials.clone().ok_or(
+            "REPLICA_S3_ACCESS_KEY and REPLICA_S3_SECRET_KEY are required for an S3 replica",
+        )?;
+        let endpoint = config
+            .replica_s3_endpoint
+            .clone()
+            .unwrap_or_else(|| format!("https://s3.{}.amazonaws.com", config.replica_s3_region));
+        let client = S3Client::new(&endpoint, bucket, &config.replica_s3_region, credentials)?;
+
+        Ok(Some(Store::S3 { client, prefix }))
+    }
+
+    async fn put(&self, key: &str, data: Vec<u8>) -> Result<(), String> {
+        match self {
+            Store::Local(root) => {
+                let path = root.join(key);
+                if let Some(parent) = path.parent() {
+                    fs::create_dir_all(parent).await.map_err(|e| e.to_string())?;
+                }
+                // Écriture puis renommage : un objet n'est jamais visible à moitié écrit
+                let partial = PathBuf::from(format!("{}.part", path.display()));
+                fs::write(&partial, data).await.map_err(|e| e.to_string())?;
+                fs::rename(&partial, &path).await.map_err(|e| e.to_string())
+            }
+            Store::S3 { client, prefix } => client.put(&format!("{}{}", prefix, key), data).await,
+        }
+    }
+
+    async fn get(&self, key: &str) -> Result<Option<Vec<u8>>, String> {
+        match self {
+            Store::Local(root) => match fs::read(root.join(key)).await {
+                Ok(data) => Ok(Some(data)),
+                Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(None),
+                Err(e) => Err(e.to_string()),
+            },
+            Store::S3 { client, prefix } => client.get(&format!("{}{}", prefix, key)).await,
+        }
+    }
+
+    async fn delete(&self, key: &str) -> Result<(), String> {
+        match self {
+            Store::Local(root) => match fs::remove_file(root.join(key)).await {
+                Err(e) if e.kind() != io::ErrorKind::NotFound => Err(e.to_string()),
+                _ => Ok(()),
+            },
+            Store::S3 { client, prefix } => client.delete(&format!("{}{}", prefix, key)).await,
+        }
+    }
+
+    /// Clés (relatives à la racine du réplica) des objets sous un préfixe se terminant par `/`
+    async fn list(&self, prefix: &str) -> Result<Vec<String>, String> {
+        match self {
+            Store::Local(root) => {
+                let mut keys = Vec::new();
+                let mut pending = vec![prefix.to_string()];
+                while let Some(dir) = pending.pop() {
+                    let mut entries = match fs::read_dir(root.join(&dir)).await {
+                        Ok(entries) => entries,
+                        Err(e) if e.kind() == io::ErrorKind::NotFound => continue,
+                        Err(e) => return Err(e.to_string()),
+                    };
+                    while let Some(entry) = entries.next_entry().await.map_err(|e| e.to_string())? {
+                        let name = entry.file_name().to_string_lossy().into_owned();
+                        let file_type = entry.file_type().await.map_err(|e| e.to_string())?;
+                        if file_type.is_dir() {
+                            pending.push(format!("{}{}/", dir, name));
+                        } else if !name.ends_with(".part") {
+                            keys.push(format!("{}{}", dir, name));
+                        }
+                    }
+                }
+                keys.sort();
+                Ok(keys)
+            }
+            Store::S3 { client, prefix: root } => Ok(client
+                .list(&format!("{}{}", root, prefix))
+                .await?
+                .into_iter()
+                .filter_map(|key| key.strip_prefix(root.as_str()).map(str::to_string))
+                .collect()),
+        }
+    }
+
+    async fn delete_prefix(&self, prefix: &str) -> Result<(), String> {
+        for key in self.list(prefix).await? {
+            self.delete(&key).await?;
+        }
+        if let Store::Local(root) = self {
+            fs::remove_dir_all(root.join(prefix)).await.ok();
+        }
+        Ok(())
+    }
+}
+
+// ==================== RÉPLICA CHIFFRÉ ====================
+
+/// Paramètres du réplica, stockés en clair à sa racine : sel et coûts Argon2id de la clé dérivée
+/// de BACKUP_KEY, témoin chiffré permettant de vérifier celle-ci et empreinte de la clé maître
+/// du coffre répliqué
+#[derive(Serialize, Deserialize)]
+struct ReplicaSettings {
+    format: u32,
+    salt: String,
+    m_cost: u32,
+    t_cost: u32,
+    p_cost: u32,
+    key_check: String,
+    master_key_check: String,
+}
+
+/// Génération : un instantané complet de la base suivi des segments du journal WAL
+#[derive(Debug, Clone)]
+struct Generation {
+    id: String,
+    started_at: DateTime<Utc>,
+}
+
+/// Segment du journal : octets [start, end) du fichier WAL de la génération
+#[derive(Debug, Clone)]
+struct Segment {
+    key: String,
+    start: u64,
+    end: u64,
+    shipped_at: DateTime<Utc>,
+}
+
+/// Génération disponible pour une restauration
+#[derive(Debug, Serialize)]
+pub struct GenerationInfo {
+    pub id: String,
+    pub started_at: String,
+    pub segments: usize,
+    /// Instant le plus récent restaurable à partir de cette génération
+    pub restorable_until: String,
+}
+
+/// Résultat d'une restauration à un instant donné
+#[derive(Debug, Serialize)]
+pub struct ReplicaRestoreReport {
+    pub generation: String,
+    pub restored_to: String,
+    pub segments: usize,
+    pub attachments: u64,
+    /// Pièces jointes référencées en base mais absentes du réplica
+    pub missing_attachments: u64,
+}
+
+/// Réplica ouvert : chaque objet (instantané, segment) est chiffré avec la clé dérivée de
+/// BACKUP_KEY et lié à son nom. Les pièces jointes sont copiées telles quelles, elles sont
+/// déjà chiffrées sur disque
+struct Replica {
+    store: Store,
+    crypto: CryptoService,
+}
+
+impl Replica {
+    /// Ouvre le réplica configuré ; `create` initialise ses paramètres s'il est vide
+    async fn open(config: &AppConfig, crypto: &CryptoService, key: &BackupKey, create: bool) -> Result<Self, String> {
+        let store = Store::from_config(config)?.ok_or("REPLICA_URL is not set")?;
+
+        let settings = match store.get(CONFIG_OBJECT).await? {
+            Some(data) => serde_json::from_slice::<ReplicaSettings>(&data)
+                .map_err(|e| format!("Invalid replica settings: {}", e))?,
+            None if create => {
+                let mut salt = [0u8; SALT_LEN];
+                rand::thread_rng().fill_bytes(&mut salt);
+                let params = Params::default();
+                let sealed = key.derive(&salt, params.clone())?.encrypt_chunk(CONFIG_OBJECT, 0, true, KEY_CHECK)?;
+                let settings = ReplicaSettings {
+                    format: FORMAT_VERSION,
+                    salt: to_hex(&salt),
+                    m_cost: params.m_cost(),
+                    t_cost: params.t_cost(),
+                    p_cost: params.p_cost(),
+                    key_check: to_hex(&sealed),
+                    master_key_check: crypto.key_check(),
+                };
+                let data = serde_json::to_vec_pretty(&settings).map_err(|e| e.to_string())?;
+                store.put(CONFIG_OBJECT, data).await?;
+                settings
+            }
+            None => return Err("The replica is empty".into()),
+        };
+
+        if settings.format != FORMAT_VERSION {
+            return Err(format!("Unsupported replica format {}", settings.format));
+        }
+        if settings.master_key_check != crypto.key_check() {
+            return Err("The replica belongs to a vault with a different MASTER_PASSWORD".into());
+        }
+
+        let salt = from_hex(&settings.salt).ok_or("Invalid replica salt")?;
+        let params = Params::new(settings.m_cost, settings.t_cost, settings.p_cost, None)
+            .map_err(|e| format!("Invalid replica key parameters: {}", e))?;
+
+        let replica_crypto = key.derive(&salt, params)?;
+        let key_check = from_hex(&settings.key_check).ok_or("Invalid replica key check")?;
+        if replica_crypto.decrypt_chunk(CONFIG_OBJECT, 0, true, &key_check).ok().as_deref() != Some(KEY_CHECK) {
+            return Err("Wrong BACKUP_KEY for this replica".into());
+        }
+
+        Ok(Self { store, crypto: replica_crypto })
+    }
+
+    async fn put_sealed(&self, key: &str, data: &[u8]) -> Result<(), String> {
+        let sealed = self.crypto.encrypt_chunk(key, 0, true, data)?;
+        self.store.put(key, sealed).await
+    }
+
+    async fn get_sealed(&self, key: &str) -> Result<Option<Vec<u8>>, String> {
+        let Some(sealed) = self.store.get(key).await? else {
+            return Ok(None);
+        };
+        self.crypto
+            .decrypt_chunk(key, 0, true, &sealed)
+            .map(Some)
+            .map_err(|_| format!("Replica object {} is corrupted or was sealed with another BACKUP_KEY", key))
+    }
+
+    /// Générations, de la plus ancienne à la plus récente
+    async fn generations(&self) -> Result<Vec<Generation>, String> {
+        let mut ids: Vec<String> = self
+            .store
+            .list(GENERATIONS)
+            .await?
+            .iter()
+            .filter_map(|key| key.strip_prefix(GENERATIONS)?.split('/').next().map(str::to_string))
+            .collect();
+        ids.dedup();
+
+        let mut generations: Vec<Generation> = ids
+            .into_iter()
+            .filter_map(|id| {
+                let started_at = parse_time(id.split('-').next()?)?;
+                Some(Generation { id, started_at })
+            })
+            .collect();
+        generations.sort_by(|a, b| a.started_at.cmp(&b.started_at).then_with(|| a.id.cmp(&b.id)));
+        Ok(generations)
+    }
+
+    /// Segments d'une génération, dans l'ordre du journal
+    async fn segments(&self, generation: &str) -> Result<Vec<Segment>, String> {
+        let prefix = format!("{}{}/wal/", GENERATIONS, generation);
+        let mut segments: Vec<Segment> = self
+            .store
+            .list(&prefix)
+            .await?
+            .into_iter()
+            .filter_map(|key| {
+                let mut parts = key.strip_prefix(&prefix)?.splitn(3, '-');
+                let start = parts.next()?.parse().ok()?;
+                let end = parts.next()?.parse().ok()?;
+                let shipped_at = parse_time(parts.next()?)?;
+                Some(Segment { key, start, end, shipped_at })
+            })
+            .collect();
+        segments.sort_by_key(|segment| segment.start);
+        Ok(segments)
+    }
+}
+
+fn snapshot_key(generation: &str) -> String {
+    format!("{}{}/snapshot", GENERATIONS, generation)
+}
+
+// ==================== JOURNAL WAL ====================
+
+struct WalHeader {
+    big_endian: bool,
+    page_size: usize,
+    salts: [u8; 8],
+    checksum: (u32, u32),
+}
+
+fn read_u32(bytes: &[u8]) -> u32 {
+    u32::from_be_bytes([bytes[0], bytes[1], bytes[2], bytes[3]])
+}
+
+/// Somme de contrôle cumulative du journal (format WAL de SQLite)
+fn wal_checksum(big_endian: bool, (mut s0, mut s1): (u32, u32), data: &[u8]) -> (u32, u32) {
+    for word in data.chunks_exact(8) {
+        let (x0, x1) = match big_endian {
+            true => (read_u32(&word[..4]), read_u32(&word[4..])),
+            false => (
+                u32::from_le_bytes([word[0], word[1], word[2], word[3]]),
+                u32::from_le_bytes([word[4], word[5], word[6], word[7]]),
+            ),
+        };
+        s0 = s0.wrapping_add(x0).wrapping_add(s1);
+        s1 = s1.wrapping_add(x1).wrapping_add(s0);
+    }
+    (s0, s1)
+}
+
+/// En-tête du journal ; `None` s'il est absent ou en cours d'écriture
+fn parse_wal_header(header: &[u8]) -> Option<WalHeader> {
+    let big_endian = match read_u32(&header[..4]) {
+        WAL_MAGIC_BE => true,
+        WAL_MAGIC_LE => false,
+        _ => return None,
+    };
+    let checksum = (read_u32(&header[24..28]), read_u32(&header[28..32]));
+    if wal_checksum(big_endian, (0, 0), &header[..24]) != checksum {
+        return None;
+    }
+
+    let page_size = match read_u32(&header[8..12]) {
+        1 => 65536,
+        size => size as usize,
+    };
+    let mut salts = [0u8; 8];
+    salts.copy_from_slice(&header[16..24]);
+
+    Some(WalHeader { big_endian, page_size, salts, checksum })
+}
+
+// ==================== RÉPLICATION CONTINUE ====================
+
+async fn wal_len(path: &Path) -> u64 {
+    fs::metadata(path).await.map(|metadata| metadata.len()).unwrap_or(0)
+}
+
+/// Position dans le journal de la génération en cours
+struct Position {
+    generation: String,
+    started_at: DateTime<Utc>,
+    /// Octets du fichier WAL déjà expédiés (0 : rien, pas même l'en-tête)
+    offset: u64,
+    salts: [u8; 8],
+    checksum: (u32, u32),
+}
+
+/// Réplicateur : expédie les transactions validées du journal WAL dès leur écriture. Les points
+/// de contrôle automatiques sont désactivés (voir `db::replicated_options`) ; le réplicateur
+/// tronque lui-même le journal en ouvrant une nouvelle génération, après avoir tout expédié
+pub struct Replicator {
+    config: AppConfig,
+    crypto: CryptoService,
+    key: BackupKey,
+    database_key: Option<String>,
+    wal_path: PathBuf,
+    conn: SqliteConnection,
+    replica: Option<Replica>,
+    position: Option<Position>,
+    shipped_attachments: HashSet<String>,
+}
+
+impl Replicator {
+    /// Ouvre la connexion dédiée à la réplication ; le réplica lui-même est ouvert au premier cycle
+    pub async fn new(config: &AppConfig, crypto: &CryptoService, key: &BackupKey) -> Result<Self, String> {
+        let database_key = encryption::database_key(config, crypto)?;
+        let mut conn = db::replicated_options(db::connect_options(&config.database_path, database_key.as_deref()))
+            .connect()
+            .await
+            .map_err(|e| e.to_string())?;
+        db::check_access(&mut conn, database_key.is_some()).await.map_err(|e| e.to_string())?;
+
+        Ok(Self {
+            config: config.clone(),
+            crypto: crypto.clone(),
+            key: key.clone(),
+            database_key,
+            wal_path: PathBuf::from(format!("{}-wal", config.database_path)),
+            conn,
+            replica: None,
+            position: None,
+            shipped_attachments: HashSet::new(),
+        })
+    }
+
+    /// Un cycle de réplication : nouvelle génération si nécessaire, nouveaux segments du journal,
+    /// nouvelles pièces jointes
+    pub async fn sync(&mut self) -> Result<(), String> {
+        if self.replica.is_none() {
+            match Replica::open(&self.config, &self.crypto, &self.key, true).await {
+                Ok(replica) => {
+                    self.shipped_attachments = replica
+                        .store
+                        .list(ATTACHMENTS)
+                        .await?
+                        .iter()
+                        .filter_map(|key| key.strip_prefix(ATTACHMENTS).map(str::to_string))
+                        .collect();
+                    self.replica = Some(replica);
+                }
+                Err(e) => {
+                    // Réplica injoignable : le journal ne doit pas grossir indéfiniment pour autant
+                    if wal_len(&self.wal_path).await >= self.config.replica_max_wal_bytes {
+                        self.checkpoint().await?;
+                    }
+                    return Err(e);
+                }
+            }
+        }
+
+        let needs_generation = match &self.position {
+            None => true,
+            Some(position) => {
+                wal_len(&self.wal_path).await >= self.config.replica_max_wal_bytes
+                    || Utc::now() - position.started_at
+                        >= Duration::hours(self.config.replica_snapshot_interval_hours as i64)
+            }
+        };
+        if needs_generation {
+            // Les dernières transactions rejoignent la génération qui se termine
+            self.ship_frames().await?;
+            self.start_generation().await?;
+        }
+
+        self.ship_frames().await?;
+        self.ship_attachments().await
+    }
+
+    /// Reporte le journal dans la base et le tronque ; `false` si des lecteurs l'en ont empêché
+    async fn checkpoint(&mut self) -> Result<bool, String> {
+        let row = sqlx::query("PRAGMA wal_checkpoint(TRUNCATE)")
+            .fetch_one(&mut self.conn)
+            .await
+            .map_err(|e| e.to_string())?;
+        let busy: i64 = row.get(0);
+        if busy != 0 {
+            return Ok(false);
+        }
+
+        // Le journal est vide : la génération en cours ne peut plus être prolongée
+        self.position = None;
+        Ok(true)
+    }
+
+    /// Nouvelle génération : journal tronqué puis instantané complet de la base
+    async fn start_generation(&mut self) -> Result<(), String> {
+        if !self.checkpoint().await? {
+            log::warn!("Replica generation postponed: the database is busy");
+            return Ok(());
+        }
+
+        let temp = PathBuf::from(format!("{}.replica-snapshot", self.config.database_path));
+        backup::remove_path(&temp).await;
+        let snapshot = backup::snapshot_connection(&mut self.conn, &temp, self.database_key.as_deref()).await;
+        let data = match snapshot {
+            Ok(()) => fs::read(&temp).await.map_err(|e| e.to_string()),
+            Err(e) => Err(e),
+        };
+        backup::remove_path(&temp).await;
+        let data = data?;
+
+        let started_at = Utc::now();
+        let generation = format!("{}-{}", started_at.format(TIME_FORMAT), &Uuid::new_v4().simple().to_string()[..8]);
+        let replica = self.replica.as_ref().ok_or("Replica not opened")?;
+        replica.put_sealed(&snapshot_key(&generation), &data).await?;
+        log::info!("📦 Replica generation {} started ({} bytes)", generation, data.len());
+
+        self.position = Some(Position {
+            generation,
+            started_at,
+            offset: 0,
+            salts: [0; 8],
+            checksum: (0, 0),
+        });
+
+        match self.apply_retention().await {
+            Ok(0) => {}
+            Ok(removed) => log::info!("🗑️  Removed {} expired replica generation(s)", removed),
+            Err(e) => log::error!("Replica retention failed: {}", e),
+        }
+        Ok(())
+    }
+
+    /// Expédie les trames ajoutées au journal, jusqu'à la dernière transaction validée dont la
+    /// somme de contrôle est correcte
+    async fn ship_frames(&mut self) -> Result<(), String> {
+        let (Some(replica), Some(position)) = (self.replica.as_ref(), self.position.as_mut()) else {
+            return Ok(());
+        };
+
+        let mut file = match fs::File::open(&self.wal_path).await {
+            Ok(file) => file,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(()),
+            Err(e) => return Err(e.to_string()),
+        };
+        let len = file.metadata().await.map_err(|e| e.to_string())?.len();
+
+        let mut header_bytes = vec![0u8; WAL_HEADER_LEN as usize];
+        let header = match len >= WAL_HEADER_LEN {
+            true => {
+                file.read_exact(&mut header_bytes).await.map_err(|e| e.to_string())?;
+                parse_wal_header(&header_bytes)
+            }
+            false => None,
+        };
+
+        // Journal remis à zéro par un autre processus : la suite ne peut plus être rejouée
+        let reset = match &header {
+            Some(header) => position.offset > 0 && (header.salts != position.salts || len < position.offset),
+            None => position.offset > 0,
+        };
+        if reset {
+            log::warn!("⚠️  The WAL was reset outside of the replicator, starting a new replica generation");
+            self.position = None;
+            return Ok(());
+        }
+        let Some(header) = header else {
+            return Ok(());
+        };
+
+        let (from, mut checksum) = match position.offset {
+            0 => (WAL_HEADER_LEN, header.checksum),
+            offset => (offset, position.checksum),
+        };
+        file.seek(io::SeekFrom::Start(from)).await.map_err(|e| e.to_string())?;
+        let mut data = Vec::new();
+        file.read_to_end(&mut data).await.map_err(|e| e.to_string())?;
+
+        let frame_len = FRAME_HEADER_LEN + header.page_size;
+        let mut cursor = 0;
+        let mut committed = None;
+        while cursor + frame_len <= data.len() {
+            let frame = &data[cursor..cursor + frame_len];
+            if frame[8..16] != header.salts {
+                break;
+            }
+            let frame_checksum = wal_checksum(
+                header.big_endian,
+                wal_checksum(header.big_endian, checksum, &frame[..8]),
+                &frame[FRAME_HEADER_LEN..],
+            );
+            if frame_checksum != (read_u32(&frame[16..20]), read_u32(&frame[20..24])) {
+                break;
+            }
+
+            checksum = frame_checksum;
+            cursor += frame_len;
+            // Taille de la base non nulle : dernière trame d'une transaction validée
+            if read_u32(&frame[4..8]) != 0 {
+                committed = Some((cursor, checksum));
+            }
+        }
+
+        let Some((end, end_checksum)) = committed else {
+            return Ok(());
+        };
+
+        let mut segment = match position.offset {
+            0 => header_bytes,
+            _ => Vec::new(),
+        };
+        segment.extend_from_slice(&data[..end]);
+        let end_offset = from + end as u64;
+
+        let key = format!(
+            "{}{}/wal/{:016}-{:016}-{}",
+            GENERATIONS, position.generation, position.offset, end_offset, Utc::now().format(TIME_FORMAT)
+        );
+        replica.put_sealed(&key, &segment).await?;
+
+        position.offset = end_offset;
+        position.salts = header.salts;
+        position.checksum = end_checksum;
+        Ok(())
+    }
+
+    /// Copie les pièces jointes pas encore présentes dans le réplica
+    async fn ship_attachments(&mut self) -> Result<(), String> {
+        let Some(replica) = self.replica.as_ref() else {
+            return Ok(());
+        };
+        let mut entries = match fs::read_dir(&self.config.attachments_dir).await {
+            Ok(entries) => entries,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(()),
+            Err(e) => return Err(e.to_string()),
+        };
+
+        while let Some(entry) = entries.next_entry().await.map_err(|e| e.to_string())? {
+            let id = entry.file_name().to_string_lossy().into_owned();
+            // Fichiers `.part` : envois en cours
+            if id.contains('.') || self.shipped_attachments.contains(&id) {
+                continue;
+            }
+            let data = match fs::read(entry.path()).await {
+                Ok(data) => data,
+                Err(e) if e.kind() == io::ErrorKind::NotFound => continue,
+                Err(e) => return Err(e.to_string()),
+            };
+            replica.store.put(&format!("{}{}", ATTACHMENTS, id), data).await?;
+            self.shipped_attachments.insert(id);
+        }
+        Ok(())
+    }
+
+    /// Supprime les générations remplacées depuis plus de REPLICA_RETENTION_HOURS : tout
+    /// instant de la fenêtre de rétention reste restaurable
+    async fn apply_retention(&mut self) -> Result<usize, String> {
+        let Some(replica) = self.replica.as_ref() else {
+            return Ok(0);
+        };
+        let cutoff = Utc::now() - Duration::hours(self.config.replica_retention_hours);
+        let generations = replica.generations().await?;
+
+        let mut removed = 0;
+        for pair in generations.windows(2) {
+            if pair[1].started_at < cutoff {
+                replica.store.delete_prefix(&format!("{}{}/", GENERATIONS, pair[0].id)).await?;
+                removed += 1;
+            }
+        }
+        Ok(removed)
+    }
+}
+
+// ==================== RESTAURATION ====================
+
+/// Générations disponibles dans le réplica, de la plus ancienne à la plus récente
+pub async fn list(config: &AppConfig, crypto: &CryptoService, key: &BackupKey) -> Result<Vec<GenerationInfo>, String> {
+    let replica = Replica::open(config, crypto, key, false).await?;
+
+    let mut infos = Vec::new();
+    for generation in replica.generations().await? {
+        let segments = replica.segments(&generation.id).await?;
+        let restorable_until = segments.last().map_or(generation.started_at, |segment| segment.shipped_at);
+        infos.push(GenerationInfo {
+            id: generation.id,
+            started_at: generation.started_at.to_rfc3339(),
+            segments: segments.len(),
+            restorable_until: restorable_until.to_rfc3339(),
+        });
+    }
+    Ok(infos)
+}
+
+/// Reconstruit la base telle qu'elle était à l'instant `at` (par défaut : le plus récent
+/// disponible) à partir du réplica, puis la met en place comme `backup::restore` (serveur arrêté)
+pub async fn restore(
+    config: &AppConfig,
+    crypto: &CryptoService,
+    key: &BackupKey,
+    at: Option<DateTime<Utc>>,
+) -> Result<ReplicaRestoreReport, String> {
+    let replica = Replica::open(config, crypto, key, false).await?;
+    let target = at.unwrap_or_else(Utc::now);
+
+    let generation = replica
+        .generations()
+        .await?
+        .into_iter()
+        .rev()
+        .find(|generation| generation.started_at <= target)
+        .ok_or_else(|| format!("No replica generation started before {}", target.to_rfc3339()))?;
+
+    let staged_database = PathBuf::from(format!("{}.replica-restore", config.database_path));
+    let staged_attachments = PathBuf::from(format!("{}.replica-restore", config.attachments_dir));
+    let staged_wal = PathBuf::from(format!("{}.replica-restore-wal", config.database_path));
+    for path in [&staged_database, &staged_attachments, &staged_wal] {
+        backup::remove_path(path).await;
+    }
+
+    let result = rebuild(config, crypto, &replica, &generation, target, &staged_database, &staged_wal, &staged_attachments).await;
+    backup::remove_path(&staged_wal).await;
+    backup::remove_path(&PathBuf::from(format!("{}.replica-restore-shm", config.database_path))).await;
+
+    let report = match result {
+        Ok(report) => report,
+        Err(e) => {
+            backup::remove_path(&staged_database).await;
+            backup::remove_path(&staged_attachments).await;
+            return Err(e);
+        }
+    };
+
+    backup::replace_data(config, &staged_database, &staged_attachments).await?;
+    Ok(report)
+}
+
+/// Instantané de la génération puis segments expédiés jusqu'à `target`, rejoués par SQLite
+#[allow(clippy::too_many_arguments)]
+async fn rebuild(
+    config: &AppConfig,
+    crypto: &CryptoService,
+    replica: &Replica,
+    generation: &Generation,
+    target: DateTime<Utc>,
+    staged_database: &Path,
+    staged_wal: &Path,
+    staged_attachments: &Path,
+) -> Result<ReplicaRestoreReport, String> {
+    let snapshot = replica
+        .get_sealed(&snapshot_key(&generation.id))
+        .await?
+        .ok_or_else(|| format!("Replica generation {} has no snapshot", generation.id))?;
+    fs::write(staged_database, snapshot).await.map_err(|e| e.to_string())?;
+
+    let mut wal = Vec::new();
+    let mut applied = 0;
+    let mut restored_to = generation.started_at;
+    for segment in replica.segments(&generation.id).await? {
+        if segment.shipped_at > target {
+            break;
+        }
+        if segment.start != wal.len() as u64 {
+            log::warn!("⚠️  Replica generation {} has a gap at offset {}, stopping there", generation.id, wal.len());
+            break;
+        }
+        let data = replica
+            .get_sealed(&segment.key)
+            .await?
+            .ok_or_else(|| format!("Replica object {} disappeared", segment.key))?;
+        if data.len() as u64 != segment.end - segment.start {
+            return Err(format!("Replica object {} has an unexpected size", segment.key));
+        }
+        wal.extend_from_slice(&data);
+        applied += 1;
+        restored_to = segment.shipped_at;
+    }
+    fs::write(staged_wal, wal).await.map_err(|e| e.to_string())?;
+
+    // SQLite rejoue le journal placé à côté de l'instantané, puis le reporte dans la base
+    let database_key = encryption::database_key(config, crypto)?;
+    let mut conn = db::connect_options(staged_database, database_key.as_deref())
+        .connect()
+        .await
+        .map_err(|e| format!("Cannot open the restored database: {}", e))?;
+    let replayed = async {
+        db::check_access(&mut conn, database_key.is_some()).await?;
+        sqlx::query("PRAGMA wal_checkpoint(TRUNCATE)").execute(&mut conn).await?;
+        let has_attachments: bool = sqlx::query_scalar(
+            "SELECT COUNT(*) > 0 FROM sqlite_master WHERE type = 'table' AND name = 'attachments'"
+        )
+        .fetch_one(&mut conn)
+        .await?;
+        match has_attachments {
+            true => sqlx::query_scalar::<_, String>("SELECT id FROM attachments").fetch_all(&mut conn).await,
+            false => Ok(Vec::new()),
+        }
+    }
+    .await;
+    conn.close().await.ok();
+    let ids = replayed.map_err(|e| e.to_string())?;
+
+    fs::create_dir_all(staged_attachments).await.map_err(|e| e.to_string())?;
+    let mut attachments = 0;
+    for id in ids {
+        if let Some(data) = replica.store.get(&format!("{}{}", ATTACHMENTS, id)).await? {
+            fs::write(staged_attachments.join(&id), data).await.map_err(|e| e.to_string())?;
+            attachments += 1;
+        }
+    }
+    let missing_attachments = backup::check_database(staged_database, staged_attachments, database_key.as_deref()).await?;
+
+    Ok(ReplicaRestoreReport {
+        generation: generation.id.clone(),
+        restored_to: restored_to.to_rfc3339(),
+        segments: applied,
+        attachments,
+        missing_attachments,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    async fn count(database_path: &str) -> i64 {
+        let pool = db::connect(database_path, false, None, false).await.unwrap();
+        let count = sqlx::query_scalar("SELECT COUNT(*) FROM replica_probe").fetch_one(&pool).await.unwrap();
+        pool.close().await;
+        count
+    }
+
+    /// Réplica dans un répertoire local : instantané, segments du journal, puis restauration
+    /// à un instant situé entre deux segments
+    #[tokio::test]
+    async fn directory_replica_restores_to_a_point_in_time() {
+        let dir = std::env::temp_dir().join(format!("replica-test-{}", Uuid::new_v4()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let mut config = AppConfig::from_env();
+        config.database_path = dir.join("vault.db").to_string_lossy().into_owned();
+        config.database_encryption = false;
+        config.attachments_dir = dir.join("attachments").to_string_lossy().into_owned();
+        config.replica_url = Some(dir.join("replica").to_string_lossy().into_owned());
+        let crypto = CryptoService::new("replica test master password").unwrap();
+        let key = BackupKey::new("replica test backup key".into()).unwrap();
+
+        let pool = db::connect(&config.database_path, true, None, true).await.unwrap();
+        db::init_tables(&pool).await.unwrap();
+        sqlx::query("CREATE TABLE replica_probe (n INTEGER NOT NULL)").execute(&pool).await.unwrap();
+
+        let mut replicator = Replicator::new(&config, &crypto, &key).await.unwrap();
+        replicator.sync().await.unwrap();
+
+        sqlx::query("INSERT INTO replica_probe (n) VALUES (1)").execute(&pool).await.unwrap();
+        replicator.sync().await.unwrap();
+        tokio::time::sleep(std::time::Duration::from_millis(20)).await;
+        let between = Utc::now();
+        tokio::time::sleep(std::time::Duration::from_millis(20)).await;
+
+        sqlx::query("INSERT INTO replica_probe (n) VALUES (2)").execute(&pool).await.unwrap();
+        replicator.sync().await.unwrap();
+        pool.close().await;
+        drop(replicator);
+
+        let generations = list(&config, &crypto, &key).await.unwrap();
+        assert_eq!(generations.len(), 1);
+        assert!(generations[0].segments >= 2);
+
+        let report = restore(&config, &crypto, &key, Some(between)).await.unwrap();
+        assert!(report.segments < generations[0].segments);
+        assert_eq!(count(&config.database_path).await, 1);
+
+        std::fs::remove_dir_all(&dir).ok();
+    }
+}
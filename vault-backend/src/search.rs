@@ -0,0 +1,183 @@
+use unicode_normalization::UnicodeNormalization;
+use unicode_normalization::char::is_combining_mark;
+use crate::crypto::CryptoService;
+
+// Les préfixes indexés vont de MIN_PREFIX à MAX_PREFIX caractères ; au-delà, un terme
+// de recherche est ramené à ses MAX_PREFIX premiers caractères
+const MIN_PREFIX: usize = 2;
+const MAX_PREFIX: usize = 12;
+// Longueur (en hexadécimal) des jetons aveugles stockés dans l'index
+const TOKEN_LEN: usize = 24;
+const SNIPPET_WORDS: usize = 12;
+
+/// Découpe un texte en mots normalisés : minuscules, sans accents, séparés par tout caractère non alphanumérique
+pub fn normalize_words(text: &str) -> Vec<String> {
+    let folded: String = text
+        .nfd()
+        .filter(|c| !is_combining_mark(*c))
+        .flat_map(char::to_lowercase)
+        .collect();
+
+    folded
+        .split(|c: char| !c.is_alphanumeric())
+        .filter(|w| !w.is_empty())
+        .map(str::to_string)
+        .collect()
+}
+
+/// Extrait l'hôte d'une URL (sans schéma, identifiants, port ni chemin), en minuscules
+pub fn url_host(url: &str) -> Option<String> {
+    let url = url.trim();
+    let rest = url.split_once("://").map(|(_, r)| r).unwrap_or(url);
+    let authority = rest.split(['/', '?', '#']).next().unwrap_or("");
+    let host = authority.rsplit_once('@').map(|(_, h)| h).unwrap_or(authority);
+    let host = host.split(':').next().unwrap_or("").trim_end_matches('.').to_lowercase();
+
+    if host.is_empty() {
+        None
+    } else {
+        Some(host)
+    }
+}
+
+fn blind_token(crypto: &CryptoService, kind: &str, value: &str) -> String {
+    let mut token = crypto.blind_index(kind, value);
+    token.truncate(TOKEN_LEN);
+    token
+}
+
+fn prefix(word: &str, len: usize) -> String {
+    word.chars().take(len).collect()
+}
+
+/// Jetons aveugles d'un champ : un jeton par mot complet et un par préfixe de mot,
+/// de sorte que l'index ne contienne jamais le texte en clair
+pub fn index_tokens(crypto: &CryptoService, text: &str) -> String {
+    let mut tokens = Vec::new();
+
+    for word in normalize_words(text) {
+        tokens.push(blind_token(crypto, "search-word", &word));
+        for len in MIN_PREFIX..=word.chars().count().min(MAX_PREFIX) {
+            tokens.push(blind_token(crypto, "search-prefix", &prefix(&word, len)));
+        }
+    }
+
+    tokens.join(" ")
+}
+
+/// Termes d'une recherche utilisateur, normalisés comme le texte indexé
+pub fn search_terms(input: &str) -> Vec<String> {
+    normalize_words(input)
+}
+
+/// Requête FTS5 sur les jetons aveugles : chaque terme est un préfixe et tous doivent correspondre
+pub fn match_query(crypto: &CryptoService, terms: &[String]) -> String {
+    terms
+        .iter()
+        .map(|term| {
+            let token = if term.chars().count() < MIN_PREFIX {
+                blind_token(crypto, "search-word", term)
+            } else {
+                blind_token(crypto, "search-prefix", &prefix(term, MAX_PREFIX))
+            };
+            format!("\"{}\"", token)
+        })
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+fn word_matches(word: &str, terms: &[String]) -> bool {
+    let normalized = normalize_words(word).concat();
+    !normalized.is_empty() && terms.iter().any(|t| normalized.starts_with(t.as_str()))
+}
+
+fn push_escaped(output: &mut String, c: char) {
+    match c {
+        '&' => output.push_str("&amp;"),
+        '<' => output.push_str("&lt;"),
+        '>' => output.push_str("&gt;"),
+        '"' => output.push_str("&quot;"),
+        '\'' => output.push_str("&#39;"),
+        _ => output.push(c),
+    }
+}
+
+/// Échappe un texte déchiffré pour l'insérer tel quel dans du HTML
+pub fn escape_html(text: &str) -> String {
+    let mut output = String::with_capacity(text.len());
+    for c in text.chars() {
+        push_escaped(&mut output, c);
+    }
+    output
+}
+
+/// Entoure de `<mark>` les mots commençant par l'un des termes ; `None` si rien ne correspond.
+/// Le texte est échappé : seuls les marqueurs ajoutés ici sont du HTML
+pub fn highlight(text: &str, terms: &[String]) -> Option<String> {
+    let mut output = String::with_capacity(text.len());
+    let mut word = String::new();
+    let mut matched = false;
+
+    // Les mots ne contiennent que des caractères alphanumériques, rien à échapper
+    let mut flush = |word: &mut String, output: &mut String| {
+        if word_matches(word, terms) {
+            matched = true;
+            output.push_str("<mark>");
+            output.push_str(word);
+            output.push_str("</mark>");
+        } else {
+            output.push_str(word);
+        }
+        word.clear();
+    };
+
+    for c in text.chars() {
+        if c.is_alphanumeric() {
+            word.push(c);
+        } else {
+            flush(&mut word, &mut output);
+            push_escaped(&mut output, c);
+        }
+    }
+    flush(&mut word, &mut output);
+
+    if matched {
+        Some(output)
+    } else {
+        None
+    }
+}
+
+/// Extrait surligné d'une douzaine de mots autour de la première correspondance
+pub fn snippet(text: &str, terms: &[String]) -> Option<String> {
+    let words: Vec<&str> = text.split_whitespace().collect();
+    let first = words.iter().position(|w| highlight(w, terms).is_some())?;
+
+    let start = first.saturating_sub(SNIPPET_WORDS / 2);
+    let end = (start + SNIPPET_WORDS).min(words.len());
+    let mut excerpt = highlight(&words[start..end].join(" "), terms)?;
+
+    if start > 0 {
+        excerpt.insert(0, '…');
+    }
+    if end < words.len() {
+        excerpt.push('…');
+    }
+
+    Some(excerpt)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn highlight_escapes_decrypted_text() {
+        let terms = search_terms("prod");
+        let html = highlight("<img src=x onerror=alert(1)> prod & \"co\"", &terms).unwrap();
+
+        assert_eq!(html, "&lt;img src=x onerror=alert(1)&gt; <mark>prod</mark> &amp; &quot;co&quot;");
+        assert_eq!(snippet("a <b>prod</b>", &terms).unwrap(), "a &lt;b&gt;<mark>prod</mark>&lt;/b&gt;");
+        assert_eq!(escape_html("<script>"), "&lt;script&gt;");
+    }
+}
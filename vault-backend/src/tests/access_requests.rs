@@ -0,0 +1,111 @@
+use chrono::{Duration, Utc};
+use serde_json::json;
+
+use super::*;
+use crate::handlers_admin;
+
+async fn request_access(pool: &web::Data<SqlitePool>, username: &str, body: Value) -> (u16, Value) {
+    read(handlers::create_access_request(as_user(username), pool.clone(), notifier(), json(body)).await).await
+}
+
+async fn decide(pool: &web::Data<SqlitePool>, username: &str, id: &str, decision: &str) -> (u16, Value) {
+    read(handlers::decide_access_request(
+        as_user(username),
+        pool.clone(),
+        notifier(),
+        json(json!({ "id": id, "decision": decision })),
+    ).await).await
+}
+
+async fn pending(pool: &web::Data<SqlitePool>, username: &str, role: &str, hours: Option<i64>) -> String {
+    let (status, body) = request_access(pool, username, json!({
+        "group_name": "ops",
+        "role": role,
+        "justification": "on-call this week",
+        "duration_hours": hours,
+    })).await;
+    assert_eq!(status, 201, "{}", body);
+    body["id"].as_str().unwrap().to_string()
+}
+
+#[actix_web::test]
+async fn requests_are_checked() {
+    let pool = pool().await;
+    group(&pool, "ops", &["alice"]).await;
+    let request = |body: Value| request_access(&pool, "bob", body);
+
+    assert_eq!(request(json!({ "group_name": "ops", "justification": " " })).await.0, 400);
+    assert_eq!(request(json!({ "group_name": "ops", "justification": "why", "duration_hours": 0 })).await.0, 400);
+    assert_eq!(request(json!({ "group_name": "ops", "justification": "why", "duration_hours": 24 * 91 })).await.0, 400);
+    assert_eq!(request(json!({ "group_name": "nowhere", "justification": "why" })).await.0, 404);
+
+    pending(&pool, "bob", "viewer", None).await;
+    assert_eq!(request(json!({ "group_name": "ops", "justification": "again" })).await.0, 409);
+}
+
+#[actix_web::test]
+async fn only_owners_decide() {
+    let pool = pool().await;
+    group(&pool, "ops", &["alice", "carol"]).await;
+    let id = pending(&pool, "bob", "editor", None).await;
+
+    assert_eq!(decide(&pool, "carol", &id, "approve").await.0, 403);
+    assert_eq!(decide(&pool, "mallory", &id, "approve").await.0, 403);
+
+    let (status, request) = decide(&pool, "alice", &id, "approve").await;
+    assert_eq!(status, 200);
+    assert_eq!(request["status"], "approved");
+    assert_eq!(db::group_role(pool.get_ref(), "ops", "bob").await.unwrap(), Some(GroupRole::Editor));
+    assert_eq!(decide(&pool, "alice", &id, "deny").await.0, 409);
+
+    let id = pending(&pool, "dave", "viewer", None).await;
+    assert_eq!(decide(&pool, "alice", &id, "deny").await.1["status"], "denied");
+    assert!(db::group_role(pool.get_ref(), "ops", "dave").await.unwrap().is_none());
+}
+
+#[actix_web::test]
+async fn ownership_is_granted_by_original_owners_and_admins() {
+    let pool = pool().await;
+    group(&pool, "ops", &["alice", "carol"]).await;
+    db::set_group_role(pool.get_ref(), "ops", "carol", GroupRole::Owner, "root").await.unwrap();
+
+    // Carol, nommée propriétaire après la création, ne peut pas en nommer d'autres
+    let id = pending(&pool, "bob", "owner", None).await;
+    assert_eq!(decide(&pool, "carol", &id, "approve").await.0, 403);
+    assert_eq!(decide(&pool, "alice", &id, "approve").await.0, 200);
+
+    let id = pending(&pool, "dave", "editor", None).await;
+    assert_eq!(decide(&pool, "dave", &id, "approve").await.0, 403);
+
+    let resp = handlers_admin::decide_access_request(
+        as_admin("root", "admin"),
+        pool.clone(),
+        notifier(),
+        json(json!({ "id": id, "decision": "deny" })),
+    ).await;
+    assert_eq!(read(resp).await.0, 200);
+}
+
+#[actix_web::test]
+async fn temporary_grants_expire() {
+    let pool = pool().await;
+    group(&pool, "ops", &["alice"]).await;
+    let id = pending(&pool, "bob", "viewer", Some(24)).await;
+
+    let (_, request) = decide(&pool, "alice", &id, "approve").await;
+    assert!(request["access_expires_at"].is_string());
+    assert_eq!(db::group_role(pool.get_ref(), "ops", "bob").await.unwrap(), Some(GroupRole::Viewer));
+
+    sqlx::query("UPDATE user_groups SET expires_at = ? WHERE username = 'bob'")
+        .bind((Utc::now() - Duration::minutes(1)).to_rfc3339())
+        .execute(pool.get_ref())
+        .await
+        .unwrap();
+    // Une adhésion échue ne donne plus accès, même avant le passage du job
+    assert!(db::group_role(pool.get_ref(), "ops", "bob").await.unwrap().is_none());
+
+    let expired = db::expire_memberships(pool.get_ref()).await.unwrap();
+    assert_eq!(expired.len(), 1);
+    let request = db::get_access_request(pool.get_ref(), &id).await.unwrap().unwrap();
+    assert_eq!(request.status, "expired");
+}
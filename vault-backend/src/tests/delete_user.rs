@@ -0,0 +1,110 @@
+use serde_json::json;
+
+use super::*;
+use crate::delete_user::delete_user;
+
+async fn delete(pool: &web::Data<SqlitePool>, body: serde_json::Value) -> (u16, serde_json::Value) {
+    read(delete_user(as_admin("root", "admin"), pool.clone(), json(body), config(), crypto()).await).await
+}
+
+async fn can_see(pool: &web::Data<SqlitePool>, username: &str, item_type: &str, id: &str) -> bool {
+    let (status, _) = read(handlers::get_secret_versions(
+        as_user(username),
+        pool.clone(),
+        json(json!({ "item_type": item_type, "item_id": id })),
+    ).await).await;
+    status == 200
+}
+
+#[actix_web::test]
+async fn only_admins_can_delete_users() {
+    let pool = pool().await;
+    let id = user(&pool, "alice").await;
+
+    let resp = delete_user(as_user("bob"), pool.clone(), json(json!({ "id": id })), config(), crypto()).await;
+    assert_eq!(read(resp).await.0, 401);
+    assert_eq!(delete(&pool, json!({ "id": "missing" })).await.0, 404);
+}
+
+#[actix_web::test]
+async fn items_are_deleted_without_a_target() {
+    let pool = pool().await;
+    let id = user(&pool, "alice").await;
+    group(&pool, "ops", &["carol", "alice"]).await;
+    let item = add_account(&pool, "alice", "mail", "p1").await;
+
+    let (status, body) = delete(&pool, json!({ "id": id })).await;
+    assert_eq!(status, 200);
+    assert_eq!(body["accounts_deleted"], 1);
+    assert_eq!(body["memberships_removed"], 1);
+    assert!(!can_see(&pool, "alice", "account", &item).await);
+    assert!(db::group_role(pool.get_ref(), "ops", "alice").await.unwrap().is_none());
+}
+
+#[actix_web::test]
+async fn items_can_be_transferred_to_a_user() {
+    let pool = pool().await;
+    let id = user(&pool, "alice").await;
+    user(&pool, "bob").await;
+    let item = add_account(&pool, "alice", "mail", "p1").await;
+
+    assert_eq!(delete(&pool, json!({ "id": id, "transfer_to_user": "alice" })).await.0, 400);
+    assert_eq!(delete(&pool, json!({ "id": id, "transfer_to_user": "nobody" })).await.0, 400);
+    assert_eq!(delete(&pool, json!({ "id": id, "transfer_to_user": "bob", "transfer_to_group": "ops" })).await.0, 400);
+
+    let (status, body) = delete(&pool, json!({ "id": id, "transfer_to_user": "bob" })).await;
+    assert_eq!(status, 200);
+    assert_eq!(body["accounts_transferred"], 1);
+    assert_eq!(body["transferred_to"], "user:bob");
+    assert!(can_see(&pool, "bob", "account", &item).await);
+}
+
+#[actix_web::test]
+async fn items_can_be_transferred_to_a_group() {
+    let pool = pool().await;
+    let id = user(&pool, "alice").await;
+    group(&pool, "ops", &["carol"]).await;
+    let item = add_account(&pool, "alice", "mail", "p1").await;
+
+    assert_eq!(delete(&pool, json!({ "id": id, "transfer_to_group": "nowhere" })).await.0, 400);
+
+    let (status, body) = delete(&pool, json!({ "id": id, "transfer_to_group": "ops" })).await;
+    assert_eq!(status, 200);
+    assert_eq!(body["accounts_transferred"], 1);
+    // L'identifiant et l'historique suivent l'élément dans le groupe
+    assert!(can_see(&pool, "carol", "account_group", &item).await);
+}
+
+#[actix_web::test]
+async fn a_title_conflict_names_the_items_and_keeps_the_user() {
+    let pool = pool().await;
+    let id = user(&pool, "alice").await;
+    group(&pool, "ops", &["carol"]).await;
+    add_group_account(&pool, "carol", "ops", "mail").await;
+    let item = add_account(&pool, "alice", "mail", "p1").await;
+    add_account(&pool, "alice", "bank", "p2").await;
+
+    let (status, body) = delete(&pool, json!({ "id": id, "transfer_to_group": "ops" })).await;
+    assert_eq!(status, 409);
+    assert_eq!(body["conflicts"], json!([{ "item_type": "account", "title": "mail" }]));
+    assert!(body["error"].as_str().unwrap().contains("account 'mail'"));
+
+    assert!(db::user_exists(pool.get_ref(), "alice").await.unwrap());
+    assert!(can_see(&pool, "alice", "account", &item).await);
+}
+
+#[actix_web::test]
+async fn the_last_owner_of_a_group_is_kept() {
+    let pool = pool().await;
+    let id = user(&pool, "alice").await;
+    group(&pool, "ops", &["alice", "carol"]).await;
+    group(&pool, "solo", &["alice"]).await;
+
+    let (status, body) = delete(&pool, json!({ "id": id })).await;
+    assert_eq!(status, 409);
+    assert_eq!(body["groups"], json!(["ops"]));
+    assert!(db::user_exists(pool.get_ref(), "alice").await.unwrap());
+
+    db::set_group_role(pool.get_ref(), "ops", "carol", GroupRole::Owner, "root").await.unwrap();
+    assert_eq!(delete(&pool, json!({ "id": id })).await.0, 200);
+}
@@ -0,0 +1,201 @@
+use chrono::{Duration, Utc};
+use serde_json::json;
+
+use super::*;
+use crate::handlers_admin;
+use crate::models::{EmergencyRequestsQuery, ListParams};
+
+async fn designate(pool: &web::Data<SqlitePool>, username: &str, contact: &str, group_name: Option<&str>) -> u16 {
+    read(handlers::set_emergency_contact(
+        as_user(username),
+        pool.clone(),
+        config(),
+        notifier(),
+        json(json!({ "contact": contact, "group_name": group_name, "wait_hours": 24 })),
+    ).await).await.0
+}
+
+async fn request_access(pool: &web::Data<SqlitePool>, username: &str, target_type: &str, target_name: &str) -> (u16, Value) {
+    read(handlers::create_emergency_request(
+        as_user(username),
+        pool.clone(),
+        notifier(),
+        json(json!({ "target_type": target_type, "target_name": target_name, "reason": "owner unreachable" })),
+    ).await).await
+}
+
+async fn pending(pool: &web::Data<SqlitePool>, username: &str, target_type: &str, target_name: &str) -> String {
+    let (status, body) = request_access(pool, username, target_type, target_name).await;
+    assert_eq!(status, 201, "{}", body);
+    body["id"].as_str().unwrap().to_string()
+}
+
+async fn decide(pool: &web::Data<SqlitePool>, username: &str, id: &str, decision: &str) -> (u16, Value) {
+    read(handlers::decide_emergency_request(
+        as_user(username),
+        pool.clone(),
+        config(),
+        notifier(),
+        json(json!({ "id": id, "decision": decision })),
+    ).await).await
+}
+
+async fn end(pool: &web::Data<SqlitePool>, username: &str, id: &str) -> (u16, Value) {
+    read(handlers::end_emergency_request(as_user(username), pool.clone(), notifier(), json(json!({ "id": id }))).await).await
+}
+
+async fn open_vault(pool: &web::Data<SqlitePool>, username: &str, owner: &str) -> (u16, Value) {
+    read(handlers::get_emergency_vault(as_user(username), pool.clone(), json(json!({ "username": owner })), crypto()).await).await
+}
+
+async fn reveal(pool: &web::Data<SqlitePool>, username: &str, id: &str) -> u16 {
+    read(handlers::reveal_item(
+        as_user(username),
+        pool.clone(),
+        json(json!({ "item_type": "account", "item_id": id })),
+        crypto(),
+    ).await).await.0
+}
+
+async fn override_wait(pool: &web::Data<SqlitePool>, req: HttpRequest, id: &str) -> (u16, Value) {
+    read(handlers_admin::override_emergency_request(req, pool.clone(), config(), notifier(), json(json!({ "id": id }))).await).await
+}
+
+async fn review(pool: &web::Data<SqlitePool>, username: &str) -> Vec<String> {
+    let (status, body) = read(handlers::get_emergency_requests(
+        as_user(username),
+        pool.clone(),
+        web::Query::<ListParams>::from_query("").unwrap(),
+        web::Query::<EmergencyRequestsQuery>::from_query("scope=review").unwrap(),
+        crypto(),
+    ).await).await;
+    assert_eq!(status, 200);
+    body["items"].as_array().unwrap().iter().map(|r| r["requester"].as_str().unwrap().to_string()).collect()
+}
+
+#[actix_web::test]
+async fn only_designated_contacts_request_access() {
+    let pool = pool().await;
+    user(&pool, "carol").await;
+    group(&pool, "ops", &["alice", "bob"]).await;
+    assert_eq!(designate(&pool, "alice", "nobody", None).await, 400);
+    assert_eq!(designate(&pool, "alice", "alice", None).await, 400);
+    assert_eq!(designate(&pool, "alice", "carol", None).await, 200);
+
+    assert_eq!(request_access(&pool, "mallory", "user", "alice").await.0, 403);
+    assert_eq!(request_access(&pool, "carol", "team", "alice").await.0, 400);
+    assert_eq!(designate(&pool, "bob", "carol", Some("ops")).await, 403);
+    assert_eq!(designate(&pool, "alice", "carol", Some("ops")).await, 200);
+
+    pending(&pool, "carol", "user", "alice").await;
+    assert_eq!(request_access(&pool, "carol", "user", "alice").await.0, 409);
+
+    let (status, body) = read(handlers::set_emergency_contact(
+        as_user("alice"),
+        pool.clone(),
+        config(),
+        notifier(),
+        json(json!({ "contact": "carol", "wait_hours": 0 })),
+    ).await).await;
+    assert_eq!(status, 400, "{}", body);
+}
+
+#[actix_web::test]
+async fn access_waits_for_the_waiting_period() {
+    let pool = pool().await;
+    user(&pool, "carol").await;
+    let item = add_account(&pool, "alice", "bank", "p1").await;
+    assert_eq!(designate(&pool, "alice", "carol", None).await, 200);
+    let id = pending(&pool, "carol", "user", "alice").await;
+
+    assert_eq!(open_vault(&pool, "carol", "alice").await.0, 403);
+    assert_eq!(reveal(&pool, "carol", &item).await, 404);
+    assert_eq!(decide(&pool, "carol", &id, "approve").await.0, 403);
+    assert_eq!(decide(&pool, "mallory", &id, "deny").await.0, 403);
+
+    // Le délai écoulé, la tâche planifiée accorde l'accès sans intervention du titulaire
+    sqlx::query("UPDATE emergency_requests SET grant_at = ?")
+        .bind((Utc::now() - Duration::minutes(1)).to_rfc3339())
+        .execute(pool.get_ref())
+        .await
+        .unwrap();
+    let (granted, _) = db::process_emergency_requests(pool.get_ref(), 24).await.unwrap();
+    assert_eq!(granted.len(), 1);
+    assert_eq!(granted[0].decided_by.as_deref(), Some("scheduler"));
+
+    let (status, body) = open_vault(&pool, "carol", "alice").await;
+    assert_eq!(status, 200);
+    assert_eq!(body[0]["id"], item);
+    assert_eq!(reveal(&pool, "carol", &item).await, 200);
+    assert_eq!(open_vault(&pool, "mallory", "alice").await.0, 403);
+
+    let (status, body) = end(&pool, "alice", &id).await;
+    assert_eq!(status, 200);
+    assert_eq!(body["status"], "revoked");
+    assert_eq!(open_vault(&pool, "carol", "alice").await.0, 403);
+}
+
+#[actix_web::test]
+async fn the_owner_denies_during_the_waiting_period() {
+    let pool = pool().await;
+    user(&pool, "carol").await;
+    assert_eq!(designate(&pool, "alice", "carol", None).await, 200);
+    let id = pending(&pool, "carol", "user", "alice").await;
+
+    assert_eq!(end(&pool, "alice", &id).await.0, 403);
+    let (status, body) = decide(&pool, "alice", &id, "deny").await;
+    assert_eq!(status, 200);
+    assert_eq!(body["status"], "denied");
+    assert_eq!(decide(&pool, "alice", &id, "approve").await.0, 409);
+
+    sqlx::query("UPDATE emergency_requests SET grant_at = ?")
+        .bind((Utc::now() - Duration::minutes(1)).to_rfc3339())
+        .execute(pool.get_ref())
+        .await
+        .unwrap();
+    let (granted, _) = db::process_emergency_requests(pool.get_ref(), 24).await.unwrap();
+    assert!(granted.is_empty());
+    assert_eq!(open_vault(&pool, "carol", "alice").await.0, 403);
+}
+
+#[actix_web::test]
+async fn overrides_need_two_administrators() {
+    let pool = pool().await;
+    user(&pool, "carol").await;
+    assert_eq!(designate(&pool, "alice", "carol", None).await, 200);
+    let id = pending(&pool, "carol", "user", "alice").await;
+
+    assert_eq!(override_wait(&pool, as_user("alice"), &id).await.0, 401);
+
+    let (status, body) = override_wait(&pool, as_admin("root", "admin"), &id).await;
+    assert_eq!(status, 200);
+    assert_eq!(body["granted"], false);
+    assert_eq!(open_vault(&pool, "carol", "alice").await.0, 403);
+    assert_eq!(override_wait(&pool, as_admin("root", "admin"), &id).await.0, 409);
+
+    let (status, body) = override_wait(&pool, as_admin("ops-admin", "admin"), &id).await;
+    assert_eq!(status, 200);
+    assert_eq!(body["granted"], true);
+    assert_eq!(body["request"]["status"], "granted");
+    assert_eq!(open_vault(&pool, "carol", "alice").await.0, 200);
+    assert_eq!(override_wait(&pool, as_admin("root", "admin"), &id).await.0, 409);
+}
+
+#[actix_web::test]
+async fn group_access_is_a_temporary_read_only_membership() {
+    let pool = pool().await;
+    user(&pool, "carol").await;
+    group(&pool, "ops", &["alice", "bob"]).await;
+    assert_eq!(designate(&pool, "alice", "carol", Some("ops")).await, 200);
+    let id = pending(&pool, "carol", "group", "ops").await;
+
+    assert_eq!(review(&pool, "alice").await, ["carol"]);
+    assert!(review(&pool, "bob").await.is_empty());
+    assert_eq!(decide(&pool, "bob", &id, "approve").await.0, 403);
+
+    assert_eq!(decide(&pool, "alice", &id, "approve").await.0, 200);
+    assert_eq!(db::group_role(pool.get_ref(), "ops", "carol").await.unwrap(), Some(GroupRole::Viewer));
+
+    assert_eq!(end(&pool, "alice", &id).await.1["status"], "revoked");
+    assert_eq!(db::group_role(pool.get_ref(), "ops", "carol").await.unwrap(), None);
+}
@@ -0,0 +1,118 @@
+use chrono::{Duration, Utc};
+use serde_json::json;
+
+use super::*;
+use crate::handlers_admin;
+use crate::models::ExpiringItemsQuery;
+
+async fn set_deadlines(pool: &web::Data<SqlitePool>, username: &str, item_type: &str, id: &str, deadlines: Value) -> u16 {
+    let mut body = deadlines;
+    body["item_type"] = json!(item_type);
+    body["item_id"] = json!(id);
+    read(handlers::set_item_deadlines(as_user(username), pool.clone(), json(body)).await).await.0
+}
+
+async fn expiring(pool: &web::Data<SqlitePool>, username: &str, query: &str) -> (u16, Value) {
+    read(handlers::get_expiring_items(
+        as_user(username),
+        pool.clone(),
+        web::Query::<ExpiringItemsQuery>::from_query(query).unwrap(),
+        crypto(),
+    ).await).await
+}
+
+/// Fait remonter le dernier renouvellement d'un élément dans le passé
+async fn rotated_days_ago(pool: &SqlitePool, id: &str, days: i64) {
+    sqlx::query("UPDATE vault_items SET rotated_at = ? WHERE id = ?")
+        .bind((Utc::now() - Duration::days(days)).to_rfc3339())
+        .bind(id)
+        .execute(pool)
+        .await
+        .unwrap();
+}
+
+#[actix_web::test]
+async fn deadlines_are_checked_and_limited_to_the_owner() {
+    let pool = pool().await;
+    let id = add_account(&pool, "alice", "mail", "p1").await;
+
+    assert_eq!(set_deadlines(&pool, "bob", "account", &id, json!({ "rotate_every": 30 })).await, 404);
+    assert_eq!(set_deadlines(&pool, "alice", "account", &id, json!({ "expires_at": "tomorrow" })).await, 400);
+    assert_eq!(set_deadlines(&pool, "alice", "account", &id, json!({ "rotate_every": 0 })).await, 400);
+    assert_eq!(expiring(&pool, "alice", "within_days=-1").await.0, 400);
+}
+
+#[actix_web::test]
+async fn statuses_follow_the_window() {
+    let pool = pool().await;
+    let expired = add_account(&pool, "alice", "old", "p1").await;
+    let soon = add_account(&pool, "alice", "soon", "p2").await;
+    let overdue = add_account(&pool, "alice", "stale", "p3").await;
+
+    let yesterday = (Utc::now() - Duration::days(1)).format("%Y-%m-%d").to_string();
+    let in_three_days = (Utc::now() + Duration::days(3)).to_rfc3339();
+    set_deadlines(&pool, "alice", "account", &expired, json!({ "expires_at": yesterday })).await;
+    set_deadlines(&pool, "alice", "account", &soon, json!({ "expires_at": in_three_days })).await;
+    set_deadlines(&pool, "alice", "account", &overdue, json!({ "rotate_every": 30 })).await;
+    rotated_days_ago(&pool, &overdue, 40).await;
+
+    let (status, report) = expiring(&pool, "alice", "").await;
+    assert_eq!(status, 200);
+    let statuses: Vec<(&str, &str)> = report["personal"].as_array().unwrap().iter()
+        .map(|item| (item["title"].as_str().unwrap(), item["status"].as_str().unwrap()))
+        .collect();
+    assert_eq!(statuses.len(), 3);
+    assert!(statuses.contains(&("old", "expired")));
+    assert!(statuses.contains(&("soon", "expires_soon")));
+    assert!(statuses.contains(&("stale", "rotation_overdue")));
+
+    let (_, report) = expiring(&pool, "alice", "within_days=1").await;
+    assert_eq!(report["personal"].as_array().unwrap().len(), 2);
+
+    // Renouveler le secret remet l'échéance de rotation à zéro
+    read(handlers::update_account(
+        as_user("alice"),
+        pool.clone(),
+        json(json!({ "id": overdue, "password_account": "p4" })),
+        config(),
+        crypto(),
+    ).await).await;
+    let (_, report) = expiring(&pool, "alice", "within_days=1").await;
+    assert_eq!(report["personal"].as_array().unwrap().len(), 1);
+
+    let (_, report) = expiring(&pool, "bob", "").await;
+    assert!(report["personal"].as_array().unwrap().is_empty());
+}
+
+#[actix_web::test]
+async fn group_policy_caps_the_rotation_interval() {
+    let pool = pool().await;
+    group(&pool, "ops", &["alice"]).await;
+    let id = add_group_account(&pool, "alice", "ops", "db").await;
+    rotated_days_ago(&pool, &id, 20).await;
+    let policy = |group_name: &str, days: i64| json!({ "group_name": group_name, "max_secret_age_days": days });
+
+    let (status, _) = read(handlers_admin::set_group_policy(as_admin("root", "auditor"), pool.clone(), json(policy("ops", 10))).await).await;
+    assert_eq!(status, 403);
+    let (status, _) = read(handlers_admin::set_group_policy(as_admin("root", "admin"), pool.clone(), json(policy("nowhere", 10))).await).await;
+    assert_eq!(status, 404);
+
+    let (_, report) = expiring(&pool, "alice", "").await;
+    assert!(report["groups"][0]["items"].as_array().unwrap().is_empty());
+
+    let (status, _) = read(handlers_admin::set_group_policy(as_admin("root", "admin"), pool.clone(), json(policy("ops", 10))).await).await;
+    assert_eq!(status, 200);
+
+    // La politique s'applique même quand l'élément a son propre intervalle, plus long
+    set_deadlines(&pool, "alice", "account_group", &id, json!({ "rotate_every": 90 })).await;
+    rotated_days_ago(&pool, &id, 20).await;
+    let (_, report) = expiring(&pool, "alice", "").await;
+    let group = &report["groups"][0];
+    assert_eq!(group["group_name"], "ops");
+    assert_eq!(group["max_secret_age_days"], 10);
+    assert_eq!(group["items"][0]["status"], "rotation_overdue");
+    assert_eq!(group["items"][0]["rotate_every"], 10);
+
+    let (_, report) = expiring(&pool, "bob", "").await;
+    assert!(report["groups"].as_array().unwrap().is_empty());
+}
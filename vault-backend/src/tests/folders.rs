@@ -0,0 +1,119 @@
+use serde_json::json;
+
+use super::*;
+use crate::models::ListParams;
+
+async fn create_folder(pool: &web::Data<SqlitePool>, username: &str, body: Value) -> (u16, Value) {
+    read(handlers::create_folder(as_user(username), pool.clone(), json(body), crypto()).await).await
+}
+
+async fn folder(pool: &web::Data<SqlitePool>, username: &str, name: &str, parent_id: Option<&str>) -> String {
+    let (status, body) = create_folder(pool, username, json!({ "name": name, "parent_id": parent_id })).await;
+    assert_eq!(status, 201, "{}", body);
+    body["id"].as_str().unwrap().to_string()
+}
+
+async fn move_folder(pool: &web::Data<SqlitePool>, username: &str, id: &str, parent_id: &str) -> u16 {
+    read(handlers::move_folder(
+        as_user(username),
+        pool.clone(),
+        json(json!({ "id": id, "parent_id": parent_id })),
+        crypto(),
+    ).await).await.0
+}
+
+async fn move_item(pool: &web::Data<SqlitePool>, username: &str, item_id: &str, folder_id: &str) -> u16 {
+    read(handlers::move_item(
+        as_user(username),
+        pool.clone(),
+        json(json!({ "item_type": "account", "item_id": item_id, "folder_id": folder_id })),
+    ).await).await.0
+}
+
+async fn list_accounts(pool: &web::Data<SqlitePool>, username: &str, query: &str) -> Value {
+    read(handlers::get_account(
+        as_user(username),
+        pool.clone(),
+        web::Query::<ListParams>::from_query(query).unwrap(),
+        crypto(),
+    ).await).await.1
+}
+
+#[actix_web::test]
+async fn folders_are_limited_to_their_vault() {
+    let pool = pool().await;
+    group(&pool, "ops", &["alice"]).await;
+    let mine = folder(&pool, "alice", "perso", None).await;
+
+    let (_, folders) = read(handlers::get_folders(as_user("bob"), pool.clone(), json(json!({})), crypto()).await).await;
+    assert!(folders.as_array().unwrap().is_empty());
+    let (status, _) = read(handlers::rename_folder(
+        as_user("bob"),
+        pool.clone(),
+        json(json!({ "id": mine, "name": "pris" })),
+        crypto(),
+    ).await).await;
+    assert_eq!(status, 404);
+
+    // Ni sous-dossier, ni élément rangé dans le coffre d'un autre
+    let (status, _) = create_folder(&pool, "bob", json!({ "name": "intrus", "parent_id": mine })).await;
+    assert_eq!(status, 400);
+    let item = add_account(&pool, "bob", "bank", "p1").await;
+    assert_eq!(move_item(&pool, "bob", &item, &mine).await, 400);
+
+    let (status, _) = create_folder(&pool, "bob", json!({ "name": "ops", "group_name": "ops" })).await;
+    assert_eq!(status, 403);
+    let (status, body) = create_folder(&pool, "alice", json!({ "name": "ops", "group_name": "ops" })).await;
+    assert_eq!(status, 201);
+    assert!(body["owner"].is_null());
+}
+
+#[actix_web::test]
+async fn a_folder_cannot_move_under_itself() {
+    let pool = pool().await;
+    let parent = folder(&pool, "alice", "parent", None).await;
+    let child = folder(&pool, "alice", "child", Some(&parent)).await;
+    let grandchild = folder(&pool, "alice", "grandchild", Some(&child)).await;
+
+    assert_eq!(move_folder(&pool, "alice", &parent, &parent).await, 400);
+    assert_eq!(move_folder(&pool, "alice", &parent, &grandchild).await, 400);
+    assert_eq!(move_folder(&pool, "alice", &grandchild, &parent).await, 200);
+}
+
+#[actix_web::test]
+async fn deleting_a_folder_moves_its_content_up() {
+    let pool = pool().await;
+    let parent = folder(&pool, "alice", "parent", None).await;
+    let child = folder(&pool, "alice", "child", Some(&parent)).await;
+    let item = add_account(&pool, "alice", "mail", "p1").await;
+    assert_eq!(move_item(&pool, "alice", &item, &child).await, 200);
+
+    let (status, body) = read(handlers::delete_folder(as_user("alice"), pool.clone(), json(json!({ "id": child })), crypto()).await).await;
+    assert_eq!(status, 200);
+    assert_eq!(body["items_moved"], 1);
+
+    let page = list_accounts(&pool, "alice", &format!("folder_id={}", parent)).await;
+    assert_eq!(page["items"][0]["id"], item);
+}
+
+#[actix_web::test]
+async fn tags_are_deduplicated_and_bounded() {
+    let pool = pool().await;
+    let item = add_account(&pool, "alice", "mail", "p1").await;
+    let tag = |username: &str, tags: Value| {
+        let body = json!({ "item_type": "account", "item_id": item, "tags": tags });
+        handlers::set_item_tags(as_user(username), pool.clone(), json(body), crypto())
+    };
+
+    let (status, body) = read(tag("alice", json!(["Work", " work ", "", "prod"])).await).await;
+    assert_eq!(status, 200);
+    assert_eq!(body["tags"], json!(["Work", "prod"]));
+
+    let too_many: Vec<String> = (0..21).map(|i| format!("t{}", i)).collect();
+    assert_eq!(read(tag("alice", json!(too_many)).await).await.0, 400);
+    assert_eq!(read(tag("bob", json!(["stolen"])).await).await.0, 404);
+
+    let page = list_accounts(&pool, "alice", "tag=WORK").await;
+    assert_eq!(page["total"], 1);
+    assert_eq!(list_accounts(&pool, "alice", "tag=perso").await["total"], 0);
+}
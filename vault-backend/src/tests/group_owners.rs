@@ -0,0 +1,111 @@
+use serde_json::json;
+
+use super::*;
+use crate::handlers_admin;
+use crate::models::ListParams;
+
+async fn add_member(pool: &web::Data<SqlitePool>, username: &str, target: &str, role: &str) -> u16 {
+    read(handlers::add_group_member(
+        as_user(username),
+        pool.clone(),
+        json(json!({ "group_name": "ops", "username": target, "role": role })),
+    ).await).await.0
+}
+
+async fn set_role(pool: &web::Data<SqlitePool>, username: &str, target: &str, role: &str) -> u16 {
+    read(handlers::set_group_role(
+        as_user(username),
+        pool.clone(),
+        json(json!({ "group_name": "ops", "username": target, "role": role })),
+    ).await).await.0
+}
+
+async fn set_policy(pool: &web::Data<SqlitePool>, username: &str, days: Option<i64>) -> u16 {
+    read(handlers::set_group_policy(
+        as_user(username),
+        pool.clone(),
+        json(json!({ "group_name": "ops", "max_secret_age_days": days })),
+    ).await).await.0
+}
+
+async fn audit_actions(pool: &web::Data<SqlitePool>) -> Vec<String> {
+    let (_, page) = read(handlers_admin::get_audit_log(
+        as_admin("root", "admin"),
+        pool.clone(),
+        web::Query::<ListParams>::from_query("").unwrap(),
+        crypto(),
+    ).await).await;
+    page["items"].as_array().unwrap().iter().map(|entry| entry["action"].as_str().unwrap().to_string()).collect()
+}
+
+#[actix_web::test]
+async fn owners_add_existing_users() {
+    let pool = pool().await;
+    for username in ["alice", "bob", "carol", "dave"] {
+        user(&pool, username).await;
+    }
+    group(&pool, "ops", &["alice", "bob"]).await;
+
+    assert_eq!(add_member(&pool, "bob", "carol", "viewer").await, 403);
+    assert_eq!(add_member(&pool, "alice", "nobody", "viewer").await, 409);
+    assert_eq!(add_member(&pool, "alice", "bob", "viewer").await, 409);
+
+    assert_eq!(add_member(&pool, "alice", "carol", "viewer").await, 201);
+    assert_eq!(db::group_role(pool.get_ref(), "ops", "carol").await.unwrap(), Some(GroupRole::Viewer));
+    assert!(audit_actions(&pool).await.contains(&"add_member".to_string()));
+}
+
+#[actix_web::test]
+async fn only_original_owners_and_admins_grant_ownership() {
+    let pool = pool().await;
+    for username in ["alice", "bob", "carol", "dave"] {
+        user(&pool, username).await;
+    }
+    group(&pool, "ops", &["alice", "bob", "carol"]).await;
+
+    // Un propriétaire nommé par l'administrateur gère les membres sans pouvoir nommer d'autres propriétaires
+    let resp = handlers_admin::set_group_role(
+        as_admin("root", "admin"),
+        pool.clone(),
+        json(json!({ "group_name": "ops", "username": "bob", "role": "owner" })),
+    ).await;
+    assert_eq!(read(resp).await.0, 200);
+    assert_eq!(set_role(&pool, "bob", "carol", "owner").await, 403);
+    assert_eq!(add_member(&pool, "bob", "dave", "owner").await, 403);
+    assert_eq!(set_role(&pool, "bob", "carol", "viewer").await, 200);
+    assert_eq!(add_member(&pool, "bob", "dave", "editor").await, 201);
+
+    assert_eq!(set_role(&pool, "alice", "carol", "owner").await, 200);
+}
+
+#[actix_web::test]
+async fn owners_can_only_tighten_the_policy() {
+    let pool = pool().await;
+    group(&pool, "ops", &["alice", "bob"]).await;
+    let get_policy = |username: &'static str| {
+        let pool = pool.clone();
+        async move {
+            read(handlers::get_group_policy(as_user(username), pool, json(json!({ "group_name": "ops" }))).await).await
+        }
+    };
+
+    assert_eq!(get_policy("bob").await.0, 404);
+    assert_eq!(get_policy("mallory").await.0, 403);
+    assert_eq!(set_policy(&pool, "bob", Some(30)).await, 403);
+
+    assert_eq!(set_policy(&pool, "alice", Some(90)).await, 200);
+    assert_eq!(set_policy(&pool, "alice", Some(30)).await, 200);
+    assert_eq!(set_policy(&pool, "alice", Some(60)).await, 400);
+    assert_eq!(set_policy(&pool, "alice", None).await, 403);
+    let (status, policy) = get_policy("bob").await;
+    assert_eq!(status, 200);
+    assert_eq!(policy["max_secret_age_days"], 30);
+
+    let resp = handlers_admin::set_group_policy(
+        as_admin("root", "admin"),
+        pool.clone(),
+        json(json!({ "group_name": "ops", "max_secret_age_days": 60 })),
+    ).await;
+    assert_eq!(read(resp).await.0, 200);
+    assert_eq!(audit_actions(&pool).await.iter().filter(|action| *action == "group_policy").count(), 3);
+}
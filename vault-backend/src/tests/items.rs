@@ -0,0 +1,149 @@
+use serde_json::json;
+
+use super::*;
+use crate::models::ListParams;
+
+// Numéro de test Visa (clé de Luhn valide)
+const CARD_NUMBER: &str = "4111 1111 1111 1111";
+
+async fn add_item(pool: &web::Data<SqlitePool>, username: &str, body: Value) -> (u16, Value) {
+    read(handlers::add_item(as_user(username), pool.clone(), json(body), crypto()).await).await
+}
+
+async fn add_card(pool: &web::Data<SqlitePool>, username: &str) -> String {
+    let (status, body) = add_item(pool, username, json!({
+        "kind": "card",
+        "title": "visa",
+        "fields": { "cardholder": "Alice", "number": CARD_NUMBER, "expiry": "12/30", "brand": "visa" },
+    })).await;
+    assert_eq!(status, 201, "{}", body);
+    body["id"].as_str().unwrap().to_string()
+}
+
+async fn update(pool: &web::Data<SqlitePool>, username: &str, id: &str, fields: Value) -> (u16, Value) {
+    read(handlers::update_item(
+        as_user(username),
+        pool.clone(),
+        json(json!({ "item_type": "card", "item_id": id, "fields": fields })),
+        config(),
+        crypto(),
+    ).await).await
+}
+
+async fn reveal(pool: &web::Data<SqlitePool>, username: &str, item_type: &str, id: &str) -> (u16, Value) {
+    read(handlers::reveal_item(
+        as_user(username),
+        pool.clone(),
+        json(json!({ "item_type": item_type, "item_id": id })),
+        crypto(),
+    ).await).await
+}
+
+#[actix_web::test]
+async fn content_is_validated_against_its_kind() {
+    let pool = pool().await;
+    let card = |fields: Value| json!({ "kind": "card", "title": "visa", "fields": fields });
+
+    assert_eq!(add_item(&pool, "alice", json!({ "kind": "boat", "title": "x", "fields": {} })).await.0, 400);
+    assert_eq!(add_item(&pool, "alice", card(json!({ "cardholder": "A", "expiry": "12/30" }))).await.0, 400);
+    assert_eq!(add_item(&pool, "alice", card(json!({ "cardholder": "A", "number": "4111 1111 1111 1112", "expiry": "12/30" }))).await.0, 400);
+    assert_eq!(add_item(&pool, "alice", card(json!({ "cardholder": "A", "number": CARD_NUMBER, "expiry": "13/30" }))).await.0, 400);
+    assert_eq!(add_item(&pool, "alice", card(json!({ "cardholder": "A", "number": CARD_NUMBER, "expiry": "12/30", "pin": "0000" }))).await.0, 400);
+    assert_eq!(add_item(&pool, "alice", card(json!({ "cardholder": 42, "number": CARD_NUMBER, "expiry": "12/30" }))).await.0, 400);
+
+    add_card(&pool, "alice").await;
+    assert_eq!(add_item(&pool, "alice", card(json!({ "cardholder": "A", "number": CARD_NUMBER, "expiry": "12/30" }))).await.0, 409);
+}
+
+#[actix_web::test]
+async fn lists_hide_secret_fields() {
+    let pool = pool().await;
+    add_card(&pool, "alice").await;
+
+    let (status, page) = read(handlers::get_items(
+        as_user("alice"),
+        pool.clone(),
+        json(json!({ "kind": "card" })),
+        web::Query::<ListParams>::from_query("").unwrap(),
+        crypto(),
+    ).await).await;
+    assert_eq!(status, 200);
+    let fields = &page["items"][0]["fields"];
+    assert_eq!(fields["cardholder"], "Alice");
+    assert!(fields.get("number").is_none());
+}
+
+#[actix_web::test]
+async fn updates_merge_into_the_current_content() {
+    let pool = pool().await;
+    let id = add_card(&pool, "alice").await;
+
+    let (status, _) = update(&pool, "alice", &id, json!({ "cvv": "123", "brand": null })).await;
+    assert_eq!(status, 200);
+    let (_, item) = reveal(&pool, "alice", "card", &id).await;
+    assert_eq!(item["fields"], json!({ "cardholder": "Alice", "number": CARD_NUMBER, "expiry": "12/30", "cvv": "123" }));
+    assert_eq!(item["secret"], CARD_NUMBER);
+
+    // Un champ obligatoire ne peut pas être effacé, et un contenu invalide laisse l'élément intact
+    assert_eq!(update(&pool, "alice", &id, json!({ "number": null })).await.0, 400);
+    assert_eq!(update(&pool, "alice", &id, json!({ "cvv": "12" })).await.0, 400);
+    let (_, item) = reveal(&pool, "alice", "card", &id).await;
+    assert_eq!(item["fields"]["cvv"], "123");
+
+    assert_eq!(update(&pool, "bob", &id, json!({ "cvv": "999" })).await.0, 404);
+}
+
+#[actix_web::test]
+async fn group_items_require_membership() {
+    let pool = pool().await;
+    group(&pool, "ops", &["alice"]).await;
+    let note = |group_name: &str| json!({ "kind": "note", "group_name": group_name, "title": "runbook", "fields": { "content": "..." } });
+
+    assert_eq!(add_item(&pool, "bob", note("ops")).await.0, 403);
+    let (status, body) = add_item(&pool, "alice", note("ops")).await;
+    assert_eq!(status, 201);
+    assert_eq!(body["item_type"], "note_group");
+    let id = body["id"].as_str().unwrap();
+
+    assert_eq!(reveal(&pool, "bob", "note_group", id).await.0, 404);
+    assert_eq!(reveal(&pool, "alice", "note_group", id).await.0, 200);
+}
+
+#[actix_web::test]
+async fn metadata_and_fields_are_saved_together() {
+    let pool = pool().await;
+    let id = add_card(&pool, "alice").await;
+    let (status, _) = add_item(&pool, "alice", json!({
+        "kind": "card",
+        "title": "backup",
+        "fields": { "cardholder": "Alice", "number": CARD_NUMBER, "expiry": "12/30" },
+    })).await;
+    assert_eq!(status, 201);
+    let change = |body: Value| {
+        let pool = pool.clone();
+        let mut body = body;
+        body["item_type"] = json!("card");
+        body["item_id"] = json!(id);
+        async move { read(handlers::update_item(as_user("alice"), pool, json(body), config(), crypto()).await).await }
+    };
+
+    // Un champ invalide ou un titre déjà pris n'enregistre ni l'un ni l'autre
+    assert_eq!(change(json!({ "title": "renamed", "fields": { "cvv": "12" } })).await.0, 400);
+    assert_eq!(change(json!({ "title": "backup", "fields": { "cvv": "123" } })).await.0, 409);
+    assert_eq!(change(json!({ "title": " " })).await.0, 400);
+    let (_, item) = reveal(&pool, "alice", "card", &id).await;
+    assert_eq!(item["title"], "visa");
+    assert!(item["fields"].get("cvv").is_none());
+
+    let (status, body) = change(json!({ "title": "renamed", "fields": { "cvv": "123" } })).await;
+    assert_eq!(status, 200);
+    assert_eq!(body["version"], 2);
+    let (_, item) = reveal(&pool, "alice", "card", &id).await;
+    assert_eq!(item["title"], "renamed");
+    assert_eq!(item["fields"]["cvv"], "123");
+
+    // Sans champ modifié, aucune version n'est créée
+    let (status, body) = change(json!({ "notes": "spare card" })).await;
+    assert_eq!(status, 200);
+    assert!(body.get("version").is_none());
+}
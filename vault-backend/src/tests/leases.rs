@@ -0,0 +1,137 @@
+use chrono::{Duration, Utc};
+use serde_json::json;
+
+use super::*;
+
+async fn checkout(pool: &web::Data<SqlitePool>, username: &str, item_type: &str, id: &str, minutes: Option<i64>) -> (u16, Value) {
+    read(handlers::checkout_item(
+        as_user(username),
+        pool.clone(),
+        json(json!({ "item_type": item_type, "item_id": id, "duration_minutes": minutes })),
+        crypto(),
+    ).await).await
+}
+
+async fn checkin(pool: &web::Data<SqlitePool>, username: &str, id: &str, rotation: &str) -> (u16, Value) {
+    read(handlers::checkin_item(
+        as_user(username),
+        pool.clone(),
+        notifier(),
+        json(json!({ "item_type": "account_group", "item_id": id, "rotation": rotation })),
+        config(),
+        crypto(),
+    ).await).await
+}
+
+async fn reveal(pool: &web::Data<SqlitePool>, username: &str, id: &str) -> (u16, Value) {
+    read(handlers::reveal_item(
+        as_user(username),
+        pool.clone(),
+        json(json!({ "item_type": "account_group", "item_id": id })),
+        crypto(),
+    ).await).await
+}
+
+async fn update(pool: &web::Data<SqlitePool>, username: &str, id: &str) -> u16 {
+    read(handlers::update_item(
+        as_user(username),
+        pool.clone(),
+        json(json!({ "item_type": "account_group", "item_id": id, "fields": { "password": "p2" } })),
+        config(),
+        crypto(),
+    ).await).await.0
+}
+
+#[actix_web::test]
+async fn checkouts_are_checked() {
+    let pool = pool().await;
+    group(&pool, "ops", &["alice"]).await;
+    let id = add_group_account(&pool, "alice", "ops", "mail").await;
+    let personal = add_account(&pool, "alice", "bank", "p1").await;
+
+    assert_eq!(checkout(&pool, "alice", "account", &personal, None).await.0, 400);
+    assert_eq!(checkout(&pool, "alice", "account_group", &id, Some(0)).await.0, 400);
+    assert_eq!(checkout(&pool, "alice", "account_group", &id, Some(24 * 60 + 1)).await.0, 400);
+    assert_eq!(checkout(&pool, "mallory", "account_group", &id, None).await.0, 404);
+    assert_eq!(checkin(&pool, "alice", &id, "keep").await.0, 404);
+}
+
+#[actix_web::test]
+async fn only_the_holder_uses_a_checked_out_credential() {
+    let pool = pool().await;
+    group(&pool, "ops", &["alice", "bob"]).await;
+    member(&pool, "ops", "vera", GroupRole::Viewer).await;
+    let id = add_group_account(&pool, "alice", "ops", "mail").await;
+
+    let (status, body) = checkout(&pool, "vera", "account_group", &id, Some(30)).await;
+    assert_eq!(status, 200);
+    assert_eq!(body["item"]["secret"], "p1");
+    assert_eq!(body["lease"]["username"], "vera");
+
+    let (status, body) = checkout(&pool, "bob", "account_group", &id, None).await;
+    assert_eq!(status, 409);
+    assert!(body["error"].as_str().unwrap().contains("vera"));
+    assert_eq!(reveal(&pool, "bob", &id).await.0, 409);
+    assert_eq!(update(&pool, "bob", &id).await, 409);
+    assert_eq!(reveal(&pool, "vera", &id).await.0, 200);
+
+    let (status, body) = checkin(&pool, "vera", &id, "keep").await;
+    assert_eq!(status, 200);
+    assert_eq!(body["lease"]["status"], "returned");
+    assert_eq!(reveal(&pool, "bob", &id).await.0, 200);
+}
+
+#[actix_web::test]
+async fn owners_force_the_release() {
+    let pool = pool().await;
+    group(&pool, "ops", &["alice", "bob", "carol"]).await;
+    let id = add_group_account(&pool, "alice", "ops", "mail").await;
+    checkout(&pool, "bob", "account_group", &id, None).await;
+
+    assert_eq!(checkin(&pool, "carol", &id, "keep").await.0, 403);
+
+    let (status, body) = checkin(&pool, "alice", &id, "keep").await;
+    assert_eq!(status, 200);
+    assert_eq!(body["lease"]["status"], "released");
+    assert_eq!(body["lease"]["returned_by"], "alice");
+    assert_eq!(update(&pool, "carol", &id).await, 200);
+}
+
+#[actix_web::test]
+async fn checkin_can_rotate_the_password() {
+    let pool = pool().await;
+    group(&pool, "ops", &["alice", "bob"]).await;
+    member(&pool, "ops", "vera", GroupRole::Viewer).await;
+    let id = add_group_account(&pool, "alice", "ops", "mail").await;
+
+    checkout(&pool, "vera", "account_group", &id, None).await;
+    assert_eq!(checkin(&pool, "vera", &id, "rotate").await.0, 403);
+    assert_eq!(checkin(&pool, "vera", &id, "keep").await.0, 200);
+
+    checkout(&pool, "bob", "account_group", &id, None).await;
+    let (status, body) = checkin(&pool, "bob", &id, "rotate").await;
+    assert_eq!(status, 200);
+    let password = body["rotated_secret"].as_str().unwrap();
+    assert_ne!(password, "p1");
+    assert_eq!(reveal(&pool, "alice", &id).await.1["secret"], password);
+}
+
+#[actix_web::test]
+async fn overdue_leases_are_closed() {
+    let pool = pool().await;
+    group(&pool, "ops", &["alice", "bob"]).await;
+    let id = add_group_account(&pool, "alice", "ops", "mail").await;
+    checkout(&pool, "bob", "account_group", &id, None).await;
+
+    sqlx::query("UPDATE item_leases SET expires_at = ?")
+        .bind((Utc::now() - Duration::minutes(1)).to_rfc3339())
+        .execute(pool.get_ref())
+        .await
+        .unwrap();
+    // Un emprunt échu ne bloque plus les autres membres, même avant le passage du job
+    assert_eq!(reveal(&pool, "alice", &id).await.0, 200);
+
+    let expired = db::expire_leases(pool.get_ref()).await.unwrap();
+    assert_eq!(expired.len(), 1);
+    assert_eq!(expired[0].status, "expired");
+}
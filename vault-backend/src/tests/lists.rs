@@ -0,0 +1,61 @@
+use super::*;
+use crate::models::ListParams;
+
+async fn list_accounts(pool: &web::Data<SqlitePool>, username: &str, query: &str) -> (u16, Value) {
+    read(handlers::get_account(
+        as_user(username),
+        pool.clone(),
+        web::Query::<ListParams>::from_query(query).unwrap(),
+        crypto(),
+    ).await).await
+}
+
+fn titles(page: &Value) -> Vec<&str> {
+    page["items"].as_array().unwrap().iter().map(|item| item["title"].as_str().unwrap()).collect()
+}
+
+#[actix_web::test]
+async fn lists_only_hold_the_callers_items() {
+    let pool = pool().await;
+    add_account(&pool, "alice", "mail", "p1").await;
+    add_account(&pool, "bob", "bank", "p2").await;
+
+    let (status, page) = list_accounts(&pool, "bob", "").await;
+    assert_eq!(status, 200);
+    assert_eq!(page["total"], 1);
+    assert_eq!(titles(&page), ["bank"]);
+
+    let (_, page) = list_accounts(&pool, "carol", "").await;
+    assert_eq!(page["total"], 0);
+}
+
+#[actix_web::test]
+async fn pages_follow_the_cursor() {
+    let pool = pool().await;
+    for title in ["c", "a", "b"] {
+        add_account(&pool, "alice", title, "p").await;
+    }
+
+    let (_, first) = list_accounts(&pool, "alice", "limit=2&order=asc").await;
+    assert_eq!(titles(&first), ["c", "a"]);
+    assert_eq!(first["total"], 3);
+    let cursor = first["next_cursor"].as_str().unwrap();
+
+    let (_, second) = list_accounts(&pool, "alice", &format!("limit=2&order=asc&cursor={}", cursor)).await;
+    assert_eq!(titles(&second), ["b"]);
+    assert!(second["next_cursor"].is_null());
+
+    // Le filtre exact passe par l'index aveugle du titre
+    let (_, exact) = list_accounts(&pool, "alice", "title=b").await;
+    assert_eq!(titles(&exact), ["b"]);
+}
+
+#[actix_web::test]
+async fn invalid_parameters_are_rejected() {
+    let pool = pool().await;
+    assert_eq!(list_accounts(&pool, "alice", "sort=password_account").await.0, 400);
+    assert_eq!(list_accounts(&pool, "alice", "cursor=garbage").await.0, 400);
+    // Les titres chiffrés ne peuvent être ni triés ni filtrés par préfixe
+    assert_eq!(list_accounts(&pool, "alice", "sort=title").await.0, 400);
+    assert_eq!(list_accounts(&pool, "alice", "title_prefix=m").await.0, 400);
+}
@@ -0,0 +1,98 @@
+use serde_json::json;
+
+use super::*;
+use crate::handlers_admin;
+use crate::models::ExpiringItemsQuery;
+
+async fn remove(pool: &web::Data<SqlitePool>, username: &str, target: &str, flag_rotation: bool) -> (u16, Value) {
+    read(handlers::remove_group_member(
+        as_user(username),
+        pool.clone(),
+        json(json!({ "group_name": "ops", "username": target, "flag_rotation": flag_rotation })),
+    ).await).await
+}
+
+async fn trash(pool: &web::Data<SqlitePool>, username: &str, id: &str) -> u16 {
+    read(handlers::delete_account_in_group(as_user(username), pool.clone(), json(json!({ "id": id }))).await).await.0
+}
+
+#[actix_web::test]
+async fn only_owners_remove_members() {
+    let pool = pool().await;
+    group(&pool, "ops", &["alice", "bob", "carol"]).await;
+
+    assert_eq!(remove(&pool, "bob", "carol", false).await.0, 403);
+    assert_eq!(remove(&pool, "mallory", "carol", false).await.0, 403);
+    assert_eq!(remove(&pool, "alice", "nobody", false).await.0, 404);
+
+    let (status, body) = remove(&pool, "alice", "carol", false).await;
+    assert_eq!(status, 200);
+    assert_eq!(body["flagged_for_rotation"], 0);
+    assert!(db::group_role(pool.get_ref(), "ops", "carol").await.unwrap().is_none());
+
+    let resp = handlers_admin::remove_group_member(
+        as_admin("root", "admin"),
+        pool.clone(),
+        json(json!({ "group_name": "ops", "username": "bob" })),
+    ).await;
+    assert_eq!(read(resp).await.0, 200);
+}
+
+#[actix_web::test]
+async fn the_last_owner_and_the_last_member_stay() {
+    let pool = pool().await;
+    group(&pool, "ops", &["alice", "bob"]).await;
+
+    assert_eq!(remove(&pool, "alice", "alice", false).await.0, 409);
+
+    db::set_group_role(pool.get_ref(), "ops", "bob", GroupRole::Owner, "alice").await.unwrap();
+    assert_eq!(remove(&pool, "alice", "alice", false).await.0, 200);
+    assert_eq!(remove(&pool, "bob", "bob", false).await.0, 409);
+}
+
+#[actix_web::test]
+async fn removal_can_flag_the_group_secrets_for_rotation() {
+    let pool = pool().await;
+    group(&pool, "ops", &["alice", "bob"]).await;
+    let id = add_group_account(&pool, "alice", "ops", "mail").await;
+
+    let (status, body) = remove(&pool, "alice", "bob", true).await;
+    assert_eq!(status, 200);
+    assert_eq!(body["flagged_for_rotation"], 1);
+
+    let expiring = || async {
+        read(handlers::get_expiring_items(
+            as_user("alice"),
+            pool.clone(),
+            web::Query::<ExpiringItemsQuery>::from_query("").unwrap(),
+            crypto(),
+        ).await).await.1
+    };
+    let report = expiring().await;
+    assert_eq!(report["groups"][0]["items"][0]["id"], json!(id));
+    assert_eq!(report["groups"][0]["items"][0]["status"], "rotation_overdue");
+
+    // Le renouvellement du secret lève le signalement
+    let resp = handlers::update_item(
+        as_user("alice"),
+        pool.clone(),
+        json(json!({ "item_type": "account_group", "item_id": id, "fields": { "password": "p2" } })),
+        config(),
+        crypto(),
+    ).await;
+    assert_eq!(read(resp).await.0, 200);
+    assert!(expiring().await["groups"][0]["items"].as_array().unwrap().is_empty());
+}
+
+#[actix_web::test]
+async fn group_items_are_deleted_by_editors() {
+    let pool = pool().await;
+    group(&pool, "ops", &["alice", "bob"]).await;
+    member(&pool, "ops", "vera", GroupRole::Viewer).await;
+    let id = add_group_account(&pool, "alice", "ops", "mail").await;
+
+    assert_eq!(trash(&pool, "vera", &id).await, 403);
+    assert_eq!(trash(&pool, "mallory", &id).await, 404);
+    assert_eq!(trash(&pool, "bob", &id).await, 200);
+    assert_eq!(trash(&pool, "bob", &id).await, 404);
+}
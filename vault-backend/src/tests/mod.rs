@@ -0,0 +1,166 @@
+//! Tests des handlers : base SQLite en mémoire et requêtes authentifiées construites à la main
+
+use actix_web::{body::to_bytes, test::TestRequest, web, HttpMessage, HttpRequest, HttpResponse};
+use serde::de::DeserializeOwned;
+use serde_json::Value;
+use sqlx::sqlite::{SqlitePool, SqlitePoolOptions};
+use std::sync::OnceLock;
+
+use crate::config::AppConfig;
+use crate::crypto::CryptoService;
+use crate::db;
+use crate::handlers;
+use crate::models::{AddUserGroups, Claims, ClaimsAdmin, CreateGroupRequest, GroupRole};
+use crate::notify::Notifier;
+
+mod access_requests;
+mod delete_user;
+mod emergency;
+mod expiry;
+mod folders;
+mod group_owners;
+mod items;
+mod leases;
+mod lists;
+mod members;
+mod nested_groups;
+mod reveal;
+mod roles;
+mod search;
+mod shares;
+mod templates;
+mod trash;
+mod versions;
+
+/// Base en mémoire avec toutes les tables (une seule connexion pour que la base soit partagée)
+pub async fn pool() -> web::Data<SqlitePool> {
+    let pool = SqlitePoolOptions::new()
+        .max_connections(1)
+        .connect("sqlite::memory:")
+        .await
+        .expect("Failed to open in-memory database");
+    db::init_tables(&pool).await.expect("Failed to initialize tables");
+    web::Data::new(pool)
+}
+
+/// Service de chiffrement partagé : la dérivation de clé est coûteuse, elle n'est faite qu'une fois
+pub fn crypto() -> web::Data<CryptoService> {
+    static CRYPTO: OnceLock<CryptoService> = OnceLock::new();
+    web::Data::new(CRYPTO.get_or_init(|| CryptoService::new("test-master-password").unwrap()).clone())
+}
+
+pub fn config() -> web::Data<AppConfig> {
+    web::Data::new(AppConfig::from_env())
+}
+
+/// Notifications écrites dans le journal
+pub fn notifier() -> web::Data<Notifier> {
+    web::Data::new(Notifier::Log)
+}
+
+/// Requête portant le token de `username`, comme après le passage du middleware
+pub fn as_user(username: &str) -> HttpRequest {
+    let req = TestRequest::default().to_http_request();
+    req.extensions_mut().insert(Claims {
+        sub: username.to_string(),
+        username: username.to_string(),
+        exp: i64::MAX,
+        iat: 0,
+    });
+    req
+}
+
+/// Requête portant un token administrateur avec le rôle donné
+pub fn as_admin(admin_username: &str, role: &str) -> HttpRequest {
+    let req = TestRequest::default().to_http_request();
+    req.extensions_mut().insert(ClaimsAdmin {
+        sub: admin_username.to_string(),
+        admin_username: admin_username.to_string(),
+        exp: i64::MAX,
+        role: role.to_string(),
+        iat: 0,
+    });
+    req
+}
+
+/// Corps JSON désérialisé dans le type attendu par le handler
+pub fn json<T: DeserializeOwned>(value: Value) -> web::Json<T> {
+    web::Json(serde_json::from_value(value).expect("Invalid test body"))
+}
+
+/// Statut et corps JSON d'une réponse
+pub async fn read(resp: HttpResponse) -> (u16, Value) {
+    let status = resp.status().as_u16();
+    let bytes = to_bytes(resp.into_body()).await.unwrap_or_default();
+    (status, serde_json::from_slice(&bytes).unwrap_or(Value::Null))
+}
+
+/// Crée un utilisateur (sans mot de passe utilisable) et retourne son identifiant
+pub async fn user(pool: &SqlitePool, username: &str) -> String {
+    let id = uuid::Uuid::new_v4().to_string();
+    sqlx::query("INSERT INTO users (id, username, password_hash, created_at) VALUES (?, ?, '', datetime('now'))")
+        .bind(&id)
+        .bind(username)
+        .execute(pool)
+        .await
+        .unwrap();
+    id
+}
+
+/// Crée un groupe avec ses membres : le premier en est propriétaire, les autres éditeurs
+pub async fn group(pool: &SqlitePool, group_name: &str, members: &[&str]) {
+    let (first, others) = members.split_first().expect("A group needs a member");
+    db::create_group(pool, CreateGroupRequest {
+        group_name: group_name.to_string(),
+        usernames: vec![first.to_string()],
+        owners: vec![first.to_string()],
+    }).await.unwrap();
+    for username in others {
+        member(pool, group_name, username, GroupRole::Editor).await;
+    }
+}
+
+/// Ajoute un membre à un groupe avec le rôle donné
+pub async fn member(pool: &SqlitePool, group_name: &str, username: &str, role: GroupRole) {
+    db::add_account(pool, AddUserGroups {
+        username: username.to_string(),
+        group_name: group_name.to_string(),
+        role,
+        expires_in_hours: None,
+    }).await.unwrap();
+}
+
+/// Ajoute un compte personnel et retourne son identifiant
+pub async fn add_account(pool: &web::Data<SqlitePool>, username: &str, title: &str, password: &str) -> String {
+    let (status, body) = read(handlers::add_account(
+        as_user(username),
+        pool.clone(),
+        json(serde_json::json!({
+            "user_account": "login",
+            "password_account": password,
+            "title": title,
+            "url": "",
+        })),
+        crypto(),
+    ).await).await;
+    assert_eq!(status, 201, "{}", body);
+    body["id"].as_str().unwrap().to_string()
+}
+
+/// Ajoute un compte de groupe et retourne son identifiant
+pub async fn add_group_account(pool: &web::Data<SqlitePool>, username: &str, group_name: &str, title: &str) -> String {
+    let (status, body) = read(handlers::add_account_in_group(
+        as_user(username),
+        pool.clone(),
+        json(serde_json::json!({
+            "group_name": group_name,
+            "title": title,
+            "user_account": "root",
+            "password_account": "p1",
+            "url": "",
+        })),
+        crypto(),
+    ).await).await;
+    assert_eq!(status, 201, "{}", body);
+    body["id"].as_str().unwrap().to_string()
+}
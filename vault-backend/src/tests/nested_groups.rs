@@ -0,0 +1,112 @@
+use serde_json::json;
+
+use super::*;
+use crate::handlers_admin;
+use crate::models::ListParams;
+
+async fn attach(pool: &web::Data<SqlitePool>, username: &str, group_name: &str, parent: Option<&str>) -> u16 {
+    read(handlers::set_group_parent(
+        as_user(username),
+        pool.clone(),
+        json(json!({ "group_name": group_name, "parent_group": parent })),
+    ).await).await.0
+}
+
+async fn admin_attach(pool: &web::Data<SqlitePool>, group_name: &str, parent: Option<&str>) -> u16 {
+    read(handlers_admin::set_group_parent(
+        as_admin("root", "admin"),
+        pool.clone(),
+        json(json!({ "group_name": group_name, "parent_group": parent })),
+    ).await).await.0
+}
+
+async fn list_group(pool: &web::Data<SqlitePool>, username: &str, group_name: &str) -> u16 {
+    read(handlers::get_account_in_group(
+        as_user(username),
+        pool.clone(),
+        json(json!({ "group_name": group_name })),
+        web::Query::<ListParams>::from_query("").unwrap(),
+        crypto(),
+    ).await).await.0
+}
+
+#[actix_web::test]
+async fn ancestors_members_inherit_their_role() {
+    let pool = pool().await;
+    group(&pool, "eng", &["alice", "bob"]).await;
+    group(&pool, "ops", &["carol"]).await;
+    add_group_account(&pool, "carol", "ops", "mail").await;
+
+    assert_eq!(list_group(&pool, "bob", "ops").await, 403);
+    assert_eq!(admin_attach(&pool, "ops", Some("eng")).await, 200);
+
+    assert_eq!(list_group(&pool, "bob", "ops").await, 200);
+    add_group_account(&pool, "bob", "ops", "bank").await;
+    assert_eq!(db::group_role(pool.get_ref(), "ops", "alice").await.unwrap(), Some(GroupRole::Owner));
+    assert_eq!(list_group(&pool, "mallory", "ops").await, 403);
+
+    let (_, members) = read(handlers::get_group_members(
+        as_user("carol"),
+        pool.clone(),
+        json(json!({ "group_name": "ops" })),
+    ).await).await;
+    let bob = members.as_array().unwrap().iter().find(|m| m["username"] == "bob").unwrap();
+    assert_eq!(bob["role"], "editor");
+    assert_eq!(bob["inherited_from"], "eng");
+
+    // Le groupe du parent ne voit pas les membres de l'enfant
+    assert_eq!(list_group(&pool, "carol", "eng").await, 403);
+
+    assert_eq!(admin_attach(&pool, "ops", None).await, 200);
+    assert_eq!(list_group(&pool, "bob", "ops").await, 403);
+}
+
+#[actix_web::test]
+async fn cycles_are_refused() {
+    let pool = pool().await;
+    group(&pool, "eng", &["alice"]).await;
+    group(&pool, "ops", &["alice"]).await;
+    group(&pool, "oncall", &["alice"]).await;
+
+    assert_eq!(attach(&pool, "alice", "ops", Some("eng")).await, 200);
+    assert_eq!(attach(&pool, "alice", "oncall", Some("ops")).await, 200);
+    assert_eq!(attach(&pool, "alice", "eng", Some("oncall")).await, 409);
+    assert_eq!(attach(&pool, "alice", "eng", Some("eng")).await, 409);
+    assert_eq!(admin_attach(&pool, "eng", Some("missing")).await, 404);
+}
+
+#[actix_web::test]
+async fn moves_need_every_owner_involved() {
+    let pool = pool().await;
+    group(&pool, "eng", &["alice"]).await;
+    group(&pool, "ops", &["carol", "alice"]).await;
+
+    // Carol possède ops mais pas eng, Alice n'est qu'éditrice d'ops
+    assert_eq!(attach(&pool, "carol", "ops", Some("eng")).await, 403);
+    assert_eq!(attach(&pool, "alice", "ops", Some("eng")).await, 403);
+    assert_eq!(admin_attach(&pool, "ops", Some("eng")).await, 200);
+
+    // Détacher retire l'accès hérité : il faut aussi posséder l'ancien parent
+    assert_eq!(attach(&pool, "carol", "ops", None).await, 403);
+    assert_eq!(attach(&pool, "alice", "ops", None).await, 200);
+}
+
+#[actix_web::test]
+async fn users_list_only_their_own_groups() {
+    let pool = pool().await;
+    group(&pool, "eng", &["alice"]).await;
+    group(&pool, "ops", &["carol"]).await;
+    group(&pool, "hr", &["dave"]).await;
+    admin_attach(&pool, "ops", Some("eng")).await;
+
+    let (status, page) = read(handlers::get_groups_by_name(
+        as_user("alice"),
+        pool.clone(),
+        web::Query::<ListParams>::from_query("").unwrap(),
+        crypto(),
+    ).await).await;
+    assert_eq!(status, 200);
+    let mut names: Vec<&str> = page["items"].as_array().unwrap().iter().map(|g| g["name"].as_str().unwrap()).collect();
+    names.sort();
+    assert_eq!(names, ["eng", "ops"]);
+}
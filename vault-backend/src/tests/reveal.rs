@@ -0,0 +1,65 @@
+use serde_json::json;
+
+use super::*;
+use crate::handlers_admin;
+use crate::models::ListParams;
+
+async fn audit_log(pool: &web::Data<SqlitePool>) -> Value {
+    let (status, page) = read(handlers_admin::get_audit_log(
+        as_admin("root", "admin"),
+        pool.clone(),
+        web::Query::<ListParams>::from_query("").unwrap(),
+        crypto(),
+    ).await).await;
+    assert_eq!(status, 200);
+    page
+}
+
+#[actix_web::test]
+async fn lists_carry_no_secrets() {
+    let pool = pool().await;
+    add_account(&pool, "alice", "mail", "hunter2").await;
+
+    let (_, page) = read(handlers::get_account(
+        as_user("alice"),
+        pool.clone(),
+        web::Query::<ListParams>::from_query("").unwrap(),
+        crypto(),
+    ).await).await;
+    assert_eq!(page["items"][0]["title"], "mail");
+    assert!(page["items"][0].get("password_account").is_none());
+    assert!(!page.to_string().contains("hunter2"));
+}
+
+#[actix_web::test]
+async fn reveals_are_checked_and_audited() {
+    let pool = pool().await;
+    let id = add_account(&pool, "alice", "mail", "hunter2").await;
+    let body = json!({ "item_type": "account", "item_id": id });
+
+    let (status, _) = read(handlers::reveal_item(as_user("bob"), pool.clone(), json(body.clone()), crypto()).await).await;
+    assert_eq!(status, 404);
+    assert_eq!(audit_log(&pool).await["total"], 0);
+
+    let (status, revealed) = read(handlers::reveal_item(as_user("alice"), pool.clone(), json(body), crypto()).await).await;
+    assert_eq!(status, 200);
+    assert_eq!(revealed["secret"], "hunter2");
+
+    let log = audit_log(&pool).await;
+    assert_eq!(log["total"], 1);
+    assert_eq!(log["items"][0]["actor"], "alice");
+    assert_eq!(log["items"][0]["action"], "reveal");
+    assert_eq!(log["items"][0]["target_id"], id);
+}
+
+#[actix_web::test]
+async fn only_admins_read_the_audit_log() {
+    let pool = pool().await;
+    let (status, _) = read(handlers_admin::get_audit_log(
+        as_admin("viewer", "auditor"),
+        pool.clone(),
+        web::Query::<ListParams>::from_query("").unwrap(),
+        crypto(),
+    ).await).await;
+    assert_eq!(status, 403);
+}
@@ -0,0 +1,122 @@
+use serde_json::json;
+
+use super::*;
+use crate::handlers_admin;
+use crate::models::ListParams;
+
+async fn list_group(pool: &web::Data<SqlitePool>, username: &str, group_name: &str) -> u16 {
+    read(handlers::get_account_in_group(
+        as_user(username),
+        pool.clone(),
+        json(json!({ "group_name": group_name })),
+        web::Query::<ListParams>::from_query("").unwrap(),
+        crypto(),
+    ).await).await.0
+}
+
+async fn reveal(pool: &web::Data<SqlitePool>, username: &str, id: &str) -> u16 {
+    read(handlers::reveal_item(
+        as_user(username),
+        pool.clone(),
+        json(json!({ "item_type": "account_group", "item_id": id })),
+        crypto(),
+    ).await).await.0
+}
+
+async fn update(pool: &web::Data<SqlitePool>, username: &str, id: &str) -> (u16, Value) {
+    read(handlers::update_item(
+        as_user(username),
+        pool.clone(),
+        json(json!({ "item_type": "account_group", "item_id": id, "fields": { "password": "p2" } })),
+        config(),
+        crypto(),
+    ).await).await
+}
+
+async fn set_role(pool: &web::Data<SqlitePool>, username: &str, target: &str, role: &str) -> (u16, Value) {
+    read(handlers::set_group_role(
+        as_user(username),
+        pool.clone(),
+        json(json!({ "group_name": "ops", "username": target, "role": role })),
+    ).await).await
+}
+
+#[actix_web::test]
+async fn viewers_read_and_editors_write() {
+    let pool = pool().await;
+    group(&pool, "ops", &["alice", "bob"]).await;
+    member(&pool, "ops", "vera", GroupRole::Viewer).await;
+    let id = add_group_account(&pool, "bob", "ops", "mail").await;
+
+    assert_eq!(list_group(&pool, "vera", "ops").await, 200);
+    assert_eq!(reveal(&pool, "vera", &id).await, 200);
+
+    let (status, body) = update(&pool, "vera", &id).await;
+    assert_eq!(status, 403);
+    assert!(body["error"].as_str().unwrap().contains("editor"));
+    let (status, _) = read(handlers::add_account_in_group(
+        as_user("vera"),
+        pool.clone(),
+        json(json!({ "group_name": "ops", "title": "bank", "user_account": "u", "password_account": "p", "url": "" })),
+        crypto(),
+    ).await).await;
+    assert_eq!(status, 403);
+
+    assert_eq!(update(&pool, "bob", &id).await.0, 200);
+}
+
+#[actix_web::test]
+async fn outsiders_cannot_read_a_group() {
+    let pool = pool().await;
+    group(&pool, "ops", &["alice"]).await;
+    let id = add_group_account(&pool, "alice", "ops", "mail").await;
+
+    assert_eq!(list_group(&pool, "mallory", "ops").await, 403);
+    assert_eq!(reveal(&pool, "mallory", &id).await, 404);
+    let (status, _) = read(handlers::get_group_members(
+        as_user("mallory"),
+        pool.clone(),
+        json(json!({ "group_name": "ops" })),
+    ).await).await;
+    assert_eq!(status, 403);
+}
+
+#[actix_web::test]
+async fn only_owners_change_roles() {
+    let pool = pool().await;
+    group(&pool, "ops", &["alice", "bob"]).await;
+
+    assert_eq!(set_role(&pool, "bob", "bob", "owner").await.0, 403);
+
+    let (status, body) = set_role(&pool, "alice", "bob", "viewer").await;
+    assert_eq!(status, 200);
+    assert_eq!(body["previous_role"], "editor");
+    assert_eq!(db::group_role(pool.get_ref(), "ops", "bob").await.unwrap(), Some(GroupRole::Viewer));
+
+    assert_eq!(set_role(&pool, "alice", "nobody", "viewer").await.0, 404);
+}
+
+#[actix_web::test]
+async fn the_last_owner_cannot_step_down() {
+    let pool = pool().await;
+    group(&pool, "ops", &["alice", "bob"]).await;
+
+    assert_eq!(set_role(&pool, "alice", "alice", "editor").await.0, 409);
+
+    assert_eq!(set_role(&pool, "alice", "bob", "owner").await.0, 200);
+    assert_eq!(set_role(&pool, "alice", "alice", "editor").await.0, 200);
+}
+
+#[actix_web::test]
+async fn admins_change_roles_in_any_group() {
+    let pool = pool().await;
+    group(&pool, "ops", &["alice", "bob"]).await;
+    let body = json!({ "group_name": "ops", "username": "bob", "role": "owner" });
+
+    let resp = handlers_admin::set_group_role(as_user("alice"), pool.clone(), json(body.clone())).await;
+    assert_eq!(read(resp).await.0, 401);
+
+    let resp = handlers_admin::set_group_role(as_admin("root", "admin"), pool.clone(), json(body)).await;
+    assert_eq!(read(resp).await.0, 200);
+    assert_eq!(db::group_role(pool.get_ref(), "ops", "bob").await.unwrap(), Some(GroupRole::Owner));
+}
@@ -0,0 +1,57 @@
+use serde_json::json;
+
+use super::*;
+
+async fn search(pool: &web::Data<SqlitePool>, username: &str, body: Value) -> (u16, Value) {
+    read(handlers::search(as_user(username), pool.clone(), json(body), crypto()).await).await
+}
+
+fn ids(results: &Value) -> Vec<&str> {
+    results.as_array().unwrap().iter().map(|r| r["item_id"].as_str().unwrap()).collect()
+}
+
+#[actix_web::test]
+async fn results_are_limited_to_own_and_group_items() {
+    let pool = pool().await;
+    group(&pool, "ops", &["alice", "carol"]).await;
+    let own = add_account(&pool, "alice", "github personal", "p1").await;
+    let shared = add_group_account(&pool, "carol", "ops", "github ops").await;
+    let other = add_account(&pool, "bob", "github bob", "p2").await;
+
+    let (status, results) = search(&pool, "alice", json!({ "query": "git" })).await;
+    assert_eq!(status, 200);
+    let found = ids(&results);
+    assert_eq!(found.len(), 2);
+    assert!(found.contains(&own.as_str()) && found.contains(&shared.as_str()));
+
+    let (_, results) = search(&pool, "bob", json!({ "query": "github" })).await;
+    assert_eq!(ids(&results), [other.as_str()]);
+}
+
+#[actix_web::test]
+async fn every_word_must_match_and_secrets_are_not_indexed() {
+    let pool = pool().await;
+    let id = add_account(&pool, "alice", "github personal", "hunter2").await;
+    add_account(&pool, "alice", "gitlab personal", "p2").await;
+
+    let (_, results) = search(&pool, "alice", json!({ "query": "personal hub" })).await;
+    assert!(ids(&results).is_empty());
+    let (_, results) = search(&pool, "alice", json!({ "query": "personal github" })).await;
+    assert_eq!(ids(&results), [id.as_str()]);
+    let (_, results) = search(&pool, "alice", json!({ "query": "hunter2" })).await;
+    assert!(ids(&results).is_empty());
+
+    // Les éléments dans la corbeille sortent de l'index
+    read(handlers::delete_account(as_user("alice"), pool.clone(), json(json!({ "id": id }))).await).await;
+    let (_, results) = search(&pool, "alice", json!({ "query": "github" })).await;
+    assert!(ids(&results).is_empty());
+}
+
+#[actix_web::test]
+async fn invalid_queries_are_rejected() {
+    let pool = pool().await;
+    assert_eq!(search(&pool, "alice", json!({ "query": "   " })).await.0, 400);
+    assert_eq!(search(&pool, "alice", json!({ "query": "mail", "limit": 0 })).await.0, 400);
+    // La syntaxe FTS5 saisie par l'utilisateur est échappée au lieu de provoquer une erreur
+    assert_eq!(search(&pool, "alice", json!({ "query": "\"mail* OR" })).await.0, 200);
+}
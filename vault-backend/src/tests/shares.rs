@@ -0,0 +1,116 @@
+use serde_json::json;
+
+use super::*;
+
+async fn share(pool: &web::Data<SqlitePool>, username: &str, id: &str, target: &str, permission: &str) -> u16 {
+    read(handlers::share_item(
+        as_user(username),
+        pool.clone(),
+        json(json!({ "item_type": "account", "item_id": id, "username": target, "permission": permission })),
+    ).await).await.0
+}
+
+async fn revoke(pool: &web::Data<SqlitePool>, username: &str, id: &str, target: &str) -> u16 {
+    read(handlers::revoke_share(
+        as_user(username),
+        pool.clone(),
+        json(json!({ "item_type": "account", "item_id": id, "username": target })),
+    ).await).await.0
+}
+
+async fn reveal(pool: &web::Data<SqlitePool>, username: &str, id: &str) -> u16 {
+    read(handlers::reveal_item(
+        as_user(username),
+        pool.clone(),
+        json(json!({ "item_type": "account", "item_id": id })),
+        crypto(),
+    ).await).await.0
+}
+
+async fn update(pool: &web::Data<SqlitePool>, username: &str, id: &str) -> u16 {
+    read(handlers::update_item(
+        as_user(username),
+        pool.clone(),
+        json(json!({ "item_type": "account", "item_id": id, "fields": { "password": "p2" } })),
+        config(),
+        crypto(),
+    ).await).await.0
+}
+
+async fn trash(pool: &web::Data<SqlitePool>, username: &str, id: &str) -> u16 {
+    read(handlers::delete_item(
+        as_user(username),
+        pool.clone(),
+        json(json!({ "item_type": "account", "item_id": id })),
+    ).await).await.0
+}
+
+#[actix_web::test]
+async fn read_shares_reveal_and_edit_shares_update() {
+    let pool = pool().await;
+    for username in ["alice", "bob", "carol"] {
+        user(&pool, username).await;
+    }
+    let id = add_account(&pool, "alice", "mail", "p1").await;
+
+    assert_eq!(reveal(&pool, "bob", &id).await, 404);
+    assert_eq!(share(&pool, "alice", &id, "bob", "read").await, 200);
+    assert_eq!(reveal(&pool, "bob", &id).await, 200);
+    assert_eq!(update(&pool, "bob", &id).await, 403);
+
+    assert_eq!(share(&pool, "alice", &id, "carol", "edit").await, 200);
+    assert_eq!(update(&pool, "carol", &id).await, 200);
+
+    // Partager, voir les accès et supprimer restent réservés au propriétaire
+    assert_eq!(trash(&pool, "carol", &id).await, 403);
+    assert_eq!(share(&pool, "carol", &id, "bob", "edit").await, 403);
+    let (status, _) = read(handlers::get_item_access(
+        as_user("carol"),
+        pool.clone(),
+        json(json!({ "item_type": "account", "item_id": id })),
+    ).await).await;
+    assert_eq!(status, 403);
+
+    let (_, shared) = read(handlers::get_shared_with_me(as_user("bob"), pool.clone(), crypto()).await).await;
+    assert_eq!(shared[0]["title"], "mail");
+    assert_eq!(shared[0]["permission"], "read");
+}
+
+#[actix_web::test]
+async fn shares_are_checked() {
+    let pool = pool().await;
+    user(&pool, "alice").await;
+    user(&pool, "bob").await;
+    group(&pool, "ops", &["alice"]).await;
+    let id = add_account(&pool, "alice", "mail", "p1").await;
+    let group_item = add_group_account(&pool, "alice", "ops", "mail").await;
+
+    assert_eq!(share(&pool, "alice", &id, "alice", "read").await, 400);
+    assert_eq!(share(&pool, "alice", &id, "nobody", "read").await, 400);
+    assert_eq!(share(&pool, "bob", &id, "bob", "read").await, 404);
+    let (status, _) = read(handlers::share_item(
+        as_user("alice"),
+        pool.clone(),
+        json(json!({ "item_type": "account_group", "item_id": group_item, "username": "bob" })),
+    ).await).await;
+    assert_eq!(status, 400);
+}
+
+#[actix_web::test]
+async fn owners_revoke_and_recipients_leave() {
+    let pool = pool().await;
+    for username in ["alice", "bob", "carol"] {
+        user(&pool, username).await;
+    }
+    let id = add_account(&pool, "alice", "mail", "p1").await;
+    share(&pool, "alice", &id, "bob", "read").await;
+    share(&pool, "alice", &id, "carol", "edit").await;
+
+    assert_eq!(revoke(&pool, "carol", &id, "bob").await, 403);
+    assert_eq!(revoke(&pool, "bob", &id, "bob").await, 200);
+    assert_eq!(reveal(&pool, "bob", &id).await, 404);
+
+    assert_eq!(revoke(&pool, "alice", &id, "carol").await, 200);
+    assert_eq!(revoke(&pool, "alice", &id, "carol").await, 404);
+    assert_eq!(update(&pool, "carol", &id).await, 404);
+}
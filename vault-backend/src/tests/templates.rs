@@ -0,0 +1,94 @@
+use serde_json::json;
+
+use super::*;
+use crate::handlers_admin;
+
+fn oauth_template() -> Value {
+    json!({
+        "name": "oauth_client",
+        "label": "OAuth client",
+        "fields": [
+            { "name": "client_id", "required": true, "pattern": "^[a-z0-9-]+$" },
+            { "name": "client_secret", "required": true, "secret": true },
+            { "name": "port", "type": "number" },
+        ],
+    })
+}
+
+async fn create_template(pool: &web::Data<SqlitePool>, role: &str, body: Value) -> (u16, Value) {
+    read(handlers_admin::create_template(as_admin("root", role), pool.clone(), json(body)).await).await
+}
+
+async fn delete_template(pool: &web::Data<SqlitePool>) -> u16 {
+    read(handlers_admin::delete_template(
+        as_admin("root", "admin"),
+        pool.clone(),
+        json(json!({ "name": "oauth_client" })),
+    ).await).await.0
+}
+
+async fn add_client(pool: &web::Data<SqlitePool>, title: &str, fields: Value) -> (u16, Value) {
+    read(handlers::add_item(
+        as_user("alice"),
+        pool.clone(),
+        json(json!({ "kind": "oauth_client", "title": title, "fields": fields })),
+        crypto(),
+    ).await).await
+}
+
+#[actix_web::test]
+async fn template_definitions_are_checked() {
+    let pool = pool().await;
+    let mut template = oauth_template();
+
+    assert_eq!(create_template(&pool, "auditor", template.clone()).await.0, 403);
+
+    for name in ["card", "oauth_group", "OAuth", "x"] {
+        template["name"] = json!(name);
+        assert_eq!(create_template(&pool, "admin", template.clone()).await.0, 400, "{}", name);
+    }
+    template["name"] = json!("oauth_client");
+    template["fields"][1]["name"] = json!("client_id");
+    assert_eq!(create_template(&pool, "admin", template.clone()).await.0, 400);
+    template["fields"][1]["name"] = json!("client_secret");
+    template["fields"][0]["pattern"] = json!("([a-z");
+    assert_eq!(create_template(&pool, "admin", template).await.0, 400);
+
+    assert_eq!(create_template(&pool, "admin", oauth_template()).await.0, 201);
+    assert_eq!(create_template(&pool, "admin", oauth_template()).await.0, 409);
+}
+
+#[actix_web::test]
+async fn items_are_validated_against_their_template() {
+    let pool = pool().await;
+    create_template(&pool, "admin", oauth_template()).await;
+
+    assert_eq!(add_client(&pool, "ci", json!({ "client_id": "ci" })).await.0, 400);
+    assert_eq!(add_client(&pool, "ci", json!({ "client_id": "CI!", "client_secret": "s" })).await.0, 400);
+    assert_eq!(add_client(&pool, "ci", json!({ "client_id": "ci", "client_secret": "s", "port": "http" })).await.0, 400);
+    let (status, body) = add_client(&pool, "ci", json!({ "client_id": "ci", "client_secret": "s", "port": "8080" })).await;
+    assert_eq!(status, 201, "{}", body);
+    assert_eq!(body["item_type"], "oauth_client");
+}
+
+#[actix_web::test]
+async fn a_template_in_use_cannot_be_deleted() {
+    let pool = pool().await;
+    create_template(&pool, "admin", oauth_template()).await;
+    let (_, body) = add_client(&pool, "ci", json!({ "client_id": "ci", "client_secret": "s" })).await;
+    let id = body["id"].as_str().unwrap();
+
+    assert_eq!(delete_template(&pool).await, 409);
+
+    // Un élément dans la corbeille compte encore
+    read(handlers::delete_item(
+        as_user("alice"),
+        pool.clone(),
+        json(json!({ "item_type": "oauth_client", "item_id": id })),
+    ).await).await;
+    assert_eq!(delete_template(&pool).await, 409);
+
+    read(handlers_admin::purge_trash(as_admin("root", "admin"), pool.clone(), json(json!({ "all": true })), config()).await).await;
+    assert_eq!(delete_template(&pool).await, 200);
+    assert_eq!(delete_template(&pool).await, 404);
+}
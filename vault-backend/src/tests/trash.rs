@@ -0,0 +1,91 @@
+use serde_json::json;
+
+use super::*;
+use crate::handlers_admin;
+
+async fn trash_account(pool: &web::Data<SqlitePool>, username: &str, id: &str) -> u16 {
+    read(handlers::delete_account(as_user(username), pool.clone(), json(json!({ "id": id }))).await).await.0
+}
+
+async fn purge(pool: &web::Data<SqlitePool>, role: &str, body: serde_json::Value) -> (u16, serde_json::Value) {
+    read(handlers_admin::purge_trash(as_admin("root", role), pool.clone(), json(body), config()).await).await
+}
+
+#[actix_web::test]
+async fn trash_and_restore_are_limited_to_the_owner() {
+    let pool = pool().await;
+    let id = add_account(&pool, "alice", "mail", "p1").await;
+
+    assert_eq!(trash_account(&pool, "bob", &id).await, 404);
+    assert_eq!(trash_account(&pool, "alice", &id).await, 200);
+
+    let (_, trash) = read(handlers::get_trash(as_user("alice"), pool.clone(), crypto()).await).await;
+    assert_eq!(trash.as_array().unwrap().len(), 1);
+    assert_eq!(trash[0]["deleted_by"], "alice");
+    let (_, trash) = read(handlers::get_trash(as_user("bob"), pool.clone(), crypto()).await).await;
+    assert!(trash.as_array().unwrap().is_empty());
+
+    let restore = json!({ "item_type": "account", "item_id": id });
+    let (status, _) = read(handlers::restore_from_trash(as_user("bob"), pool.clone(), json(restore.clone()), crypto()).await).await;
+    assert_eq!(status, 404);
+    let (status, _) = read(handlers::restore_from_trash(as_user("alice"), pool.clone(), json(restore.clone()), crypto()).await).await;
+    assert_eq!(status, 200);
+    let (status, _) = read(handlers::restore_from_trash(as_user("alice"), pool.clone(), json(restore), crypto()).await).await;
+    assert_eq!(status, 404);
+}
+
+#[actix_web::test]
+async fn group_trash_is_limited_to_members() {
+    let pool = pool().await;
+    group(&pool, "ops", &["alice", "carol"]).await;
+    let id = add_group_account(&pool, "alice", "ops", "db").await;
+
+    // Pas encore de suppression d'élément de groupe par l'API : on le place directement dans la corbeille
+    sqlx::query("UPDATE vault_items SET deleted_at = datetime('now'), deleted_by = 'carol' WHERE id = ?")
+        .bind(&id)
+        .execute(pool.get_ref())
+        .await
+        .unwrap();
+
+    let (status, _) = read(handlers::get_trash_in_group(as_user("bob"), pool.clone(), json(json!({ "group_name": "ops" })), crypto()).await).await;
+    assert_eq!(status, 403);
+    let (status, trash) = read(handlers::get_trash_in_group(as_user("alice"), pool.clone(), json(json!({ "group_name": "ops" })), crypto()).await).await;
+    assert_eq!(status, 200);
+    assert_eq!(trash[0]["deleted_by"], "carol");
+
+    let restore = json!({ "item_type": "account_group", "item_id": id });
+    let (status, _) = read(handlers::restore_from_trash(as_user("bob"), pool.clone(), json(restore.clone()), crypto()).await).await;
+    assert_eq!(status, 404);
+    let (status, _) = read(handlers::restore_from_trash(as_user("alice"), pool.clone(), json(restore), crypto()).await).await;
+    assert_eq!(status, 200);
+}
+
+#[actix_web::test]
+async fn purging_the_whole_trash_must_be_explicit() {
+    let pool = pool().await;
+    let first = add_account(&pool, "alice", "mail", "p1").await;
+    let second = add_account(&pool, "alice", "bank", "p2").await;
+    let kept = add_account(&pool, "alice", "wiki", "p3").await;
+    for id in [&first, &second] {
+        assert_eq!(trash_account(&pool, "alice", id).await, 200);
+    }
+
+    assert_eq!(purge(&pool, "auditor", json!({ "all": true })).await.0, 403);
+    assert_eq!(purge(&pool, "admin", json!({})).await.0, 400);
+    assert_eq!(purge(&pool, "admin", json!({ "item_type": "account" })).await.0, 400);
+    assert_eq!(purge(&pool, "admin", json!({ "item_type": "account", "item_id": first, "all": true })).await.0, 400);
+
+    // Un élément encore actif n'est pas dans la corbeille et ne peut pas être purgé
+    let (status, body) = purge(&pool, "admin", json!({ "item_type": "account", "item_id": kept })).await;
+    assert_eq!(status, 200);
+    assert_eq!(body["purged"], 0);
+
+    let (_, body) = purge(&pool, "admin", json!({ "item_type": "account", "item_id": first })).await;
+    assert_eq!(body["purged"], 1);
+    let (_, trash) = read(handlers::get_trash(as_user("alice"), pool.clone(), crypto()).await).await;
+    assert_eq!(trash.as_array().unwrap().len(), 1);
+
+    let (_, body) = purge(&pool, "admin", json!({ "all": true })).await;
+    assert_eq!(body["purged"], 1);
+    assert_eq!(trash_account(&pool, "alice", &kept).await, 200);
+}
@@ -0,0 +1,139 @@
+use serde_json::json;
+
+use super::*;
+
+#[actix_web::test]
+async fn owner_and_first_version_come_from_the_token() {
+    let pool = pool().await;
+    let (status, body) = read(handlers::add_account(
+        as_user("alice"),
+        pool.clone(),
+        json(json!({
+            "username": "mallory",
+            "user_account": "login",
+            "password_account": "p1",
+            "title": "mail",
+            "url": "",
+        })),
+        crypto(),
+    ).await).await;
+    assert_eq!(status, 201);
+    let id = body["id"].as_str().unwrap().to_string();
+
+    let (status, versions) = read(handlers::get_secret_versions(
+        as_user("alice"),
+        pool.clone(),
+        json(json!({ "item_type": "account", "item_id": id })),
+    ).await).await;
+    assert_eq!(status, 200);
+    assert_eq!(versions[0]["created_by"], "alice");
+
+    // Le nom glissé dans le corps ne donne aucun droit sur le compte
+    let (status, _) = read(handlers::get_secret_versions(
+        as_user("mallory"),
+        pool.clone(),
+        json(json!({ "item_type": "account", "item_id": id })),
+    ).await).await;
+    assert_eq!(status, 404);
+}
+
+#[actix_web::test]
+async fn restoring_a_version_creates_a_new_one() {
+    let pool = pool().await;
+    let id = add_account(&pool, "alice", "mail", "p1").await;
+
+    let (status, body) = read(handlers::update_account(
+        as_user("alice"),
+        pool.clone(),
+        json(json!({ "id": id, "password_account": "p2" })),
+        config(),
+        crypto(),
+    ).await).await;
+    assert_eq!(status, 200);
+    assert_eq!(body["version"], 2);
+
+    let (status, body) = read(handlers::restore_secret_version(
+        as_user("alice"),
+        pool.clone(),
+        json(json!({ "item_type": "account", "item_id": id, "version": 1 })),
+        config(),
+        crypto(),
+    ).await).await;
+    assert_eq!(status, 200);
+    assert_eq!(body["version"], 3);
+
+    let (status, body) = read(handlers::reveal_secret_version(
+        as_user("alice"),
+        pool.clone(),
+        json(json!({ "item_type": "account", "item_id": id, "version": 3 })),
+        crypto(),
+    ).await).await;
+    assert_eq!(status, 200);
+    assert_eq!(body["secret"], "p1");
+
+    let (status, _) = read(handlers::restore_secret_version(
+        as_user("alice"),
+        pool.clone(),
+        json(json!({ "item_type": "account", "item_id": id, "version": 9 })),
+        config(),
+        crypto(),
+    ).await).await;
+    assert_eq!(status, 404);
+}
+
+#[actix_web::test]
+async fn other_users_cannot_touch_a_personal_secret() {
+    let pool = pool().await;
+    let id = add_account(&pool, "alice", "mail", "p1").await;
+
+    let (status, _) = read(handlers::update_account(
+        as_user("bob"),
+        pool.clone(),
+        json(json!({ "id": id, "password_account": "stolen" })),
+        config(),
+        crypto(),
+    ).await).await;
+    assert_eq!(status, 404);
+
+    let (status, _) = read(handlers::reveal_secret_version(
+        as_user("bob"),
+        pool.clone(),
+        json(json!({ "item_type": "account", "item_id": id, "version": 1 })),
+        crypto(),
+    ).await).await;
+    assert_eq!(status, 404);
+
+    let (status, _) = read(handlers::get_secret_versions(
+        as_user("bob"),
+        pool.clone(),
+        json(json!({ "item_type": "secret", "item_id": id })),
+    ).await).await;
+    assert_eq!(status, 400);
+}
+
+#[actix_web::test]
+async fn group_secrets_are_limited_to_members() {
+    let pool = pool().await;
+    group(&pool, "ops", &["alice", "carol"]).await;
+
+    let id = add_group_account(&pool, "alice", "ops", "db").await;
+
+    let (status, _) = read(handlers::update_account_in_group(
+        as_user("bob"),
+        pool.clone(),
+        json(json!({ "id": id, "password_account": "stolen" })),
+        config(),
+        crypto(),
+    ).await).await;
+    assert_eq!(status, 404);
+
+    let (status, body) = read(handlers::update_account_in_group(
+        as_user("carol"),
+        pool.clone(),
+        json(json!({ "id": id, "password_account": "p2" })),
+        config(),
+        crypto(),
+    ).await).await;
+    assert_eq!(status, 200);
+    assert_eq!(body["version"], 2);
+}
@@ -3,6 +3,24 @@ import { LogOut, Users, Key, Shield, Menu, X, Trash2, Search, RefreshCw, UserPlu
 
 const API_BASE_URL = 'http://192.168.1.40:30081/api';
 
+// Les listes de l'API sont paginées : suit `next_cursor` jusqu'à la dernière page.
+// `response` n'est renseignée qu'en cas d'échec, pour que l'appelant puisse lire le statut et l'erreur.
+const fetchAllPages = async (url, options) => {
+  const items = [];
+  let total = 0;
+  let cursor = null;
+  do {
+    const pageUrl = cursor ? `${url}?cursor=${encodeURIComponent(cursor)}` : url;
+    const response = await fetch(pageUrl, options);
+    if (!response.ok) return { response, items, total };
+    const data = await response.json();
+    items.push(...(data.items ?? []));
+    total = data.total ?? items.length;
+    cursor = data.next_cursor;
+  } while (cursor);
+  return { response: null, items, total };
+};
+
 // ========================================
 // UTILITY COMPONENTS
 // ========================================
@@ -771,7 +789,7 @@ export default function AdminDashboard() {
     }
     
     try {
-      const response = await fetch(`${API_BASE_URL}/secure/get/users`, {
+      const { response, items, total } = await fetchAllPages(`${API_BASE_URL}/secure/get/users`, {
         method: 'POST',
         headers: { 
           'Content-Type': 'application/json',
@@ -779,21 +797,20 @@ export default function AdminDashboard() {
         },
       });
       
-      if (response.status === 401) {
+      if (response?.status === 401) {
         showAlert('error', 'Session expired');
         localStorage.removeItem('token');
         setTimeout(() => window.location.href = '/login-admin', 2000);
         return;
       }
       
-      if (!response.ok) {
+      if (response) {
         const errorData = await response.json();
         throw new Error(errorData.error || 'Failed to fetch users');
       }
       
-      const data = await response.json();
-      setUsers(Array.isArray(data) ? data : []);
-      updateStats({ totalUsers: data.length });
+      setUsers(items);
+      updateStats({ totalUsers: total });
       
     } catch (error) {
       console.error('Failed to fetch users:', error);
@@ -816,16 +833,15 @@ export default function AdminDashboard() {
     const token = getAuthToken();
     
     try {
-      const response = await fetch(`${API_BASE_URL}/secure/get/groups`, {
+      const { response, items, total } = await fetchAllPages(`${API_BASE_URL}/secure/get/groups`, {
         method: 'GET',
         headers: { 'Authorization': `Bearer ${token}` },
       });
       
-      if (!response.ok) throw new Error('Failed to fetch groups');
-      const data = await response.json();
-      console.log(data);
-      setGroups(Array.isArray(data) ? data : []);
-      updateStats({ totalGroups: data.length });
+      if (response) throw new Error('Failed to fetch groups');
+      console.log(items);
+      setGroups(items);
+      updateStats({ totalGroups: total });
     } catch (error) {
       console.error('Failed to fetch groups:', error);
       const mockGroups = [
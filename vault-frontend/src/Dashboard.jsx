@@ -5,6 +5,24 @@ import { faUser, faLock } from '@fortawesome/free-solid-svg-icons';
 
 const API_BASE_URL = 'http://192.168.1.40:30081/api';
 
+// Les listes de l'API sont paginées : suit `next_cursor` jusqu'à la dernière page.
+// `response` n'est renseignée qu'en cas d'échec, pour que l'appelant puisse lire le statut et l'erreur.
+const fetchAllPages = async (url, options) => {
+  const items = [];
+  let total = 0;
+  let cursor = null;
+  do {
+    const pageUrl = cursor ? `${url}?cursor=${encodeURIComponent(cursor)}` : url;
+    const response = await fetch(pageUrl, options);
+    if (!response.ok) return { response, items, total };
+    const data = await response.json();
+    items.push(...(data.items ?? []));
+    total = data.total ?? items.length;
+    cursor = data.next_cursor;
+  } while (cursor);
+  return { response: null, items, total };
+};
+
 export default function Dashboard() {
   // ========== STATE MANAGEMENT ==========
   const [activeTab, setActiveTab] = useState('accounts');
@@ -34,6 +52,7 @@ export default function Dashboard() {
   const [groupPasswords, setGroupPasswords] = useState({});
   const [showGroupPasswords, setShowGroupPasswords] = useState({});
   const [groupApiKeys, setGroupApiKeys] = useState({});
+  const [revealed, setRevealed] = useState({});
   
   // User states
   const [currentUsername, setCurrentUsername] = useState('');
@@ -97,23 +116,20 @@ export default function Dashboard() {
     setLoading(prev => ({ ...prev, apiKeys: true }));
 
     try {
-      const response = await fetch(`${API_BASE_URL}/secure/get/api-key`, {
+      const { response, items } = await fetchAllPages(`${API_BASE_URL}/secure/get/api-key`, {
         method: 'POST',
         headers: {
-          'Content-Type': 'application/json',
           'Authorization': `Bearer ${getAuthToken()}`,
         },
-        body: JSON.stringify({ username }),
       });
       
-      if (response.status === 404) {
+      if (response?.status === 404) {
         setApiKeys([]);
         return;
       }
       
-      if (!response.ok) throw new Error(`Erreur ${response.status}`);
-      const data = await response.json();
-      setApiKeys(Array.isArray(data) ? data : [data]);
+      if (response) throw new Error(`Erreur ${response.status}`);
+      setApiKeys(items);
     } catch (error) {
       console.error('Failed to fetch API keys:', error);
       setApiKeys([]);
@@ -127,23 +143,20 @@ export default function Dashboard() {
     setLoading(prev => ({ ...prev, accounts: true }));
     
     try {
-      const response = await fetch(`${API_BASE_URL}/secure/get/account`, {
+      const { response, items } = await fetchAllPages(`${API_BASE_URL}/secure/get/account`, {
         method: 'POST',
         headers: {
-          'Content-Type': 'application/json',
           'Authorization': `Bearer ${getAuthToken()}`,
         },
-        body: JSON.stringify({ username }),
       });
       
-      if (response.status === 404) {
+      if (response?.status === 404) {
         setAccounts([]);
         return;
       }
       
-      if (!response.ok) throw new Error(`Erreur ${response.status}`);
-      const data = await response.json();
-      setAccounts(Array.isArray(data) ? data : [data]);
+      if (response) throw new Error(`Erreur ${response.status}`);
+      setAccounts(items);
     } catch (error) {
       console.error('Failed to fetch accounts:', error);
       setAccounts([]);
@@ -157,23 +170,20 @@ export default function Dashboard() {
     setLoading(prev => ({ ...prev, groups: true }));
     
     try {
-      const response = await fetch(`${API_BASE_URL}/secure/get/groups-by-name`, {
+      const { response, items } = await fetchAllPages(`${API_BASE_URL}/secure/get/groups-by-name`, {
         method: 'POST',
         headers: {
-          'Content-Type': 'application/json',
           'Authorization': `Bearer ${getAuthToken()}`,
         },
-        body: JSON.stringify({ username }),
       });
       
-      if (response.status === 404) {
+      if (response?.status === 404) {
         setGroups([]);
         return;
       }
       
-      if (!response.ok) throw new Error(`Erreur ${response.status}`);
-      const data = await response.json();
-      setGroups(Array.isArray(data) ? data : [data]);
+      if (response) throw new Error(`Erreur ${response.status}`);
+      setGroups(items);
     } catch (error) {
       console.error('Failed to fetch groups:', error);
       setGroups([]);
@@ -189,7 +199,7 @@ export default function Dashboard() {
     setLoading(prev => ({ ...prev, accounts: true }));
 
     try {
-      const response = await fetch(`${API_BASE_URL}/secure/get/account/groups`, {
+      const { response, items } = await fetchAllPages(`${API_BASE_URL}/secure/get/account/groups`, {
         method: 'POST',
         headers: {
           'Content-Type': 'application/json',
@@ -198,16 +208,15 @@ export default function Dashboard() {
         body: JSON.stringify({ group_name: groupName }),
       });
 
-      if (response.status === 404) {
+      if (response?.status === 404) {
         console.warn(`Aucun compte trouvé pour le groupe ${groupName}`);
         setGroupPasswords(prev => ({ ...prev, [groupName]: [] }));
         return;
       }
 
-      if (!response.ok) throw new Error(`Erreur ${response.status}`);
+      if (response) throw new Error(`Erreur ${response.status}`);
 
-      const data = await response.json();
-      setGroupPasswords(prev => ({ ...prev, [groupName]: Array.isArray(data) ? data : [data] }));
+      setGroupPasswords(prev => ({ ...prev, [groupName]: items }));
 
     } catch (error) {
       console.error(`Failed to fetch accounts for group ${groupName}:`, error);
@@ -221,7 +230,7 @@ export default function Dashboard() {
     setLoading(prev => ({ ...prev, apiKeys: true }));
 
     try {
-      const response = await fetch(`${API_BASE_URL}/secure/get/api-key/groups`, {
+      const { response, items } = await fetchAllPages(`${API_BASE_URL}/secure/get/api-key/groups`, {
         method: 'POST',
         headers: {
           'Content-Type': 'application/json',
@@ -230,17 +239,16 @@ export default function Dashboard() {
         body: JSON.stringify({ group_name: groupName }),
       });
 
-      if (response.status === 404) {
+      if (response?.status === 404) {
         console.warn(`Aucune clé API trouvée pour le groupe ${groupName}`);
         setGroupApiKeys(prev => ({ ...prev, [groupName]: [] }));
         return;
       }
 
-      if (!response.ok) throw new Error(`Erreur ${response.status}`);
+      if (response) throw new Error(`Erreur ${response.status}`);
 
-      const data = await response.json();
-      setGroupApiKeys(prev => ({ ...prev, [groupName]: Array.isArray(data) ? data : [data] }));
-      console.log('API Keys fetched:', data);
+      setGroupApiKeys(prev => ({ ...prev, [groupName]: items }));
+      console.log('API Keys fetched:', items);
 
     } catch (error) {
       console.error(`Failed to fetch API keys for group ${groupName}:`, error);
@@ -287,11 +295,43 @@ export default function Dashboard() {
     window.open(url, '_blank');
   };
 
-  const toggleShowApiKey = (id) => {
+  // Les listes ne contiennent plus les secrets : on les déchiffre un par un à la demande
+  const revealSecret = async (itemType, id) => {
+    if (revealed[id] !== undefined) return revealed[id];
+
+    try {
+      const response = await fetch(`${API_BASE_URL}/secure/reveal`, {
+        method: 'POST',
+        headers: {
+          'Content-Type': 'application/json',
+          'Authorization': `Bearer ${getAuthToken()}`,
+        },
+        body: JSON.stringify({ item_type: itemType, item_id: id }),
+      });
+
+      if (!response.ok) throw new Error(`Erreur ${response.status}`);
+      const data = await response.json();
+      setRevealed(prev => ({ ...prev, [id]: data.secret }));
+      return data.secret;
+    } catch (error) {
+      console.error('Failed to reveal secret:', error);
+      showAlert('error', 'Failed to reveal secret');
+      return null;
+    }
+  };
+
+  const copySecret = async (itemType, id) => {
+    const secret = await revealSecret(itemType, id);
+    if (secret !== null) copyToClipboard(secret);
+  };
+
+  const toggleShowApiKey = async (id, itemType = 'api_key') => {
+    if (!showApiKey[id] && (await revealSecret(itemType, id)) === null) return;
     setShowApiKey(prev => ({ ...prev, [id]: !prev[id] }));
   };
 
-  const toggleShowPassword = (id) => {
+  const toggleShowPassword = async (id, itemType = 'account') => {
+    if (!showPassword[id] && (await revealSecret(itemType, id)) === null) return;
     setShowPassword(prev => ({ ...prev, [id]: !prev[id] }));
   };
 
@@ -402,7 +442,6 @@ export default function Dashboard() {
           'Authorization': `Bearer ${getAuthToken()}`
         },
         body: JSON.stringify({
-          username: username,
           api_key: newApiKey.key,
           title: newApiKey.title
         })
@@ -445,7 +484,6 @@ export default function Dashboard() {
           'Authorization': `Bearer ${getAuthToken()}`
         },
         body: JSON.stringify({
-          username: username,
           user_account: newAccount.userAccount,
           password_account: newAccount.passwordAccount,
           title: newAccount.title,
@@ -902,7 +940,7 @@ export default function Dashboard() {
                     {showApiKey[api.id] ? <EyeOff size={16} /> : <Eye size={16} />}
                   </button>
                   <button
-                    onClick={() => copyToClipboard(api.apiKey)}
+                    onClick={() => copySecret('api_key', api.id)}
                     style={styles.iconButton}
                     onMouseEnter={(e) => e.target.style.backgroundColor = '#2d3748'}
                     onMouseLeave={(e) => e.target.style.backgroundColor = 'transparent'}
@@ -915,7 +953,7 @@ export default function Dashboard() {
 
               <div style={styles.sensitiveField}>
                 <span style={styles.sensitiveText}>
-                  {showApiKey[api.id] ? api.apiKey : '•'.repeat(api.apiKey.length)}
+                  {showApiKey[api.id] ? revealed[api.id] : '•'.repeat(12)}
                 </span>
               </div>
 
@@ -991,7 +1029,7 @@ export default function Dashboard() {
                       {showPassword[acc.id] ? <EyeOff size={14} /> : <Eye size={14} />}
                     </button>
                     <button
-                      onClick={() => copyToClipboard(acc.password_account)}
+                      onClick={() => copySecret('account', acc.id)}
                       style={styles.iconButton}
                       onMouseEnter={(e) => e.target.style.backgroundColor = '#2d3748'}
                       onMouseLeave={(e) => e.target.style.backgroundColor = 'transparent'}
@@ -1003,7 +1041,7 @@ export default function Dashboard() {
                 </div>
                 <div style={styles.sensitiveField}>
                   <span style={styles.sensitiveText}>
-                    {showPassword[acc.id] ? acc.password_account : '•'.repeat(acc.password_account.length)}
+                    {showPassword[acc.id] ? revealed[acc.id] : '•'.repeat(12)}
                   </span>
                 </div>
               </div>
@@ -1185,7 +1223,14 @@ export default function Dashboard() {
                           User: {account.user_account}
                         </p>
                         <p style={{ color: '#a0aec0', fontSize: '0.8rem', marginBottom: '0.25rem' }}>
-                          Pass: {account.password_account}
+                          Pass:{' '}
+                          <span
+                            onClick={() => toggleShowPassword(account.id, 'account_group')}
+                            style={{ cursor: 'pointer' }}
+                            title={showPassword[account.id] ? 'Hide Password' : 'Show Password'}
+                          >
+                            {showPassword[account.id] ? revealed[account.id] : '•'.repeat(12)}
+                          </span>
                         </p>
                         {account.url && (
                           <a
@@ -1228,8 +1273,12 @@ export default function Dashboard() {
                           <p style={{ color: '#ffffff', fontSize: '0.875rem', fontWeight: '600', margin: 0, marginBottom: '0.25rem' }}>
                             {api.title}
                           </p>
-                          <p style={{ color: '#a0aec0', fontSize: '0.8rem', margin: 0, fontFamily: 'monospace', wordBreak: 'break-all' }}>
-                            {api.api_key}
+                          <p
+                            onClick={() => toggleShowApiKey(api.id, 'api_key_group')}
+                            style={{ color: '#a0aec0', fontSize: '0.8rem', margin: 0, fontFamily: 'monospace', wordBreak: 'break-all', cursor: 'pointer' }}
+                            title={showApiKey[api.id] ? 'Hide API Key' : 'Show API Key'}
+                          >
+                            {showApiKey[api.id] ? revealed[api.id] : '•'.repeat(12)}
                           </p>
                         </div>
                       ))}